@@ -1,7 +1,7 @@
 const actions = require('./actions');
 const {BLOCK_UPDATE, QUORUM_UPDATE, THRESHOLD_UPDATE, DEAL_CREATED_UPDATE, DEAL_EXECUTED_UPDATE, SUBMIT_DEPOSIT_METADATA, SUBMIT_DEPOSIT_METADATA_RESULT, FETCH_FILLABLE_DEPOSITS, FETCH_FILLABLE_SUCCESS, QUORUM_NOT_REACHED_UPDATE, FETCH_CONFIG, FETCH_CONFIG_SUCCESS} = actions;
 const debug = require('debug')('client');
-const {recoverTypedSignature_v4} = require('eth-sig-util');
+const {recoverTypedSignature_v4, TypedDataUtils} = require('eth-sig-util');
 debug.enabled = true;
 
 const EventEmitter = require('events');
@@ -29,6 +29,7 @@ if (typeof window === 'undefined') {
  * @property {string} amount - The deposit amount in wei
  * @property {string} encRecipient - The encrypted recipient Ethereum address
  * @property {string} pubKey - The user generated pubKey
+ * @property {string} expiresAt - The block number after which the deposit is refunded instead of mixed
  */
 
 /**
@@ -41,6 +42,36 @@ if (typeof window === 'undefined') {
 // TODO: Move path to config and reference Github
 const SaladContract = require('../../build/smart_contracts/Salad.json');
 
+// Flag of the splits size byte of a payload followed by the guardian pubKey of its output
+const GUARDIAN_FLAG = 0x80;
+// Flag of the percent byte of a split whose output is held as a note in the enclave ledger
+const NOTE_FLAG = 0x80;
+// First byte of the envelopes prefixed with a TLV extension area, padded beyond the largest splits payload
+const EXTENSIONS_MARKER = 0x40;
+const EXTENSION_PADDING = 0x00;
+const MAX_SPLITS_SIZE = 84;
+// Salted payloads: the magic and a random salt prefix the ciphertext, its key being expanded from the shared key
+const SALTED_PAYLOAD_MAGIC = 'SALADKS1';
+const SALT_SIZE = 16;
+const DEPOSIT_PAYLOAD_CONTEXT = 'deposit-payload/v1';
+
+/**
+ * HKDF-SHA256 (RFC 5869) of a single output block, as derived by the enclave
+ * @param {string} salt - Binary string
+ * @param {string} seed - Binary string
+ * @param {string} info - Binary string
+ * @returns {string} The hex key
+ */
+function hkdf(salt, seed, info) {
+    const hmac = (key, message) => {
+        const mac = forge.hmac.create();
+        mac.start('sha256', key);
+        mac.update(message);
+        return mac.digest().getBytes();
+    };
+    return forge.util.bytesToHex(hmac(hmac(salt, seed), info + String.fromCharCode(1)));
+}
+
 class CoinjoinClient {
     constructor(operatorUrl = 'ws://localhost:8080', web3) {
         this.web3 = web3;
@@ -82,6 +113,30 @@ class CoinjoinClient {
         return web3.utils.hexToBytes(web3.utils.padLeft(web3.utils.numberToHex(val), 64));
     }
 
+    /**
+     * Prefix a payload with a TLV extension area (v5 envelope), padded beyond the largest splits payload
+     * @param web3
+     * @param {Array<number>} payload - The payload bytes
+     * @param {Array<{type: number, value: string}>} extensions - The extensions, values in hex
+     * @returns {Array<number>}
+     */
+    static prefixExtensions(web3, payload, extensions) {
+        let area = [];
+        for (const {type, value} of extensions) {
+            const bytes = CoinjoinClient.hexToBytes(web3, value);
+            if (bytes.length > 255) {
+                throw new Error(`Extension ${type} too large: ${bytes.length}`);
+            }
+            area = [...area, type, bytes.length, ...bytes];
+        }
+        const missing = MAX_SPLITS_SIZE + 1 - (3 + area.length + payload.length);
+        if (missing > 0) {
+            const padding = Math.max(missing - 2, 0);
+            area = [...area, EXTENSION_PADDING, padding, ...new Array(padding).fill(0)];
+        }
+        return [EXTENSIONS_MARKER, area.length >> 8, area.length & 0xff, ...area, ...payload];
+    }
+
     static hexToBytes(web3, val) {
         if (!val.startsWith('0x')) {
             val = `0x${val}`;
@@ -90,7 +145,7 @@ class CoinjoinClient {
     }
 
     static buildDepositTypedData(payload, chainId) {
-        const {sender, amount, encRecipient, pubKey} = payload;
+        const {sender, amount, encRecipient, pubKey, expiresAt} = payload;
         return {
             types: {
                 EIP712Domain: [
@@ -103,12 +158,13 @@ class CoinjoinClient {
                     {name: 'amount', type: 'uint256'},
                     {name: 'encRecipient', type: 'bytes'},
                     {name: 'pubKey', type: 'bytes'},
+                    {name: 'expiresAt', type: 'uint256'},
                 ],
             },
             primaryType: 'Deposit',
             domain: {
                 name: 'Salad Deposit',
-                version: '1',
+                version: '2',
                 chainId,
             },
             message: {
@@ -116,10 +172,57 @@ class CoinjoinClient {
                 amount,
                 encRecipient: `0x${encRecipient}`,
                 pubKey: `0x${pubKey}`,
+                expiresAt,
             },
         };
     }
 
+    /**
+     * Build the request of a deposit signature for the signers holding their own keys, such as hardware wallets.
+     * The EIP-712 typed data is signed as is by the wallets supporting it (e.g. Trezor), while the wallets
+     * signing pre-hashed EIP-712 messages (e.g. Ledger) sign the domain separator and message hash.
+     * The digest is the EIP-712 digest recovered by the enclave.
+     * @param {DepositPayload} payload
+     * @param {number} chainId
+     * @returns {{typedData: Object, domainSeparator: string, messageHash: string, digest: string}}
+     */
+    static buildDepositSigningRequest(payload, chainId) {
+        const typedData = CoinjoinClient.buildDepositTypedData(payload, chainId);
+        const {types, primaryType, domain, message} = TypedDataUtils.sanitizeData(typedData);
+        return {
+            typedData,
+            domainSeparator: `0x${TypedDataUtils.hashStruct('EIP712Domain', domain, types, true).toString('hex')}`,
+            messageHash: `0x${TypedDataUtils.hashStruct(primaryType, message, types, true).toString('hex')}`,
+            digest: `0x${TypedDataUtils.sign(typedData, true).toString('hex')}`,
+        };
+    }
+
+    /**
+     * Join the {v, r, s} components returned by the hardware wallets into a 65-byte signature
+     * @param {{v: number|string, r: string, s: string}} signature - r and s in hex, with or without prefix
+     * @returns {string}
+     */
+    static joinSignature(signature) {
+        const {v, r, s} = signature;
+        const strip = (hex) => hex.replace(/^0x/, '').padStart(64, '0');
+        // Some wallets return the recovery id (0 or 1) rather than v
+        const recovery = Number(v) < 27 ? Number(v) + 27 : Number(v);
+        return `0x${strip(r)}${strip(s)}${recovery.toString(16)}`;
+    }
+
+    /**
+     * Verify the deposit signature returned by a signer against its signing request, before submitting it
+     * @param {Object} request - The signing request of `buildDepositSigningRequest`
+     * @param {string|Object} signature - The 65-byte signature, or its {v, r, s} components
+     * @param {string} signer - The address expected to sign the deposit
+     * @returns {boolean}
+     */
+    static verifyDepositSignature(request, signature, signer) {
+        const sig = typeof signature === 'string' ? signature : CoinjoinClient.joinSignature(signature);
+        const recovered = recoverTypedSignature_v4({data: request.typedData, sig});
+        return recovered.toLowerCase() === signer.toLowerCase();
+    }
+
     /**
      * Generate DealId
      * @param web3
@@ -314,6 +417,82 @@ class CoinjoinClient {
         return this.contract.methods.makeDeposit().send({...opts, from: sender, value: amount});
     }
 
+    /**
+     * Make a voucher deposit on Ethereum, the deposit being identified by an opaque voucher id
+     * bound to the commitment of its payload instead of the sender address
+     * @param {string} sender - The deposit sender's Ethereum address
+     * @param {string} amount - The deposit amount in WEI (e.g. "10000000")
+     * @param {string} pubKey - The user pubKey of the encrypted payload
+     * @param {string} encRecipient - The encrypted payload
+     * @param {Object} [opts] - The optional Web3 send options, sender will be overwritten
+     * @returns {Promise<Receipt>}
+     */
+    async makeVoucherDepositAsync(sender, amount, pubKey, encRecipient, opts) {
+        if (!this.web3.utils.isAddress(sender)) {
+            throw new Error(`Invalid sender ${sender}`);
+        }
+        if (isNaN(parseInt(amount))) {
+            throw new Error(`Invalid amount ${amount}`);
+        }
+        const commitment = this.web3.utils.soliditySha3({t: 'bytes', value: pubKey}, {t: 'bytes', value: encRecipient});
+        debug('Posting voucher deposit to the smart contract', amount, commitment);
+        return this.contract.methods.makeVoucherDeposit(commitment).send({...opts, from: sender, value: amount});
+    }
+
+    /**
+     * Release an escrowed output as its recipient or guardian, the output being paid to its recipient
+     * once released by both
+     * @param {string} sender - The recipient or guardian Ethereum address
+     * @param {string} escrowId - The escrow id of the `NewEscrow` event
+     * @param {Object} [opts] - The optional Web3 send options, sender will be overwritten
+     * @returns {Promise<Receipt>}
+     */
+    async releaseEscrowAsync(sender, escrowId, opts) {
+        if (!this.web3.utils.isAddress(sender)) {
+            throw new Error(`Invalid sender ${sender}`);
+        }
+        debug('Releasing escrow', escrowId);
+        return this.contract.methods.releaseEscrow(escrowId).send({...opts, from: sender});
+    }
+
+    /**
+     * Decrypt the claim secrets of the notes of a deposit from its claim code returned by `get_claim_codes`
+     * @param {string} claimCode - The claim code of the deposit, encrypted to its pubKey
+     * @returns {Array<string>} The hex claim secrets, in the order of the splits of the deposit
+     */
+    decryptClaimSecrets(claimCode) {
+        const derivedKey = utils.getDerivedKey(this.getPlaintextPubKey(), this.keyPair.privateKey);
+        const secrets = utils.decryptMessage(derivedKey, claimCode);
+        return secrets.match(/.{64}/g).map((secret) => `0x${secret}`);
+    }
+
+    /**
+     * Decrypt the fee invoice of a deposit returned by `get_fee_invoice`
+     * @param {string} invoice - The fee invoice of the deposit, encrypted to its pubKey
+     * @returns {Object} The deal id, and the gross, fee, net and relayer share amounts in wei
+     */
+    decryptFeeInvoice(invoice) {
+        const derivedKey = utils.getDerivedKey(this.getPlaintextPubKey(), this.keyPair.privateKey);
+        const words = utils.decryptMessage(derivedKey, invoice).match(/.{64}/g);
+        // The amounts are little-endian uint256
+        const [gross, fee, net, relayerShare] = words.slice(1)
+            .map((word) => this.web3.utils.toBN(`0x${word.match(/../g).reverse().join('')}`).toString());
+        return {dealId: `0x${words[0]}`, gross, fee, net, relayerShare};
+    }
+
+    /**
+     * Sign the claim of a note with the payout address, which may be a fresh address
+     * @param {string} claimSecret - The hex claim secret of the note
+     * @param {string} payout - The payout address
+     * @returns {Promise<string>}
+     */
+    async signClaimAsync(claimSecret, payout) {
+        const {padLeft, keccak256, utf8ToHex} = this.web3.utils;
+        const words = [utf8ToHex('Salad Note Claim'), keccak256(claimSecret), padLeft(payout, 64)];
+        const claim = `0x${words.map((word) => word.slice(2)).join('')}`;
+        return this.web3.eth.sign(keccak256(claim), payout);
+    }
+
     /**
      * Verify the public key against the registry
      * @returns {Promise<void>}
@@ -345,13 +524,24 @@ class CoinjoinClient {
 
     /**
      * Encrypt the user recipient address in-memory. Plaintext recipient should not leave the browser.
+     * With a guardian, the output is held in escrow until released by both the recipient and the guardian.
+     * As a note, the output is held in the enclave ledger until withdrawn by the recipient.
      * @param  {string} recipient - The plaintext recipient Ethereum address
+     * @param  {string} [guardianPubKey] - The uncompressed 64-byte public key (hex) of the guardian
+     * @param  {boolean} [note] - Hold the output as a note of the recipient
+     * @param  {Array<{type: number, value: string}>} [extensions] - The extensions of the envelope, values in hex
      * @returns {Promise<string>}
      */
-    async encryptRecipientAsync(recipient) {
+    async encryptRecipientAsync(recipient, guardianPubKey, note, extensions) {
         if (!this.web3.utils.isAddress(recipient)) {
             throw new Error(`Invalid recipient address ${recipient}`);
         }
+        if (guardianPubKey && this.web3.utils.hexToBytes(guardianPubKey).length !== 64) {
+            throw new Error(`Invalid guardian pubKey ${guardianPubKey}`);
+        }
+        if (guardianPubKey && note) {
+            throw new Error('Notes cannot be held in escrow by a guardian');
+        }
         if (!this.pubKeyData) {
             throw new Error("Attribute pubKeyData not set. Please call initAsync");
         }
@@ -363,8 +553,22 @@ class CoinjoinClient {
         const {privateKey} = this.keyPair;
         debug('Deriving encryption from private key', privateKey);
         const derivedKey = utils.getDerivedKey(pubKey, privateKey);
-        const recipientBytes = new Uint8Array(this.web3.utils.hexToBytes(recipient));
-        return utils.encryptMessage(derivedKey, recipientBytes);
+        // A fresh salt per deposit, so that deposits under the same key pair never share an encryption key
+        const salt = forge.random.getBytesSync(SALT_SIZE);
+        const saltedKey = hkdf(salt, forge.util.hexToBytes(derivedKey), DEPOSIT_PAYLOAD_CONTEXT);
+        let payload = this.web3.utils.hexToBytes(recipient);
+        if (guardianPubKey) {
+            // v3 envelope: the splits size flagged with the guardian, the recipient and the guardian pubKey
+            payload = [GUARDIAN_FLAG | payload.length, ...payload, ...this.web3.utils.hexToBytes(guardianPubKey)];
+        } else if (note) {
+            // v4 envelope: a single split of the whole deposit, its percent flagged as a note
+            payload = [...payload, NOTE_FLAG | 100];
+        }
+        if (extensions && extensions.length > 0) {
+            payload = CoinjoinClient.prefixExtensions(this.web3, payload, extensions);
+        }
+        const header = forge.util.bytesToHex(SALTED_PAYLOAD_MAGIC + salt);
+        return header + utils.encryptMessage(saltedKey, new Uint8Array(payload));
     }
 
     /**
@@ -373,10 +577,11 @@ class CoinjoinClient {
      * @param {string} amount - The deposit amount in WEI (e.g. "10000000")
      * @param {string} encRecipient - The encrypted recipient Ethereum address
      * @param {string} pubKey - The user pubKey
+     * @param {string} expiresAt - The block number after which the deposit is refunded instead of mixed
      * @param {string} signature - The deposit payload signature
      * @returns {Promise<boolean>}
      */
-    async submitDepositMetadataAsync(sender, amount, encRecipient, pubKey, signature) {
+    async submitDepositMetadataAsync(sender, amount, encRecipient, pubKey, expiresAt, signature) {
         if (!this.web3.utils.isAddress(sender)) {
             throw new Error(`Invalid sender address ${sender}`);
         }
@@ -389,6 +594,9 @@ class CoinjoinClient {
         if (!this.web3.utils.isHex(pubKey)) {
             throw new Error(`Invalid pub key ${pubKey}`);
         }
+        if (isNaN(parseInt(expiresAt))) {
+            throw new Error(`Invalid expiry ${expiresAt}`);
+        }
         if (!this.web3.utils.isHex(signature)) {
             throw new Error(`Invalid signature ${signature}`);
         }
@@ -403,7 +611,7 @@ class CoinjoinClient {
         });
         this.ws.send(JSON.stringify({
             action: SUBMIT_DEPOSIT_METADATA,
-            payload: {sender, amount, encRecipient, pubKey, signature}
+            payload: {sender, amount, encRecipient, pubKey, expiresAt, signature}
         }));
         return promise;
     }
@@ -500,9 +708,25 @@ class CoinjoinClient {
      * @param {string} amount - The deposit amount in WEI (e.g. "10000000")
      * @param {string} encRecipient - The encrypted recipient Ethereum address
      * @param {string} pubKey - The user pubKey
+     * @param {string} expiresAt - The block number after which the deposit is refunded instead of mixed
      * @returns {Promise<string>}
      */
-    async signDepositMetadataAsync(sender, amount, encRecipient, pubKey) {
+    async signDepositMetadataAsync(sender, amount, encRecipient, pubKey, expiresAt) {
+        const request = await this.buildDepositSigningRequestAsync(sender, amount, encRecipient, pubKey, expiresAt);
+        return this.signMsgAsync(request.typedData, sender);
+    }
+
+    /**
+     * Build the request of the deposit metadata signature, to be signed outside of the SDK (e.g. by a hardware
+     * wallet) and checked with `verifyDepositSignature` before `submitDepositMetadataAsync`
+     * @param {string} sender
+     * @param {string} amount - The deposit amount in WEI (e.g. "10000000")
+     * @param {string} encRecipient - The encrypted recipient Ethereum address
+     * @param {string} pubKey - The user pubKey
+     * @param {string} expiresAt - The block number after which the deposit is refunded instead of mixed
+     * @returns {Promise<{typedData: Object, domainSeparator: string, messageHash: string, digest: string}>}
+     */
+    async buildDepositSigningRequestAsync(sender, amount, encRecipient, pubKey, expiresAt) {
         if (!this.web3.utils.isAddress(sender)) {
             throw new Error(`Invalid sender address ${sender}`);
         }
@@ -515,11 +739,39 @@ class CoinjoinClient {
         if (!this.web3.utils.isHex(pubKey)) {
             throw new Error(`Invalid pub key ${pubKey}`);
         }
+        if (isNaN(parseInt(expiresAt))) {
+            throw new Error(`Invalid expiry ${expiresAt}`);
+        }
+        /** @type DepositPayload */
+        const payload = {sender, amount, encRecipient, pubKey, expiresAt};
+        const chainId = await this.web3.eth.net.getId();
+        return CoinjoinClient.buildDepositSigningRequest(payload, chainId);
+    }
+
+    /**
+     * Sign the deposit metadata of a deposit bundled by an on-chain aggregator, the deposit message naming
+     * the depositing contract of the aggregator as sender. The aggregator appends its own signature for the
+     * depositor to build the bundled deposit signature.
+     * @param {string} aggregator - The depositing contract of the aggregator
+     * @param {string} depositor - The depositor account signing the deposit, which never sends a tx
+     * @param {string} amount - The deposit amount in WEI (e.g. "10000000")
+     * @param {string} encRecipient - The encrypted recipient Ethereum address
+     * @param {string} pubKey - The user pubKey
+     * @param {string} expiresAt - The block number after which the deposit is refunded instead of mixed
+     * @returns {Promise<string>}
+     */
+    async signBundledDepositMetadataAsync(aggregator, depositor, amount, encRecipient, pubKey, expiresAt) {
+        if (!this.web3.utils.isAddress(aggregator)) {
+            throw new Error(`Invalid aggregator address ${aggregator}`);
+        }
+        if (!this.web3.utils.isAddress(depositor)) {
+            throw new Error(`Invalid depositor address ${depositor}`);
+        }
         /** @type DepositPayload */
-        const payload = {sender, amount, encRecipient, pubKey};
+        const payload = {sender: aggregator, amount, encRecipient, pubKey, expiresAt};
         const chainId = await this.web3.eth.net.getId();
         const typedData = CoinjoinClient.buildDepositTypedData(payload, chainId);
-        return this.signMsgAsync(typedData, sender);
+        return this.signMsgAsync(typedData, depositor);
     }
 
     /**
@@ -16,6 +16,8 @@ import {initializeSalad} from "../actions";
 import {openSnackbar} from './Notifier';
 
 const DEPOSIT_AMOUNT = 0.01;
+// Blocks after which a deposit left out of the deals is refunded instead of mixed
+const DEPOSIT_TTL_BLOCKS = 40000;
 
 class Mixer extends Component {
     constructor(props) {
@@ -87,12 +89,13 @@ class Mixer extends Component {
             const encRecipient = await salad.encryptRecipientAsync(recipient);
             console.log('The encrypted recipient');
             const myPubKey = salad.keyPair.publicKey;
-            console.log('Signing deposit payload', sender, amountInWei, encRecipient, myPubKey);
-            const signature = await salad.signDepositMetadataAsync(sender, amountInWei, encRecipient, myPubKey);
+            const expiresAt = (await web3.eth.getBlockNumber()) + DEPOSIT_TTL_BLOCKS;
+            console.log('Signing deposit payload', sender, amountInWei, encRecipient, myPubKey, expiresAt);
+            const signature = await salad.signDepositMetadataAsync(sender, amountInWei, encRecipient, myPubKey, expiresAt);
             console.log('Deposit payload signed', signature);
             // The public key of the user must be submitted
             // This is DH encryption, Enigma needs the user pub key to decrypt the data
-            await salad.submitDepositMetadataAsync(sender, amountInWei, encRecipient, myPubKey, signature);
+            await salad.submitDepositMetadataAsync(sender, amountInWei, encRecipient, myPubKey, expiresAt, signature);
             console.log('Deposit metadata submitted');
             openSnackbar({message: 'Deposit accepted by the Relayer'});
             this.setState({isSubmitting: false, isPending: true});
@@ -38,7 +38,27 @@ function getEnigmaTokenContractAddressFromJson() {
     return enigmaTokenContract.networks[process.env.ETH_NETWORK_ID].address;
 }
 
-async function deploySecretContract(config, saladAddr, enigmaAddr, enigmaTokenAddr) {
+/**
+ * Parse a comma-separated list of addresses into their canonical lowercase form, so that the mixed-case
+ * (checksummed) and lowercase spellings of an address are one entry
+ * @param {string} value
+ * @param {boolean} unique - Refuse the lists naming an address twice
+ * @returns {string[]}
+ */
+function parseAddresses(value, unique) {
+    const addresses = value.split(',').map(address => address.trim().toLowerCase());
+    for (const [i, address] of addresses.entries()) {
+        if (!web3.utils.isAddress(address)) {
+            throw new Error(`Invalid address: ${address}`);
+        }
+        if (unique && addresses.indexOf(address) !== i) {
+            throw new Error(`Duplicate address: ${address}`);
+        }
+    }
+    return addresses;
+}
+
+async function deploySecretContract(config, saladAddr, oracleSignerAddrs, outputOrdering, recipientAllowlistRoot, velocityLimits, auditors, dustPolicy, operatorKeys, amountBounds, gasStipend, maxDummyOutputs, cancelQuorum, bucketUnit, mixerTimelock, networkProfile, enigmaAddr, enigmaTokenAddr) {
     debug(`Deploying Secret Contract "${config.filename}"...`);
     debug('The Enigma address / token address', enigmaAddr, enigmaTokenAddr);
     let preCode;
@@ -50,6 +70,27 @@ async function deploySecretContract(config, saladAddr, enigmaAddr, enigmaTokenAd
     }
     const {args} = config;
     args.push([saladAddr, 'address']);
+    args.push([oracleSignerAddrs, 'address[]']);
+    args.push([outputOrdering, 'uint256']);
+    args.push([recipientAllowlistRoot, 'bytes32']);
+    args.push([velocityLimits.epochDeals, 'uint256']);
+    args.push([velocityLimits.maxDeposits, 'uint256']);
+    args.push([velocityLimits.maxVolume, 'uint256']);
+    args.push([auditors.pubKeys, 'bytes[]']);
+    args.push([auditors.roles, 'uint256[]']);
+    args.push([dustPolicy.policy, 'uint256']);
+    args.push([dustPolicy.threshold, 'uint256']);
+    args.push([operatorKeys.operators, 'address[]']);
+    args.push([operatorKeys.signers, 'address[]']);
+    args.push([amountBounds.min, 'uint256']);
+    args.push([amountBounds.max, 'uint256']);
+    args.push([amountBounds.maxDealValue, 'uint256']);
+    args.push([gasStipend, 'uint256']);
+    args.push([maxDummyOutputs, 'uint256']);
+    args.push([cancelQuorum, 'uint256']);
+    args.push([bucketUnit, 'uint256']);
+    args.push([mixerTimelock, 'uint256']);
+    args.push([networkProfile, 'uint256']);
 
     let enigmaHost = process.env.ENIGMA_HOST || 'localhost';
     let enigmaPort = process.env.ENIGMA_PORT || '3333';
@@ -141,10 +182,15 @@ module.exports = async function (deployer, network, accounts) {
     const dealIntervalInBlocks = process.env.DEAL_INTERVAL_IN_BLOCKS;
     const relayerFeePercent = process.env.RELAYER_FEE_PERCENT;
     const participationThreshold = process.env.PARTICIPATION_THRESHOLD;
-    debug('Deploying Salad(', depositLockPeriodInBlocks, dealIntervalInBlocks, relayerFeePercent, participationThreshold, ')');
-    await deployer.deploy(Salad, depositLockPeriodInBlocks, dealIntervalInBlocks, sender, relayerFeePercent, participationThreshold);
+    // Deals never committed are refunded past this window, longer than the schedule delay and execution window of the enclave
+    const executionWindowInBlocks = process.env.EXECUTION_WINDOW_IN_BLOCKS || 300;
+    debug('Deploying Salad(', depositLockPeriodInBlocks, dealIntervalInBlocks, relayerFeePercent, participationThreshold, executionWindowInBlocks, ')');
+    await deployer.deploy(Salad, depositLockPeriodInBlocks, dealIntervalInBlocks, sender, relayerFeePercent, participationThreshold, executionWindowInBlocks);
     debug(`Smart Contract "Salad.Sol" has been deployed at ETH address: ${Salad.address}`);
     await store.insertSmartContractAddress(Salad.address);
+    const salad = await Salad.deployed();
+    // The callouts of the secret contract are only accepted from the Enigma contract
+    await salad.setEnigma(enigmaAddr);
 
     const config = {
         filename: 'salad.wasm',
@@ -154,7 +200,54 @@ module.exports = async function (deployer, network, accounts) {
         gasPrice: utils.toGrains(1e-8),
         from: sender
     };
-    const scAddress = await deploySecretContract(config, Salad.address, enigmaAddr, enigmaTokenAddr);
+    // The price oracle signers allowlist defaults to the operator account
+    const oracleSignerAddrs = parseAddresses(process.env.ORACLE_SIGNER_ADDRESSES || sender, true);
+    // The output ordering policy: 0 for a random shuffle (default), 1 for outputs sorted by recipient address
+    const outputOrdering = process.env.OUTPUT_ORDERING || 0;
+    // The Merkle root of the allowed recipients of a permissioned pool, zero for a pool open to any recipient
+    const recipientAllowlistRoot = process.env.RECIPIENT_ALLOWLIST_ROOT || `0x${'0'.repeat(64)}`;
+    // The max deposits and volume (in wei) of each sender per epoch of deals, no epoch disabling the limits
+    const velocityLimits = {
+        epochDeals: process.env.VELOCITY_EPOCH_DEALS || 0,
+        maxDeposits: process.env.VELOCITY_MAX_DEPOSITS || 0,
+        maxVolume: process.env.VELOCITY_MAX_VOLUME || 0,
+    };
+    // The public keys (hex) of the auditors the deal receipts and records are sealed to, with their roles:
+    // 0 reading the receipts, 1 also the deal records
+    const auditors = {
+        pubKeys: process.env.AUDITOR_PUB_KEYS ? process.env.AUDITOR_PUB_KEYS.split(',') : [],
+        roles: process.env.AUDITOR_ROLES ? process.env.AUDITOR_ROLES.split(',') : [],
+    };
+    // The handling of the outputs below the dust threshold (in wei): 0 refunding the sender (default),
+    // 1 paying the operator, 2 carrying into the pool reserve, a zero threshold disabling the policy
+    const dustPolicy = {
+        policy: process.env.DUST_POLICY || 0,
+        threshold: process.env.DUST_THRESHOLD || 0,
+    };
+    // The operators with the keys signing their deal payloads, defaulting to the operator account signing its own
+    const operatorKeys = {
+        operators: parseAddresses(process.env.OPERATOR_ADDRESSES || sender, true),
+        signers: parseAddresses(process.env.OPERATOR_SIGNERS || sender, false),
+    };
+    // The min and max deal amounts and the max total value of a deal (in wei), no max leaving them unbounded
+    const amountBounds = {
+        min: process.env.MIN_AMOUNT || 0,
+        max: process.env.MAX_AMOUNT || 0,
+        maxDealValue: process.env.MAX_DEAL_VALUE || 0,
+    };
+    // The gas stipend (in wei) paid out of the operator fee to the fresh recipients, none by default
+    const gasStipend = process.env.GAS_STIPEND || 0;
+    // The max number of zero-amount dummy outputs added to each deal (at most 255), none by default
+    const maxDummyOutputs = process.env.MAX_DUMMY_OUTPUTS || 0;
+    // The percentage of the senders of a deal whose votes cancel it, no cancel votes by default
+    const cancelQuorum = process.env.CANCEL_QUORUM || 0;
+    // The unit (in wei) of the 1-2-5 amount buckets the outputs are rounded down to, no rounding by default
+    const bucketUnit = process.env.BUCKET_UNIT || 0;
+    // The number of deals between the proposal of a new Mixer address and its application, no migrations by default
+    const mixerTimelock = process.env.MIXER_TIMELOCK || 0;
+    // The network profile: 0 for mainnet, 1 for ropsten, 2 for the local ganache simulation (default)
+    const networkProfile = process.env.NETWORK_PROFILE || 2;
+    const scAddress = await deploySecretContract(config, Salad.address, oracleSignerAddrs, outputOrdering, recipientAllowlistRoot, velocityLimits, auditors, dustPolicy, operatorKeys, amountBounds, gasStipend, maxDummyOutputs, cancelQuorum, bucketUnit, mixerTimelock, networkProfile, enigmaAddr, enigmaTokenAddr);
     await store.insertSecretContractAddress(scAddress);
     debug(`Secret Contract "${config.filename}" deployed at Enigma address: ${scAddress}`);
     await store.closeAsync();
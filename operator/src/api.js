@@ -19,6 +19,12 @@ const ENG_GAS_PRICE = process.env.ENG_GAS_PRICE || 1e-8;
 const GET_ENCRYPTION_PUB_KEY_GAS_LIMIT = 0.05e+8;
 const EXECUTE_DEAL_BASE_GAS_UNIT = 0.05e+8;
 const EXECUTE_DEAL_PARTICIPANT_GAS_UNIT = 1e+8;
+// The mode byte of the deposits relayed by other secret contracts, followed by the relayer enclave signature
+const RELAYED_SIGNATURE_PREFIX = '0x03';
+const RELAYED_SIGNATURE_SIZE = 66;
+// The mode byte of the deposits bundled by an on-chain aggregator, followed by the depositor and aggregator signatures
+const BUNDLED_SIGNATURE_PREFIX = '0x04';
+const BUNDLED_SIGNATURE_SIZE = 131;
 
 class OperatorApi {
     constructor(web3, enigmaUrl, contractAddr, scAddr, threshold, pauseOnRetryInSeconds = 10) {
@@ -175,7 +181,7 @@ class OperatorApi {
         if (deposits.length >= this.threshold) {
             debug('Quorum reached with deposits', deposits);
             debug('Creating new deal on Ethereum');
-            const deal = await this.dealManager.createDealAsync(depositAmount, deposits);
+            const deal = await this.dealManager.createDealAsync(depositAmount, deposits, taskRecordOpts);
             debug('Broadcasting new deal', deal);
             this.ee.emit(DEAL_CREATED_UPDATE, deal);
             debug('Broadcasting quorum value 0 after new deal');
@@ -248,6 +254,16 @@ class OperatorApi {
      * @private
      */
     async _verifyDepositSignatureAsync(payload, sig) {
+        if (sig.startsWith(RELAYED_SIGNATURE_PREFIX) && this.web3.utils.hexToBytes(sig).length === RELAYED_SIGNATURE_SIZE) {
+            // Relayed deposits are authenticated by the enclave against its registered relayers
+            debug('Deferring the verification of the relayed deposit of', payload.sender);
+            return true;
+        }
+        if (sig.startsWith(BUNDLED_SIGNATURE_PREFIX) && this.web3.utils.hexToBytes(sig).length === BUNDLED_SIGNATURE_SIZE) {
+            // Bundled deposits are authenticated by the enclave against the registered aggregators
+            debug('Deferring the verification of the deposit bundled by', payload.sender);
+            return true;
+        }
         const chainId = await this.web3.eth.net.getId();
         const data = CoinjoinClient.buildDepositTypedData(payload, chainId);
         const sender = this.web3.utils.toChecksumAddress(recoverTypedSignature_v4({data, sig}));
@@ -261,18 +277,19 @@ class OperatorApi {
      * @param amount
      * @param pubKey
      * @param encRecipient
+     * @param expiresAt
      * @param signature
      * @returns {Promise<OperatorAction>}
      */
-    async submitDepositMetadataAsync(sender, amount, pubKey, encRecipient, signature) {
-        debug('In submitDepositMetadataAsync(', sender, amount, pubKey, encRecipient, signature, ')');
-        const payload = {sender, amount, encRecipient, pubKey};
+    async submitDepositMetadataAsync(sender, amount, pubKey, encRecipient, expiresAt, signature) {
+        debug('In submitDepositMetadataAsync(', sender, amount, pubKey, encRecipient, expiresAt, signature, ')');
+        const payload = {sender, amount, encRecipient, pubKey, expiresAt};
         const isValidSig = await this._verifyDepositSignatureAsync(payload, signature);
         if (!isValidSig) {
             debug(`Signature verification failed: ${signature}`);
             return {action: SUBMIT_DEPOSIT_METADATA_RESULT, payload: {err: 'Invalid signature'}};
         }
-        const registeredDeposit = await this.dealManager.registerDepositAsync(sender, amount, pubKey, encRecipient, signature);
+        const registeredDeposit = await this.dealManager.registerDepositAsync(sender, amount, pubKey, encRecipient, expiresAt, signature);
         debug('Registered deposit', registeredDeposit);
 
         const fillableDeposits = await this.dealManager.balanceFillableDepositsAsync();
@@ -1,6 +1,7 @@
 // TODO: Move path to config and reference Github
 const SaladContract = require('../../build/smart_contracts/Salad.json');
 const {CoinjoinClient} = require('@salad/client');
+const {utils} = require('enigma-js/node');
 const debug = require('debug')('operator:deal-manager');
 
 const DEAL_STATUS = {
@@ -10,6 +11,22 @@ const DEAL_STATUS = {
 };
 // TODO: Definitely move to config and pass to frontend
 const DEPOSIT_AMOUNT = '0.01';
+// Attempts of the receipt callout, matching the retries allowed by the secret contract
+const RECEIPT_ATTEMPTS = 4;
+// Blocks after its scheduled block during which a deal may execute, within the max of the network profile
+const EXECUTION_WINDOW = parseInt(process.env.EXECUTION_WINDOW || 100);
+// Blocks the attested block of a scheduled deal must be confirmed for before it executes, within the execution window,
+// defaulting to the min confirmations of the network profile
+const CONFIRMATIONS = parseInt(process.env.CONFIRMATIONS || 0);
+// Preview the calldata of the distribution callout and check it before confirming the distribution
+const TWO_STEP_DISTRIBUTION = process.env.TWO_STEP_DISTRIBUTION === 'true';
+// Selectors of the distribution callouts of the payout adapters, and their parameters
+const DISTRIBUTION_CALLOUTS = {
+    'distribute': ['uint256', 'address[]', 'uint256[]', 'uint256', 'uint256', 'uint256'],
+    'distributeWithEscrow': ['uint256', 'address[]', 'address[]', 'uint256[]', 'uint256', 'uint256', 'uint256'],
+    'distributeToBridge': ['uint256', 'address[]', 'uint256[]', 'uint256', 'uint256', 'uint256'],
+    'distributeAssets': ['uint256', 'address[]', 'address[]', 'address[]', 'uint256[]', 'uint256'],
+};
 
 /**
  * @typedef {Object} Deal
@@ -17,6 +34,7 @@ const DEPOSIT_AMOUNT = '0.01';
  * @property {string} depositAmount - The deposit amount in wei
  * @property {string[]} participants - A list of participants Ethereum addresses
  * @property {string} nonce - The deal nonce (operator tx count)
+ * @property {number} nbRedeemed - The number of staged deposits joining the deal, recorded on-chain
  * @property {number} status - A list of participants Ethereum addresses
  * @property {string|null} _tx - The `createDeal` Ethereum transaction hash
  * @property {string|null} taskId - The Enigma Task Id
@@ -28,6 +46,7 @@ const DEPOSIT_AMOUNT = '0.01';
  * @property {string} amount - The deposit amount in wei
  * @property {string} encRecipient - The encrypted recipient Ethereum address
  * @property {string} pubKey - The user generated pubKey
+ * @property {string} expiresAt - The block number after which the deposit is refunded instead of mixed
  * @property {string} signature - The deposit payload signature
  */
 
@@ -92,13 +111,14 @@ class DealManager {
      * @param {string} amount - The deposit amount in wei
      * @param {string} pubKey - The user pubKey
      * @param {string} encRecipient - The recipient's encrypted Ethereum address
+     * @param {string} expiresAt - The block number after which the deposit is refunded instead of mixed
      * @param {string} signature - The deposit payload signature
      * @returns {Promise<Deposit>}
      */
-    async registerDepositAsync(sender, amount, pubKey, encRecipient, signature) {
-        debug('Registering deposit', sender, amount, encRecipient);
+    async registerDepositAsync(sender, amount, pubKey, encRecipient, expiresAt, signature) {
+        debug('Registering deposit', sender, amount, encRecipient, expiresAt);
         await this.verifyDepositAmountAsync(sender, amount);
-        const deposit = {sender, amount, pubKey, encRecipient, signature};
+        const deposit = {sender, amount, pubKey, encRecipient, expiresAt, signature};
         await this.store.insertDepositAsync(deposit);
         return deposit;
     }
@@ -107,10 +127,10 @@ class DealManager {
      * Create new Deal on Ethereum
      * @param {string} depositAmount
      * @param {Array<Deposit>} deposits - The Deposits linked to the Deal
-     * @param {Object} opts - Ethereum tx options
+     * @param {Object} taskRecordOpts
      * @returns {Promise<Deal>}
      */
-    async createDealAsync(depositAmount, deposits) {
+    async createDealAsync(depositAmount, deposits, taskRecordOpts) {
         const pendingDeals = await this.store.queryDealsAsync(DEAL_STATUS.EXECUTABLE);
         if (pendingDeals.length > 0) {
             debug('The executable deals', pendingDeals);
@@ -122,15 +142,18 @@ class DealManager {
         const sender = this.scClient.getOperatorAccount();
         const nonce = (await this.web3.eth.getTransactionCount(sender)).toString();
         debug('The nonce', nonce);
+        // The staged deposits joining the deal are recorded on-chain, the Mixer paying them out of the pool
+        const redeemableTask = await this.scClient.getRedeemableAsync(depositAmount, participants.length, taskRecordOpts);
+        const nbRedeemed = parseInt(redeemableTask.decryptedOutput, 16);
         const dealIdMessage = CoinjoinClient.generateDealIdMessage(this.web3, depositAmount, participants, sender, nonce);
         const dealId = this.web3.utils.soliditySha3({
             t: 'bytes',
             v: this.web3.utils.bytesToHex(dealIdMessage),
         });
         /** @type Deal */
-        const deal = {dealId, depositAmount, participants, nonce, status: DEAL_STATUS.NEW, _tx: null, taskId: null};
+        const deal = {dealId, depositAmount, participants, nonce, nbRedeemed, status: DEAL_STATUS.NEW, _tx: null, taskId: null};
         await this.store.insertDealAsync(deal, participants);
-        const receipt = await this.contract.methods.newDeal(depositAmount, participants, nonce).send({
+        const receipt = await this.contract.methods.newDeal(depositAmount, participants, nonce, nbRedeemed).send({
             gas: this.gasValues.createDeal,
         });
         const receiptDealId = receipt.events.NewDeal.returnValues._dealId;
@@ -179,10 +202,41 @@ class DealManager {
      * @returns {Promise<void>}
      */
     async executeDealAsync(deal, taskRecordOpts) {
-        const {depositAmount, nonce} = deal;
+        const {depositAmount, nonce, nbRedeemed} = deal;
         const deposits = await this.store.getDepositAsync(deal.dealId);
         const chainId = await this.web3.eth.net.getId();
-        const task = await this.scClient.executeDealAsync(depositAmount, deposits, nonce, chainId, taskRecordOpts);
+        const {depositsPerTask, minConfirmations} = await this.getNetworkProfileAsync(taskRecordOpts);
+        const confirmations = CONFIRMATIONS || minConfirmations;
+        // The enclave delays the execution by a random number of blocks, then refuses it once the window has passed
+        const scheduleAttestation = await this.attestDepositBlocksAsync([]);
+        const scheduleSignature = await this.signScheduleAsync(deal.dealId, EXECUTION_WINDOW, confirmations);
+        const scheduleTask = await this.scClient.scheduleDealAsync(deal.dealId, EXECUTION_WINDOW, confirmations, scheduleAttestation,
+            depositAmount, deposits, nonce, scheduleSignature, taskRecordOpts);
+        // The attested block must also be confirmed, lest a reorg roll the deal back
+        const confirmedBlock = parseInt(scheduleAttestation.attestation.slice(2, 66), 16) + confirmations;
+        await this.waitBlockAsync(Math.max(parseInt(scheduleTask.decryptedOutput, 16), confirmedBlock));
+        const blockAttestation = await this.attestDepositBlocksAsync(deposits);
+        const features = await this.getDealFeaturesAsync(taskRecordOpts);
+        const operatorSignature = await this.signDealPayloadAsync(deal.dealId, deposits, chainId, features);
+        const task = await this.scClient.executeDealAsync(depositAmount, deposits, nonce, chainId, features, nbRedeemed, blockAttestation, operatorSignature, taskRecordOpts);
+        // Deals of more deposits than verified per task by the secret contract are resumed until verified
+        if (deposits.length > depositsPerTask) {
+            let remaining = deposits.length - depositsPerTask;
+            while (remaining > 0) {
+                const continueTask = await this.scClient.continueDealAsync(deal.dealId, taskRecordOpts);
+                remaining = parseInt(continueTask.decryptedOutput, 16);
+                debug('Deposits left to verify in deal', deal.dealId, remaining);
+            }
+        }
+        // The deal roots are now committed on-chain, distribute once the challenge window elapses
+        await this.waitChallengeWindowAsync();
+        await this.answerChallengesAsync(deal.dealId, taskRecordOpts);
+        if (TWO_STEP_DISTRIBUTION) {
+            await this.previewAndConfirmDistributionAsync(deal.dealId, taskRecordOpts);
+        } else {
+            await this.scClient.finalizeDistributionAsync(deal.dealId, taskRecordOpts);
+        }
+        await this.anchorReceiptAsync(deal.dealId, taskRecordOpts);
         deal.taskId = task.taskId;
         deal.status = DEAL_STATUS.EXECUTED;
         await this.store.updateDealAsync(deal);
@@ -193,6 +247,219 @@ class DealManager {
         await this.store.setLastMixBlockNumber(blockNumber);
     }
 
+    /**
+     * Timestamp the receipt hash of a distributed deal on-chain, retrying the callout until recorded
+     * @param {string} dealId
+     * @param {Object} opts - The task options
+     * @returns {Promise<void>}
+     */
+    async anchorReceiptAsync(dealId, opts) {
+        for (let i = 0; i < RECEIPT_ATTEMPTS; i++) {
+            try {
+                await this.scClient.anchorReceiptAsync(dealId, opts);
+            } catch (e) {
+                debug('Receipt callout of deal', dealId, 'failed', e);
+            }
+            const receipt = await this.contract.methods.receipts(dealId).call();
+            if (!this.web3.utils.toBN(receipt.receiptHash).isZero()) {
+                debug('Receipt of deal', dealId, 'recorded at', receipt.timestamp);
+                return;
+            }
+        }
+        // The deal is paid, only its receipt is left unanchored
+        debug('Receipt of deal', dealId, 'not recorded after', RECEIPT_ATTEMPTS, 'attempts');
+    }
+
+    /**
+     * Attest the current block number and the block confirming the last deposit of each sender,
+     * signed by the operator account as an oracle signer of the secret contract
+     * @param {Array<Deposit>} deposits
+     * @returns {Promise<Object>}
+     */
+    async attestDepositBlocksAsync(deposits) {
+        const {padLeft, toHex, keccak256, utf8ToHex} = this.web3.utils;
+        const words = [padLeft(toHex(await this.web3.eth.getBlockNumber()), 64)];
+        for (const deposit of deposits) {
+            const events = await this.contract.getPastEvents('Deposit', {filter: {_depositor: deposit.sender}, fromBlock: 0});
+            if (events.length === 0) {
+                throw new Error(`No deposit found on-chain for sender ${deposit.sender}`);
+            }
+            words.push(padLeft(deposit.sender, 64), padLeft(toHex(events[events.length - 1].blockNumber), 64));
+        }
+        const attestation = `0x${words.map((word) => word.slice(2)).join('')}`;
+        const signature = await this.web3.eth.sign(keccak256(`${utf8ToHex('Salad Oracle Block')}${attestation.slice(2)}`), this.web3.eth.defaultAccount);
+        debug('The deposit blocks attestation', attestation, signature);
+        return {attestation, signature};
+    }
+
+    /**
+     * The features negotiated for the deals: all those supported by the secret contract, fetched once
+     * @param {Object} opts - The task options
+     * @returns {Promise<number>}
+     */
+    async getDealFeaturesAsync(opts) {
+        if (this.features === undefined) {
+            const task = await this.scClient.getSupportedFeaturesAsync(opts);
+            this.features = parseInt(task.decryptedOutput, 16);
+        }
+        return this.features;
+    }
+
+    /**
+     * The network profile selected at the construction of the secret contract, fetched once
+     * @param {Object} opts - The task options
+     * @returns {Promise<Object>} - The profile id, chain id, min deposit age, deposits per task, max execution window and min confirmations
+     */
+    async getNetworkProfileAsync(opts) {
+        if (this.networkProfile === undefined) {
+            const task = await this.scClient.getNetworkProfileAsync(opts);
+            const [id, chainId, minDepositAge, depositsPerTask, maxExecutionWindow, minConfirmations] = this.web3.eth.abi
+                .decodeParameter('uint256[]', `0x${task.decryptedOutput}`)
+                .map((value) => parseInt(value));
+            this.networkProfile = {id, chainId, minDepositAge, depositsPerTask, maxExecutionWindow, minConfirmations};
+        }
+        return this.networkProfile;
+    }
+
+    /**
+     * Sign the schedule of the deal with the operator account, so that no one else schedules the deal
+     * nor picks its execution window and confirmation depth
+     * @param {string} dealId
+     * @param {number} window - The execution window of the deal in blocks
+     * @param {number} confirmations - The confirmation depth of the attested block
+     * @returns {Promise<string>}
+     */
+    async signScheduleAsync(dealId, window, confirmations) {
+        const {padLeft, toHex, keccak256, utf8ToHex} = this.web3.utils;
+        const words = [utf8ToHex('Salad Schedule'), dealId, padLeft(toHex(window), 64), padLeft(toHex(confirmations), 64)];
+        const schedule = `0x${words.map((word) => word.slice(2)).join('')}`;
+        const signature = await this.web3.eth.sign(keccak256(schedule), this.web3.eth.defaultAccount);
+        debug('The schedule signature', signature);
+        return signature;
+    }
+
+    /**
+     * Sign the deal payload with the operator account, registered as the operator key of the secret contract,
+     * binding the deposits in their order to the deal
+     * @param {string} dealId
+     * @param {Array<Deposit>} deposits
+     * @param {number} chainId
+     * @param {number} features - The bitfield of the deal features negotiated
+     * @returns {Promise<string>}
+     */
+    async signDealPayloadAsync(dealId, deposits, chainId, features) {
+        const {padLeft, toHex, keccak256} = this.web3.utils;
+        const words = [dealId];
+        for (const deposit of deposits) {
+            words.push(
+                keccak256(`0x${deposit.pubKey}`),
+                keccak256(`0x${deposit.encRecipient}`),
+                keccak256(deposit.signature),
+                padLeft(toHex(deposit.expiresAt), 64),
+            );
+        }
+        // No aggregate signature follows the chain id and the deal features
+        words.push(padLeft(toHex(chainId), 64), padLeft(toHex(features), 64));
+        const payload = `0x${words.map((word) => word.slice(2)).join('')}`;
+        const signature = await this.web3.eth.sign(keccak256(payload), this.web3.eth.defaultAccount);
+        debug('The deal payload signature', signature);
+        return signature;
+    }
+
+    /**
+     * Sign the handoff of the operator to a new key, with either its current or its new key
+     * @param {string} operator - The operator address
+     * @param {string} newSigner - The address of the new key
+     * @param {number} nonce - The number of completed handoffs of the operator
+     * @param {string} account - The signing account
+     * @returns {Promise<string>}
+     */
+    async signHandoffAsync(operator, newSigner, nonce, account) {
+        const {padLeft, toHex, keccak256, utf8ToHex} = this.web3.utils;
+        const words = [utf8ToHex('Salad Operator Handoff'), padLeft(operator, 64), padLeft(newSigner, 64), padLeft(toHex(nonce), 64)];
+        const handoff = `0x${words.map((word) => word.slice(2)).join('')}`;
+        const signature = await this.web3.eth.sign(keccak256(handoff), account);
+        debug('The handoff signature', signature);
+        return signature;
+    }
+
+    /**
+     * Wait for the block number
+     * @param {number} blockNumber
+     * @returns {Promise<void>}
+     */
+    async waitBlockAsync(blockNumber) {
+        while (await this.web3.eth.getBlockNumber() < blockNumber) {
+            debug('Waiting for block', blockNumber);
+            await new Promise((resolve) => setTimeout(resolve, 5000));
+        }
+    }
+
+    /**
+     * Answer the challenges left open in the deal, the distribution is refused until none remains
+     * @param {string} dealId
+     * @param {Object} opts
+     * @returns {Promise<void>}
+     */
+    async answerChallengesAsync(dealId, opts) {
+        const openTask = await this.scClient.getOpenChallengesAsync(dealId, opts);
+        const depositDigests = this.web3.eth.abi.decodeParameter('bytes32[]', `0x${openTask.decryptedOutput}`);
+        for (const depositDigest of depositDigests) {
+            debug('Answering challenge of deposit', depositDigest, 'in deal', dealId);
+            await this.scClient.respondChallengeAsync(dealId, depositDigest, opts);
+        }
+    }
+
+    /**
+     * Distribute the deal in two steps: preview the calldata of the distribution callout, sanity-check its
+     * DealId, recipients and amounts, then confirm it. Throws without distributing if a check fails.
+     * @param {string} dealId
+     * @param {Object} opts
+     * @returns {Promise<void>}
+     */
+    async previewAndConfirmDistributionAsync(dealId, opts) {
+        const {keccak256, utf8ToHex, toBN} = this.web3.utils;
+        const keyPair = CoinjoinClient.obtainKeyPair();
+        const request = `${utf8ToHex('Salad Distribution Preview')}${dealId.slice(2)}${keyPair.publicKey}`;
+        const signature = await this.web3.eth.sign(keccak256(request), this.web3.eth.defaultAccount);
+        const task = await this.scClient.previewDistributionAsync(dealId, `0x${keyPair.publicKey}`, signature, opts);
+        const enclavePubKey = await this.scClient.getPlaintextPubKeyAsync(opts);
+        const calldata = `0x${utils.decryptMessage(utils.getDerivedKey(enclavePubKey, keyPair.privateKey), task.decryptedOutput)}`;
+        const name = Object.keys(DISTRIBUTION_CALLOUTS)
+            .find((callout) => this.web3.eth.abi.encodeFunctionSignature(`${callout}(${DISTRIBUTION_CALLOUTS[callout].join(',')})`) === calldata.slice(0, 10));
+        if (!name) {
+            throw new Error(`Unknown distribution callout in deal ${dealId}: ${calldata.slice(0, 10)}`);
+        }
+        const params = this.web3.eth.abi.decodeParameters(DISTRIBUTION_CALLOUTS[name], `0x${calldata.slice(10)}`);
+        if (!toBN(params[0]).eq(toBN(dealId))) {
+            throw new Error(`Distribution calldata of deal ${dealId} pays deal ${params[0]}`);
+        }
+        const types = DISTRIBUTION_CALLOUTS[name];
+        const recipients = params[types.indexOf('address[]', name === 'distributeAssets' ? 3 : 1)];
+        const amounts = params[types.indexOf('uint256[]')];
+        if (recipients.length !== amounts.length || recipients.some((recipient) => toBN(recipient).isZero())) {
+            throw new Error(`Invalid recipients in the distribution calldata of deal ${dealId}`);
+        }
+        const total = amounts.reduce((sum, amount) => sum.add(toBN(amount)), toBN(0));
+        const balance = toBN(await this.web3.eth.getBalance(this.contract.options.address));
+        if (name !== 'distributeAssets' && total.gt(balance)) {
+            throw new Error(`Distribution of deal ${dealId} pays ${total} out of a balance of ${balance}`);
+        }
+        debug('Confirming the', name, 'callout of deal', dealId, 'paying', total.toString(), 'to', recipients.length, 'outputs');
+        await this.scClient.confirmDistributionAsync(dealId, keccak256(calldata), opts);
+    }
+
+    /**
+     * Wait for the challenge window between the roots commitment and the distribution
+     * @returns {Promise<void>}
+     */
+    async waitChallengeWindowAsync() {
+        const windowInBlocks = parseInt(await this.contract.methods.challengeWindowInBlocks().call());
+        const startBlockNumber = await this.web3.eth.getBlockNumber();
+        debug('Waiting for the challenge window to elapse');
+        await this.waitBlockAsync(startBlockNumber + windowInBlocks);
+    }
+
     /**
      * Verify the deposits on Enigma similarity to `executeDeal` but without transferring funds
      * @param {string} amount
@@ -206,6 +473,33 @@ class DealManager {
         debug('The verify deposit task', task);
     }
 
+    /**
+     * Preview the deal of the deposits on Enigma before creating it on Ethereum, reporting the duplicate
+     * deposits, the deposits of another amount, the deposits screened out and the size of the deal inputs
+     * @param {string} amount
+     * @param {Array<Deposit>} deposits
+     * @param {Object} taskRecordOpts
+     * @returns {Promise<string>} - The encoded preview report
+     */
+    async previewDealAsync(amount, deposits, taskRecordOpts) {
+        const task = await this.scClient.previewDealAsync(amount, deposits, taskRecordOpts);
+        debug('The deal preview task', task);
+        return task.decryptedOutput;
+    }
+
+    /**
+     * Pay out the next batch of the withdrawals queued in the enclave ledger, the priority fees of the batch
+     * being paid to the operator account
+     * @param {number} maxWithdrawals - The maximum number of withdrawals paid out
+     * @param {Object} taskRecordOpts
+     * @returns {Promise<number>} - The number of withdrawals paid out
+     */
+    async processWithdrawalsAsync(maxWithdrawals, taskRecordOpts) {
+        const task = await this.scClient.processWithdrawalsAsync(this.web3.eth.defaultAccount, maxWithdrawals, taskRecordOpts);
+        debug('The process withdrawals task', task);
+        return parseInt(task.decryptedOutput, 16);
+    }
+
     /**
      * Resetting the last mix block number regardless of task status
      * All deposits received after current block will be included in the next Deal
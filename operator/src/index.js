@@ -74,8 +74,8 @@ async function startServer(web3, enigmaUrl, contractAddr, scAddr, threshold) {
                     ws.send(JSON.stringify(configAction));
                     break;
                 case SUBMIT_DEPOSIT_METADATA:
-                    const {sender, amount, pubKey, encRecipient, signature} = payload;
-                    const submitDepositMetadataAction = await api.submitDepositMetadataAsync(sender, amount, pubKey, encRecipient, signature);
+                    const {sender, amount, pubKey, encRecipient, expiresAt, signature} = payload;
+                    const submitDepositMetadataAction = await api.submitDepositMetadataAsync(sender, amount, pubKey, encRecipient, expiresAt, signature);
                     ws.send(JSON.stringify(submitDepositMetadataAction));
                     break;
                 case FETCH_FILLABLE_DEPOSITS:
@@ -1,6 +1,9 @@
 const {Enigma, eeConstants, utils} = require('enigma-js/node');
 const debug = require('debug')('operator:secret-contract');
 
+// Reference of the operator bond slashed on misbehavior, none by default
+const OPERATOR_BOND = process.env.OPERATOR_BOND || `0x${'0'.repeat(64)}`;
+
 // https://gist.github.com/valentinkostadinov/5875467
 function fromHex(h) {
     let s = '';
@@ -119,30 +122,102 @@ class SecretContractClient {
         const encRecipients = [];
         const senders = [];
         const signatures = [];
+        const expiries = [];
         for (const deposit of deposits) {
             pubKeys.push(`0x${deposit.pubKey}`);
             encRecipients.push(`0x${deposit.encRecipient}`);
             senders.push(deposit.sender);
             signatures.push(deposit.signature);
+            expiries.push(deposit.expiresAt);
         }
-        return {pubKeys, encRecipients, senders, signatures};
+        return {pubKeys, encRecipients, senders, signatures, expiries};
     }
 
-    async executeDealAsync(amount, deposits, nonce, chainId, opts) {
-        const {pubKeys, encRecipients, senders, signatures} = this._prepareDepositsParams(deposits);
+    /**
+     * Schedule the deal, as its operator
+     * @param {string} dealId
+     * @param {number} window - The execution window of the deal in blocks
+     * @param {number} confirmations - The confirmation depth of the attested block
+     * @param {Object} blockAttestation - The oracle-signed `attestation` of the current block and its `signature`
+     * @param {string} amount - The deposit amount of the deal in WEI
+     * @param {Array<Deposit>} deposits - The deposits of the deal, in order
+     * @param {string} nonce - The operator nonce of the deal
+     * @param {string} scheduleSignature - The signature of the schedule by the operator key
+     * @param {Object} opts
+     */
+    async scheduleDealAsync(dealId, window, confirmations, blockAttestation, amount, deposits, nonce, scheduleSignature, opts) {
+        const {senders} = this._prepareDepositsParams(deposits);
+        const operatorAddress = this.getOperatorAccount();
+        debug('Calling `schedule_deal(bytes32,uint256,uint256,bytes,bytes,uint256,address[],address,uint256,bytes)`', dealId, window, confirmations);
+        const taskFn = 'schedule_deal(bytes32,uint256,uint256,bytes,bytes,uint256,address[],address,uint256,bytes)';
+        const taskArgs = [
+            [dealId, 'bytes32'],
+            [window, 'uint256'],
+            [confirmations, 'uint256'],
+            [blockAttestation.attestation, 'bytes'],
+            [blockAttestation.signature, 'bytes'],
+            [amount, 'uint256'],
+            [senders, 'address[]'],
+            [operatorAddress, 'address'],
+            [nonce, 'uint256'],
+            [scheduleSignature, 'bytes'],
+        ];
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, opts.taskGasLimit, opts.taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got schedule deal task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async invalidateRelayedFactAsync(factId, proof, opts) {
+        debug('Calling `invalidate_relayed_fact(bytes32,bytes)`', factId);
+        const taskFn = 'invalidate_relayed_fact(bytes32,bytes)';
+        const taskArgs = [
+            [factId, 'bytes32'],
+            [proof, 'bytes'],
+        ];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got invalidate relayed fact task', task.taskId);
+        return task;
+    }
+
+    /**
+     * Execute the deal
+     * @param {string} amount
+     * @param {Array<Deposit>} deposits
+     * @param {string} nonce
+     * @param {number} chainId
+     * @param {number} features - The bitfield of the deal features negotiated
+     * @param {number} nbRedeemed - The number of staged deposits joining the deal, as recorded on-chain
+     * @param {Object} blockAttestation - The oracle-signed `attestation` of the deposit blocks and its `signature`
+     * @param {string} operatorSignature - The signature of the deal payload by the operator key
+     * @param {Object} opts
+     */
+    async executeDealAsync(amount, deposits, nonce, chainId, features, nbRedeemed, blockAttestation, operatorSignature, opts) {
+        const {pubKeys, encRecipients, senders, signatures, expiries} = this._prepareDepositsParams(deposits);
         const operatorAddress = this.getOperatorAccount();
         debug('Calling `execute_deal(address,uint256,uint256,bytes[],bytes[],address[],bytes[])`',
             operatorAddress, amount, pubKeys, encRecipients, senders, signatures);
-        const taskFn = 'execute_deal(address,uint256,uint256,uint256,bytes[],bytes[],address[],bytes[])';
+        const taskFn = 'execute_deal(address,uint256,bytes32,uint256,bytes[],bytes[],address[],bytes[],uint256[],uint256,uint256,uint256,bytes,bytes,bytes,bytes)';
         const taskArgs = [
             [operatorAddress, 'address'],
             [nonce, 'uint256'],
+            [OPERATOR_BOND, 'bytes32'],
             [amount, 'uint256'],
             [pubKeys, 'bytes[]'],
             [encRecipients, 'bytes[]'],
             [senders, 'address[]'],
             [signatures, 'bytes[]'],
+            [expiries, 'uint256[]'],
             [chainId, 'uint256'],
+            [features, 'uint256'],
+            [nbRedeemed, 'uint256'],
+            // No aggregated deposits, every deposit carries its own signature
+            ['0x', 'bytes'],
+            [blockAttestation.attestation, 'bytes'],
+            [blockAttestation.signature, 'bytes'],
+            [operatorSignature, 'bytes'],
         ];
         const {taskGasLimit, taskGasPx} = opts;
         const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
@@ -156,18 +231,232 @@ class SecretContractClient {
         return task;
     }
 
+    async executeVoucherDealAsync(amount, vouchers, nonce, opts) {
+        const operatorAddress = this.getOperatorAccount();
+        debug('Calling `execute_voucher_deal(address,uint256,bytes32,uint256,bytes32[],bytes32[],bytes[],bytes[])`',
+            operatorAddress, amount, vouchers);
+        const taskFn = 'execute_voucher_deal(address,uint256,bytes32,uint256,bytes32[],bytes32[],bytes[],bytes[])';
+        const taskArgs = [
+            [operatorAddress, 'address'],
+            [nonce, 'uint256'],
+            [OPERATOR_BOND, 'bytes32'],
+            [amount, 'uint256'],
+            [vouchers.map((voucher) => voucher.voucherId), 'bytes32[]'],
+            [vouchers.map((voucher) => voucher.commitment), 'bytes32[]'],
+            [vouchers.map((voucher) => voucher.pubKey), 'bytes[]'],
+            [vouchers.map((voucher) => voucher.encRecipient), 'bytes[]'],
+        ];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got execute voucher deal task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async continueDealAsync(dealId, opts) {
+        debug('Calling `continue_deal(bytes32)`', dealId);
+        const taskFn = 'continue_deal(bytes32)';
+        const taskArgs = [
+            [dealId, 'bytes32'],
+        ];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got continue deal task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async finalizeDistributionAsync(dealId, opts) {
+        debug('Calling `finalize_distribution(bytes32)`', dealId);
+        const taskFn = 'finalize_distribution(bytes32)';
+        const taskArgs = [
+            [dealId, 'bytes32'],
+        ];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got finalize distribution task', task.taskId);
+        return task;
+    }
+
+    /**
+     * Preview the calldata of the distribution callout of a committed deal, encrypted to the operator
+     * @param {string} dealId
+     * @param {string} operatorPubKey - The 64-byte public key (hex) the calldata is encrypted to
+     * @param {string} signature - The signature of the preview request by the operator
+     * @param {Object} opts
+     */
+    async previewDistributionAsync(dealId, operatorPubKey, signature, opts) {
+        debug('Calling `preview_distribution(bytes32,bytes,bytes)`', dealId, operatorPubKey);
+        const taskFn = 'preview_distribution(bytes32,bytes,bytes)';
+        const taskArgs = [
+            [dealId, 'bytes32'],
+            [operatorPubKey, 'bytes'],
+            [signature, 'bytes'],
+        ];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got preview distribution task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    /**
+     * Distribute a previewed deal, confirming the keccak256 of its previewed calldata
+     * @param {string} dealId
+     * @param {string} calldataHash
+     * @param {Object} opts
+     */
+    async confirmDistributionAsync(dealId, calldataHash, opts) {
+        debug('Calling `confirm_distribution(bytes32,bytes32)`', dealId, calldataHash);
+        const taskFn = 'confirm_distribution(bytes32,bytes32)';
+        const taskArgs = [
+            [dealId, 'bytes32'],
+            [calldataHash, 'bytes32'],
+        ];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got confirm distribution task', task.taskId);
+        return task;
+    }
+
+    /**
+     * The plaintext encryption pub key of the secret contract
+     * @param {Object} opts
+     * @returns {Promise<string>}
+     */
+    async getPlaintextPubKeyAsync(opts) {
+        const {workerPubKey, userPrivateKey, encryptedOutput} = await this.getPubKeyDataAsync(opts);
+        const output = utils.decryptMessage(utils.getDerivedKey(workerPubKey, userPrivateKey), encryptedOutput);
+        // The pub key follows the offset and length words of its ABI encoding
+        return output.slice(128);
+    }
+
+    async respondChallengeAsync(dealId, depositDigest, opts) {
+        debug('Calling `respond_challenge(bytes32,bytes32)`', dealId, depositDigest);
+        const taskFn = 'respond_challenge(bytes32,bytes32)';
+        const taskArgs = [
+            [dealId, 'bytes32'],
+            [depositDigest, 'bytes32'],
+        ];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got respond challenge task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async getOpenChallengesAsync(dealId, opts) {
+        debug('Calling `get_open_challenges(bytes32)`', dealId);
+        const taskFn = 'get_open_challenges(bytes32)';
+        const taskArgs = [
+            [dealId, 'bytes32'],
+        ];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got open challenges task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async retryDistributionAsync(dealId, opts) {
+        debug('Calling `retry_distribution(bytes32)`', dealId);
+        const taskFn = 'retry_distribution(bytes32)';
+        const taskArgs = [
+            [dealId, 'bytes32'],
+        ];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got retry distribution task', task.taskId);
+        return task;
+    }
+
+    /**
+     * Vote for the cancellation of a planned deal, as one of its senders
+     * @param {string} amount - The deposit amount of the deal in WEI
+     * @param {Array<string>} senders - The senders of the deal, in order
+     * @param {string} operatorAddress - The operator planning the deal
+     * @param {string} nonce - The operator nonce of the deal
+     * @param {string} signature - The cancel vote signature of the sender
+     * @param {Object} opts
+     */
+    async voteCancelDealAsync(amount, senders, operatorAddress, nonce, signature, opts) {
+        debug('Calling `vote_cancel_deal(uint256,address[],address,uint256,bytes)`', amount, senders, operatorAddress, nonce);
+        const taskFn = 'vote_cancel_deal(uint256,address[],address,uint256,bytes)';
+        const taskArgs = [
+            [amount, 'uint256'],
+            [senders, 'address[]'],
+            [operatorAddress, 'address'],
+            [nonce, 'uint256'],
+            [signature, 'bytes'],
+        ];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got vote cancel deal task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    /**
+     * Refund the deposits of a deal left unexecuted past its execution window, as any keeper
+     * @param {string} dealId
+     * @param {string} currentBlock - The current block, past the execution window of the deal
+     * @param {string} blockSignature - The oracle signature of the attestation of the current block, of no deposits
+     * @param {string} amount - The deposit amount of the deal in WEI
+     * @param {Array<string>} senders - The senders of the deal, in order
+     * @param {string} operatorAddress - The operator planning the deal
+     * @param {string} nonce - The operator nonce of the deal
+     * @param {Object} opts
+     */
+    async triggerTimeoutAsync(dealId, currentBlock, blockSignature, amount, senders, operatorAddress, nonce, opts) {
+        debug('Calling `trigger_timeout(bytes32,uint256,bytes,uint256,address[],address,uint256)`', dealId, currentBlock);
+        const taskFn = 'trigger_timeout(bytes32,uint256,bytes,uint256,address[],address,uint256)';
+        const taskArgs = [
+            [dealId, 'bytes32'],
+            [currentBlock, 'uint256'],
+            [blockSignature, 'bytes'],
+            [amount, 'uint256'],
+            [senders, 'address[]'],
+            [operatorAddress, 'address'],
+            [nonce, 'uint256'],
+        ];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got trigger timeout task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async anchorReceiptAsync(dealId, opts) {
+        debug('Calling `anchor_receipt(bytes32)`', dealId);
+        const taskFn = 'anchor_receipt(bytes32)';
+        const taskArgs = [
+            [dealId, 'bytes32'],
+        ];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got anchor receipt task', task.taskId);
+        return task;
+    }
+
     async verifyDepositsAsync(amount, deposits, chainId, opts) {
-        const {pubKeys, encRecipients, senders, signatures} = this._prepareDepositsParams(deposits);
+        const {pubKeys, encRecipients, senders, signatures, expiries} = this._prepareDepositsParams(deposits);
         debug('Calling `verify_deposits(uint256,bytes[],bytes[],address[],bytes[])`',
             amount, pubKeys, encRecipients, senders, signatures);
-        const taskFn = 'verify_deposits(uint256,uint256,bytes[],bytes[],address[],bytes[])';
+        const taskFn = 'verify_deposits(uint256,bytes[],bytes[],address[],bytes[],uint256[],uint256,bytes)';
         const taskArgs = [
             [amount, 'uint256'],
             [pubKeys, 'bytes[]'],
             [encRecipients, 'bytes[]'],
             [senders, 'address[]'],
             [signatures, 'bytes[]'],
+            [expiries, 'uint256[]'],
             [chainId, 'uint256'],
+            // No aggregated deposits, every deposit carries its own signature
+            ['0x', 'bytes'],
         ];
         const {taskGasLimit, taskGasPx} = opts;
         const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
@@ -179,6 +468,541 @@ class SecretContractClient {
         return task;
     }
 
+    /**
+     * Preview the deal of the deposits before committing it on-chain
+     * @param {string} amount - The deal amount in wei
+     * @param {Array<Deposit>} deposits
+     * @param {Object} opts
+     */
+    async previewDealAsync(amount, deposits, opts) {
+        const {pubKeys, encRecipients, senders, signatures, expiries} = this._prepareDepositsParams(deposits);
+        const amounts = deposits.map((deposit) => deposit.amount);
+        debug('Calling `preview_deal(uint256,uint256[],bytes[],bytes[],address[],bytes[],uint256[])`', amount, amounts, senders);
+        const taskFn = 'preview_deal(uint256,uint256[],bytes[],bytes[],address[],bytes[],uint256[])';
+        const taskArgs = [
+            [amount, 'uint256'],
+            [amounts, 'uint256[]'],
+            [pubKeys, 'bytes[]'],
+            [encRecipients, 'bytes[]'],
+            [senders, 'address[]'],
+            [signatures, 'bytes[]'],
+            [expiries, 'uint256[]'],
+        ];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got deal preview task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async getLimitsAsync(opts) {
+        debug('Calling `get_limits()`');
+        const taskFn = 'get_limits()';
+        const taskArgs = [];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got limits task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    /**
+     * Fetch the share of a threshold peer in the deposit payloads, encrypted to the executing instance
+     * @param {string} peerScAddr - The secret contract address of the peer instance
+     * @param {string} executorPubKey - The encryption public key of this instance
+     * @param {Array<Deposit>} deposits
+     * @param {Object} opts
+     */
+    async partialDecryptAsync(peerScAddr, executorPubKey, deposits, opts) {
+        const {pubKeys, encRecipients} = this._prepareDepositsParams(deposits);
+        debug('Calling `partial_decrypt(bytes,bytes[],bytes[])` on peer', peerScAddr);
+        const taskFn = 'partial_decrypt(bytes,bytes[],bytes[])';
+        const taskArgs = [
+            [executorPubKey, 'bytes'],
+            [pubKeys, 'bytes[]'],
+            [encRecipients, 'bytes[]'],
+        ];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, peerScAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got partial decryption task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async submitPartialsAsync(peerIndex, deposits, partials, opts) {
+        const {encRecipients} = this._prepareDepositsParams(deposits);
+        debug('Calling `submit_partials(uint256,bytes[],bytes[])`', peerIndex);
+        const taskFn = 'submit_partials(uint256,bytes[],bytes[])';
+        const taskArgs = [
+            [peerIndex, 'uint256'],
+            [encRecipients, 'bytes[]'],
+            [partials, 'bytes[]'],
+        ];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got submit partials task', task.taskId);
+        return task;
+    }
+
+    async getStagedSummaryAsync(opts) {
+        debug('Calling `get_staged_summary()`');
+        const taskFn = 'get_staged_summary()';
+        const taskArgs = [];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got staged summary task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async getRedeemableAsync(amount, nbSenders, opts) {
+        debug('Calling `get_redeemable(uint256,uint256)`', amount, nbSenders);
+        const taskFn = 'get_redeemable(uint256,uint256)';
+        const taskArgs = [
+            [amount, 'uint256'],
+            [nbSenders, 'uint256'],
+        ];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got redeemable task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async selfTestAsync(opts) {
+        debug('Calling `self_test()`');
+        const taskFn = 'self_test()';
+        const taskArgs = [];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got self-test task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async getBuildInfoAsync(opts) {
+        debug('Calling `get_build_info()`');
+        const taskFn = 'get_build_info()';
+        const taskArgs = [];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got build info task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async getConfigAsync(opts) {
+        debug('Calling `get_config()`');
+        const taskFn = 'get_config()';
+        const taskArgs = [];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got config task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async getSupportedFeaturesAsync(opts) {
+        debug('Calling `get_supported_features()`');
+        const taskFn = 'get_supported_features()';
+        const taskArgs = [];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got supported features task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async getNetworkProfileAsync(opts) {
+        debug('Calling `get_network_profile()`');
+        const taskFn = 'get_network_profile()';
+        const taskArgs = [];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got network profile task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async getConfigHistoryAsync(n, opts) {
+        debug('Calling `get_config_history(uint256)`', n);
+        const taskFn = 'get_config_history(uint256)';
+        const taskArgs = [
+            [n, 'uint256'],
+        ];
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, opts.taskGasLimit, opts.taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got config history task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async getTokenConfigAsync(token, opts) {
+        debug('Calling `get_token_config(address)`', token);
+        const taskFn = 'get_token_config(address)';
+        const taskArgs = [
+            [token, 'address'],
+        ];
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, opts.taskGasLimit, opts.taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got token config task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async getTokensAsync(opts) {
+        debug('Calling `get_tokens()`');
+        const taskFn = 'get_tokens()';
+        const taskArgs = [];
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, opts.taskGasLimit, opts.taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got tokens task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async getSchemaAsync(opts) {
+        debug('Calling `get_schema()`');
+        const taskFn = 'get_schema()';
+        const taskArgs = [];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got schema task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    /**
+     * Propose handing the deal payloads of the operator off to a new key
+     * @param {string} operator - The operator address
+     * @param {string} newSignerPubKey - The 64-byte public key (hex) of the new key
+     * @param {string} signature - The signature of the handoff by the current key
+     * @param {Object} opts
+     */
+    async proposeOperatorAsync(operator, newSignerPubKey, signature, opts) {
+        debug('Calling `propose_operator(address,bytes,bytes)`', operator, newSignerPubKey);
+        const taskFn = 'propose_operator(address,bytes,bytes)';
+        const taskArgs = [
+            [operator, 'address'],
+            [newSignerPubKey, 'bytes'],
+            [signature, 'bytes'],
+        ];
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, opts.taskGasLimit, opts.taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got propose operator task', task.taskId);
+        return task;
+    }
+
+    /**
+     * Accept the handoff pending for the operator
+     * @param {string} operator - The operator address
+     * @param {string} signature - The signature of the handoff by the new key
+     * @param {Object} opts
+     */
+    async acceptOperatorAsync(operator, signature, opts) {
+        debug('Calling `accept_operator(address,bytes)`', operator);
+        const taskFn = 'accept_operator(address,bytes)';
+        const taskArgs = [
+            [operator, 'address'],
+            [signature, 'bytes'],
+        ];
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, opts.taskGasLimit, opts.taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got accept operator task', task.taskId);
+        return task;
+    }
+
+    async getOperatorHandoffAsync(operator, opts) {
+        debug('Calling `get_operator_handoff(address)`', operator);
+        const taskFn = 'get_operator_handoff(address)';
+        const taskArgs = [
+            [operator, 'address'],
+        ];
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, opts.taskGasLimit, opts.taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got operator handoff task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    /**
+     * Export the state delta of the standby instance
+     * @param {number} since - The revision last imported by the standby instance
+     * @param {Object} opts
+     */
+    async exportStandbyDeltaAsync(since, opts) {
+        debug('Calling `export_standby_delta(uint256)`', since);
+        const taskFn = 'export_standby_delta(uint256)';
+        const taskArgs = [
+            [since, 'uint256'],
+        ];
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, opts.taskGasLimit, opts.taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got standby delta task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    /**
+     * Import a state delta on the standby instance of this client
+     * @param {string} primaryPubKey - The encryption public key of the primary instance
+     * @param {string} delta - The delta exported by the primary instance
+     * @param {Object} opts
+     */
+    async importStandbyDeltaAsync(primaryPubKey, delta, opts) {
+        debug('Calling `import_standby_delta(bytes,bytes)`', primaryPubKey);
+        const taskFn = 'import_standby_delta(bytes,bytes)';
+        const taskArgs = [
+            [primaryPubKey, 'bytes'],
+            [delta, 'bytes'],
+        ];
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, opts.taskGasLimit, opts.taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got import standby delta task', task.taskId);
+        return task;
+    }
+
+    /**
+     * Withdraw notes held in the enclave ledger
+     * @param {string} owner - The owner of the notes
+     * @param {string} payout - The address paid out
+     * @param {string} amount - The amount withdrawn in WEI
+     * @param {string} nonce - The nonce of the withdrawal chosen by the owner
+     * @param {string} signature - The withdrawal signature of the owner
+     * @param {Object} opts
+     */
+    async withdrawNotesAsync(owner, payout, amount, nonce, signature, opts) {
+        debug('Calling `withdraw_notes(address,address,uint256,uint256,bytes)`', owner, payout, amount);
+        const taskFn = 'withdraw_notes(address,address,uint256,uint256,bytes)';
+        const taskArgs = [
+            [owner, 'address'],
+            [payout, 'address'],
+            [amount, 'uint256'],
+            [nonce, 'uint256'],
+            [signature, 'bytes'],
+        ];
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, opts.taskGasLimit, opts.taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got withdraw notes task', task.taskId);
+        return task;
+    }
+
+    /**
+     * Queue a withdrawal of notes held in the enclave ledger for a later payout batch
+     * @param {string} owner - The owner of the notes
+     * @param {string} payout - The address paid out
+     * @param {string} amount - The amount withdrawn in WEI
+     * @param {string} fee - The priority fee in WEI paid on top of the amount
+     * @param {string} nonce - The nonce of the withdrawal chosen by the owner
+     * @param {string} signature - The queued withdrawal signature of the owner
+     * @param {Object} opts
+     */
+    async queueWithdrawalAsync(owner, payout, amount, fee, nonce, signature, opts) {
+        debug('Calling `queue_withdrawal(address,address,uint256,uint256,uint256,bytes)`', owner, payout, amount, fee);
+        const taskFn = 'queue_withdrawal(address,address,uint256,uint256,uint256,bytes)';
+        const taskArgs = [
+            [owner, 'address'],
+            [payout, 'address'],
+            [amount, 'uint256'],
+            [fee, 'uint256'],
+            [nonce, 'uint256'],
+            [signature, 'bytes'],
+        ];
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, opts.taskGasLimit, opts.taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got queue withdrawal task', task.taskId);
+        return task;
+    }
+
+    /**
+     * Pay out the next batch of queued withdrawals
+     * @param {string} operatorAddress - The operator address paid the priority fees
+     * @param {number} maxWithdrawals - The maximum number of withdrawals paid out
+     * @param {Object} opts
+     */
+    async processWithdrawalsAsync(operatorAddress, maxWithdrawals, opts) {
+        debug('Calling `process_withdrawals(address,uint256)`', operatorAddress, maxWithdrawals);
+        const taskFn = 'process_withdrawals(address,uint256)';
+        const taskArgs = [
+            [operatorAddress, 'address'],
+            [maxWithdrawals, 'uint256'],
+        ];
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, opts.taskGasLimit, opts.taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got process withdrawals task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    /**
+     * Prune the deal records and notifications older than the retention policy allows
+     * @param {number} now - The epoch pruned at, at most the number of deals executed
+     * @param {Object} opts
+     */
+    async pruneAsync(now, opts) {
+        debug('Calling `prune(uint256)`', now);
+        const taskFn = 'prune(uint256)';
+        const taskArgs = [
+            [now, 'uint256'],
+        ];
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, opts.taskGasLimit, opts.taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got prune task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    /**
+     * Fetch the recipient notifications stored for a notification pubKey
+     * @param {string} pointer - The keccak256 hash of the notification pubKey
+     * @param {Object} opts
+     */
+    async fetchNotificationsAsync(pointer, opts) {
+        debug('Calling `fetch_notifications(bytes32)`', pointer);
+        const taskFn = 'fetch_notifications(bytes32)';
+        const taskArgs = [
+            [pointer, 'bytes32'],
+        ];
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, opts.taskGasLimit, opts.taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got notifications task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    /**
+     * Get the claim codes of the notes issued in a deal
+     * @param {string} dealId - The deal id
+     * @param {Object} opts
+     */
+    async getClaimCodesAsync(dealId, opts) {
+        debug('Calling `get_claim_codes(bytes32)`', dealId);
+        const taskFn = 'get_claim_codes(bytes32)';
+        const taskArgs = [
+            [dealId, 'bytes32'],
+        ];
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, opts.taskGasLimit, opts.taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got claim codes task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    /**
+     * Get the fee invoice of a deposit of a deal, encrypted to the pubKey of the deposit
+     * @param {string} dealId - The deal id
+     * @param {number} index - The index of the deposit in the deal
+     * @param {Object} opts
+     */
+    async getFeeInvoiceAsync(dealId, index, opts) {
+        debug('Calling `get_fee_invoice(bytes32,uint256)`', dealId, index);
+        const taskFn = 'get_fee_invoice(bytes32,uint256)';
+        const taskArgs = [
+            [dealId, 'bytes32'],
+            [index, 'uint256'],
+        ];
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, opts.taskGasLimit, opts.taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got fee invoice task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    /**
+     * Claim a note to a payout address
+     * @param {string} claimSecret - The claim secret of the note
+     * @param {string} payout - The address paid out
+     * @param {string} signature - The claim signature of the payout address
+     * @param {Object} opts
+     */
+    async claimAsync(claimSecret, payout, signature, opts) {
+        debug('Calling `claim(bytes,address,bytes)`', payout);
+        const taskFn = 'claim(bytes,address,bytes)';
+        const taskArgs = [
+            [claimSecret, 'bytes'],
+            [payout, 'address'],
+            [signature, 'bytes'],
+        ];
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, opts.taskGasLimit, opts.taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got claim task', task.taskId);
+        return task;
+    }
+
+    async isInitializedAsync(opts) {
+        debug('Calling `is_initialized()`');
+        const taskFn = 'is_initialized()';
+        const taskArgs = [];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got initialization task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async getMetricsAsync(opts) {
+        debug('Calling `get_metrics()`');
+        const taskFn = 'get_metrics()';
+        const taskArgs = [];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got metrics task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async getHealthAsync(opts) {
+        debug('Calling `health()`');
+        const taskFn = 'health()';
+        const taskArgs = [];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got health task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async getAnomalyAsync(opts) {
+        debug('Calling `get_anomaly()`');
+        const taskFn = 'get_anomaly()';
+        const taskArgs = [];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got anomaly task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async getShuffleCommitmentAsync(dealId, opts) {
+        debug('Calling `get_shuffle_commitment(bytes32)`', dealId);
+        const taskFn = 'get_shuffle_commitment(bytes32)';
+        const taskArgs = [
+            [dealId, 'bytes32'],
+        ];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got shuffle commitment task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async revealShuffleSeedAsync(dealId, opts) {
+        debug('Calling `reveal_shuffle_seed(bytes32)`', dealId);
+        const taskFn = 'reveal_shuffle_seed(bytes32)';
+        const taskArgs = [
+            [dealId, 'bytes32'],
+        ];
+        const {taskGasLimit, taskGasPx} = opts;
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, taskGasLimit, taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got shuffle seed task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
+    async suggestBatchSizeAsync(avgDecryptCost, taskGasLimit, opts) {
+        debug('Calling `suggest_batch_size(uint256,uint256)`', avgDecryptCost, taskGasLimit);
+        const taskFn = 'suggest_batch_size(uint256,uint256)';
+        const taskArgs = [
+            [avgDecryptCost, 'uint256'],
+            [taskGasLimit, 'uint256'],
+        ];
+        const pendingTask = await this.submitTaskAsync(taskFn, taskArgs, opts.taskGasLimit, opts.taskGasPx, this.scAddr);
+        const task = await this.waitTaskSuccessAsync(pendingTask);
+        debug('Got suggested batch size task', task.taskId, 'with output:', task.decryptedOutput);
+        return task;
+    }
+
     async getPubKeyDataAsync(opts) {
         if (!this.pubKeyData) {
             debug('PubKey not found in cache, fetching from Enigma...');
@@ -0,0 +1,246 @@
+#!/usr/bin/env python3
+"""Generate test/fixtures/v0_deals.json, the regression corpus of v0 deal payloads.
+
+Each deposit is a v0 payload: a bare recipient address encrypted without salt to the enclave under the ECDH key
+of the depositor, and signed under message version 0 (EIP712 domain version "1", no expiry). The expected
+digests, keys and permutations are computed here independently of the enclave, so that the `v0_deals` test of
+salad-core fails if the enclave drifts from the v0 wire format.
+
+The inputs are derived from fixed labels, so that running the script again rewrites the same corpus.
+
+    python3 scripts/generate_v0_deals.py > test/fixtures/v0_deals.json
+
+Requires the `cryptography` package for AES-GCM.
+"""
+import hashlib
+import hmac
+import json
+
+from cryptography.hazmat.primitives.ciphers.aead import AESGCM
+
+# Keccak-256 (the pre-FIPS padding of Ethereum, not hashlib's sha3_256)
+ROUND_CONSTANTS = [
+    0x0000000000000001, 0x0000000000008082, 0x800000000000808A, 0x8000000080008000, 0x000000000000808B,
+    0x0000000080000001, 0x8000000080008081, 0x8000000000008009, 0x000000000000008A, 0x0000000000000088,
+    0x0000000080008009, 0x000000008000000A, 0x000000008000808B, 0x800000000000008B, 0x8000000000008089,
+    0x8000000000008003, 0x8000000000008002, 0x8000000000000080, 0x000000000000800A, 0x800000008000000A,
+    0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008,
+]
+ROTATIONS = [[0, 36, 3, 41, 18], [1, 44, 10, 45, 2], [62, 6, 43, 15, 61], [28, 55, 25, 21, 56], [27, 20, 39, 8, 14]]
+MASK = (1 << 64) - 1
+
+
+def rotate(lane, n):
+    return ((lane << n) | (lane >> (64 - n))) & MASK if n else lane
+
+
+def keccak_f(state):
+    for constant in ROUND_CONSTANTS:
+        c = [state[x][0] ^ state[x][1] ^ state[x][2] ^ state[x][3] ^ state[x][4] for x in range(5)]
+        d = [c[(x - 1) % 5] ^ rotate(c[(x + 1) % 5], 1) for x in range(5)]
+        state = [[state[x][y] ^ d[x] for y in range(5)] for x in range(5)]
+        b = [[0] * 5 for _ in range(5)]
+        for x in range(5):
+            for y in range(5):
+                b[y][(2 * x + 3 * y) % 5] = rotate(state[x][y], ROTATIONS[x][y])
+        state = [[b[x][y] ^ (~b[(x + 1) % 5][y] & b[(x + 2) % 5][y]) for y in range(5)] for x in range(5)]
+        state[0][0] ^= constant
+    return state
+
+
+def keccak256(data):
+    rate = 136
+    padded = bytearray(data) + b'\x01'
+    while len(padded) % rate:
+        padded += b'\x00'
+    padded[-1] |= 0x80
+    state = [[0] * 5 for _ in range(5)]
+    for offset in range(0, len(padded), rate):
+        for i in range(rate // 8):
+            state[i % 5][i // 5] ^= int.from_bytes(padded[offset + 8 * i:offset + 8 * i + 8], 'little')
+        state = keccak_f(state)
+    return b''.join(state[i % 5][i // 5].to_bytes(8, 'little') for i in range(4))
+
+
+# secp256k1
+P = 0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F
+N = 0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141
+G = (0x79BE667EF9DCBBAC55A06295CE870B07029BFCDB2DCE28D959F2815B16F81798,
+     0x483ADA7726A3C4655DA4FBFC0E1108A8FD17B448A68554199C47D08FFB10D4B8)
+
+
+def point_add(a, b):
+    if a is None:
+        return b
+    if b is None:
+        return a
+    if a[0] == b[0] and (a[1] + b[1]) % P == 0:
+        return None
+    if a == b:
+        slope = 3 * a[0] * a[0] * pow(2 * a[1], P - 2, P) % P
+    else:
+        slope = (b[1] - a[1]) * pow(b[0] - a[0], P - 2, P) % P
+    x = (slope * slope - a[0] - b[0]) % P
+    return x, (slope * (a[0] - x) - a[1]) % P
+
+
+def point_mul(k, point):
+    result = None
+    while k:
+        if k & 1:
+            result = point_add(result, point)
+        point = point_add(point, point)
+        k >>= 1
+    return result
+
+
+def pub_key(secret):
+    x, y = point_mul(int.from_bytes(secret, 'big'), G)
+    return x.to_bytes(32, 'big') + y.to_bytes(32, 'big')
+
+
+def parse_point(key):
+    return int.from_bytes(key[:32], 'big'), int.from_bytes(key[32:], 'big')
+
+
+def ecdh_key(secret, other_pub_key):
+    """The ECDH key of enigma-crypto: SHA256 of the compressed shared point"""
+    x, y = point_mul(int.from_bytes(secret, 'big'), parse_point(other_pub_key))
+    return hashlib.sha256(bytes([0x02 | (y & 1)]) + x.to_bytes(32, 'big')).digest()
+
+
+def nonce(secret, digest):
+    """RFC 6979 nonce for SHA-256 sized digests"""
+    v, k = b'\x01' * 32, b'\x00' * 32
+    k = hmac.new(k, v + b'\x00' + secret + digest, hashlib.sha256).digest()
+    v = hmac.new(k, v, hashlib.sha256).digest()
+    k = hmac.new(k, v + b'\x01' + secret + digest, hashlib.sha256).digest()
+    v = hmac.new(k, v, hashlib.sha256).digest()
+    while True:
+        v = hmac.new(k, v, hashlib.sha256).digest()
+        candidate = int.from_bytes(v, 'big')
+        if 0 < candidate < N:
+            return candidate
+        k = hmac.new(k, v + b'\x00', hashlib.sha256).digest()
+        v = hmac.new(k, v, hashlib.sha256).digest()
+
+
+def sign(secret, message):
+    """The r || s || v signature of keccak256(message), v being 27 or 28 and s low, as enigma-crypto signs"""
+    digest = keccak256(message)
+    k = nonce(secret, digest)
+    x, y = point_mul(k, G)
+    r = x % N
+    s = pow(k, N - 2, N) * (int.from_bytes(digest, 'big') + r * int.from_bytes(secret, 'big')) % N
+    recovery_id = (y & 1) | (2 if x >= N else 0)
+    if s > N // 2:
+        s, recovery_id = N - s, recovery_id ^ 1
+    return r.to_bytes(32, 'big') + s.to_bytes(32, 'big') + bytes([27 + recovery_id])
+
+
+def recover(message, signature):
+    digest = int.from_bytes(keccak256(message), 'big')
+    r, s, recovery_id = int.from_bytes(signature[:32], 'big'), int.from_bytes(signature[32:64], 'big'), signature[64] - 27
+    x = r + (N if recovery_id & 2 else 0)
+    y = pow((x * x * x + 7) % P, (P + 1) // 4, P)
+    if y & 1 != recovery_id & 1:
+        y = P - y
+    r_inv = pow(r, N - 2, N)
+    point = point_add(point_mul(s * r_inv % N, (x, y)), point_mul((N - digest) * r_inv % N, G))
+    return point[0].to_bytes(32, 'big') + point[1].to_bytes(32, 'big')
+
+
+def address(key):
+    return keccak256(key)[12:]
+
+
+def word(value):
+    return value.to_bytes(32, 'big')
+
+
+def u64(value):
+    return value.to_bytes(8, 'big')
+
+
+def deposit_message(sender, amount, enc_recipient, user_pub_key, chain_id):
+    """The EIP712 typed data of a deposit under message version 0"""
+    domain = (keccak256(b'EIP712Domain(string name,string version,uint256 chainId)') + keccak256(b'Salad Deposit')
+              + keccak256(b'1') + word(chain_id))
+    type_hash = keccak256(b'Deposit(address sender,uint256 amount,bytes encRecipient,bytes pubKey)')
+    deposit = type_hash + bytes(12) + sender + word(amount) + keccak256(enc_recipient) + keccak256(user_pub_key)
+    return b'\x19\x01' + keccak256(domain) + keccak256(deposit)
+
+
+def deal_id(amount, senders, operator, operator_nonce):
+    message = u64(32) + word(amount) + u64(len(senders))
+    for sender in senders:
+        message += u64(20) + sender
+    return keccak256(message + u64(20) + operator + u64(32) + word(operator_nonce))
+
+
+def permutation(seed, size):
+    """Fisher-Yates shuffle of RandomShuffler, reading the low 32 bits of the seed as the wasm32 enclave does"""
+    indices = list(range(size))
+    for i in reversed(range(size)):
+        j = (seed & 0xffffffff) % (i + 1)
+        indices[i], indices[j] = indices[j], indices[i]
+    return indices
+
+
+def label(*parts):
+    return keccak256(' '.join(['salad v0 fixture'] + [str(part) for part in parts]).encode())
+
+
+DEALS = [
+    # (amount, chain id, operator nonce, shuffle seed, number of deposits)
+    (10 ** 18, 1, 0, 0, 2),
+    (10 ** 16, 4447, 7, 0x0123456789abcdef, 5),
+    (5 * 10 ** 17, 1, 42, 0xfffffffffffffffe, 8),
+]
+
+
+def generate():
+    enclave_secret = label('enclave')
+    enclave_pub_key = pub_key(enclave_secret)
+    operator = address(pub_key(label('operator')))
+    deals = []
+    for d, (amount, chain_id, operator_nonce, seed, nb_deposits) in enumerate(DEALS):
+        deposits = []
+        for i in range(nb_deposits):
+            user_secret = label('deal', d, 'user', i)
+            user_pub_key = pub_key(user_secret)
+            sender = address(user_pub_key)
+            recipient = label('deal', d, 'recipient', i)[12:]
+            key = ecdh_key(user_secret, enclave_pub_key)
+            assert key == ecdh_key(enclave_secret, user_pub_key)
+            iv = label('deal', d, 'iv', i)[:12]
+            enc_recipient = AESGCM(key).encrypt(iv, recipient, None) + iv
+            message = deposit_message(sender, amount, enc_recipient, user_pub_key, chain_id)
+            signature = sign(user_secret, message)
+            assert recover(message, signature) == user_pub_key
+            deposits.append({
+                'userSecret': user_secret.hex(),
+                'pubKey': user_pub_key.hex(),
+                'sender': sender.hex(),
+                'recipient': recipient.hex(),
+                'key': key.hex(),
+                'encRecipient': enc_recipient.hex(),
+                'signature': signature.hex(),
+                'digest': keccak256(message).hex(),
+            })
+        senders = [bytes.fromhex(deposit['sender']) for deposit in deposits]
+        deals.append({
+            'amount': str(amount),
+            'chainId': chain_id,
+            'operator': operator.hex(),
+            'operatorNonce': str(operator_nonce),
+            'dealId': deal_id(amount, senders, operator, operator_nonce).hex(),
+            'seed': str(seed),
+            'deposits': deposits,
+            'permutation': permutation(seed, nb_deposits),
+        })
+    return {'enclaveSecret': enclave_secret.hex(), 'enclavePubKey': enclave_pub_key.hex(), 'deals': deals}
+
+
+if __name__ == '__main__':
+    print(json.dumps(generate(), indent=4))
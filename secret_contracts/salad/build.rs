@@ -0,0 +1,202 @@
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
+use std::process::Command;
+
+/// The ABI of the Mixer callouts the `eth_contract` binding is generated from
+const MIXER_ABI: &str = "ISalad.json";
+/// The Solidity interface the Mixer contract implements
+const MIXER_INTERFACE: &str = "../../smart_contracts/ISalad.sol";
+
+fn main() {
+    embed_git_commit();
+    check_mixer_abi();
+}
+
+/// Embed the git commit of the source tree the contract is built from, taken from SALAD_GIT_COMMIT when set,
+/// as for the builds from a copy of the sources without their git directory
+fn embed_git_commit() {
+    println!("cargo:rerun-if-env-changed=SALAD_GIT_COMMIT");
+    println!("cargo:rerun-if-changed=../../.git/HEAD");
+    println!("cargo:rerun-if-changed=../../.git/index");
+    let commit = env::var("SALAD_GIT_COMMIT").ok().or_else(|| {
+        Command::new("git")
+            .args(&["describe", "--always", "--dirty", "--abbrev=40", "--match=NONE"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+            .map(|commit| commit.trim().to_string())
+    });
+    println!("cargo:rustc-env=SALAD_GIT_COMMIT={}", commit.unwrap_or_else(|| String::from("unknown")));
+}
+
+/// Refuse to build when the callouts of the ABI drift from the functions of the Solidity interface,
+/// a callout missing from the Mixer contract otherwise failing silently once deployed. Builds from a
+/// copy of the secret contract alone, without the Solidity sources, skip the check.
+fn check_mixer_abi() {
+    println!("cargo:rerun-if-changed={}", MIXER_ABI);
+    println!("cargo:rerun-if-changed={}", MIXER_INTERFACE);
+    let abi = fs::read_to_string(MIXER_ABI).unwrap_or_else(|err| panic!("Unable to read {}: {}", MIXER_ABI, err));
+    let interface = match fs::read_to_string(MIXER_INTERFACE) {
+        Ok(interface) => interface,
+        Err(_) => {
+            println!("cargo:warning=Mixer ABI not checked, {} not found", MIXER_INTERFACE);
+            return;
+        }
+    };
+    let callouts = abi_signatures(&abi);
+    let functions = interface_signatures(&interface);
+    let missing: Vec<&String> = callouts.difference(&functions).collect();
+    let unbound: Vec<&String> = functions.difference(&callouts).collect();
+    if !missing.is_empty() || !unbound.is_empty() {
+        panic!("{} drifted from {}: callouts missing from the interface {:?}, functions missing from the ABI {:?}",
+               MIXER_ABI, MIXER_INTERFACE, missing, unbound);
+    }
+}
+
+/// The canonical signature `name(type,...)` of the functions of a JSON ABI
+fn abi_signatures(abi: &str) -> BTreeSet<String> {
+    let mut parser = Json { input: abi.as_bytes(), pos: 0 };
+    let entries = match parser.value() {
+        Value::Array(entries) => entries,
+        _ => panic!("{} is not an array of ABI entries", MIXER_ABI),
+    };
+    entries.iter()
+        .filter(|entry| entry.get("type").and_then(Value::as_str) == Some("function"))
+        .map(|entry| {
+            let name = entry.get("name").and_then(Value::as_str).unwrap_or_else(|| panic!("Unnamed function in {}", MIXER_ABI));
+            let types: Vec<String> = match entry.get("inputs") {
+                Some(Value::Array(inputs)) => inputs.iter()
+                    .map(|input| canonical_type(input.get("type").and_then(Value::as_str).unwrap_or_else(|| panic!("Untyped input of {}", name))))
+                    .collect(),
+                _ => Vec::new(),
+            };
+            format!("{}({})", name, types.join(","))
+        })
+        .collect()
+}
+
+/// The canonical signature `name(type,...)` of the functions declared by a Solidity interface
+fn interface_signatures(interface: &str) -> BTreeSet<String> {
+    interface.split("function ").skip(1).map(|declaration| {
+        let open = declaration.find('(').unwrap_or_else(|| panic!("Invalid declaration: {}", declaration));
+        let close = declaration.find(')').unwrap_or_else(|| panic!("Invalid declaration: {}", declaration));
+        let types: Vec<String> = declaration[open + 1..close].split(',')
+            .map(|param| param.replace(" payable", ""))
+            .filter_map(|param| param.split_whitespace().next().map(canonical_type))
+            .collect();
+        format!("{}({})", declaration[..open].trim(), types.join(","))
+    }).collect()
+}
+
+/// The canonical form of an ABI type, `uint` and `int` standing for their 256-bit forms
+fn canonical_type(ty: &str) -> String {
+    let (base, suffix) = ty.split_at(ty.find('[').unwrap_or(ty.len()));
+    match base {
+        "uint" => format!("uint256{}", suffix),
+        "int" => format!("int256{}", suffix),
+        _ => ty.to_string(),
+    }
+}
+
+/// The JSON values of an ABI, the literals (numbers, booleans, null) being skipped
+enum Value {
+    Literal,
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// A minimal JSON parser, the build script having no dependencies
+struct Json<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Json<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.pos < self.input.len() && self.input[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) {
+        self.skip_whitespace();
+        if self.input.get(self.pos) != Some(&byte) {
+            panic!("Invalid JSON in {} at {}: expected '{}'", MIXER_ABI, self.pos, byte as char);
+        }
+        self.pos += 1;
+    }
+
+    fn peek(&mut self) -> u8 {
+        self.skip_whitespace();
+        *self.input.get(self.pos).unwrap_or_else(|| panic!("Unexpected end of {}", MIXER_ABI))
+    }
+
+    fn value(&mut self) -> Value {
+        match self.peek() {
+            b'{' => {
+                self.expect(b'{');
+                let mut members = Vec::new();
+                while self.peek() != b'}' {
+                    if !members.is_empty() {
+                        self.expect(b',');
+                    }
+                    let name = self.string();
+                    self.expect(b':');
+                    members.push((name, self.value()));
+                }
+                self.expect(b'}');
+                Value::Object(members)
+            }
+            b'[' => {
+                self.expect(b'[');
+                let mut items = Vec::new();
+                while self.peek() != b']' {
+                    if !items.is_empty() {
+                        self.expect(b',');
+                    }
+                    items.push(self.value());
+                }
+                self.expect(b']');
+                Value::Array(items)
+            }
+            b'"' => Value::String(self.string()),
+            _ => {
+                while self.pos < self.input.len() && !b",]} \t\r\n".contains(&self.input[self.pos]) {
+                    self.pos += 1;
+                }
+                Value::Literal
+            }
+        }
+    }
+
+    /// A string, the escapes being kept as written since the ABI names and types have none
+    fn string(&mut self) -> String {
+        self.expect(b'"');
+        let start = self.pos;
+        while self.pos < self.input.len() && self.input[self.pos] != b'"' {
+            self.pos += if self.input[self.pos] == b'\\' { 2 } else { 1 };
+        }
+        let value = String::from_utf8_lossy(&self.input[start..self.pos]).into_owned();
+        self.expect(b'"');
+        value
+    }
+}
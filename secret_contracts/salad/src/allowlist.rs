@@ -0,0 +1,92 @@
+use eng_wasm::{String, Vec, H160, H256};
+use enigma_crypto::hash::Keccak256;
+
+use crate::compliance::{ComplianceEngine, RecipientsFn};
+use crate::protocol;
+use crate::runtime::Runtime;
+use crate::state::Hash;
+
+// State key name "recipient_allowlist_root" holding the Merkle root of the allowed recipients, if any
+static RECIPIENT_ALLOWLIST_ROOT: &str = "recipient_allowlist_root";
+
+/// Max depth of the allowlist inclusion proofs
+const MAX_PROOF_DEPTH: usize = 20;
+
+/// Set the recipient allowlist of the deployment, a zero root leaving the pool open to any recipient
+pub fn set_root<R: Runtime>(runtime: &R, root: &H256) {
+    if !root.is_zero() {
+        runtime.write_state(RECIPIENT_ALLOWLIST_ROOT, Hash(*root));
+    }
+}
+
+pub fn get_root<R: Runtime>(runtime: &R) -> Option<H256> {
+    let root: Option<Hash> = runtime.read_state(RECIPIENT_ALLOWLIST_ROOT);
+    root.map(|root| root.0)
+}
+
+fn hash(data: &[u8]) -> H256 {
+    H256::from(&data.keccak256()[..])
+}
+
+/// Verify the inclusion of the recipient in a tree of H(address) leaves, hashing each pair of nodes in sorted order
+fn verify(root: &H256, recipient: &H160, proof: &[H256]) -> bool {
+    let mut node = hash(recipient);
+    for sibling in proof.iter() {
+        let mut pair: Vec<u8> = Vec::new();
+        if node <= *sibling {
+            pair.extend_from_slice(&node);
+            pair.extend_from_slice(sibling);
+        } else {
+            pair.extend_from_slice(sibling);
+            pair.extend_from_slice(&node);
+        }
+        node = hash(&pair);
+    }
+    node == *root
+}
+
+/// Check the recipients of an allowlist envelope against the root, returning the recipients payload.
+/// The envelope holds the payload size (1 byte), the payload, then the inclusion proof of each
+/// payload recipient as its number of nodes (1 byte) followed by the nodes.
+fn check_envelope(i: usize, root: &H256, envelope: &[u8], recipients: RecipientsFn) -> Result<Vec<u8>, String> {
+    let truncated = || format!("Truncated allowlist envelope for participant {}", i);
+    let payload_size = *envelope.first().ok_or_else(truncated)? as usize;
+    if envelope.len() < 1 + payload_size {
+        return Err(truncated());
+    }
+    let payload = &envelope[1..1 + payload_size];
+    let mut offset = 1 + payload_size;
+    for recipient in recipients(payload)?.iter() {
+        let nb_nodes = *envelope.get(offset).ok_or_else(truncated)? as usize;
+        offset += 1;
+        if nb_nodes > MAX_PROOF_DEPTH || envelope.len() < offset + nb_nodes * 32 {
+            return Err(truncated());
+        }
+        let proof: Vec<H256> = envelope[offset..offset + nb_nodes * 32].chunks(32).map(H256::from).collect();
+        offset += nb_nodes * 32;
+        if !verify(root, recipient, &proof) {
+            return Err(format!("Recipient of participant {} is not allowlisted: {:?}", i, recipient));
+        }
+    }
+    if offset != envelope.len() {
+        return Err(format!("Trailing bytes in allowlist envelope for participant {}", i));
+    }
+    trace!("Allowlisted recipients of participant {}", i);
+    Ok(payload.to_vec())
+}
+
+/// Only pays the recipients included in the allowlist of the deployment
+pub struct RecipientAllowlist {
+    pub root: H256,
+}
+
+impl<R: Runtime> ComplianceEngine<R> for RecipientAllowlist {
+    /// The payload size and an inclusion proof of max depth for each recipient
+    fn payload_overhead(&self) -> usize {
+        1 + protocol::envelope().max_splits * (1 + MAX_PROOF_DEPTH * 32)
+    }
+
+    fn open_envelope(&self, i: usize, envelope: Vec<u8>, recipients: RecipientsFn) -> Result<Vec<u8>, String> {
+        check_envelope(i, &self.root, &envelope, recipients)
+    }
+}
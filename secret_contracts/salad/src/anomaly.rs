@@ -0,0 +1,146 @@
+use eng_wasm::Vec;
+use serde::{Deserialize, Serialize};
+
+use crate::codec::Encoder;
+use crate::metrics;
+use crate::runtime::Runtime;
+
+// State key name "anomaly_breaker" holding the anomaly the circuit breaker tripped on, if any
+static ANOMALY_BREAKER: &str = "anomaly_breaker";
+// State key name "anomaly_history" holding the recent deal rate and deposits screened for anomalies
+static ANOMALY_HISTORY: &str = "anomaly_history";
+
+/// Anomalies tripping the circuit breaker
+pub const ANOMALY_DEAL_SPIKE: u8 = 0;
+pub const ANOMALY_REPEATED_RECIPIENTS: u8 = 1;
+pub const ANOMALY_REJECT_RATIO: u8 = 2;
+
+/// Blocks of each window the deal rate is measured over
+const RATE_WINDOW_BLOCKS: u64 = 240;
+/// A window with more deals than this factor of the average rate, and than MIN_SPIKE_DEALS, is a spike
+const SPIKE_FACTOR: u64 = 4;
+const MIN_SPIKE_DEALS: u64 = 8;
+/// Basis points of the outputs of a deal paying recipients of the recent deals, from MIN_SCREENED_OUTPUTS outputs
+const MAX_REPEATED_BPS: u64 = 5000;
+const MIN_SCREENED_OUTPUTS: u64 = 4;
+/// Basis points of the deposits rejected over the last REJECT_WINDOW_DEALS deals, from MIN_SCREENED_DEPOSITS deposits
+const MAX_REJECT_BPS: u64 = 2000;
+const MIN_SCREENED_DEPOSITS: u64 = 16;
+const REJECT_WINDOW_DEALS: usize = 10;
+
+/// An anomaly the circuit breaker tripped on, along with the epoch (number of deals executed) it tripped at
+#[derive(Serialize, Deserialize)]
+struct Anomaly {
+    kind: u8,
+    epoch: u64,
+    observed: u64,
+    threshold: u64,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct History {
+    /// The rate window of the last deal with an attested block, and the deals executed in it
+    window: u64,
+    window_deals: u64,
+    /// Moving average of the deals per window, scaled by SPIKE_FACTOR
+    average: u64,
+    /// The (deposits, rejected) of each of the last REJECT_WINDOW_DEALS deals
+    deposits: Vec<(u64, u64)>,
+}
+
+fn get_history<R: Runtime>(runtime: &R) -> History {
+    runtime.read_state(ANOMALY_HISTORY).unwrap_or_default()
+}
+
+fn load<R: Runtime>(runtime: &R) -> Option<Anomaly> {
+    runtime.read_state::<Option<Anomaly>>(ANOMALY_BREAKER).unwrap_or_default()
+}
+
+fn trip<R: Runtime>(runtime: &R, kind: u8, observed: u64, threshold: u64) {
+    if load(runtime).is_some() {
+        return;
+    }
+    let epoch = metrics::nb_deals(runtime);
+    trace!("Circuit breaker tripped on anomaly {} at epoch {}: {} > {}", kind, epoch, observed, threshold);
+    runtime.write_state(ANOMALY_BREAKER, Some(Anomaly { kind, epoch, observed, threshold }));
+}
+
+/// Refuse to execute new deals while the circuit breaker is tripped, the deals already executed still
+/// being distributed, refunded and anchored
+pub fn check<R: Runtime>(runtime: &R) {
+    if let Some(anomaly) = load(runtime) {
+        panic!("Circuit breaker tripped on anomaly {} at epoch {}, awaiting acknowledgment", anomaly.kind, anomaly.epoch);
+    }
+}
+
+/// Screen an executed deal for a spike in the deals per window of blocks, for the deals with an attested block,
+/// and for outputs repeatedly paying the recipients of the recent deals
+pub fn screen_deal<R: Runtime>(runtime: &R, attested_block: u64, nb_deposits: usize, nb_outputs: usize, nb_repeated: u64) {
+    let mut history = get_history(runtime);
+    if attested_block != 0 {
+        let window = attested_block / RATE_WINDOW_BLOCKS;
+        if window != history.window {
+            history.average = (history.average * 3 + history.window_deals * SPIKE_FACTOR) / 4;
+            history.window = window;
+            history.window_deals = 0;
+        }
+        history.window_deals += 1;
+        let threshold = history.average.max(MIN_SPIKE_DEALS);
+        if history.window_deals > threshold {
+            trip(runtime, ANOMALY_DEAL_SPIKE, history.window_deals, threshold);
+        }
+    }
+    let nb_outputs = nb_outputs as u64;
+    if nb_outputs >= MIN_SCREENED_OUTPUTS && nb_repeated * 10_000 > nb_outputs * MAX_REPEATED_BPS {
+        trip(runtime, ANOMALY_REPEATED_RECIPIENTS, nb_repeated * 10_000 / nb_outputs, MAX_REPEATED_BPS);
+    }
+    record_deposits(runtime, &mut history, nb_deposits as u64, 0);
+    runtime.write_state(ANOMALY_HISTORY, history);
+}
+
+/// Screen a failed deal for an abnormal ratio of rejected deposits over the recent deals
+pub fn screen_rejects<R: Runtime>(runtime: &R, nb_deposits: usize, nb_rejected: usize) {
+    let mut history = get_history(runtime);
+    record_deposits(runtime, &mut history, nb_deposits as u64, nb_rejected as u64);
+    runtime.write_state(ANOMALY_HISTORY, history);
+}
+
+fn record_deposits<R: Runtime>(runtime: &R, history: &mut History, nb_deposits: u64, nb_rejected: u64) {
+    history.deposits.push((nb_deposits, nb_rejected));
+    let overflow = history.deposits.len().saturating_sub(REJECT_WINDOW_DEALS);
+    history.deposits.drain(..overflow);
+    let deposits: u64 = history.deposits.iter().map(|(deposits, _)| deposits).sum();
+    let rejected: u64 = history.deposits.iter().map(|(_, rejected)| rejected).sum();
+    if deposits >= MIN_SCREENED_DEPOSITS && rejected * 10_000 > deposits * MAX_REJECT_BPS {
+        trip(runtime, ANOMALY_REJECT_RATIO, rejected * 10_000 / deposits, MAX_REJECT_BPS);
+    }
+}
+
+/// Reset the circuit breaker tripped at the epoch, refusing to acknowledge an anomaly other than the one tripped
+pub fn acknowledge<R: Runtime>(runtime: &R, epoch: u64) {
+    let anomaly = match load(runtime) {
+        Some(anomaly) => anomaly,
+        None => panic!("No anomaly to acknowledge"),
+    };
+    if anomaly.epoch != epoch {
+        panic!("Acknowledging the anomaly of epoch {} while tripped at epoch {}", epoch, anomaly.epoch);
+    }
+    trace!("Acknowledging anomaly {} at epoch {}", anomaly.kind, anomaly.epoch);
+    runtime.write_state::<Option<Anomaly>>(ANOMALY_BREAKER, None);
+}
+
+/// The anomaly the circuit breaker is tripped on, canonically encoded as the container (kind: uint8, epoch: uint64,
+/// observed: uint64, threshold: uint64), the ratios being in basis points, empty when not tripped
+pub fn export<R: Runtime>(runtime: &R) -> Vec<u8> {
+    match load(runtime) {
+        Some(anomaly) => {
+            let mut encoder = Encoder::default();
+            encoder.append_u8(anomaly.kind);
+            encoder.append_u64(anomaly.epoch);
+            encoder.append_u64(anomaly.observed);
+            encoder.append_u64(anomaly.threshold);
+            encoder.finish()
+        }
+        None => Vec::new(),
+    }
+}
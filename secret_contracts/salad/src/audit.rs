@@ -0,0 +1,117 @@
+use eng_wasm::{String, Vec, H256, U256};
+use enigma_crypto::KeyPair;
+use rustc_hex::ToHex;
+use serde::{Deserialize, Serialize};
+
+use crate::codec::Encoder;
+use crate::protocol::{ENCRYPTION_OVERHEAD, PUB_KEY_SIZE};
+use crate::runtime::Runtime;
+
+// State key name "auditors" holding the auditors registered at deployment
+static AUDITORS: &str = "auditors";
+// State key prefix of the audit envelopes of each deal, followed by the hex DealId
+static AUDIT_PREFIX: &str = "audit_";
+
+/// Auditor roles: operator auditors read the deal receipts, regulators also read the deal records
+/// linking the deposits to their outputs
+pub const ROLE_OPERATOR_AUDITOR: u8 = 0;
+pub const ROLE_REGULATOR: u8 = 1;
+
+/// Scopes of the audit envelopes, in their order in the state of each deal
+pub const SCOPE_RECEIPTS: u8 = 0;
+pub const SCOPE_DEAL_RECORDS: u8 = 1;
+
+/// The content key encrypted to an auditor
+const WRAPPED_KEY_SIZE: usize = 32 + ENCRYPTION_OVERHEAD;
+
+#[derive(Serialize, Deserialize)]
+struct Auditor {
+    pub_key: Vec<u8>,
+    role: u8,
+}
+
+impl Auditor {
+    fn reads(&self, scope: u8) -> bool {
+        match self.role {
+            ROLE_REGULATOR => true,
+            _ => scope == SCOPE_RECEIPTS,
+        }
+    }
+
+    fn shared_key(&self, keypair: &KeyPair) -> Result<[u8; 32], String> {
+        let mut pub_key = [0_u8; PUB_KEY_SIZE];
+        pub_key.copy_from_slice(&self.pub_key);
+        keypair.derive_key(&pub_key).map_err(|err| format!("Invalid auditor public key: {:?}", err))
+    }
+}
+
+/// Register the auditors of the deployment with their roles, none leaving the deals unaudited
+pub fn register<R: Runtime>(runtime: &R, pub_keys: &[Vec<u8>], roles: &[U256], keypair: &KeyPair) {
+    if pub_keys.len() != roles.len() {
+        panic!("Auditor public keys and roles mismatch: {} keys, {} roles", pub_keys.len(), roles.len());
+    }
+    if pub_keys.len() > u8::max_value() as usize {
+        panic!("Too many auditors: {}", pub_keys.len());
+    }
+    let mut auditors: Vec<Auditor> = Vec::new();
+    for (pub_key, role) in pub_keys.iter().zip(roles.iter()) {
+        if pub_key.len() != PUB_KEY_SIZE {
+            panic!("Wrong auditor public key size: {}", pub_key.len());
+        }
+        if *role > U256::from(ROLE_REGULATOR) {
+            panic!("Unknown auditor role: {:?}", role);
+        }
+        let auditor = Auditor { pub_key: pub_key.clone(), role: role.low_u32() as u8 };
+        auditor.shared_key(keypair).unwrap();
+        auditors.push(auditor);
+    }
+    runtime.write_state(AUDITORS, auditors);
+}
+
+fn audit_key(deal_id: &H256) -> String {
+    format!("{}{}", AUDIT_PREFIX, deal_id.to_hex::<String>())
+}
+
+/// Multi-recipient envelope of the payload, encrypted once under a random content key wrapped for each
+/// auditor reading the scope with the key shared between the enclave and the auditor, as the container
+/// (ciphertext: List[uint8], auditor_indices: List[uint8], wrapped_keys: List[Bytes60])
+fn seal<R: Runtime>(runtime: &R, auditors: &[Auditor], scope: u8, payload: &[u8], keypair: &KeyPair) -> Vec<u8> {
+    let content_key = runtime.generate_key();
+    let mut indices: Vec<u8> = Vec::new();
+    let mut wrapped_keys: Vec<Vec<u8>> = Vec::new();
+    for (i, auditor) in auditors.iter().enumerate().filter(|(_, auditor)| auditor.reads(scope)) {
+        let wrapped_key = runtime.encrypt(&content_key, &auditor.shared_key(keypair).unwrap());
+        assert_eq!(wrapped_key.len(), WRAPPED_KEY_SIZE);
+        indices.push(i as u8);
+        wrapped_keys.push(wrapped_key);
+    }
+    let mut encoder = Encoder::default();
+    encoder.append_list(&runtime.encrypt(payload, &content_key), |byte| vec![*byte]);
+    encoder.append_list(&indices, |index| vec![*index]);
+    encoder.append_list(&wrapped_keys, |wrapped_key| wrapped_key.clone());
+    encoder.finish()
+}
+
+/// The roles of the registered auditors, in registration order
+pub fn roles<R: Runtime>(runtime: &R) -> Vec<u8> {
+    let auditors: Vec<Auditor> = runtime.read_state(AUDITORS).unwrap_or_default();
+    auditors.iter().map(|auditor| auditor.role).collect()
+}
+
+/// Seal the receipt and the record of the deal to the registered auditors, one envelope per scope
+pub fn record<R: Runtime>(runtime: &R, deal_id: &H256, receipt: &[u8], deal_record: &[u8], keypair: &KeyPair) {
+    let auditors: Vec<Auditor> = runtime.read_state(AUDITORS).unwrap_or_default();
+    if auditors.is_empty() {
+        return;
+    }
+    trace!("Sealing the audit envelopes of deal {:?} to {} auditors", deal_id, auditors.len());
+    let envelopes: Vec<Vec<u8>> = vec![
+        seal(runtime, &auditors, SCOPE_RECEIPTS, receipt, keypair),
+        seal(runtime, &auditors, SCOPE_DEAL_RECORDS, deal_record, keypair),
+    ];
+    runtime.write_state(&audit_key(deal_id), envelopes);
+}
+
+pub fn load<R: Runtime>(runtime: &R, deal_id: &H256) -> Vec<Vec<u8>> {
+    runtime.read_state(&audit_key(deal_id)).unwrap_or_default()
+}
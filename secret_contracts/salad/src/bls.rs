@@ -0,0 +1,85 @@
+use bls12_381::{pairing, G1Affine, G1Projective, G2Affine, Gt, Scalar};
+use eng_wasm::Vec;
+use enigma_crypto::hash::Keccak256;
+
+use crate::domains::BLS_HASH_DST;
+use crate::protocol::{BLS_PUB_KEY_SIZE, BLS_SIG_SIZE};
+/// The G1 cofactor 0x396c8c005555e1568c00aaab0000aaab as little-endian limbs
+const G1_COFACTOR: [u64; 4] = [0x8c00_aaab_0000_aaab, 0x396c_8c00_5555_e156, 0, 0];
+
+/// Hash a message to G1 by try-and-increment over keccak256 candidate x-coordinates,
+/// then clear the cofactor to land in the prime order subgroup
+fn hash_to_g1(message: &[u8]) -> G1Affine {
+    for counter in 0..=255_u8 {
+        let mut seed: Vec<u8> = Vec::new();
+        seed.extend_from_slice(BLS_HASH_DST);
+        seed.extend_from_slice(message);
+        seed.push(counter);
+        let mut candidate = [0_u8; BLS_SIG_SIZE];
+        let mut high = seed.clone();
+        high.push(0);
+        let mut low = seed;
+        low.push(1);
+        candidate[0..32].copy_from_slice(high.keccak256().as_ref());
+        candidate[32..48].copy_from_slice(&low.keccak256()[0..16]);
+        // Compressed point flag, keeping the hashed sign bit and clearing the infinity flag
+        candidate[0] = (candidate[0] & 0x3f) | 0x80;
+        let point: Option<G1Affine> = G1Affine::from_compressed_unchecked(&candidate).into();
+        if let Some(point) = point {
+            let point = G1Affine::from(G1Projective::from(point) * Scalar::from_raw(G1_COFACTOR));
+            if !bool::from(point.is_identity()) {
+                return point;
+            }
+        }
+    }
+    panic!("Unable to hash message to G1");
+}
+
+fn parse_pub_key(pub_key: &[u8]) -> Option<G2Affine> {
+    if pub_key.len() != BLS_PUB_KEY_SIZE {
+        return None;
+    }
+    let mut raw = [0_u8; BLS_PUB_KEY_SIZE];
+    raw.copy_from_slice(pub_key);
+    let pub_key: Option<G2Affine> = G2Affine::from_compressed(&raw).into();
+    pub_key.filter(|pub_key| !bool::from(pub_key.is_identity()))
+}
+
+fn parse_signature(signature: &[u8]) -> Option<G1Affine> {
+    if signature.len() != BLS_SIG_SIZE {
+        return None;
+    }
+    let mut raw = [0_u8; BLS_SIG_SIZE];
+    raw.copy_from_slice(signature);
+    G1Affine::from_compressed(&raw).into()
+}
+
+/// Verify a BLS signature: e(signature, g2) == e(H(message), pub_key)
+pub fn verify(pub_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    match (parse_pub_key(pub_key), parse_signature(signature)) {
+        (Some(pub_key), Some(signature)) => {
+            pairing(&signature, &G2Affine::generator()) == pairing(&hash_to_g1(message), &pub_key)
+        }
+        _ => false,
+    }
+}
+
+/// Verify an aggregate BLS signature over distinct messages: e(aggregate, g2) == Σ e(H(message_i), pub_key_i).
+/// Messages embed the depositor address, so they are distinct by construction.
+pub fn verify_aggregate(pub_keys: &[Vec<u8>], messages: &[Vec<u8>], aggregate_signature: &[u8]) -> bool {
+    if pub_keys.len() != messages.len() {
+        return false;
+    }
+    let aggregate_signature = match parse_signature(aggregate_signature) {
+        Some(aggregate_signature) => aggregate_signature,
+        None => return false,
+    };
+    let mut expected = Gt::identity();
+    for (pub_key, message) in pub_keys.iter().zip(messages.iter()) {
+        match parse_pub_key(pub_key) {
+            Some(pub_key) => expected = expected + pairing(&hash_to_g1(message), &pub_key),
+            None => return false,
+        }
+    }
+    pairing(&aggregate_signature, &G2Affine::generator()) == expected
+}
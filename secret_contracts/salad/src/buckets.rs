@@ -0,0 +1,64 @@
+use eng_wasm::{Vec, H160, U256};
+
+use crate::amounts;
+use crate::runtime::Runtime;
+use crate::state::Word;
+
+// State key name "bucket_unit" holding the unit of the amount buckets of the outputs
+static BUCKET_UNIT: &str = "bucket_unit";
+
+/// Multiples of each power of ten of the unit making the buckets: 1, 2, 5, 10, 20, 50... units
+static BUCKET_FACTORS: &[u64] = &[5, 2, 1];
+
+/// Set the unit of the amount buckets, zero leaving the outputs unrounded
+pub fn set_unit<R: Runtime>(runtime: &R, unit: &U256) {
+    runtime.write_state(BUCKET_UNIT, Word(*unit));
+}
+
+/// The unit of the amount buckets of the deployment, zero when disabled
+pub fn unit<R: Runtime>(runtime: &R) -> U256 {
+    let unit: Option<Word> = runtime.read_state(BUCKET_UNIT);
+    unit.map_or_else(U256::zero, |unit| unit.0)
+}
+
+/// The largest bucket not above the amount, zero for amounts below the unit
+pub fn bucket(amount: &U256, unit: &U256) -> U256 {
+    if unit.is_zero() || amount < unit {
+        return U256::zero();
+    }
+    let mut decade = *unit;
+    loop {
+        match decade.overflowing_mul(U256::from(10)) {
+            (next, false) if next <= *amount => decade = next,
+            _ => break,
+        }
+    }
+    BUCKET_FACTORS.iter()
+        .filter_map(|factor| match decade.overflowing_mul(U256::from(*factor)) {
+            (bucket, false) if bucket <= *amount => Some(bucket),
+            _ => None,
+        })
+        .next()
+        .unwrap_or(decade)
+}
+
+/// Round the outputs of a deposit down to their bucket, the remainder of each output being held as a note
+/// of its recipient in the enclave ledger, so that the amounts paid on-chain are the same few buckets
+/// across participants. Outputs below the unit are held as notes altogether.
+pub fn round<R: Runtime>(runtime: &R, outputs: &mut Vec<(H160, U256)>, notes: &mut Vec<(H160, U256)>) {
+    let unit = unit(runtime);
+    if unit.is_zero() {
+        return;
+    }
+    let mut rounded: Vec<(H160, U256)> = Vec::new();
+    for (recipient, amount) in outputs.iter() {
+        let bucket = bucket(amount, &unit);
+        if !bucket.is_zero() {
+            rounded.push((*recipient, bucket));
+        }
+        if bucket != *amount {
+            notes.push((*recipient, amounts::sub(amount, &bucket)));
+        }
+    }
+    *outputs = rounded;
+}
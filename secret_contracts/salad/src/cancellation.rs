@@ -0,0 +1,67 @@
+use eng_wasm::{String, Vec, H160, H256, U256};
+use rustc_hex::ToHex;
+
+use crate::digest;
+use crate::domains::{self, CANCEL_DOMAIN};
+use crate::protocol;
+use crate::runtime::Runtime;
+use crate::state::Address;
+
+// State key name "cancel_quorum" holding the percentage of the senders of a deal whose votes cancel it
+static CANCEL_QUORUM: &str = "cancel_quorum";
+// State key prefix of the cancel votes of a deal, followed by the hex DealId
+static CANCEL_VOTES_PREFIX: &str = "cancel_votes_";
+
+/// Set the percentage of the senders of a deal whose votes cancel it, zero disabling the votes
+pub fn set_quorum<R: Runtime>(runtime: &R, quorum: &U256) {
+    if *quorum > U256::from(100) {
+        panic!("Invalid cancel quorum: {}", quorum);
+    }
+    runtime.write_state(CANCEL_QUORUM, quorum.low_u32() as u8);
+}
+
+/// The cancel quorum of the deployment, deployments predating the votes having none
+pub fn quorum<R: Runtime>(runtime: &R) -> u8 {
+    runtime.read_state(CANCEL_QUORUM).unwrap_or(0)
+}
+
+fn votes_key(deal_id: &H256) -> String {
+    format!("{}{}", CANCEL_VOTES_PREFIX, deal_id.to_hex::<String>())
+}
+
+fn get_votes<R: Runtime>(runtime: &R, deal_id: &H256) -> Vec<Address> {
+    runtime.read_state(&votes_key(deal_id)).unwrap_or_default()
+}
+
+/// The EIP191 personal message of H(CANCEL_DOMAIN || DealId), signed by a sender of the deal
+fn cancel_message(deal_id: &H256) -> Vec<u8> {
+    domains::domain_message(CANCEL_DOMAIN, deal_id)
+}
+
+/// Record the cancel vote of a sender of the deal, returning the number of votes so far.
+/// The DealId binds the senders of the deal, the signer being one of them.
+pub fn vote<R: Runtime>(runtime: &R, deal_id: &H256, senders: &[H160], raw_signature: &[u8]) -> usize {
+    if quorum(runtime) == 0 {
+        panic!("Cancel votes disabled");
+    }
+    let signature = protocol::ecdsa_signature(raw_signature)
+        .unwrap_or_else(|| panic!("Invalid cancel vote signature size: {}", raw_signature.len()));
+    let voter = digest::recover_address(&cancel_message(deal_id), signature)
+        .unwrap_or_else(|err| panic!("Invalid cancel vote signature: {}", err));
+    if voter.is_zero() || !senders.contains(&voter) {
+        panic!("Cancel vote not signed by a sender of the deal: {:?}", voter);
+    }
+    let mut votes = get_votes(runtime, deal_id);
+    if !votes.iter().any(|vote| vote.0 == voter) {
+        trace!("Recording the cancel vote of {:?} for deal {:?}", voter, deal_id);
+        votes.push(Address(voter));
+        runtime.write_state(&votes_key(deal_id), &votes);
+    }
+    votes.len()
+}
+
+/// Whether the votes of the senders of the deal reached the cancel quorum
+pub fn cancelled<R: Runtime>(runtime: &R, deal_id: &H256, nb_senders: usize) -> bool {
+    let quorum = quorum(runtime) as usize;
+    quorum != 0 && get_votes(runtime, deal_id).len() * 100 >= quorum * nb_senders
+}
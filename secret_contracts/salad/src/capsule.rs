@@ -0,0 +1,151 @@
+use eng_wasm::{Vec, H160, U256};
+
+use crate::convert;
+use crate::features;
+use crate::protocol::{self, CAPSULE_MAGIC};
+
+/// The full input of a deal, as submitted by the operator to `execute_deal`
+pub struct DealCapsule {
+    pub operator_address: H160,
+    pub operator_nonce: U256,
+    pub amount: U256,
+    pub pub_keys: Vec<Vec<u8>>,
+    pub enc_recipients: Vec<Vec<u8>>,
+    pub senders: Vec<H160>,
+    pub signatures: Vec<Vec<u8>>,
+    pub expiries: Vec<U256>,
+    pub chain_id: U256,
+    pub aggregate_signature: Vec<u8>,
+    pub block_attestation: Vec<u8>,
+    pub block_signature: Vec<u8>,
+    pub features: U256,
+    pub nb_redeemed: U256,
+}
+
+fn append_bytes(message: &mut Vec<u8>, value: &[u8]) {
+    message.extend_from_slice(&(value.len() as u64).to_be_bytes());
+    message.extend_from_slice(value);
+}
+
+fn append_list(message: &mut Vec<u8>, values: &[Vec<u8>]) {
+    message.extend_from_slice(&(values.len() as u64).to_be_bytes());
+    for value in values.iter() {
+        append_bytes(message, value);
+    }
+}
+
+struct Reader<'a> {
+    capsule: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> &'a [u8] {
+        if self.capsule.len() - self.offset < len {
+            panic!("Truncated deal capsule at offset {}", self.offset);
+        }
+        let value = &self.capsule[self.offset..self.offset + len];
+        self.offset += len;
+        value
+    }
+
+    fn read_len(&mut self) -> usize {
+        let mut len = [0_u8; 8];
+        len.copy_from_slice(self.take(8));
+        u64::from_be_bytes(len) as usize
+    }
+
+    fn read_bytes(&mut self) -> Vec<u8> {
+        let len = self.read_len();
+        self.take(len).to_vec()
+    }
+
+    fn read_list(&mut self) -> Vec<Vec<u8>> {
+        let len = self.read_len();
+        (0..len).map(|_| self.read_bytes()).collect()
+    }
+
+    fn read_address(&mut self) -> H160 {
+        let value = self.read_bytes();
+        if value.len() != 20 {
+            panic!("Invalid address size in deal capsule: {}", value.len());
+        }
+        H160::from(&value[..])
+    }
+
+    fn read_uint(&mut self) -> U256 {
+        let value = self.read_bytes();
+        if value.len() != 32 {
+            panic!("Invalid uint256 size in deal capsule: {}", value.len());
+        }
+        convert::word_to_uint(&value)
+    }
+}
+
+impl DealCapsule {
+    /// Canonical binary encoding: magic, version, then each field with a u64 big-endian length prefix,
+    /// the features and the number of redeemed deposits last so that the capsules predating them still decode
+    pub fn encode(&self) -> Vec<u8> {
+        let mut capsule: Vec<u8> = Vec::new();
+        capsule.extend_from_slice(CAPSULE_MAGIC);
+        capsule.push(protocol::message().capsule_version);
+        append_bytes(&mut capsule, &self.operator_address);
+        append_bytes(&mut capsule, &convert::uint_to_word(&self.operator_nonce));
+        append_bytes(&mut capsule, &convert::uint_to_word(&self.amount));
+        append_list(&mut capsule, &self.pub_keys);
+        append_list(&mut capsule, &self.enc_recipients);
+        let senders: Vec<Vec<u8>> = self.senders.iter().map(|sender| sender.to_vec()).collect();
+        append_list(&mut capsule, &senders);
+        append_list(&mut capsule, &self.signatures);
+        let expiries: Vec<Vec<u8>> = self.expiries.iter().map(|expiry| convert::uint_to_word(expiry).to_vec()).collect();
+        append_list(&mut capsule, &expiries);
+        append_bytes(&mut capsule, &convert::uint_to_word(&self.chain_id));
+        append_bytes(&mut capsule, &self.aggregate_signature);
+        append_bytes(&mut capsule, &self.block_attestation);
+        append_bytes(&mut capsule, &self.block_signature);
+        append_bytes(&mut capsule, &convert::uint_to_word(&self.features));
+        append_bytes(&mut capsule, &convert::uint_to_word(&self.nb_redeemed));
+        capsule
+    }
+
+    pub fn decode(capsule: &[u8]) -> DealCapsule {
+        let mut reader = Reader { capsule, offset: 0 };
+        if reader.take(CAPSULE_MAGIC.len()) != CAPSULE_MAGIC {
+            panic!("Invalid deal capsule magic");
+        }
+        let version = reader.take(1)[0];
+        if version != protocol::message().capsule_version {
+            panic!("Unsupported deal capsule version: {}", version);
+        }
+        let deal_capsule = DealCapsule {
+            operator_address: reader.read_address(),
+            operator_nonce: reader.read_uint(),
+            amount: reader.read_uint(),
+            pub_keys: reader.read_list(),
+            enc_recipients: reader.read_list(),
+            senders: reader.read_list().iter().map(|sender| {
+                if sender.len() != 20 {
+                    panic!("Invalid sender size in deal capsule: {}", sender.len());
+                }
+                H160::from(&sender[..])
+            }).collect(),
+            signatures: reader.read_list(),
+            expiries: reader.read_list().iter().map(|expiry| {
+                if expiry.len() != 32 {
+                    panic!("Invalid expiry size in deal capsule: {}", expiry.len());
+                }
+                convert::word_to_uint(expiry)
+            }).collect(),
+            chain_id: reader.read_uint(),
+            aggregate_signature: reader.read_bytes(),
+            block_attestation: reader.read_bytes(),
+            block_signature: reader.read_bytes(),
+            features: if reader.offset < capsule.len() { reader.read_uint() } else { U256::from(features::ALL) },
+            nb_redeemed: if reader.offset < capsule.len() { reader.read_uint() } else { U256::zero() },
+        };
+        if reader.offset != capsule.len() {
+            panic!("Trailing bytes in deal capsule: {}", capsule.len() - reader.offset);
+        }
+        deal_capsule
+    }
+}
@@ -0,0 +1,173 @@
+use eng_wasm::{String, Vec, H256};
+use enigma_crypto::KeyPair;
+use rustc_hex::ToHex;
+use serde::{Deserialize, Serialize};
+
+use crate::digest;
+use crate::evidence::{self, Misbehavior};
+use crate::domains::{self, CHALLENGE_DOMAIN};
+use crate::protocol::{PUB_KEY_SIZE, SIG_SIZE};
+use crate::deals::{self, DealStatus};
+use crate::runtime::Runtime;
+use crate::state::{Address, Hash};
+use crate::merkle;
+
+// State key prefix of the challenges, followed by the hex DealId and deposit digest
+static CHALLENGE_PREFIX: &str = "challenge_";
+// State key prefix of the digests of the deposits whose challenge is unanswered, followed by the hex DealId
+static OPEN_CHALLENGES_PREFIX: &str = "open_challenges_";
+
+const EXCLUDED: u8 = 0;
+const INCLUDED: u8 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct ChallengeRecord {
+    challenger: Address,
+    responded: bool,
+}
+
+fn challenge_key(deal_id: &H256, deposit_digest: &H256) -> String {
+    format!("{}{}_{}", CHALLENGE_PREFIX, deal_id.to_hex::<String>(), deposit_digest.to_hex::<String>())
+}
+
+fn open_challenges_key(deal_id: &H256) -> String {
+    format!("{}{}", OPEN_CHALLENGES_PREFIX, deal_id.to_hex::<String>())
+}
+
+fn get_open<R: Runtime>(runtime: &R, deal_id: &H256) -> Vec<Hash> {
+    runtime.read_state(&open_challenges_key(deal_id)).unwrap_or_default()
+}
+
+/// The digests of the deposits whose challenge in the deal is not answered yet
+pub fn open_challenges<R: Runtime>(runtime: &R, deal_id: &H256) -> Vec<H256> {
+    get_open(runtime, deal_id).iter().map(|digest| digest.0).collect()
+}
+
+/// Refuse to reveal the outputs of the deal while a challenge of its challenge period is unanswered,
+/// so that the exclusion of a deposit is proven before the distribution rather than after
+pub fn check_answered<R: Runtime>(runtime: &R, deal_id: &H256) {
+    let open = get_open(runtime, deal_id);
+    if !open.is_empty() {
+        panic!("Deal {:?} has {} unanswered challenges", deal_id, open.len());
+    }
+}
+
+/// The EIP191 personal message of H("Salad Challenge" || DealId || deposit digest) signed by the challenger
+fn challenge_message(deal_id: &H256, deposit_digest: &H256) -> Vec<u8> {
+    let mut challenge: Vec<u8> = Vec::new();
+    challenge.extend_from_slice(deal_id);
+    challenge.extend_from_slice(deposit_digest);
+    domains::domain_message(CHALLENGE_DOMAIN, &challenge)
+}
+
+fn append_proof(message: &mut Vec<u8>, proof: &[H256]) {
+    message.extend_from_slice(&(proof.len() as u64).to_be_bytes());
+    for node in proof.iter() {
+        message.extend_from_slice(node);
+    }
+}
+
+/// Open a challenge on the inclusion of the deposit with the specified digest,
+/// recording the challenger recovered from the `proof` signature
+pub fn open<R: Runtime>(runtime: &R, deal_id: &H256, deposit_digest: &H256, proof: &[u8]) {
+    let deal = match deals::load(runtime, deal_id) {
+        Some(ref record) if record.status == DealStatus::Failed => panic!("Failed deal: {:?}", deal_id),
+        // The deposits of a pruned deal are no longer known, they would all read as excluded
+        Some(ref record) if record.status == DealStatus::Pruned => panic!("Pruned deal: {:?}", deal_id),
+        Some(record) => record,
+        None => panic!("Unknown deal: {:?}", deal_id),
+    };
+    let key = challenge_key(deal_id, deposit_digest);
+    let existing: Option<ChallengeRecord> = runtime.read_state(&key);
+    if existing.is_some() {
+        panic!("Challenge already open for deposit: {:?}", deposit_digest);
+    }
+    if proof.len() != SIG_SIZE {
+        panic!("Invalid challenge proof size: {}", proof.len());
+    }
+    let mut signature = [0; SIG_SIZE];
+    signature.copy_from_slice(proof);
+    let challenger = digest::recover_address(&challenge_message(deal_id, deposit_digest), signature)
+        .unwrap_or_else(|err| panic!("Invalid challenge proof: {}", err));
+    // Only the depositor challenges an included deposit, so that no one holds back the distribution with a
+    // challenge the enclave cannot answer
+    if let Some(i) = deal.deposit_index(deposit_digest) {
+        if deal.sender(i) != challenger {
+            panic!("Challenger is not the depositor: {:?} != {:?}", challenger, deal.sender(i));
+        }
+    }
+    trace!("Opening challenge of {:?} for deposit {:?} in deal {:?}", challenger, deposit_digest, deal_id);
+    let record = ChallengeRecord { challenger: Address(challenger), responded: false };
+    runtime.write_state(&key, record);
+    let mut open = get_open(runtime, deal_id);
+    open.push(Hash(*deposit_digest));
+    runtime.write_state(&open_challenges_key(deal_id), open);
+}
+
+/// Respond to an open challenge with an enclave-signed statement:
+/// DealId || deposit digest || status, followed for included deposits by the deposit index, the participant
+/// Merkle proof and the output indices and Merkle proofs encrypted to the depositor, then the enclave signature.
+/// Only the depositor may learn where the outputs of an included deposit went.
+/// Excluded deposits are recorded as withheld deposit evidence against the operator bond.
+pub fn respond<R: Runtime>(runtime: &R, deal_id: &H256, deposit_digest: &H256, keypair: &KeyPair, signer: &KeyPair) -> Vec<u8> {
+    let key = challenge_key(deal_id, deposit_digest);
+    let mut challenge: ChallengeRecord = match runtime.read_state(&key) {
+        Some(challenge) => challenge,
+        None => panic!("No open challenge for deposit: {:?}", deposit_digest),
+    };
+    let record = match deals::load(runtime, deal_id) {
+        Some(record) => record,
+        None => panic!("Unknown deal: {:?}", deal_id),
+    };
+    let mut response: Vec<u8> = Vec::new();
+    response.extend_from_slice(deal_id);
+    response.extend_from_slice(deposit_digest);
+    match record.deposit_index(deposit_digest) {
+        None => {
+            response.push(EXCLUDED);
+            if !challenge.responded {
+                evidence::record(runtime, &Misbehavior {
+                    kind: evidence::WITHHELD_DEPOSIT,
+                    deal_id: *deal_id,
+                    operator: record.operator(),
+                    bond: record.bond(),
+                    handles: Vec::new(),
+                    senders: vec![challenge.challenger.0],
+                    digests: vec![*deposit_digest],
+                }, signer);
+            }
+        }
+        Some(i) => {
+            let sender = record.sender(i);
+            if sender != challenge.challenger.0 {
+                panic!("Challenger is not the depositor: {:?} != {:?}", challenge.challenger.0, sender);
+            }
+            response.push(INCLUDED);
+            response.extend_from_slice(&(i as u64).to_be_bytes());
+            append_proof(&mut response, &merkle::proof(&record.participant_leaves(), i));
+
+            let output_leaves = record.output_leaves();
+            let mut output_proofs: Vec<u8> = Vec::new();
+            for j in record.output_indices(i) {
+                output_proofs.extend_from_slice(&(j as u64).to_be_bytes());
+                output_proofs.extend_from_slice(&output_leaves[j]);
+                append_proof(&mut output_proofs, &merkle::proof(&output_leaves, j));
+            }
+            let mut user_pubkey = [0; PUB_KEY_SIZE];
+            user_pubkey.copy_from_slice(&record.pub_key(i));
+            let shared_key = keypair.derive_key(&user_pubkey).unwrap();
+            let enc_output_proofs = runtime.encrypt(&output_proofs, &shared_key);
+            response.extend_from_slice(&(enc_output_proofs.len() as u64).to_be_bytes());
+            response.extend_from_slice(&enc_output_proofs);
+        }
+    }
+    let signature = signer.sign(&response).unwrap();
+    response.extend_from_slice(&signature);
+    if !challenge.responded {
+        let open: Vec<Hash> = get_open(runtime, deal_id).into_iter().filter(|digest| digest.0 != *deposit_digest).collect();
+        runtime.write_state(&open_challenges_key(deal_id), open);
+    }
+    challenge.responded = true;
+    runtime.write_state(&key, challenge);
+    response
+}
@@ -0,0 +1,60 @@
+use eng_wasm::{String, Vec, H256};
+use rustc_hex::ToHex;
+use serde::{Deserialize, Serialize};
+
+use crate::redeposits::StagedDeposit;
+use crate::runtime::Runtime;
+use crate::state::{Address, Hash, Word};
+
+// State key prefix of the checkpoints of the deals executed across several tasks, followed by the hex DealId
+static CHECKPOINT_PREFIX: &str = "deal_checkpoint_";
+
+/// A deposit verified by a previous task of the deal, its amounts as 32-byte words
+#[derive(Serialize, Deserialize)]
+pub struct CheckpointedDeposit {
+    pub outputs: Vec<(Address, Word)>,
+    pub digest: Hash,
+    pub redeposit: Option<StagedDeposit>,
+    pub guardian: Option<Address>,
+    #[serde(default)]
+    pub notes: Vec<(Address, Word)>,
+    #[serde(default)]
+    pub notification_key: Option<Vec<u8>>,
+}
+
+/// Progress of a deal executed across several tasks: its input, as encoded in the deal capsule,
+/// and the deposits verified so far
+#[derive(Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub capsule: Vec<u8>,
+    pub operator_bond: Hash,
+    pub attested_block: u64,
+    /// Index of the next deposit to verify
+    pub next: usize,
+    pub deposits: Vec<CheckpointedDeposit>,
+    /// The (signer public key, message) of the aggregated deposits, verified once all deposits are
+    pub aggregated: Vec<(Vec<u8>, Vec<u8>)>,
+    pub rejected: Vec<usize>,
+    /// The phase costs metered by the previous tasks
+    #[serde(default)]
+    pub phase_costs: Vec<u64>,
+}
+
+fn checkpoint_key(deal_id: &H256) -> String {
+    format!("{}{}", CHECKPOINT_PREFIX, deal_id.to_hex::<String>())
+}
+
+/// The checkpoint of a deal still executing, if any
+pub fn load<R: Runtime>(runtime: &R, deal_id: &H256) -> Option<Checkpoint> {
+    let checkpoint: Option<Option<Checkpoint>> = runtime.read_state(&checkpoint_key(deal_id));
+    checkpoint.and_then(|checkpoint| checkpoint)
+}
+
+pub fn save<R: Runtime>(runtime: &R, deal_id: &H256, checkpoint: &Checkpoint) {
+    runtime.write_state(&checkpoint_key(deal_id), Some(checkpoint));
+}
+
+/// Drop the checkpoint of a deal done executing, so that it may not be resumed again
+pub fn clear<R: Runtime>(runtime: &R, deal_id: &H256) {
+    runtime.write_state(&checkpoint_key(deal_id), None::<Checkpoint>);
+}
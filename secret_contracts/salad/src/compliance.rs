@@ -0,0 +1,71 @@
+use eng_wasm::{String, Vec, H160, U256};
+
+use crate::allowlist::{self, RecipientAllowlist};
+use crate::runtime::Runtime;
+use crate::velocity;
+
+/// Recipients of a deposit payload, for the engines screening recipients
+pub type RecipientsFn<'a> = &'a dyn Fn(&[u8]) -> Result<Vec<H160>, String>;
+
+/// Screening policy of the deposits entering a deal. Each hook defaults to letting the deposit through,
+/// so that a policy only implements the hooks it screens, and deployments compose policies with `Chain`.
+pub trait ComplianceEngine<R: Runtime> {
+    /// Bytes the policy adds to the deposit payloads on top of the recipients payload
+    fn payload_overhead(&self) -> usize {
+        0
+    }
+
+    /// Screen the decrypted envelope of deposit i, returning the recipients payload it wraps
+    fn open_envelope(&self, _i: usize, envelope: Vec<u8>, _recipients: RecipientsFn) -> Result<Vec<u8>, String> {
+        Ok(envelope)
+    }
+
+    /// Screen deposit i once its sender is authenticated
+    fn screen_deposit(&self, _runtime: &R, _i: usize, _sender: &H160, _amount: &U256) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Record the deposits screened in the deal, once the deal commits
+    fn commit(&self, _runtime: &R) {}
+}
+
+/// Lets every deposit through
+pub struct PassThrough;
+
+impl<R: Runtime> ComplianceEngine<R> for PassThrough {}
+
+/// Policies applied in sequence, each envelope wrapping the envelope of the next policy
+pub struct Chain<'a, R: Runtime>(pub Vec<Box<dyn ComplianceEngine<R> + 'a>>);
+
+impl<'a, R: Runtime> ComplianceEngine<R> for Chain<'a, R> {
+    fn payload_overhead(&self) -> usize {
+        self.0.iter().map(|engine| engine.payload_overhead()).sum()
+    }
+
+    fn open_envelope(&self, i: usize, envelope: Vec<u8>, recipients: RecipientsFn) -> Result<Vec<u8>, String> {
+        self.0.iter().try_fold(envelope, |envelope, engine| engine.open_envelope(i, envelope, recipients))
+    }
+
+    fn screen_deposit(&self, runtime: &R, i: usize, sender: &H160, amount: &U256) -> Result<(), String> {
+        self.0.iter().try_for_each(|engine| engine.screen_deposit(runtime, i, sender, amount))
+    }
+
+    fn commit(&self, runtime: &R) {
+        self.0.iter().for_each(|engine| engine.commit(runtime))
+    }
+}
+
+/// The policies configured for the deployment, a deployment without any letting every deposit through
+pub fn engine<'a, R: Runtime>(runtime: &'a R) -> Box<dyn ComplianceEngine<R> + 'a> {
+    let mut engines: Vec<Box<dyn ComplianceEngine<R> + 'a>> = Vec::new();
+    if let Some(root) = allowlist::get_root(runtime) {
+        engines.push(Box::new(RecipientAllowlist { root }));
+    }
+    if let Some(limiter) = velocity::limiter(runtime) {
+        engines.push(Box::new(limiter));
+    }
+    match engines.len() {
+        0 => Box::new(PassThrough),
+        _ => Box::new(Chain(engines)),
+    }
+}
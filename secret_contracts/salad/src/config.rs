@@ -0,0 +1,118 @@
+use eng_wasm::{Vec, H160, H256};
+use serde::{Deserialize, Serialize};
+
+use crate::codec::{self, Encoder};
+use crate::runtime::Runtime;
+use crate::{allowlist, amounts, audit, buckets, cancellation, dummies, fees, kdf, metrics, migration, operators, oracle, ordering, payout, profile, protocol, relay, retention, stipend, threshold, tokens, velocity};
+
+// State key name "config_history" holding the configuration snapshots following each admin operation, in order
+static CONFIG_HISTORY: &str = "config_history";
+
+/// Admin operations changing the configuration
+pub const CHANGE_DEPLOYMENT: u8 = 0;
+pub const CHANGE_THRESHOLD: u8 = 1;
+pub const CHANGE_RELAYER: u8 = 2;
+pub const CHANGE_OPERATOR: u8 = 3;
+pub const CHANGE_STANDBY: u8 = 4;
+pub const CHANGE_PROMOTION: u8 = 5;
+pub const CHANGE_VALUE_CAP: u8 = 6;
+pub const CHANGE_TOKEN: u8 = 7;
+pub const CHANGE_MIXER: u8 = 8;
+pub const CHANGE_BRIDGE: u8 = 9;
+pub const CHANGE_RETENTION: u8 = 10;
+pub const CHANGE_ANOMALY: u8 = 11;
+
+#[derive(Serialize, Deserialize)]
+struct ConfigChange {
+    operation: u8,
+    /// The number of deals executed before the operation
+    deal_count: u64,
+    snapshot: Vec<u8>,
+}
+
+impl ConfigChange {
+    /// Canonical encoding of the change as the container (operation: uint8, deal_count: uint64, snapshot: List[uint8])
+    fn encode(&self) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        encoder.append_u8(self.operation);
+        encoder.append_u64(self.deal_count);
+        encoder.append_list(&self.snapshot, |byte| vec![*byte]);
+        encoder.finish()
+    }
+}
+
+/// The effective configuration, canonically encoded as the container (mixer: Bytes20, oracle_signers: List[Bytes20],
+/// output_ordering: uint8, allowlist_root: Bytes32, velocity_epoch_deals: uint64, velocity_max_deposits: uint64,
+/// velocity_max_volume: uint256, dust_policy: uint8, dust_threshold: uint256, min_amount: uint256, max_amount: uint256,
+/// gas_stipend: uint256, max_dummy_outputs: uint64, auditor_roles: List[uint8], nb_relayers: uint64,
+/// nb_operators: uint64, threshold: uint8, threshold_peers: uint64, envelope_version: uint8, message_version: uint8,
+/// encryption_key: List[uint8], signing_key: List[uint8], network_profile: uint8, max_deal_value: uint256,
+/// cancel_quorum: uint8, bucket_unit: uint256, nb_tokens: uint64, mixer_timelock: uint64, pending_mixer: Bytes20,
+/// pending_mixer_deal: uint64, payout_bridge: Bytes20, deal_retention: uint64, notification_retention: uint64), a zero
+/// root, threshold, max amount, stipend, max dummy outputs, epoch, max deal value, cancel quorum, bucket unit or Mixer
+/// timelock being disabled, a zero pending Mixer meaning no migration pending, a zero payout bridge paying the outputs
+/// directly, and a zero retention keeping the deal records or notifications forever
+pub fn snapshot<R: Runtime>(runtime: &R, mixer: &H160) -> Vec<u8> {
+    let (epoch_deals, max_deposits, max_volume) = velocity::limits(runtime);
+    let (dust_policy, dust_threshold) = fees::dust_policy(runtime);
+    let (min_amount, max_amount) = amounts::bounds(runtime);
+    let (threshold, threshold_peers) = match threshold::config(runtime) {
+        Some(config) => (config.threshold(), config.nb_peers() as u64),
+        None => (0, 0),
+    };
+    let mut encoder = Encoder::default();
+    encoder.append_bytes(mixer);
+    encoder.append_list(&oracle::signers(runtime), codec::encode_address);
+    encoder.append_u8(ordering::get_policy(runtime));
+    encoder.append_bytes(&allowlist::get_root(runtime).unwrap_or_else(H256::zero));
+    encoder.append_u64(epoch_deals);
+    encoder.append_u64(max_deposits);
+    encoder.append_bytes(&codec::encode_uint256(&max_volume));
+    encoder.append_u8(dust_policy);
+    encoder.append_bytes(&codec::encode_uint256(&dust_threshold));
+    encoder.append_bytes(&codec::encode_uint256(&min_amount));
+    encoder.append_bytes(&codec::encode_uint256(&max_amount));
+    encoder.append_bytes(&codec::encode_uint256(&stipend::amount(runtime)));
+    encoder.append_u64(dummies::max(runtime));
+    encoder.append_list(&audit::roles(runtime), |role| vec![*role]);
+    encoder.append_u64(relay::nb_relayers(runtime) as u64);
+    encoder.append_u64(operators::nb_operators(runtime) as u64);
+    encoder.append_u8(threshold);
+    encoder.append_u64(threshold_peers);
+    encoder.append_u8(protocol::envelope().version);
+    encoder.append_u8(protocol::message().version);
+    encoder.append_list(kdf::ENCRYPTION_V1, |byte| vec![*byte]);
+    encoder.append_list(kdf::SIGNING_V1, |byte| vec![*byte]);
+    encoder.append_u8(profile::get(runtime).id);
+    encoder.append_bytes(&codec::encode_uint256(&amounts::value_cap(runtime)));
+    encoder.append_u8(cancellation::quorum(runtime));
+    encoder.append_bytes(&codec::encode_uint256(&buckets::unit(runtime)));
+    encoder.append_u64(tokens::nb_tokens(runtime) as u64);
+    let (pending_mixer, pending_mixer_deal) = migration::pending(runtime);
+    encoder.append_u64(migration::timelock(runtime));
+    encoder.append_bytes(&pending_mixer);
+    encoder.append_u64(pending_mixer_deal);
+    encoder.append_bytes(&payout::bridge(runtime));
+    let retention = retention::get(runtime);
+    encoder.append_u64(retention.deal_epochs);
+    encoder.append_u64(retention.notification_epochs);
+    encoder.finish()
+}
+
+fn get_history<R: Runtime>(runtime: &R) -> Vec<ConfigChange> {
+    runtime.read_state(CONFIG_HISTORY).unwrap_or_default()
+}
+
+/// Record the configuration following the admin operation
+pub fn record<R: Runtime>(runtime: &R, operation: u8, mixer: &H160) {
+    let mut history = get_history(runtime);
+    let change = ConfigChange { operation, deal_count: metrics::nb_deals(runtime), snapshot: snapshot(runtime, mixer) };
+    trace!("Recording configuration change {} of operation {}", history.len(), operation);
+    history.push(change);
+    runtime.write_state(CONFIG_HISTORY, history);
+}
+
+/// The last n configuration changes, most recent first
+pub fn history<R: Runtime>(runtime: &R, n: usize) -> Vec<Vec<u8>> {
+    get_history(runtime).iter().rev().take(n).map(|change| change.encode()).collect()
+}
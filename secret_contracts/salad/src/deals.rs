@@ -0,0 +1,303 @@
+use eng_wasm::{String, Vec, H160, H256, U256};
+use rustc_hex::ToHex;
+
+use crate::amounts;
+use crate::codec::{self, Decoder, Encoder};
+use crate::merkle;
+use crate::protocol::PUB_KEY_SIZE;
+use crate::runtime::Runtime;
+use crate::state::Hash;
+
+// State key prefix of the deal records, followed by the hex DealId
+static DEAL_PREFIX: &str = "deal_";
+// State key name "executed_deals" holding the DealId of the executed deals, in execution order
+static EXECUTED_DEALS: &str = "executed_deals";
+
+/// Status of an executed deal: Committed then Distributed then Anchored, possibly Refunded, or Failed, and
+/// eventually Pruned. Each execution step is answered from the record once done, so that operators can retry
+/// after a timeout.
+#[derive(PartialEq, Debug)]
+pub enum DealStatus {
+    /// The Merkle roots are committed on-chain, distribution awaits the challenge window
+    Committed,
+    Distributed,
+    /// The operator committed to deposits failing verification, the evidence being recorded against its bond
+    Failed,
+    /// The distribution callout kept failing, or the senders voted the deal cancelled, the deposits being
+    /// paid back to their senders instead
+    Refunded,
+    /// The receipt hash is recorded on-chain after distribution, timestamping the receipt
+    Anchored,
+    /// The settled record was pruned past the retention policy, only its operator, bond and receipt hash being
+    /// kept so that the deal is never executed again
+    Pruned,
+}
+
+impl DealStatus {
+    fn to_u8(&self) -> u8 {
+        match self {
+            DealStatus::Committed => 0,
+            DealStatus::Distributed => 1,
+            DealStatus::Failed => 2,
+            DealStatus::Refunded => 3,
+            DealStatus::Anchored => 4,
+            DealStatus::Pruned => 5,
+        }
+    }
+
+    fn from_u8(status: u8) -> DealStatus {
+        match status {
+            0 => DealStatus::Committed,
+            1 => DealStatus::Distributed,
+            2 => DealStatus::Failed,
+            3 => DealStatus::Refunded,
+            4 => DealStatus::Anchored,
+            5 => DealStatus::Pruned,
+            _ => panic!("Invalid deal status: {}", status),
+        }
+    }
+}
+
+/// Enclave record of a deal, holding the deposits and shuffled outputs until distribution
+pub struct DealRecord {
+    pub status: DealStatus,
+    operator: H160,
+    /// Reference of the operator bond slashed on misbehavior
+    bond: H256,
+    senders: Vec<H160>,
+    digests: Vec<H256>,
+    pub_keys: Vec<Vec<u8>>,
+    recipients: Vec<H160>,
+    amounts: Vec<U256>,
+    /// Index of the deposit funding each output
+    deposit_indices: Vec<u64>,
+    /// Total of the deposit shares staged back into the pool, and of the staged deposits joining the deal
+    redeposited: U256,
+    redeemed: U256,
+    receipt_hash: H256,
+    /// Number of dummy outputs among the outputs, only known to the enclave and to the record readers
+    nb_dummies: u64,
+    /// Cost of each metered phase of the execution, empty when the runtime does not expose its meter
+    pub phase_costs: Vec<u64>,
+}
+
+impl DealRecord {
+    /// Create a record from the deposits, in deal order, and the shuffled (deposit index, recipient, amount) outputs.
+    /// Staged deposits joining the deal have a zero sender, and `nb_dummies` of the outputs are dummy outputs.
+    pub fn new(
+        status: DealStatus,
+        operator: &H160,
+        bond: &H256,
+        senders: &[H160],
+        digests: &[H256],
+        pub_keys: &[Vec<u8>],
+        outputs: &[(usize, H160, U256)],
+        redeposited: &U256,
+        redeemed: &U256,
+        receipt_hash: &H256,
+        nb_dummies: u64,
+    ) -> DealRecord {
+        DealRecord {
+            status,
+            operator: *operator,
+            bond: *bond,
+            senders: senders.to_vec(),
+            digests: digests.to_vec(),
+            pub_keys: pub_keys.to_vec(),
+            recipients: outputs.iter().map(|(_, recipient, _)| *recipient).collect(),
+            amounts: outputs.iter().map(|(_, _, amount)| *amount).collect(),
+            deposit_indices: outputs.iter().map(|(i, _, _)| *i as u64).collect(),
+            redeposited: *redeposited,
+            redeemed: *redeemed,
+            receipt_hash: *receipt_hash,
+            nb_dummies,
+            phase_costs: Vec::new(),
+        }
+    }
+
+    /// Canonical encoding of the record as the container
+    /// (status: uint8, operator: Bytes20, bond: Bytes32, senders: List[Bytes20], digests: List[Bytes32], pub_keys: List[Bytes64],
+    /// recipients: List[Bytes20], amounts: List[uint256], deposit_indices: List[uint64], redeposited: uint256,
+    /// redeemed: uint256, receipt_hash: Bytes32, nb_dummies: uint64, phase_costs: List[uint64])
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        encoder.append_u8(self.status.to_u8());
+        encoder.append_bytes(&self.operator);
+        encoder.append_bytes(&self.bond);
+        encoder.append_list(&self.senders, codec::encode_address);
+        encoder.append_list(&self.digests, codec::encode_hash);
+        encoder.append_list(&self.pub_keys, |pub_key| pub_key.clone());
+        encoder.append_list(&self.recipients, codec::encode_address);
+        encoder.append_list(&self.amounts, codec::encode_uint256);
+        encoder.append_list(&self.deposit_indices, codec::encode_u64);
+        encoder.append_bytes(&codec::encode_uint256(&self.redeposited));
+        encoder.append_bytes(&codec::encode_uint256(&self.redeemed));
+        encoder.append_bytes(&self.receipt_hash);
+        encoder.append_u64(self.nb_dummies);
+        encoder.append_list(&self.phase_costs, codec::encode_u64);
+        encoder.finish()
+    }
+
+    pub fn decode(data: &[u8]) -> DealRecord {
+        let mut decoder = Decoder::new(data);
+        let status = DealStatus::from_u8(decoder.read_u8());
+        let operator = H160::from(decoder.read_bytes(20));
+        let bond = H256::from(decoder.read_bytes(32));
+        for _ in 0..6 {
+            decoder.read_offset();
+        }
+        let redeposited = codec::decode_uint256(decoder.read_bytes(32));
+        let redeemed = codec::decode_uint256(decoder.read_bytes(32));
+        let receipt_hash = H256::from(decoder.read_bytes(32));
+        let nb_dummies = codec::decode_u64(decoder.read_bytes(8));
+        // Records encoded before the phase costs were metered have none
+        let metered = decoder.has_more();
+        if metered {
+            decoder.read_offset();
+        }
+        let lists = decoder.finish();
+        DealRecord {
+            status,
+            operator,
+            bond,
+            senders: codec::items(lists[0], 20).into_iter().map(H160::from).collect(),
+            digests: codec::items(lists[1], 32).into_iter().map(H256::from).collect(),
+            pub_keys: codec::items(lists[2], PUB_KEY_SIZE).into_iter().map(<[u8]>::to_vec).collect(),
+            recipients: codec::items(lists[3], 20).into_iter().map(H160::from).collect(),
+            amounts: codec::items(lists[4], 32).into_iter().map(codec::decode_uint256).collect(),
+            deposit_indices: codec::items(lists[5], 8).into_iter().map(codec::decode_u64).collect(),
+            redeposited,
+            redeemed,
+            receipt_hash,
+            nb_dummies,
+            phase_costs: if metered { codec::items(lists[6], 8).into_iter().map(codec::decode_u64).collect() } else { Vec::new() },
+        }
+    }
+
+    pub fn operator(&self) -> H160 {
+        self.operator
+    }
+
+    pub fn bond(&self) -> H256 {
+        self.bond
+    }
+
+    pub fn sender(&self, i: usize) -> H160 {
+        self.senders[i]
+    }
+
+    pub fn pub_key(&self, i: usize) -> Vec<u8> {
+        self.pub_keys[i].clone()
+    }
+
+    /// Index of the deposit with the specified typed data message digest
+    pub fn deposit_index(&self, digest: &H256) -> Option<usize> {
+        self.digests.iter().position(|d| d == digest)
+    }
+
+    /// Indices of the outputs funded by the deposit
+    pub fn output_indices(&self, i: usize) -> Vec<usize> {
+        self.deposit_indices.iter().enumerate().filter(|(_, d)| **d == i as u64).map(|(j, _)| j).collect()
+    }
+
+    pub fn outputs(&self) -> Vec<(H160, U256)> {
+        self.recipients.iter().cloned().zip(self.amounts.iter().cloned()).collect()
+    }
+
+    /// The compensating refund of the outputs: the outputs funded by each deposit paid back to its sender,
+    /// staged deposits keeping their outputs as their funds were already mixed
+    pub fn refunds(&self) -> Vec<(H160, U256)> {
+        let mut refunds: Vec<(H160, U256)> = Vec::new();
+        for (i, sender) in self.senders.iter().enumerate() {
+            let outputs: Vec<(H160, U256)> = self.output_indices(i).iter().map(|j| (self.recipients[*j], self.amounts[*j])).collect();
+            if sender.is_zero() {
+                refunds.extend(outputs);
+            } else {
+                let total = amounts::sum(outputs.iter().map(|(_, amount)| amount));
+                refunds.push((*sender, total));
+            }
+        }
+        refunds
+    }
+
+    pub fn participant_leaves(&self) -> Vec<Vec<u8>> {
+        self.senders.iter().map(|sender| sender.to_vec()).collect()
+    }
+
+    pub fn output_leaves(&self) -> Vec<Vec<u8>> {
+        self.outputs().iter().map(|(recipient, amount)| merkle::output_leaf(recipient, amount)).collect()
+    }
+
+    pub fn redeposited(&self) -> U256 {
+        self.redeposited
+    }
+
+    pub fn redeemed(&self) -> U256 {
+        self.redeemed
+    }
+
+    pub fn receipt_hash(&self) -> H256 {
+        self.receipt_hash
+    }
+
+    /// The tombstone left by pruning the record, keeping what identifies the deal without its participants
+    fn tombstone(&self) -> DealRecord {
+        DealRecord::new(DealStatus::Pruned, &self.operator, &self.bond, &[], &[], &[], &[], &U256::zero(), &U256::zero(),
+                        &self.receipt_hash, 0)
+    }
+}
+
+fn deal_key(deal_id: &H256) -> String {
+    format!("{}{}", DEAL_PREFIX, deal_id.to_hex::<String>())
+}
+
+pub fn load<R: Runtime>(runtime: &R, deal_id: &H256) -> Option<DealRecord> {
+    let record: Option<Vec<u8>> = runtime.read_state(&deal_key(deal_id));
+    record.map(|record| DealRecord::decode(&record))
+}
+
+pub fn save<R: Runtime>(runtime: &R, deal_id: &H256, record: &DealRecord) {
+    if load(runtime, deal_id).is_none() {
+        let mut deal_ids = get_deal_ids(runtime);
+        deal_ids.push(Hash(*deal_id));
+        runtime.write_state(EXECUTED_DEALS, deal_ids);
+    }
+    runtime.write_state(&deal_key(deal_id), record.encode());
+}
+
+fn get_deal_ids<R: Runtime>(runtime: &R) -> Vec<Hash> {
+    runtime.read_state(EXECUTED_DEALS).unwrap_or_default()
+}
+
+/// The DealId of the executed deals, most recent first
+pub fn recent<R: Runtime>(runtime: &R) -> Vec<H256> {
+    get_deal_ids(runtime).iter().rev().map(|deal_id| deal_id.0).collect()
+}
+
+/// The DealId of the deals executed from the epoch `from` up to `to` excluded, in execution order,
+/// the epoch of a deal being the number of deals executed before it
+pub fn executed_between<R: Runtime>(runtime: &R, from: u64, to: u64) -> Vec<H256> {
+    get_deal_ids(runtime).iter().skip(from as usize).take(to.saturating_sub(from) as usize).map(|deal_id| deal_id.0).collect()
+}
+
+/// The current epoch, the number of deals executed
+pub fn epoch<R: Runtime>(runtime: &R) -> u64 {
+    get_deal_ids(runtime).len() as u64
+}
+
+/// Replace the record of a settled deal (Anchored, Refunded or Failed) with its tombstone, returning false
+/// for the records still awaiting distribution or anchoring, which are kept
+pub fn prune<R: Runtime>(runtime: &R, deal_id: &H256) -> bool {
+    let record = match load(runtime, deal_id) {
+        Some(record) => record,
+        None => panic!("Unknown deal: {:?}", deal_id),
+    };
+    match record.status {
+        DealStatus::Anchored | DealStatus::Refunded | DealStatus::Failed => {
+            runtime.write_state(&deal_key(deal_id), record.tombstone().encode());
+            true
+        }
+        DealStatus::Pruned => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,72 @@
+use eng_wasm::{Vec, H160, H256};
+use enigma_crypto::hash::Keccak256;
+use serde::{Deserialize, Serialize};
+
+use crate::codec::Encoder;
+use crate::runtime::Runtime;
+use crate::state::{Address, Hash};
+
+// State key name "initialization" holding the record of the construction of the contract
+static INITIALIZATION: &str = "initialization";
+
+/// Record of the construction, binding the contract to its Mixer and to the hashes of its enclave keys
+#[derive(Serialize, Deserialize)]
+struct Initialization {
+    mixer: Address,
+    pub_key_hash: Hash,
+    signing_pub_key_hash: Hash,
+}
+
+fn get_initialization<R: Runtime>(runtime: &R) -> Option<Initialization> {
+    runtime.read_state(INITIALIZATION)
+}
+
+/// Refuse constructing the contract twice, or against a zero Mixer address, so that it may only be re-pointed
+/// at a different Mixer through a timelocked migration. Contracts constructed before the initialization record are told by their Mixer address.
+pub fn check<R: Runtime>(runtime: &R, mixer: &H160, mixer_set: bool) {
+    if get_initialization(runtime).is_some() || mixer_set {
+        panic!("Contract already initialized");
+    }
+    if mixer.is_zero() {
+        panic!("Invalid zero Mixer address");
+    }
+}
+
+pub fn record<R: Runtime>(runtime: &R, mixer: &H160, pub_key: &[u8], signing_pub_key: &[u8]) {
+    let initialization = Initialization {
+        mixer: Address(*mixer),
+        pub_key_hash: Hash(H256::from(&pub_key.keccak256()[..])),
+        signing_pub_key_hash: Hash(H256::from(&signing_pub_key.keccak256()[..])),
+    };
+    trace!("Initialized against Mixer {:?} with pubKey hash {:?}", mixer, initialization.pub_key_hash.0);
+    runtime.write_state(INITIALIZATION, initialization);
+}
+
+/// Bind the initialization record to the Mixer the contract migrated to, keeping the hashes of its enclave keys
+pub fn migrate<R: Runtime>(runtime: &R, mixer: &H160) {
+    if let Some(mut initialization) = get_initialization(runtime) {
+        initialization.mixer = Address(*mixer);
+        runtime.write_state(INITIALIZATION, initialization);
+    }
+}
+
+/// The initialization record, canonically encoded as the container
+/// (initialized: uint8, mixer: Bytes20, pub_key_hash: Bytes32, signing_pub_key_hash: Bytes32), zeroed before construction
+pub fn export<R: Runtime>(runtime: &R) -> Vec<u8> {
+    let mut encoder = Encoder::default();
+    match get_initialization(runtime) {
+        Some(initialization) => {
+            encoder.append_u8(1);
+            encoder.append_bytes(&initialization.mixer.0);
+            encoder.append_bytes(&initialization.pub_key_hash.0);
+            encoder.append_bytes(&initialization.signing_pub_key_hash.0);
+        }
+        None => {
+            encoder.append_u8(0);
+            encoder.append_bytes(&H160::zero());
+            encoder.append_bytes(&H256::zero());
+            encoder.append_bytes(&H256::zero());
+        }
+    }
+    encoder.finish()
+}
@@ -0,0 +1,66 @@
+use eng_wasm::{String, Vec, H256};
+use enigma_crypto::hash::Keccak256;
+use rustc_hex::ToHex;
+
+use crate::runtime::Runtime;
+use crate::state::Hash;
+
+// State key prefix of the distribution retries of each deal, followed by the hex DealId
+static DISTRIBUTION_RETRIES_PREFIX: &str = "distribution_retries_";
+// State key prefix of the receipt callout retries of each deal, followed by the hex DealId
+static RECEIPT_RETRIES_PREFIX: &str = "receipt_retries_";
+// State key prefix of the keccak256 of the distribution calldata previewed for each deal, followed by the hex DealId
+static DISTRIBUTION_PREVIEW_PREFIX: &str = "distribution_preview_";
+
+/// Retries of a failed distribution callout before falling back to the compensating refund
+pub const MAX_DISTRIBUTION_RETRIES: u8 = 3;
+/// Retries of a failed receipt callout, the receipt being left unanchored past them
+pub const MAX_RECEIPT_RETRIES: u8 = 3;
+
+fn deal_key(prefix: &str, deal_id: &H256) -> String {
+    format!("{}{}", prefix, deal_id.to_hex::<String>())
+}
+
+/// Record a retry of the distribution of the deal, returning the number of retries so far
+pub fn record_retry<R: Runtime>(runtime: &R, deal_id: &H256) -> u8 {
+    increment(runtime, &deal_key(DISTRIBUTION_RETRIES_PREFIX, deal_id))
+}
+
+/// Record a retry of the receipt callout of the deal, returning the number of retries so far
+pub fn record_receipt_retry<R: Runtime>(runtime: &R, deal_id: &H256) -> u8 {
+    increment(runtime, &deal_key(RECEIPT_RETRIES_PREFIX, deal_id))
+}
+
+/// Record the distribution calldata previewed by the operator, the deal then only being distributed
+/// once the operator confirms the calldata
+pub fn record_preview<R: Runtime>(runtime: &R, deal_id: &H256, calldata: &[u8]) {
+    let hash = H256::from(&calldata.keccak256()[..]);
+    trace!("Previewed distribution calldata of deal {:?}: {:?}", deal_id, hash);
+    runtime.write_state(&deal_key(DISTRIBUTION_PREVIEW_PREFIX, deal_id), Hash(hash));
+}
+
+/// Refuse the distribution of a previewed deal unless `confirmation` is the keccak256 of both the calldata
+/// previewed and the calldata about to be sent, built by `calldata`, and the confirmation of a deal not previewed
+pub fn check_confirmed<R: Runtime, F: FnOnce() -> Vec<u8>>(runtime: &R, deal_id: &H256, confirmation: Option<&H256>, calldata: F) {
+    let previewed: Option<Hash> = runtime.read_state(&deal_key(DISTRIBUTION_PREVIEW_PREFIX, deal_id));
+    let (previewed, confirmation) = match (previewed, confirmation) {
+        (None, None) => return,
+        (Some(previewed), Some(confirmation)) => (previewed.0, confirmation),
+        (Some(_), None) => panic!("Distribution of deal {:?} previewed, awaiting its confirmation", deal_id),
+        (None, Some(_)) => panic!("Distribution of deal {:?} not previewed", deal_id),
+    };
+    if *confirmation != previewed {
+        panic!("Confirmed calldata {:?} of deal {:?} differs from the calldata previewed {:?}", confirmation, deal_id, previewed);
+    }
+    let hash = H256::from(&calldata().keccak256()[..]);
+    if hash != previewed {
+        panic!("Distribution calldata of deal {:?} changed since previewed: {:?}", deal_id, hash);
+    }
+}
+
+fn increment<R: Runtime>(runtime: &R, key: &str) -> u8 {
+    let retries: u8 = runtime.read_state(key).unwrap_or(0);
+    let retries = retries.saturating_add(1);
+    runtime.write_state(key, retries);
+    retries
+}
@@ -0,0 +1,38 @@
+use eng_wasm::{Vec, H160, U256};
+
+use crate::runtime::Runtime;
+
+// State key name "max_dummy_outputs" holding the max number of dummy outputs added to each deal, if any
+static MAX_DUMMY_OUTPUTS: &str = "max_dummy_outputs";
+
+/// Set the max number of dummy outputs added to each deal, zero disabling the dummy outputs
+pub fn set_max<R: Runtime>(runtime: &R, max: &U256) {
+    if max.is_zero() {
+        return;
+    }
+    if *max > U256::from(u8::max_value()) {
+        panic!("Too many dummy outputs: {}", max);
+    }
+    runtime.write_state(MAX_DUMMY_OUTPUTS, max.as_u64());
+}
+
+/// The max number of dummy outputs of the deployment, zero when disabled
+pub fn max<R: Runtime>(runtime: &R) -> u64 {
+    runtime.read_state(MAX_DUMMY_OUTPUTS).unwrap_or(0)
+}
+
+/// Add k dummy outputs to the deal, k being drawn in [0, max] from the enclave entropy, so that the number
+/// of outputs does not reveal the number of participants. Each dummy output pays a zero amount to a burn address
+/// generated by the enclave, whose key is never derived, attributed to the first deposit. Returns k.
+pub fn add<R: Runtime>(runtime: &R, outputs: &mut Vec<(usize, H160, U256)>) -> u64 {
+    let max = max(runtime);
+    if max == 0 {
+        return 0;
+    }
+    let k = runtime.random_seed() % (max + 1);
+    trace!("Adding {} dummy outputs", k);
+    for _ in 0..k {
+        outputs.push((0, H160::from(&runtime.generate_key()[12..32]), U256::zero()));
+    }
+    k
+}
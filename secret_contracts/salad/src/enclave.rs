@@ -0,0 +1,148 @@
+use aes_gcm::aead::generic_array::GenericArray;
+use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::Aes256Gcm;
+use eng_wasm::*;
+use eng_wasm::{Rand, SymmetricKey, Vec, H160, H256, U256};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::eth;
+use crate::runtime::Runtime;
+
+/// Size of the AES-GCM IV appended to the ciphertexts after the tag by the Enigma runtime
+const IV_SIZE: usize = 12;
+
+/// The Enigma enclave runtime
+pub struct Enclave;
+
+impl Runtime for Enclave {
+    fn random_seed(&self) -> u64 {
+        Rand::gen()
+    }
+
+    fn generate_key(&self) -> SymmetricKey {
+        generate_key()
+    }
+
+    fn encrypt(&self, plaintext: &[u8], key: &SymmetricKey) -> Vec<u8> {
+        encrypt(plaintext, key)
+    }
+
+    /// Decrypted in the contract rather than by the Enigma runtime, which traps on the ciphertexts failing
+    /// authentication and would abort the deal of the other participants
+    fn decrypt(&self, ciphertext: &[u8], key: &SymmetricKey) -> Result<Vec<u8>, String> {
+        if ciphertext.len() < IV_SIZE {
+            return Err(format!("Ciphertext too short: {}", ciphertext.len()));
+        }
+        let (ciphertext, iv) = ciphertext.split_at(ciphertext.len() - IV_SIZE);
+        let cipher = Aes256Gcm::new(GenericArray::from_slice(key));
+        cipher.decrypt(GenericArray::from_slice(iv), ciphertext).map_err(|_| "Ciphertext failing authentication".to_string())
+    }
+
+    fn trace(&self, line: &str) {
+        crate::trace::emit(line);
+    }
+
+    /// The gas of a task is metered by the Enigma runtime outside of the contract, without exposing its counter
+    fn cost_counter(&self) -> Option<u64> {
+        None
+    }
+
+    fn read_state<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        read_state!(key)
+    }
+
+    fn write_state<T: Serialize>(&self, key: &str, value: T) {
+        write_state!(key => value);
+    }
+
+    fn commit_roots(&self, mixer_eth_addr: &str, deal_id: &H256, participants_root: &H256, outputs_root: &H256) {
+        eth::commit_roots(mixer_eth_addr, deal_id, participants_root, outputs_root);
+    }
+
+    fn distribute(
+        &self,
+        mixer_eth_addr: &str,
+        deal_id: &H256,
+        recipients: Vec<H160>,
+        amounts: Vec<U256>,
+        redeposited: U256,
+        redeemed: U256,
+        receipt_hash: &H256,
+    ) {
+        eth::distribute(mixer_eth_addr, deal_id, recipients, amounts, redeposited, redeemed, receipt_hash);
+    }
+
+    fn distribute_with_escrow(
+        &self,
+        mixer_eth_addr: &str,
+        deal_id: &H256,
+        recipients: Vec<H160>,
+        guardians: Vec<H160>,
+        amounts: Vec<U256>,
+        redeposited: U256,
+        redeemed: U256,
+        receipt_hash: &H256,
+    ) {
+        eth::distribute_with_escrow(mixer_eth_addr, deal_id, recipients, guardians, amounts, redeposited, redeemed, receipt_hash);
+    }
+
+    fn distribute_assets(
+        &self,
+        mixer_eth_addr: &str,
+        deal_id: &H256,
+        assets: Vec<H160>,
+        payees: Vec<H160>,
+        recipients: Vec<H160>,
+        amounts: Vec<U256>,
+        receipt_hash: &H256,
+    ) {
+        eth::distribute_assets(mixer_eth_addr, deal_id, assets, payees, recipients, amounts, receipt_hash);
+    }
+
+    fn distribute_to_bridge(
+        &self,
+        mixer_eth_addr: &str,
+        deal_id: &H256,
+        recipients: Vec<H160>,
+        amounts: Vec<U256>,
+        redeposited: U256,
+        redeemed: U256,
+        receipt_hash: &H256,
+    ) {
+        eth::distribute_to_bridge(mixer_eth_addr, deal_id, recipients, amounts, redeposited, redeemed, receipt_hash);
+    }
+
+    fn set_payout_bridge(&self, mixer_eth_addr: &str, bridge: &H160) {
+        eth::set_payout_bridge(mixer_eth_addr, bridge);
+    }
+
+    fn record_receipt(&self, mixer_eth_addr: &str, deal_id: &H256, receipt_hash: &H256) {
+        eth::record_receipt(mixer_eth_addr, deal_id, receipt_hash);
+    }
+
+    fn refund_deal(&self, mixer_eth_addr: &str, deal_id: &H256) {
+        eth::refund_deal(mixer_eth_addr, deal_id);
+    }
+
+    fn commit_note_payouts(&self, mixer_eth_addr: &str, deal_id: &H256, payouts_root: &H256, nb_payouts: usize) {
+        eth::commit_note_payouts(mixer_eth_addr, deal_id, payouts_root, nb_payouts);
+    }
+
+    fn withdraw_note(
+        &self,
+        mixer_eth_addr: &str,
+        deal_id: &H256,
+        nullifier: &H256,
+        payout: &H160,
+        amount: &U256,
+        index: usize,
+        proof: &[H256],
+    ) {
+        eth::withdraw_note(mixer_eth_addr, deal_id, nullifier, payout, amount, index, proof);
+    }
+}
+
+/// The runtime the contract is built against, passed down by the entry points of the contract to the
+/// helpers and modules, which only see a `Runtime`
+pub static HOST: Enclave = Enclave;
@@ -0,0 +1,67 @@
+use eng_wasm::{String, Vec, H256};
+use rustc_hex::ToHex;
+use serde::{Deserialize, Serialize};
+
+use crate::codec::Encoder;
+use crate::deals::{self, DealStatus};
+use crate::digest;
+use crate::runtime::Runtime;
+use crate::state::Hash;
+
+// State key prefix "shuffle_seed_" of the seed each deal was shuffled with, by DealId
+static SHUFFLE_SEED_PREFIX: &str = "shuffle_seed_";
+
+/// The seed a deal was shuffled with, along with the salt of its commitment
+#[derive(Serialize, Deserialize)]
+struct ShuffleSeed {
+    seed: u64,
+    salt: Hash,
+}
+
+impl ShuffleSeed {
+    fn commitment(&self, deal_id: &H256) -> H256 {
+        digest::shuffle_commitment(deal_id, self.seed, &self.salt.0)
+    }
+}
+
+fn seed_key(deal_id: &H256) -> String {
+    format!("{}{}", SHUFFLE_SEED_PREFIX, deal_id.to_hex::<String>())
+}
+
+fn load<R: Runtime>(runtime: &R, deal_id: &H256) -> Option<ShuffleSeed> {
+    runtime.read_state(&seed_key(deal_id))
+}
+
+/// Record the seed the deal was shuffled with under a fresh salt, returning the commitment to the seed
+pub fn commit<R: Runtime>(runtime: &R, deal_id: &H256, seed: u64) -> H256 {
+    let shuffle_seed = ShuffleSeed { seed, salt: Hash(H256::from(&runtime.generate_key()[..])) };
+    let commitment = shuffle_seed.commitment(deal_id);
+    trace!("Committed to the shuffle seed: {:?}", commitment);
+    runtime.write_state(&seed_key(deal_id), shuffle_seed);
+    commitment
+}
+
+/// The commitment to the seed the deal was shuffled with, none for the deals shuffled before the commitments
+pub fn commitment<R: Runtime>(runtime: &R, deal_id: &H256) -> Option<H256> {
+    load(runtime, deal_id).map(|shuffle_seed| shuffle_seed.commitment(deal_id))
+}
+
+/// The seed the deal was shuffled with, canonically encoded as the container (seed: uint64, salt: Bytes32,
+/// commitment: Bytes32). Refused until the deal is distributed, so that its permutation is never predictable
+/// while the deal may still be challenged or refunded.
+pub fn reveal<R: Runtime>(runtime: &R, deal_id: &H256) -> Vec<u8> {
+    let status = match deals::load(runtime, deal_id) {
+        Some(record) => record.status,
+        None => panic!("Unknown deal: {:?}", deal_id),
+    };
+    match status {
+        DealStatus::Distributed | DealStatus::Anchored => (),
+        status => panic!("Shuffle seed not revealed before distribution: {:?}", status),
+    }
+    let shuffle_seed = load(runtime, deal_id).unwrap_or_else(|| panic!("No shuffle seed committed for deal: {:?}", deal_id));
+    let mut encoder = Encoder::default();
+    encoder.append_u64(shuffle_seed.seed);
+    encoder.append_bytes(&shuffle_seed.salt.0);
+    encoder.append_bytes(&shuffle_seed.commitment(deal_id));
+    encoder.finish()
+}
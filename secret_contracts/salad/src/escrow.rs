@@ -0,0 +1,28 @@
+use eng_wasm::{String, Vec, H160, H256};
+use rustc_hex::ToHex;
+
+use crate::runtime::Runtime;
+use crate::state::Address;
+
+// State key prefix of the guardian of each output of a deal, followed by the hex DealId
+static GUARDIANS_PREFIX: &str = "deal_guardians_";
+
+fn guardians_key(deal_id: &H256) -> String {
+    format!("{}{}", GUARDIANS_PREFIX, deal_id.to_hex::<String>())
+}
+
+/// Record the guardian of each shuffled output of the deal, zero for the outputs paid directly,
+/// deals without any guardian being distributed as usual
+pub fn save<R: Runtime>(runtime: &R, deal_id: &H256, guardians: &[H160]) {
+    if guardians.iter().all(|guardian| guardian.is_zero()) {
+        return;
+    }
+    let guardians: Vec<Address> = guardians.iter().cloned().map(Address).collect();
+    runtime.write_state(&guardians_key(deal_id), guardians);
+}
+
+/// The guardian of each output of the deal, if any of its outputs is held in escrow
+pub fn load<R: Runtime>(runtime: &R, deal_id: &H256) -> Option<Vec<H160>> {
+    let guardians: Option<Vec<Address>> = runtime.read_state(&guardians_key(deal_id));
+    guardians.map(|guardians| guardians.into_iter().map(|guardian| guardian.0).collect())
+}
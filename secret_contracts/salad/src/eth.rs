@@ -0,0 +1,146 @@
+use eng_wasm::*;
+use eng_wasm::{H160, H256, U256, Vec};
+use eng_wasm_derive::eth_contract;
+
+use crate::convert;
+
+#[eth_contract("ISalad.json")]
+struct EthContract;
+
+/// Call `distribute` on the Mixer contract deployed at `mixer_eth_addr` (hex without prefix),
+/// paying `amounts[i]` to `recipients[i]`, `redeposited` being staged back into the pool
+/// and `redeemed` paid out of the pool
+pub fn distribute(
+    mixer_eth_addr: &str,
+    deal_id: &H256,
+    recipients: Vec<H160>,
+    amounts: Vec<U256>,
+    redeposited: U256,
+    redeemed: U256,
+    receipt_hash: &H256,
+) {
+    let prefixed_eth_addr = format!("0x{}", mixer_eth_addr);
+    let eth_contract = EthContract::new(&prefixed_eth_addr);
+    // The bytes32 arguments are passed as uint256 for lack of bytes32 support
+    let deal_id_uint = convert::hash_to_uint(deal_id);
+    let receipt_hash_uint = convert::hash_to_uint(receipt_hash);
+    eth_contract.distribute(deal_id_uint, recipients, amounts, redeposited, redeemed, receipt_hash_uint);
+}
+
+/// Call `distributeWithEscrow` on the Mixer contract, the outputs with a non-zero `guardians[i]`
+/// being held in escrow until released by both their recipient and their guardian
+pub fn distribute_with_escrow(
+    mixer_eth_addr: &str,
+    deal_id: &H256,
+    recipients: Vec<H160>,
+    guardians: Vec<H160>,
+    amounts: Vec<U256>,
+    redeposited: U256,
+    redeemed: U256,
+    receipt_hash: &H256,
+) {
+    let prefixed_eth_addr = format!("0x{}", mixer_eth_addr);
+    let eth_contract = EthContract::new(&prefixed_eth_addr);
+    let deal_id_uint = convert::hash_to_uint(deal_id);
+    let receipt_hash_uint = convert::hash_to_uint(receipt_hash);
+    eth_contract.distributeWithEscrow(deal_id_uint, recipients, guardians, amounts, redeposited, redeemed, receipt_hash_uint);
+}
+
+/// Call `distributeAssets` on the Mixer contract with per-asset distribution lists, where
+/// `assets[i]` is the asset paid to `recipients[i]` for an amount of `amounts[i]` through `payees[i]`,
+/// the asset itself or the adapter contract configured to pay out the asset
+pub fn distribute_assets(
+    mixer_eth_addr: &str,
+    deal_id: &H256,
+    assets: Vec<H160>,
+    payees: Vec<H160>,
+    recipients: Vec<H160>,
+    amounts: Vec<U256>,
+    receipt_hash: &H256,
+) {
+    let prefixed_eth_addr = format!("0x{}", mixer_eth_addr);
+    let eth_contract = EthContract::new(&prefixed_eth_addr);
+    let deal_id_uint = convert::hash_to_uint(deal_id);
+    let receipt_hash_uint = convert::hash_to_uint(receipt_hash);
+    eth_contract.distributeAssets(deal_id_uint, assets, payees, recipients, amounts, receipt_hash_uint);
+}
+
+/// Call `distributeToBridge` on the Mixer contract, relaying `amounts[i]` through the bridge contract
+/// committed with `setPayoutBridge` to `recipients[i]` on the other side of the bridge
+pub fn distribute_to_bridge(
+    mixer_eth_addr: &str,
+    deal_id: &H256,
+    recipients: Vec<H160>,
+    amounts: Vec<U256>,
+    redeposited: U256,
+    redeemed: U256,
+    receipt_hash: &H256,
+) {
+    let prefixed_eth_addr = format!("0x{}", mixer_eth_addr);
+    let eth_contract = EthContract::new(&prefixed_eth_addr);
+    let deal_id_uint = convert::hash_to_uint(deal_id);
+    let receipt_hash_uint = convert::hash_to_uint(receipt_hash);
+    eth_contract.distributeToBridge(deal_id_uint, recipients, amounts, redeposited, redeemed, receipt_hash_uint);
+}
+
+/// Call `setPayoutBridge` on the Mixer contract, committing the bridge contract the outputs are relayed through
+pub fn set_payout_bridge(mixer_eth_addr: &str, bridge: &H160) {
+    let prefixed_eth_addr = format!("0x{}", mixer_eth_addr);
+    let eth_contract = EthContract::new(&prefixed_eth_addr);
+    eth_contract.setPayoutBridge(*bridge);
+}
+
+/// Call `recordReceipt` on the Mixer contract, timestamping the receipt hash of a distributed deal on-chain
+pub fn record_receipt(mixer_eth_addr: &str, deal_id: &H256, receipt_hash: &H256) {
+    let prefixed_eth_addr = format!("0x{}", mixer_eth_addr);
+    let eth_contract = EthContract::new(&prefixed_eth_addr);
+    let deal_id_uint = convert::hash_to_uint(deal_id);
+    let receipt_hash_uint = convert::hash_to_uint(receipt_hash);
+    eth_contract.recordReceipt(deal_id_uint, receipt_hash_uint);
+}
+
+/// Call `refundDeal` on the Mixer contract, paying the deposits of a deal never committed back to their senders
+pub fn refund_deal(mixer_eth_addr: &str, deal_id: &H256) {
+    let prefixed_eth_addr = format!("0x{}", mixer_eth_addr);
+    let eth_contract = EthContract::new(&prefixed_eth_addr);
+    let deal_id_uint = convert::hash_to_uint(deal_id);
+    eth_contract.refundDeal(deal_id_uint);
+}
+
+/// Call `commitNotePayouts` on the Mixer contract, committing the note withdrawals paid out after the deal
+pub fn commit_note_payouts(mixer_eth_addr: &str, deal_id: &H256, payouts_root: &H256, nb_payouts: usize) {
+    let prefixed_eth_addr = format!("0x{}", mixer_eth_addr);
+    let eth_contract = EthContract::new(&prefixed_eth_addr);
+    let deal_id_uint = convert::hash_to_uint(deal_id);
+    let payouts_root_uint = convert::hash_to_uint(payouts_root);
+    eth_contract.commitNotePayouts(deal_id_uint, payouts_root_uint, U256::from(nb_payouts));
+}
+
+/// Call `withdrawNote` on the Mixer contract, paying a withdrawal of notes committed with the deal out of
+/// the pool reserve, `proof` linking it to the committed root
+pub fn withdraw_note(
+    mixer_eth_addr: &str,
+    deal_id: &H256,
+    nullifier: &H256,
+    payout: &H160,
+    amount: &U256,
+    index: usize,
+    proof: &[H256],
+) {
+    let prefixed_eth_addr = format!("0x{}", mixer_eth_addr);
+    let eth_contract = EthContract::new(&prefixed_eth_addr);
+    let deal_id_uint = convert::hash_to_uint(deal_id);
+    let nullifier_uint = convert::hash_to_uint(nullifier);
+    let proof_uints: Vec<U256> = proof.iter().map(convert::hash_to_uint).collect();
+    eth_contract.withdrawNote(deal_id_uint, nullifier_uint, *payout, *amount, U256::from(index), proof_uints);
+}
+
+/// Call `commitRoots` on the Mixer contract, opening the challenge window before distribution
+pub fn commit_roots(mixer_eth_addr: &str, deal_id: &H256, participants_root: &H256, outputs_root: &H256) {
+    let prefixed_eth_addr = format!("0x{}", mixer_eth_addr);
+    let eth_contract = EthContract::new(&prefixed_eth_addr);
+    let deal_id_uint = convert::hash_to_uint(deal_id);
+    let participants_root_uint = convert::hash_to_uint(participants_root);
+    let outputs_root_uint = convert::hash_to_uint(outputs_root);
+    eth_contract.commitRoots(deal_id_uint, participants_root_uint, outputs_root_uint);
+}
@@ -0,0 +1,63 @@
+use eng_wasm::{String, Vec, H160, H256};
+use enigma_crypto::KeyPair;
+use rustc_hex::ToHex;
+
+use crate::codec::{self, Encoder};
+use crate::runtime::Runtime;
+
+// State key prefix of the misbehavior evidence of each deal, followed by the hex DealId
+static EVIDENCE_PREFIX: &str = "evidence_";
+
+/// The operator submitted deposits failing verification, stalling the deal
+pub const BAD_COMMITMENT: u8 = 0;
+/// A depositor challenged the deal over a deposit left out by the operator
+pub const WITHHELD_DEPOSIT: u8 = 1;
+
+/// Operator misbehavior detected by the enclave, to be slashed from the operator bond
+pub struct Misbehavior {
+    pub kind: u8,
+    pub deal_id: H256,
+    pub operator: H160,
+    pub bond: H256,
+    pub handles: Vec<H256>,
+    pub senders: Vec<H160>,
+    pub digests: Vec<H256>,
+}
+
+impl Misbehavior {
+    /// Canonical encoding of the evidence as the container
+    /// (kind: uint8, deal_id: Bytes32, operator: Bytes20, bond: Bytes32,
+    /// handles: List[Bytes32], senders: List[Bytes20], digests: List[Bytes32])
+    fn encode(&self) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        encoder.append_u8(self.kind);
+        encoder.append_bytes(&self.deal_id);
+        encoder.append_bytes(&self.operator);
+        encoder.append_bytes(&self.bond);
+        encoder.append_list(&self.handles, codec::encode_hash);
+        encoder.append_list(&self.senders, codec::encode_address);
+        encoder.append_list(&self.digests, codec::encode_hash);
+        encoder.finish()
+    }
+}
+
+fn evidence_key(deal_id: &H256) -> String {
+    format!("{}{}", EVIDENCE_PREFIX, deal_id.to_hex::<String>())
+}
+
+/// Record the evidence of the misbehavior, signed by the enclave as encoding || signature
+/// for submission to the slashing function of the bond contract
+pub fn record<R: Runtime>(runtime: &R, misbehavior: &Misbehavior, keypair: &KeyPair) {
+    trace!("Recording misbehavior {} of operator {:?} in deal {:?}", misbehavior.kind, misbehavior.operator, misbehavior.deal_id);
+    let mut blob = misbehavior.encode();
+    let signature = keypair.sign(&blob).unwrap();
+    blob.extend_from_slice(&signature);
+    let key = evidence_key(&misbehavior.deal_id);
+    let mut evidence = load(runtime, &misbehavior.deal_id);
+    evidence.push(blob);
+    runtime.write_state(&key, evidence);
+}
+
+pub fn load<R: Runtime>(runtime: &R, deal_id: &H256) -> Vec<Vec<u8>> {
+    runtime.read_state(&evidence_key(deal_id)).unwrap_or_default()
+}
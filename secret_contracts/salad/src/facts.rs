@@ -0,0 +1,111 @@
+use eng_wasm::{String, Vec, H256};
+use enigma_crypto::hash::Keccak256;
+use rustc_hex::ToHex;
+use serde::{Deserialize, Serialize};
+
+use crate::domains::REORG_DOMAIN;
+use crate::oracle;
+use crate::profile;
+use crate::runtime::Runtime;
+use crate::state::Hash;
+
+// State key prefix of each on-chain fact relayed by the operator, followed by the hex fact id
+static RELAYED_FACT_PREFIX: &str = "relayed_fact_";
+// State key prefix of the relayed facts each deal depends on, followed by the hex DealId
+static DEAL_FACTS_PREFIX: &str = "deal_facts_";
+
+/// An on-chain fact relayed by the operator in an oracle attestation
+#[derive(Serialize, Deserialize)]
+struct RelayedFact {
+    block: u64,
+    invalidated: bool,
+}
+
+/// A relayed fact a deal depends on, along with the confirmation depth declared for it
+#[derive(Serialize, Deserialize)]
+struct Dependency {
+    fact_id: Hash,
+    confirmations: u64,
+}
+
+fn fact_key(fact_id: &H256) -> String {
+    format!("{}{}", RELAYED_FACT_PREFIX, fact_id.to_hex::<String>())
+}
+
+fn deal_facts_key(deal_id: &H256) -> String {
+    format!("{}{}", DEAL_FACTS_PREFIX, deal_id.to_hex::<String>())
+}
+
+/// The id of a relayed fact, the keccak256 of its attestation
+pub fn fact_id(attestation: &[u8]) -> H256 {
+    H256::from(&attestation.keccak256()[..])
+}
+
+fn load<R: Runtime>(runtime: &R, fact_id: &H256) -> Option<RelayedFact> {
+    runtime.read_state(&fact_key(fact_id))
+}
+
+fn get_dependencies<R: Runtime>(runtime: &R, deal_id: &H256) -> Vec<Dependency> {
+    runtime.read_state(&deal_facts_key(deal_id)).unwrap_or_default()
+}
+
+fn check_valid<R: Runtime>(runtime: &R, fact_id: &H256) {
+    if load(runtime, fact_id).map_or(false, |fact| fact.invalidated) {
+        panic!("Relayed fact invalidated by a reorg: {:?}", fact_id);
+    }
+}
+
+/// Record that the deal depends on the fact attested at the block, to be confirmed `confirmations` blocks
+/// deep before the deal executes, at least the min confirmations of the network profile. The dependencies of
+/// a deal are replaced each time it is scheduled, the facts invalidated since being rolled back.
+pub fn depend<R: Runtime>(runtime: &R, deal_id: &H256, attestation: &[u8], block: u64, confirmations: u64) {
+    let min_confirmations = profile::get(runtime).min_confirmations;
+    if confirmations < min_confirmations {
+        panic!("Confirmation depth too low: {} < {}", confirmations, min_confirmations);
+    }
+    let fact_id = fact_id(attestation);
+    check_valid(runtime, &fact_id);
+    runtime.write_state(&fact_key(&fact_id), RelayedFact { block, invalidated: false });
+    trace!("Deal {:?} depends on fact {:?} at block {} with {} confirmations", deal_id, fact_id, block, confirmations);
+    runtime.write_state(&deal_facts_key(deal_id), vec![Dependency { fact_id: Hash(fact_id), confirmations }]);
+}
+
+/// Whether a fact the deal depends on was invalidated, the deal then being rescheduled
+pub fn invalidated<R: Runtime>(runtime: &R, deal_id: &H256) -> bool {
+    get_dependencies(runtime, deal_id).iter().any(|dependency| load(runtime, &dependency.fact_id.0).map_or(false, |fact| fact.invalidated))
+}
+
+/// Refuse to execute the deal at the attested block if the attestation or a fact it depends on was invalidated,
+/// or if a fact it depends on is not yet confirmed at its declared depth
+pub fn check<R: Runtime>(runtime: &R, deal_id: &H256, attestation: &[u8], attested_block: u64) {
+    check_valid(runtime, &fact_id(attestation));
+    for dependency in get_dependencies(runtime, deal_id).iter() {
+        let fact = match load(runtime, &dependency.fact_id.0) {
+            Some(fact) => fact,
+            None => panic!("Unknown relayed fact: {:?}", dependency.fact_id.0),
+        };
+        if fact.invalidated {
+            panic!("Deal {:?} depends on a fact invalidated by a reorg: {:?}", deal_id, dependency.fact_id.0);
+        }
+        if attested_block < fact.block + dependency.confirmations {
+            panic!("Deal {:?} depends on a fact at block {} not confirmed {} blocks deep at block {}",
+                   deal_id, fact.block, dependency.confirmations, attested_block);
+        }
+    }
+}
+
+/// Invalidate a relayed fact reorged out of the chain, from the `proof` of an oracle signer signing
+/// H(REORG_DOMAIN || fact id). The deals depending on it may no longer execute until rescheduled.
+pub fn invalidate<R: Runtime>(runtime: &R, fact_id: &H256, proof: &[u8]) {
+    oracle::verify_signer(runtime, REORG_DOMAIN, fact_id, proof);
+    let mut fact = match load(runtime, fact_id) {
+        Some(fact) => fact,
+        None => panic!("Unknown relayed fact: {:?}", fact_id),
+    };
+    if fact.invalidated {
+        panic!("Relayed fact already invalidated: {:?}", fact_id);
+    }
+    trace!("Invalidating fact {:?} at block {}", fact_id, fact.block);
+    fact.invalidated = true;
+    runtime.write_state(&fact_key(fact_id), fact);
+}
@@ -0,0 +1,57 @@
+use eng_wasm::{String, U256};
+
+use crate::runtime::Runtime;
+use crate::{dummies, fees};
+
+/// Optional behaviors of a deal, negotiated by the operator as a bitfield of the deal inputs, so that
+/// they are enabled explicitly rather than by the shape of the deposit payloads.
+/// Outputs held as notes in the enclave ledger and withdrawn later
+pub const DELAYED_OUTPUTS: u64 = 1 << 0;
+/// Dummy outputs added to the deal
+pub const DUMMY_OUTPUTS: u64 = 1 << 1;
+/// Outputs below the dust threshold coalesced under the dust policy
+pub const COALESCING: u64 = 1 << 2;
+/// Deposits carrying a notification pubKey their recipients are notified under
+pub const MEMOS: u64 = 1 << 3;
+/// Every feature, as negotiated by the deals predating the negotiation
+pub const ALL: u64 = DELAYED_OUTPUTS | DUMMY_OUTPUTS | COALESCING | MEMOS;
+
+/// The features supported by the config of the deployment, dummy outputs and coalescing only once configured
+pub fn supported<R: Runtime>(runtime: &R) -> u64 {
+    let mut features = DELAYED_OUTPUTS | MEMOS;
+    if dummies::max(runtime) > 0 {
+        features |= DUMMY_OUTPUTS;
+    }
+    if !fees::dust_policy(runtime).1.is_zero() {
+        features |= COALESCING;
+    }
+    features
+}
+
+/// Refuse the deal features unknown or unsupported by the deployment
+pub fn check<R: Runtime>(runtime: &R, features: &U256) -> u64 {
+    if *features > U256::from(ALL) {
+        panic!("Unknown deal features: {:?}", features);
+    }
+    let features = features.as_u64();
+    let unsupported = features & !supported(runtime);
+    if unsupported != 0 {
+        panic!("Unsupported deal features: {:#x}", unsupported);
+    }
+    features
+}
+
+pub fn enabled(features: u64, feature: u64) -> bool {
+    features & feature != 0
+}
+
+/// Refuse a deposit relying on features the deal did not negotiate
+pub fn admit(features: u64, has_notes: bool, has_memo: bool) -> Result<(), String> {
+    if has_notes && !enabled(features, DELAYED_OUTPUTS) {
+        return Err(String::from("Notes without the delayed outputs feature"));
+    }
+    if has_memo && !enabled(features, MEMOS) {
+        return Err(String::from("Notification pubKey without the memos feature"));
+    }
+    Ok(())
+}
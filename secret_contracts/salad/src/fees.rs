@@ -0,0 +1,83 @@
+use eng_wasm::{Vec, H160, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::amounts;
+use crate::runtime::Runtime;
+use crate::state::Word;
+
+// State key name "dust_policy" holding the dust policy of the deployment
+static DUST_POLICY: &str = "dust_policy";
+
+/// Dust policies: outputs below the dust threshold are paid back to the sender of their deposit,
+/// paid to the operator as a fee, or carried into the on-chain pool reserve
+pub const DUST_REFUND: u8 = 0;
+pub const DUST_TO_FEE: u8 = 1;
+pub const DUST_TO_POOL: u8 = 2;
+
+#[derive(Serialize, Deserialize)]
+struct DustPolicy {
+    policy: u8,
+    /// Amount below which an output is dust, zero disabling the policy
+    threshold: Word,
+}
+
+/// Set the dust policy of the deployment, a zero threshold leaving the outputs untouched
+pub fn set_dust_policy<R: Runtime>(runtime: &R, policy: &U256, threshold: &U256) {
+    if *policy > U256::from(DUST_TO_POOL) {
+        panic!("Unknown dust policy: {:?}", policy);
+    }
+    runtime.write_state(DUST_POLICY, DustPolicy { policy: policy.low_u32() as u8, threshold: Word(*threshold) });
+}
+
+/// The (policy, threshold) of the deployment, a zero threshold when disabled
+pub fn dust_policy<R: Runtime>(runtime: &R) -> (u8, U256) {
+    let policy: Option<DustPolicy> = runtime.read_state(DUST_POLICY);
+    match policy {
+        Some(policy) => (policy.policy, policy.threshold.0),
+        None => (DUST_REFUND, U256::zero()),
+    }
+}
+
+/// Sweep the outputs below the dust threshold and apply the dust policy, returning the dust carried into the
+/// pool reserve and the dust due to the operator, paid by the caller. Dust is carried into the pool whenever
+/// its payment would be dust itself: refunds of staged deposits or under the threshold, and operator fees
+/// under the threshold.
+pub fn sweep_dust<R: Runtime>(runtime: &R, outputs: &mut Vec<(usize, H160, U256)>, senders: &[H160]) -> (U256, U256) {
+    let policy: DustPolicy = match runtime.read_state(DUST_POLICY) {
+        Some(policy) => policy,
+        None => return (U256::zero(), U256::zero()),
+    };
+    let threshold = policy.threshold.0;
+    let mut dust: Vec<(usize, U256)> = Vec::new();
+    outputs.retain(|(i, _, amount)| {
+        if *amount >= threshold {
+            return true;
+        }
+        match dust.iter_mut().find(|(j, _)| j == i) {
+            Some((_, total)) => *total = amounts::add(total, amount),
+            None => dust.push((*i, *amount)),
+        }
+        false
+    });
+    if dust.is_empty() {
+        return (U256::zero(), U256::zero());
+    }
+    let total = amounts::sum(dust.iter().map(|(_, amount)| amount));
+    trace!("Sweeping the dust of {} deposits with policy {}: {:?}", dust.len(), policy.policy, total);
+    let mut pooled = U256::zero();
+    let mut fee = U256::zero();
+    match policy.policy {
+        DUST_REFUND => {
+            for (i, amount) in dust.into_iter() {
+                if senders[i].is_zero() || amount < threshold {
+                    pooled = amounts::add(&pooled, &amount);
+                } else {
+                    outputs.push((i, senders[i], amount));
+                }
+            }
+        }
+        DUST_TO_FEE if total >= threshold => fee = total,
+        _ => pooled = total,
+    }
+    (pooled, fee)
+}
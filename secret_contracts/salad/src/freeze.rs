@@ -0,0 +1,52 @@
+use eng_wasm::{String, Vec, H256};
+use enigma_crypto::hash::Keccak256;
+use rustc_hex::ToHex;
+
+use crate::runtime::Runtime;
+
+// State key prefix of the participant set each deal was frozen with, followed by the hex DealId
+static FROZEN_PARTICIPANTS_PREFIX: &str = "frozen_participants_";
+
+fn frozen_key(deal_id: &H256) -> String {
+    format!("{}{}", FROZEN_PARTICIPANTS_PREFIX, deal_id.to_hex::<String>())
+}
+
+/// The entry of a participant in a frozen set: its id (sender address or voucher) followed by
+/// H(pubKey || encrypted payload) of its deposit, so that its deposit may not be substituted either
+pub fn entry(participant: &[u8], pub_key: &[u8], enc_recipient: &[u8]) -> Vec<u8> {
+    let mut deposit: Vec<u8> = Vec::new();
+    deposit.extend_from_slice(pub_key);
+    deposit.extend_from_slice(enc_recipient);
+    let mut entry = participant.to_vec();
+    entry.extend_from_slice(&deposit.keccak256()[..]);
+    entry
+}
+
+fn load<R: Runtime>(runtime: &R, deal_id: &H256) -> Option<Vec<Vec<u8>>> {
+    runtime.read_state(&frozen_key(deal_id))
+}
+
+/// Freeze the participant set of the deal once its on-chain commitment is verified, the set of a deal
+/// already frozen being checked instead, so that a retried execution may not add or substitute participants
+pub fn freeze<R: Runtime>(runtime: &R, deal_id: &H256, participants: Vec<Vec<u8>>) {
+    match load(runtime, deal_id) {
+        Some(frozen) => check(deal_id, &frozen, &participants),
+        None => {
+            trace!("Freezing the {} participants of deal {:?}", participants.len(), deal_id);
+            runtime.write_state(&frozen_key(deal_id), participants);
+        }
+    }
+}
+
+/// Refuse a participant set differing from the one the deal was frozen with
+fn check(deal_id: &H256, frozen: &[Vec<u8>], participants: &[Vec<u8>]) {
+    if participants.len() > frozen.len() {
+        panic!("Participants added to deal {:?} after its commitment: {} > {}", deal_id, participants.len(), frozen.len());
+    }
+    if participants.len() < frozen.len() {
+        panic!("Participants removed from deal {:?} after its commitment: {} < {}", deal_id, participants.len(), frozen.len());
+    }
+    if let Some(i) = participants.iter().zip(frozen.iter()).position(|(participant, frozen)| participant != frozen) {
+        panic!("Participant {} of deal {:?} substituted after its commitment", i, deal_id);
+    }
+}
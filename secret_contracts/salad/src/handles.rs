@@ -0,0 +1,18 @@
+use eng_wasm::{Vec, H256};
+use enigma_crypto::hash::Keccak256;
+
+use crate::digest;
+
+/// The blinded handle of a participant, keying the per-participant results instead of its position in the deal:
+/// H(H(pub_key || enc_recipient)), the hash of the digest of its deposit payload (the commitment of a voucher
+/// deposit), which the depositor computes from its own deposit while the results reveal nothing of the order
+/// the operator submitted the deposits in
+pub fn handle(pub_key: &[u8], enc_recipient: &[u8]) -> H256 {
+    H256::from(&digest::voucher_commitment(pub_key, enc_recipient).keccak256()[..])
+}
+
+/// The per-participant entries keyed by handle, in the order of the handles rather than of the deal
+pub fn sorted<T>(mut entries: Vec<(H256, T)>) -> Vec<(H256, T)> {
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    entries
+}
@@ -0,0 +1,68 @@
+use eng_wasm::Vec;
+use serde::{Deserialize, Serialize};
+
+use crate::codec::{self, Encoder};
+use crate::metrics;
+use crate::runtime::Runtime;
+
+// State key name "health" holding the last activity of each subsystem
+static HEALTH: &str = "health";
+
+/// Subsystems whose last activity is tracked
+pub const ACTIVITY_DEAL: u8 = 0;
+pub const ACTIVITY_STAGED: u8 = 1;
+pub const ACTIVITY_KEY_ROTATION: u8 = 2;
+
+#[derive(Serialize, Deserialize, Default)]
+struct Health {
+    /// The (subsystem, epoch) of the last activity of each active subsystem, the epoch being the number
+    /// of deals executed before the activity
+    activities: Vec<(u8, u64)>,
+    /// The block attested to the last deal executed with an attested block
+    last_deal_block: u64,
+}
+
+fn get_health<R: Runtime>(runtime: &R) -> Health {
+    runtime.read_state(HEALTH).unwrap_or_default()
+}
+
+impl Health {
+    fn record<R: Runtime>(&mut self, runtime: &R, activity: u8) {
+        self.activities.retain(|(recorded, _)| *recorded != activity);
+        self.activities.push((activity, metrics::nb_deals(runtime)));
+    }
+}
+
+/// Record an activity of the subsystem at the current epoch
+pub fn record<R: Runtime>(runtime: &R, activity: u8) {
+    let mut health = get_health(runtime);
+    health.record(runtime, activity);
+    runtime.write_state(HEALTH, health);
+}
+
+/// Record the execution of a deal along with its attested block, zero for the deals without one
+pub fn record_deal<R: Runtime>(runtime: &R, attested_block: u64) {
+    let mut health = get_health(runtime);
+    health.record(runtime, ACTIVITY_DEAL);
+    if attested_block != 0 {
+        health.last_deal_block = attested_block;
+    }
+    runtime.write_state(HEALTH, health);
+}
+
+/// Canonical encoding of the last activities as the container (deals: uint64, last_deal_block: uint64,
+/// subsystems: List[uint8], last_epochs: List[uint64]), listing the subsystems active since the tracking
+/// started, in the order of their subsystem code
+pub fn export<R: Runtime>(runtime: &R) -> Vec<u8> {
+    let health = get_health(runtime);
+    let mut activities = health.activities;
+    activities.sort();
+    let subsystems: Vec<u8> = activities.iter().map(|(activity, _)| *activity).collect();
+    let epochs: Vec<u64> = activities.iter().map(|(_, epoch)| *epoch).collect();
+    let mut encoder = Encoder::default();
+    encoder.append_u64(metrics::nb_deals(runtime));
+    encoder.append_u64(health.last_deal_block);
+    encoder.append_list(&subsystems, |subsystem| vec![*subsystem]);
+    encoder.append_list(&epochs, codec::encode_u64);
+    encoder.finish()
+}
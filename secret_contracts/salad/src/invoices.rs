@@ -0,0 +1,77 @@
+use eng_wasm::{String, Vec, H160, H256, U256};
+use enigma_crypto::KeyPair;
+use rustc_hex::ToHex;
+
+use crate::amounts;
+use crate::codec::{self, Encoder};
+use crate::protocol::PUB_KEY_SIZE;
+use crate::runtime::Runtime;
+
+// State key prefix of the fee invoices of the deposits of each deal, followed by the hex DealId
+static INVOICES_PREFIX: &str = "invoices_";
+
+/// The fee breakdown of a deposit of a deal
+pub struct Invoice {
+    /// The amount deposited
+    pub gross: U256,
+    /// The amount paid out, held as notes or staged back into the pool for the depositor
+    pub net: U256,
+    /// The part of the fee paid to the operator, the rest being carried into the pool reserve or funding stipends
+    pub relayer_share: U256,
+}
+
+impl Invoice {
+    pub fn fee(&self) -> U256 {
+        if self.gross > self.net { self.gross - self.net } else { U256::zero() }
+    }
+
+    /// Canonical encoding of the invoice as the container (deal_id: Bytes32, gross: uint256, fee: uint256,
+    /// net: uint256, relayer_share: uint256)
+    fn encode(&self, deal_id: &H256) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        encoder.append_bytes(deal_id);
+        encoder.append_bytes(&codec::encode_uint256(&self.gross));
+        encoder.append_bytes(&codec::encode_uint256(&self.fee()));
+        encoder.append_bytes(&codec::encode_uint256(&self.net));
+        encoder.append_bytes(&codec::encode_uint256(&self.relayer_share));
+        encoder.finish()
+    }
+}
+
+fn invoices_key(deal_id: &H256) -> String {
+    format!("{}{}", INVOICES_PREFIX, deal_id.to_hex::<String>())
+}
+
+fn shared_key(keypair: &KeyPair, pub_key: &[u8]) -> [u8; 32] {
+    let mut key = [0_u8; PUB_KEY_SIZE];
+    key.copy_from_slice(pub_key);
+    keypair.derive_key(&key).unwrap_or_else(|err| panic!("Invalid participant public key: {:?}", err))
+}
+
+/// The total of the outputs funded by each of the deposits
+pub fn tally(outputs: &[(usize, H160, U256)], nb_deposits: usize) -> Vec<U256> {
+    let mut totals = vec![U256::zero(); nb_deposits];
+    for (i, _, amount) in outputs.iter() {
+        totals[*i] = amounts::add(&totals[*i], amount);
+    }
+    totals
+}
+
+/// Store the fee invoice of each deposit of the deal, encrypted to the public key of the deposit so that
+/// only its depositor learns what it was charged
+pub fn issue<R: Runtime>(runtime: &R, deal_id: &H256, keypair: &KeyPair, pub_keys: &[Vec<u8>], invoices: &[Invoice]) {
+    let encrypted: Vec<Vec<u8>> = pub_keys.iter().zip(invoices.iter())
+        .map(|(pub_key, invoice)| runtime.encrypt(&invoice.encode(deal_id), &shared_key(keypair, pub_key)))
+        .collect();
+    runtime.write_state(&invoices_key(deal_id), encrypted);
+}
+
+/// The encrypted fee invoice of the deposit at the index in the order of the deal record
+pub fn get<R: Runtime>(runtime: &R, deal_id: &H256, index: usize) -> Vec<u8> {
+    let invoices: Vec<Vec<u8>> = runtime.read_state(&invoices_key(deal_id))
+        .unwrap_or_else(|| panic!("No fee invoices for deal: {:?}", deal_id));
+    match invoices.into_iter().nth(index) {
+        Some(invoice) => invoice,
+        None => panic!("No fee invoice for deposit {} of deal {:?}", index, deal_id),
+    }
+}
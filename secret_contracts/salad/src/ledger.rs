@@ -0,0 +1,330 @@
+use eng_wasm::{String, Vec, H160, H256, U256};
+use enigma_crypto::hash::Keccak256;
+use enigma_crypto::KeyPair;
+use rustc_hex::ToHex;
+use serde::{Deserialize, Serialize};
+
+use crate::amounts;
+use crate::convert;
+use crate::digest;
+use crate::merkle;
+use crate::metrics;
+use crate::domains::{self, NOTE_CLAIM_DOMAIN, NOTE_WITHDRAWAL_DOMAIN, QUEUED_WITHDRAWAL_DOMAIN};
+use crate::protocol::{self, PUB_KEY_SIZE};
+use crate::runtime::Runtime;
+use crate::shuffle::{self, RandomShuffler};
+use crate::state::{self, Address, Hash, Word};
+
+// State key prefix of the notes held for an owner in the ledger, followed by the hex owner address
+static LEDGER_PREFIX: &str = "ledger_";
+// State key prefix of the claim of a note, followed by the hex hash of its claim secret
+static CLAIM_PREFIX: &str = "claim_";
+// State key prefix of the claim codes issued in a deal, followed by the hex DealId
+static CLAIM_CODES_PREFIX: &str = "claim_codes_";
+// State key name "withdrawal_queue" holding the queued withdrawals waiting for a payout batch
+static WITHDRAWAL_QUEUE: &str = "withdrawal_queue";
+// State key name "pending_note_payouts" holding the note withdrawals waiting to be committed with the next deal
+static PENDING_PAYOUTS: &str = "pending_note_payouts";
+// State key prefix of the note withdrawals committed with a deal, followed by the hex DealId
+static COMMITTED_PAYOUTS_PREFIX: &str = "note_payouts_";
+
+/// Size of the random claim secret issued with each note
+pub const CLAIM_SECRET_SIZE: usize = 32;
+
+/// The notes held for an owner, only known to the enclave
+#[derive(Serialize, Deserialize, Default)]
+struct Account {
+    /// The amount and issuing epoch (the number of deals executed before) of each unspent note, oldest first
+    notes: Vec<(Word, u64)>,
+    /// The digests of the withdrawals of the owner, so that none is replayed
+    withdrawals: Vec<Hash>,
+}
+
+impl Account {
+    fn balance(&self) -> U256 {
+        self.notes.iter().fold(U256::zero(), |total, (amount, _)| amounts::add(&total, &amount.0))
+    }
+}
+
+/// A note claimable by anyone holding its claim secret, up to its amount out of the balance of its owner
+#[derive(Serialize, Deserialize)]
+struct Claim {
+    owner: Address,
+    amount: Word,
+    claimed: bool,
+}
+
+/// A withdrawal spent out of the notes of its owner, waiting for a payout batch. The owner is not kept.
+#[derive(Serialize, Deserialize)]
+struct QueuedWithdrawal {
+    payout: Address,
+    amount: Word,
+    /// Priority fee paid to the operator of the batch on top of the amount
+    fee: Word,
+}
+
+/// A withdrawal spent out of the notes, paid out of the pool reserve once committed on-chain with a deal.
+/// Its random nullifier is only paid once by the Mixer contract.
+#[derive(Serialize, Deserialize)]
+struct NotePayout {
+    nullifier: Hash,
+    payout: Address,
+    amount: Word,
+}
+
+impl QueuedWithdrawal {
+    /// The priority tier of the withdrawal, fees within the same power of two sharing a tier
+    fn tier(&self) -> usize {
+        self.fee.0.bits()
+    }
+}
+
+fn account_key(owner: &H160) -> String {
+    state::address_key(LEDGER_PREFIX, owner)
+}
+
+fn get_account<R: Runtime>(runtime: &R, owner: &H160) -> Account {
+    runtime.read_state(&account_key(owner)).unwrap_or_default()
+}
+
+fn claim_key(claim_hash: &H256) -> String {
+    format!("{}{}", CLAIM_PREFIX, claim_hash.to_hex::<String>())
+}
+
+fn claim_codes_key(deal_id: &H256) -> String {
+    format!("{}{}", CLAIM_CODES_PREFIX, deal_id.to_hex::<String>())
+}
+
+fn shared_key(keypair: &KeyPair, pub_key: &[u8]) -> [u8; 32] {
+    let mut key = [0_u8; PUB_KEY_SIZE];
+    key.copy_from_slice(pub_key);
+    keypair.derive_key(&key).unwrap_or_else(|err| panic!("Invalid participant public key: {:?}", err))
+}
+
+/// Hold the outputs of a deal as notes of their recipient, withdrawable later with `withdraw`. Each note is
+/// issued a random claim secret, its hash kept in state, the secrets of the notes of each deposit being
+/// encrypted to the public key of the deposit as its claim code, empty for the deposits without notes.
+pub fn issue<R: Runtime>(runtime: &R, deal_id: &H256, keypair: &KeyPair, pub_keys: &[Vec<u8>], notes: &[&[(H160, U256)]]) {
+    if notes.iter().all(|deposit_notes| deposit_notes.is_empty()) {
+        return;
+    }
+    trace!("Issuing notes of deal {:?}", deal_id);
+    let epoch = metrics::nb_deals(runtime);
+    let mut claim_codes: Vec<Vec<u8>> = Vec::new();
+    for (pub_key, deposit_notes) in pub_keys.iter().zip(notes.iter()) {
+        let mut secrets: Vec<u8> = Vec::new();
+        for (owner, amount) in deposit_notes.iter() {
+            let mut account = get_account(runtime, owner);
+            account.notes.push((Word(*amount), epoch));
+            runtime.write_state(&account_key(owner), account);
+            let secret = runtime.generate_key();
+            let claim = Claim { owner: Address(*owner), amount: Word(*amount), claimed: false };
+            runtime.write_state(&claim_key(&H256::from(&secret.keccak256()[..])), claim);
+            secrets.extend_from_slice(&secret);
+        }
+        claim_codes.push(if secrets.is_empty() { Vec::new() } else { runtime.encrypt(&secrets, &shared_key(keypair, pub_key)) });
+    }
+    runtime.write_state(&claim_codes_key(deal_id), claim_codes);
+}
+
+/// The claim codes issued in a deal, one per deposit in the order of the deal record, each holding the
+/// claim secrets of the notes of the deposit in the order of its splits
+pub fn claim_codes<R: Runtime>(runtime: &R, deal_id: &H256) -> Vec<Vec<u8>> {
+    runtime.read_state(&claim_codes_key(deal_id)).unwrap_or_default()
+}
+
+/// The EIP191 personal message of H(NOTE_WITHDRAWAL_DOMAIN || owner || payout || amount || nonce), addresses
+/// padded to 32 bytes, signed by the owner of the notes, the nonce being chosen by the owner
+fn withdrawal_message(owner: &H160, payout: &H160, amount: &U256, nonce: &U256) -> Vec<u8> {
+    let mut withdrawal: Vec<u8> = Vec::new();
+    for address in [owner, payout].iter() {
+        withdrawal.extend_from_slice(&convert::address_to_word(address));
+    }
+    withdrawal.extend_from_slice(&convert::uint_to_word(amount));
+    withdrawal.extend_from_slice(&convert::uint_to_word(nonce));
+    domains::domain_message(NOTE_WITHDRAWAL_DOMAIN, &withdrawal)
+}
+
+/// Recover the signer of a 65-byte or compact 64-byte signature of `message`
+fn recover(message: &[u8], raw_signature: &[u8], kind: &str) -> H160 {
+    let signature = protocol::ecdsa_signature(raw_signature)
+        .unwrap_or_else(|| panic!("Invalid {} signature size: {}", kind, raw_signature.len()));
+    digest::recover_address(message, signature).unwrap_or_else(|err| panic!("Invalid {} signature: {}", kind, err))
+}
+
+/// Spend the notes of the owner for the amount withdrawn, oldest first, the change being held as a new note
+fn spend<R: Runtime>(runtime: &R, owner: &H160, amount: &U256, withdrawal: &H256) {
+    let mut account = get_account(runtime, owner);
+    let digest = Hash(*withdrawal);
+    if account.withdrawals.contains(&digest) {
+        panic!("Withdrawal already made: {:?}", withdrawal);
+    }
+    let balance = account.balance();
+    if amount.is_zero() || *amount > balance {
+        panic!("Invalid withdrawal amount: {} of {}", amount, balance);
+    }
+    let mut spent = U256::zero();
+    while spent < *amount {
+        let (note, _) = account.notes.remove(0);
+        spent = amounts::add(&spent, &note.0);
+    }
+    if spent > *amount {
+        account.notes.insert(0, (Word(amounts::sub(&spent, amount)), metrics::nb_deals(runtime)));
+    }
+    account.withdrawals.push(digest);
+    runtime.write_state(&account_key(owner), account);
+}
+
+/// Withdraw an amount of the notes of `owner` to `payout`, from the withdrawal signed by the owner
+pub fn withdraw<R: Runtime>(runtime: &R, owner: &H160, payout: &H160, amount: &U256, nonce: &U256, raw_signature: &[u8]) {
+    let message = withdrawal_message(owner, payout, amount, nonce);
+    let signer = recover(&message, raw_signature, "withdrawal");
+    if signer != *owner {
+        panic!("Withdrawal not signed by the owner of the notes: {:?}", signer);
+    }
+    spend(runtime, owner, amount, &H256::from(&message.keccak256()[..]));
+    trace!("Withdrawing notes to {:?}", payout);
+}
+
+/// The EIP191 personal message of H(NOTE_CLAIM_DOMAIN || claim_hash || payout), the payout address padded
+/// to 32 bytes, signed by the payout address so that the claim secret is only good for the address it binds
+fn claim_message(claim_hash: &H256, payout: &H160) -> Vec<u8> {
+    let mut claim: Vec<u8> = Vec::new();
+    claim.extend_from_slice(claim_hash.as_ref());
+    claim.extend_from_slice(&[0_u8; 12]);
+    claim.extend_from_slice(payout.as_ref());
+    domains::domain_message(NOTE_CLAIM_DOMAIN, &claim)
+}
+
+/// Claim the note of a claim secret to `payout`, from the claim signed by the payout address, no signature of
+/// the owner being needed. Returns the amount of the note, spent out of the balance of its owner.
+pub fn claim<R: Runtime>(runtime: &R, secret: &[u8], payout: &H160, raw_signature: &[u8]) -> U256 {
+    if secret.len() != CLAIM_SECRET_SIZE {
+        panic!("Invalid claim secret size: {}", secret.len());
+    }
+    let claim_hash = H256::from(&secret.keccak256()[..]);
+    let key = claim_key(&claim_hash);
+    let mut claim: Claim = runtime.read_state(&key).expect("Unknown claim secret");
+    if claim.claimed {
+        panic!("Note already claimed");
+    }
+    let signer = recover(&claim_message(&claim_hash, payout), raw_signature, "claim");
+    if signer != *payout {
+        panic!("Claim not signed by the payout address: {:?}", signer);
+    }
+    let owner = claim.owner.0;
+    let amount = claim.amount.0;
+    spend(runtime, &owner, &amount, &claim_hash);
+    claim.claimed = true;
+    runtime.write_state(&key, claim);
+    trace!("Claiming note to {:?}", payout);
+    amount
+}
+
+/// The EIP191 personal message of H(QUEUED_WITHDRAWAL_DOMAIN || owner || payout || amount || fee || nonce),
+/// addresses padded to 32 bytes, signed by the owner of the notes so that the priority fee is bound
+fn queued_withdrawal_message(owner: &H160, payout: &H160, amount: &U256, fee: &U256, nonce: &U256) -> Vec<u8> {
+    let mut withdrawal: Vec<u8> = Vec::new();
+    for address in [owner, payout].iter() {
+        withdrawal.extend_from_slice(&convert::address_to_word(address));
+    }
+    withdrawal.extend_from_slice(&convert::uint_to_word(amount));
+    withdrawal.extend_from_slice(&convert::uint_to_word(fee));
+    withdrawal.extend_from_slice(&convert::uint_to_word(nonce));
+    domains::domain_message(QUEUED_WITHDRAWAL_DOMAIN, &withdrawal)
+}
+
+fn committed_payouts_key(deal_id: &H256) -> String {
+    format!("{}{}", COMMITTED_PAYOUTS_PREFIX, deal_id.to_hex::<String>())
+}
+
+/// Hold the payout of a withdrawal spent out of the notes until the next deal commits it on-chain,
+/// the Mixer contract only paying the withdrawals committed with a deal
+pub fn hold_payout<R: Runtime>(runtime: &R, payout: &H160, amount: &U256) {
+    let mut pending: Vec<NotePayout> = runtime.read_state(PENDING_PAYOUTS).unwrap_or_default();
+    pending.push(NotePayout {
+        nullifier: Hash(H256::from(&runtime.generate_key()[..])),
+        payout: Address(*payout),
+        amount: Word(*amount),
+    });
+    trace!("Holding note payout {} until the next deal", pending.len());
+    runtime.write_state(PENDING_PAYOUTS, pending);
+}
+
+fn payout_leaves(payouts: &[NotePayout]) -> Vec<Vec<u8>> {
+    payouts.iter()
+        .map(|payout| merkle::note_payout_leaf(&payout.nullifier.0, &payout.payout.0, &payout.amount.0))
+        .collect()
+}
+
+/// Commit the pending note payouts with the deal, returning their Merkle root and number to post on-chain
+/// before the roots of the deal, if any is pending
+pub fn commit_payouts<R: Runtime>(runtime: &R, deal_id: &H256) -> Option<(H256, usize)> {
+    let pending: Vec<NotePayout> = runtime.read_state(PENDING_PAYOUTS).unwrap_or_default();
+    if pending.is_empty() {
+        return None;
+    }
+    let root = merkle::root(&payout_leaves(&pending));
+    let nb_payouts = pending.len();
+    trace!("Committing {} note payouts with deal {:?}", nb_payouts, deal_id);
+    runtime.write_state(&committed_payouts_key(deal_id), pending);
+    runtime.write_state(PENDING_PAYOUTS, Vec::<NotePayout>::new());
+    Some((root, nb_payouts))
+}
+
+/// The (nullifier, payout, amount, proof) of each note payout committed with the deal, in committed order
+pub fn committed_payouts<R: Runtime>(runtime: &R, deal_id: &H256) -> Vec<(H256, H160, U256, Vec<H256>)> {
+    let payouts: Vec<NotePayout> = runtime.read_state(&committed_payouts_key(deal_id)).unwrap_or_default();
+    let leaves = payout_leaves(&payouts);
+    payouts.iter().enumerate().map(|(i, payout)| (
+        payout.nullifier.0,
+        payout.payout.0,
+        payout.amount.0,
+        merkle::proof(&leaves, i),
+    )).collect()
+}
+
+fn get_queue<R: Runtime>(runtime: &R) -> Vec<QueuedWithdrawal> {
+    runtime.read_state(WITHDRAWAL_QUEUE).unwrap_or_default()
+}
+
+/// Queue a withdrawal of an amount of the notes of `owner` to `payout`, from the withdrawal signed by the owner.
+/// The amount and the priority fee are spent from the notes right away, the amount being paid out by a later batch.
+pub fn enqueue<R: Runtime>(runtime: &R, owner: &H160, payout: &H160, amount: &U256, fee: &U256, nonce: &U256, raw_signature: &[u8]) {
+    if amount.is_zero() {
+        panic!("Invalid queued withdrawal amount: {}", amount);
+    }
+    let message = queued_withdrawal_message(owner, payout, amount, fee, nonce);
+    let signer = recover(&message, raw_signature, "queued withdrawal");
+    if signer != *owner {
+        panic!("Queued withdrawal not signed by the owner of the notes: {:?}", signer);
+    }
+    spend(runtime, owner, &amounts::add(amount, fee), &H256::from(&message.keccak256()[..]));
+    let mut queue = get_queue(runtime);
+    queue.push(QueuedWithdrawal { payout: Address(*payout), amount: Word(*amount), fee: Word(*fee) });
+    trace!("Queuing withdrawal {} of the queue", queue.len());
+    runtime.write_state(WITHDRAWAL_QUEUE, queue);
+}
+
+/// Take the next batch of at most `max` queued withdrawals, highest priority tier first, the withdrawals of a tier
+/// being ordered by a shuffle seeded from `seed` so that no position within a tier can be bought or predicted.
+/// Returns the (payout, amount) of the batch and the total of its priority fees.
+pub fn next_batch<R: Runtime>(runtime: &R, max: usize, seed: u64) -> (Vec<(H160, U256)>, U256) {
+    let queue = get_queue(runtime);
+    let mut order: Vec<usize> = (0..queue.len()).collect();
+    // Shuffling the whole queue then stably sorting it by tier leaves each tier in shuffled order
+    shuffle::apply(&RandomShuffler { seed }, &mut order, |i| queue[*i].payout.0);
+    order.sort_by(|a, b| queue[*b].tier().cmp(&queue[*a].tier()));
+    order.truncate(max);
+    let batch: Vec<(H160, U256)> = order.iter()
+        .map(|i| (queue[*i].payout.0, queue[*i].amount.0))
+        .collect();
+    let fees = order.iter().fold(U256::zero(), |total, i| amounts::add(&total, &queue[*i].fee.0));
+    let remaining: Vec<QueuedWithdrawal> = queue.into_iter().enumerate()
+        .filter(|(i, _)| !order.contains(i))
+        .map(|(_, withdrawal)| withdrawal)
+        .collect();
+    trace!("Paying out {} queued withdrawals, {} left", batch.len(), remaining.len());
+    runtime.write_state(WITHDRAWAL_QUEUE, remaining);
+    (batch, fees)
+}
@@ -1,262 +1,1520 @@
 use eng_wasm::*;
-use eng_wasm::{String, Vec, H160, H256, U256, eprint, decrypt, generate_key, SymmetricKey, Rand};
-use eng_wasm_derive::eth_contract;
+use eng_wasm::{String, Vec, H160, H256, U256};
 use eng_wasm_derive::pub_interface;
-use enigma_crypto::hash::Keccak256;
 use enigma_crypto::KeyPair;
 use rustc_hex::ToHex;
+use std::cmp;
+use std::ops::Range;
+use capsule::DealCapsule;
+use checkpoint::Checkpoint;
+use compliance::ComplianceEngine;
+use deals::{DealRecord, DealStatus};
+use digest::{DepositHasher, DepositTerms};
+use evidence::Misbehavior;
+use invoices::Invoice;
+use notifications::Notification;
+use oracle::PriceRound;
+use payout::{DirectEth, Payout, PayoutAdapter};
+use receipts::DealStats;
+use redeposits::StagedDeposit;
+use protocol::PUB_KEY_SIZE;
+use enclave::HOST;
+use runtime::Runtime;
+use state::{Address, Hash};
+use verification::VerifiedDeposit;
+use salad_core::{amounts, kdf, oracle, parsing, profile, runtime, shuffle, state, validation, wire, MAX_PARTICIPANTS};
+use salad_types::{calldata, codec, convert, digest, domains, merkle, protocol};
 
-#[eth_contract("ISalad.json")]
-struct EthContract;
+// Declared first, for its `trace!` macro to be in scope of the other modules
+#[macro_use]
+mod trace;
+mod allowlist;
+mod anomaly;
+mod audit;
+#[cfg(feature = "bls")]
+mod bls;
+mod buckets;
+mod cancellation;
+mod capsule;
+mod challenge;
+mod checkpoint;
+mod compliance;
+mod config;
+mod deals;
+mod deployment;
+mod verification;
+mod distribution;
+mod dummies;
+mod enclave;
+mod entropy;
+mod escrow;
+mod eth;
+mod evidence;
+mod facts;
+mod features;
+mod fees;
+mod freeze;
+mod handles;
+mod health;
+mod invoices;
+mod ledger;
+mod metering;
+mod metrics;
+mod migration;
+mod notifications;
+mod operators;
+mod ordering;
+mod pagination;
+mod payout;
+mod preview;
+mod provenance;
+mod receipts;
+mod redeposits;
+mod relay;
+mod retention;
+mod risk;
+mod schedule;
+mod schema;
+mod selftest;
+mod signature;
+mod standby;
+mod stipend;
+mod threshold;
+mod tokens;
+mod vectors;
+mod velocity;
 
 // State key name "mixer_eth_addr" holding eth address of Mixer contract
 static MIXER_ETH_ADDR: &str = "mixer_eth_addr";
-static ENCRYPTION_KEY: &str = "encryption_key";
-
-const PUB_KEY_SIZE: usize = 64;
-const UNIT256_SIZE: usize = 32;
-const SIG_SIZE: usize = 65;
-const ADDRESS_SIZE: usize = 20;
 
 #[pub_interface]
 trait ContractInterface {
-    /// Constructor function that takes in MIXER_ETH_ADDR ethereum contract address
-    fn construct(mixer_eth_addr: H160);
+    /// Constructor function that takes in MIXER_ETH_ADDR ethereum contract address,
+    /// the allowlist of oracle signers attesting asset prices, the output ordering policy,
+    /// the Merkle root of the allowed recipients, zero for a pool open to any recipient,
+    /// and the max deposits and volume of each sender per epoch of `velocity_epoch_deals` deals,
+    /// zero epochs disabling the velocity limits and zero caps being unlimited,
+    /// the public keys of the auditors with their roles (0 reading the receipts, 1 also the deal records),
+    /// the dust policy (0 refunding, 1 paying the operator, 2 carrying into the pool reserve) of the outputs
+    /// below `dust_threshold`, a zero threshold disabling the policy,
+    /// the addresses of the keys signing the deal payloads of the operators,
+    /// the min and max deal amounts, a zero max leaving the amounts unbounded, and the max total value
+    /// (amount × participants) of a deal, a zero max leaving the deal values unbounded,
+    /// the gas stipend paid out of the operator fee to fresh recipients, zero disabling the stipends,
+    /// the max number of dummy outputs added to each deal, zero disabling the dummy outputs,
+    /// the percentage of the senders of a deal whose votes cancel it, zero disabling the cancel votes,
+    /// the unit of the 1-2-5 amount buckets the outputs are rounded down to, the remainders being held as notes
+    /// in the enclave ledger, zero leaving the outputs unrounded,
+    /// the number of deals between the proposal of a new Mixer address and its application, zero disabling the migrations,
+    /// and the network profile (0 mainnet, 1 ropsten, 2 local-sim) bundling the chain id, the deposit
+    /// confirmations, the deposits verified per task and the simulation entry points of the deployment.
+    /// Refused for a zero MIXER_ETH_ADDR address, and once the contract is constructed.
+    fn construct(
+        mixer_eth_addr: H160,
+        oracle_signers: Vec<H160>,
+        output_ordering: U256,
+        recipient_allowlist_root: H256,
+        velocity_epoch_deals: U256,
+        velocity_max_deposits: U256,
+        velocity_max_volume: U256,
+        auditor_pub_keys: Vec<Vec<u8>>,
+        auditor_roles: Vec<U256>,
+        dust_policy: U256,
+        dust_threshold: U256,
+        operator_addresses: Vec<H160>,
+        operator_signers: Vec<H160>,
+        min_amount: U256,
+        max_amount: U256,
+        max_deal_value: U256,
+        gas_stipend: U256,
+        max_dummy_outputs: U256,
+        cancel_quorum: U256,
+        bucket_unit: U256,
+        mixer_timelock: U256,
+        network_profile: U256,
+    );
 
     fn get_pub_key() -> Vec<u8>;
 
+    /// The initialization record written by `construct`, canonically encoded as the container
+    /// (initialized: uint8, mixer: Bytes20, pub_key_hash: Bytes32, signing_pub_key_hash: Bytes32),
+    /// zeroed before construction
+    fn is_initialized() -> Vec<u8>;
+
+    /// The public key of the enclave signing the challenge responses and misbehavior evidence
+    fn get_signing_pub_key() -> Vec<u8>;
+
+    /// The deposit limits of the contract, canonically encoded as the container
+    /// (max_participants: uint64, max_payload_size: uint64, envelope_versions: List[uint8], signature_modes: List[uint8])
+    fn get_limits() -> Vec<u8>;
+
+    /// JSON description of the deposit message, payload envelopes and protocol codes, for the operator
+    /// and wallet integrations to be generated from the definitions the enclave verifies against
+    fn get_schema() -> Vec<u8>;
+
+    /// The cumulated sizes of the executed deals along with the number of rejected deposits per
+    /// (envelope version, message version) of their depositor and the privacy statistics of the deals,
+    /// canonically encoded as the container (deals: uint64, participants: uint64, outputs: uint64,
+    /// reject_envelope_versions: List[uint8], reject_message_versions: List[uint8], reject_counts: List[uint64],
+    /// median_anonymity_set: uint64, fresh_recipients_bps: uint64, refunds_bps: uint64)
+    fn get_metrics() -> Vec<u8>;
+
+    /// The last activity of each subsystem (0 deal executed, 1 deposit share staged back into the pool,
+    /// 2 operator key registered or handed off), canonically encoded as the container (deals: uint64,
+    /// last_deal_block: uint64, subsystems: List[uint8], last_epochs: List[uint64]), each epoch being the number
+    /// of deals executed before the activity, so that monitoring tells a stalled mixer from an idle one
+    fn health() -> Vec<u8>;
+
+    /// The anomaly the circuit breaker is tripped on (0 spike in the deals per window of blocks, 1 outputs
+    /// repeatedly paying the recipients of the recent deals, 2 abnormal ratio of rejected deposits), canonically
+    /// encoded as the container (kind: uint8, epoch: uint64, observed: uint64, threshold: uint64), the ratios being
+    /// in basis points, empty when not tripped. No new deal executes until the anomaly is acknowledged.
+    fn get_anomaly() -> Vec<u8>;
+
+    /// Schedule the planned deal, returning the block drawn from enclave entropy from which it may execute,
+    /// within a window after the block attested by an oracle signer in `block_attestation`. The deal may then
+    /// only execute during the `window` blocks following that block, at most the max execution window of the
+    /// network profile. Attested blocks older than the latest one attested to the enclave are refused.
+    /// The deal depends on the attested block, which must be `confirmations` blocks deep, at least the min
+    /// confirmations of the network profile and at most the window, before the deal executes.
+    /// Only the operator of the deal schedules it: the deal of `amount` between `senders` planned by
+    /// `operator_address` with `operator_nonce` must be the deal committed on-chain, and `signature` the
+    /// signature of the schedule, binding the window and the confirmations, by the key of the operator.
+    fn schedule_deal(
+        deal_id: H256,
+        window: U256,
+        confirmations: U256,
+        block_attestation: Vec<u8>,
+        block_signature: Vec<u8>,
+        amount: U256,
+        senders: Vec<H160>,
+        operator_address: H160,
+        operator_nonce: U256,
+        signature: Vec<u8>,
+    ) -> U256;
+
+    /// Invalidate an on-chain fact relayed by the operator, identified by the keccak256 of its attestation, from
+    /// the `proof` of an oracle signer that it was reorged out of the chain. The deals depending on it are rolled
+    /// back to be scheduled again, and the attestation is refused from then on.
+    fn invalidate_relayed_fact(fact_id: H256, proof: Vec<u8>);
+
+    /// Execute a deal bonded by the operator bond reference `operator_bond`. Deals with deposits
+    /// failing verification are not executed, their evidence being recorded against the operator bond.
+    /// So are deals with deposits confirmed less than the min deposit age of the network profile before the block attested
+    /// by an oracle signer in `block_attestation`, the attested block being bound into the receipt.
+    /// Deals are refused outside of their execution window, and unless `operator_signature` is the signature
+    /// of the deal payload by the registered key of the operator.
+    /// Once the execution window of the deal is checked, its participants along with their deposits are frozen,
+    /// a retried or resumed execution adding, removing or substituting any of them being refused.
+    /// Undecryptable deposits, deposits screened out by the compliance engine, and deposits attested after
+    /// the expiry block signed by their depositor in `expiries`, are refunded to their sender among the deal outputs.
+    /// `features` is the bitfield of the optional behaviors negotiated for the deal (1 delayed outputs, 2 dummy
+    /// outputs, 4 coalescing, 8 memos), refused unless supported by the deployment. Deposits relying on features
+    /// not negotiated are refunded to their sender among the deal outputs.
+    /// `nb_redeemed` is the number of deposits staged back into the pool joining the deal, as recorded on-chain
+    /// when the deal was created, refused unless that many deposits of the amount are staged.
+    /// Deals of more than the deposits per task of the network profile only verify that many deposits first,
+    /// returning no recipients, their execution being resumed with `continue_deal`.
     fn execute_deal(
         operator_address: H160,
         operator_nonce: U256,
+        operator_bond: H256,
         amount: U256,
         pub_keys: Vec<Vec<u8>>,
         enc_recipients: Vec<Vec<u8>>,
         senders: Vec<H160>,
         signatures: Vec<Vec<u8>>,
+        expiries: Vec<U256>,
         chain_id: U256,
+        features: U256,
+        nb_redeemed: U256,
+        aggregate_signature: Vec<u8>,
+        block_attestation: Vec<u8>,
+        block_signature: Vec<u8>,
+        operator_signature: Vec<u8>,
     ) -> Vec<H160>;
 
+    /// Resume the execution of a deal too large for a single task, verifying its next deposits per task
+    /// deposits, and return the number of deposits left to verify. Once all deposits are verified,
+    /// the deal is executed like in `execute_deal`. The deposits screened by each task are recorded
+    /// in the velocity limits of their senders even if the deal then fails.
+    fn continue_deal(deal_id: H256) -> U256;
+
     fn verify_deposits(
         amount: U256,
         pub_keys: Vec<Vec<u8>>,
         enc_recipients: Vec<Vec<u8>>,
         senders: Vec<H160>,
         signatures: Vec<Vec<u8>>,
+        expiries: Vec<U256>,
+        chain_id: U256,
+        aggregate_signature: Vec<u8>,
+    ) -> Vec<Vec<u8>>;
+
+    /// Preview the deal of the deposits the operator is about to commit, returning the canonical encoding of
+    /// the report of the duplicate deposits, the deposits of another amount than `amount`, the deposits whose
+    /// payload does not open or screened out by the compliance policies, and the size of the deal inputs.
+    /// Nothing is recorded, the signatures being verified by `verify_deposits`.
+    fn preview_deal(
+        amount: U256,
+        amounts: Vec<U256>,
+        pub_keys: Vec<Vec<u8>>,
+        enc_recipients: Vec<Vec<u8>>,
+        senders: Vec<H160>,
+        signatures: Vec<Vec<u8>>,
+        expiries: Vec<U256>,
+    ) -> Vec<u8>;
+
+    /// Execute a deal where each participant deposits a (possibly different) asset of equal USD value.
+    /// Each encrypted payload holds the recipient address followed by the requested output asset.
+    /// The deal created by `newAssetDeal` on the Mixer is committed, its outputs being paid in their assets
+    /// by `finalize_distribution` past the challenge window.
+    fn execute_multi_asset_deal(
+        operator_address: H160,
+        operator_nonce: U256,
+        price_attestation: Vec<u8>,
+        price_signature: Vec<u8>,
+        assets: Vec<H160>,
+        amounts: Vec<U256>,
+        pub_keys: Vec<Vec<u8>>,
+        enc_recipients: Vec<Vec<u8>>,
+        senders: Vec<H160>,
+        signatures: Vec<Vec<u8>>,
         chain_id: U256,
-    ) -> bool;
+    ) -> Vec<H160>;
+
+    /// Execute a deal of voucher deposits, listing the opaque voucher ids issued by the Mixer contract
+    /// instead of the sender addresses, along with the commitments the deposits were made with.
+    /// The DealId binds each voucher to its on-chain commitment, each payload being checked against its commitment.
+    /// The senders being unknown to the enclave, a deal with deposits failing verification is rejected with their
+    /// blinded handles, for the operator to renew the deal without them.
+    fn execute_voucher_deal(
+        operator_address: H160,
+        operator_nonce: U256,
+        operator_bond: H256,
+        amount: U256,
+        vouchers: Vec<H256>,
+        commitments: Vec<H256>,
+        pub_keys: Vec<Vec<u8>>,
+        enc_recipients: Vec<Vec<u8>>,
+    ) -> Vec<H160>;
+
+    /// Distribute the outputs of a deal whose Merkle roots were committed by `execute_deal`,
+    /// once the on-chain challenge window has elapsed and every challenge opened on the deal is answered
+    fn finalize_distribution(deal_id: H256);
+
+    /// Preview the exact ABI-encoded calldata of the distribution callout of a committed deal, encrypted to
+    /// `operator_pub_key`, `signature` being the signature by the registered key of the operator of the deal of
+    /// the personal message of H("Salad Distribution Preview" || DealId || operator_pub_key). The deal is then
+    /// distributed in two steps, by `confirm_distribution` only, so that the operator tooling sanity-checks the
+    /// addresses, amounts and gas of the irreversible callout first.
+    fn preview_distribution(deal_id: H256, operator_pub_key: Vec<u8>, signature: Vec<u8>) -> Vec<u8>;
+
+    /// Distribute the outputs of a previewed deal as `finalize_distribution` does, refused unless `calldata_hash`
+    /// is the keccak256 of the calldata previewed and the calldata of the callout is unchanged since
+    fn confirm_distribution(deal_id: H256, calldata_hash: H256);
+
+    /// Retry the distribution callout of a deal whose distribution failed or was reverted on-chain.
+    /// Past MAX_DISTRIBUTION_RETRIES, the deposits are refunded to their senders instead, so that
+    /// no deal ends revealed but unpaid. The refunds of deals never committed are retried with the `refundDeal`
+    /// callout. Callouts of deals already paid on-chain revert.
+    fn retry_distribution(deal_id: H256);
+
+    /// Vote for the cancellation of the deal of `amount` between `senders` planned by `operator_address`
+    /// with `operator_nonce`, `signature` being the signature of the cancellation by one of the senders.
+    /// Once the votes reach the cancel quorum, the deal is refunded to its senders instead of being executed,
+    /// with the `refundDeal` callout paid past the execution window of the deal on-chain.
+    /// Returns the number of votes so far.
+    fn vote_cancel_deal(amount: U256, senders: Vec<H160>, operator_address: H160, operator_nonce: U256, signature: Vec<u8>) -> U256;
+
+    /// Refund the deposits of a deal left unexecuted past its execution window, so that its depositors recover
+    /// their funds even if the operator disappears. Permissionless: any keeper may trigger it with the deal of
+    /// `amount` between `senders` planned by `operator_address` with `operator_nonce`, as committed on-chain,
+    /// and `current_block` signed by an oracle signer in `block_signature` (the block attestation of no deposits).
+    /// A deal stalled while resumed with `continue_deal` is refunded likewise. Deals scheduled before the
+    /// execution windows never time out. Returns the number of deposits refunded.
+    fn trigger_timeout(
+        deal_id: H256,
+        current_block: U256,
+        block_signature: Vec<u8>,
+        amount: U256,
+        senders: Vec<H160>,
+        operator_address: H160,
+        operator_nonce: U256,
+    ) -> U256;
+
+    /// Record the receipt hash of a distributed deal on-chain with the `recordReceipt` callout, timestamping
+    /// the receipt. A failed callout is retried by calling again, up to MAX_RECEIPT_RETRIES times.
+    /// Callouts of receipts already recorded, or of deals not yet paid on-chain, revert.
+    fn anchor_receipt(deal_id: H256);
+
+    /// Open a challenge on the inclusion of a deposit in a deal, the proof being the depositor signature
+    /// of the challenge message
+    fn open_challenge(deal_id: H256, deposit_digest: H256, proof: Vec<u8>);
+
+    /// Respond to an open challenge with an enclave-signed inclusion statement
+    fn respond_challenge(deal_id: H256, deposit_digest: H256) -> Vec<u8>;
+
+    /// The digests of the deposits whose challenge in the deal is unanswered, holding back its distribution
+    fn get_open_challenges(deal_id: H256) -> Vec<H256>;
+
+    /// Recommend the max participants per deal for a task gas limit, from the recorded deal metrics
+    /// and the average decryption cost of a deposit observed by the operator
+    fn suggest_batch_size(avg_decrypt_cost: U256, task_gas_limit: U256) -> U256;
+
+    /// A page of at most `limit` (capped at MAX_PAGE_SIZE) signed misbehavior evidence recorded against
+    /// the operator of a deal from `offset`, each being the canonical encoding of the misbehavior
+    /// followed by the enclave signature
+    fn get_evidence(deal_id: H256, offset: U256, limit: U256) -> Vec<Vec<u8>>;
+
+    /// The number of misbehavior evidence recorded against the operator of a deal
+    fn get_evidence_count(deal_id: H256) -> U256;
+
+    /// A page of at most `limit` (capped at MAX_PAGE_SIZE) DealIds of the executed deals from `offset`,
+    /// most recent first
+    fn list_recent_deals(offset: U256, limit: U256) -> Vec<H256>;
+
+    /// The number of executed deals
+    fn get_deal_count() -> U256;
+
+    /// The audit envelopes of a deal sealed to the registered auditors: the envelope of the receipt,
+    /// then the envelope of the deal record, each openable by the auditors whose role reads its scope
+    fn get_audit_envelopes(deal_id: H256) -> Vec<Vec<u8>>;
+
+    /// The statistics of a deal for public dashboards, free of per-participant information, as the canonical
+    /// encoding of the statistics followed by the enclave signature, empty for deals not executed
+    fn get_deal_receipt(deal_id: H256) -> Vec<u8>;
+
+    /// The commitment H(deal_id || seed || salt) to the seed the outputs of a deal were shuffled with, recorded
+    /// when the deal executes, zero for the deals without a record or shuffled before the commitments
+    fn get_shuffle_commitment(deal_id: H256) -> H256;
+
+    /// The seed the outputs of a deal were shuffled with, canonically encoded as the container (seed: uint64,
+    /// salt: Bytes32, commitment: Bytes32), refused until the deal is distributed, so that the permutation
+    /// can be audited without ever being predictable in advance
+    fn reveal_shuffle_seed(deal_id: H256) -> Vec<u8>;
+
+    /// The number of deposits staged back into the pool per denomination and staging epoch (the number
+    /// of deals executed before staging), canonically encoded as the container
+    /// (denominations: List[uint256], epochs: List[uint64], counts: List[uint64]), never their identities
+    fn get_staged_summary() -> Vec<u8>;
+
+    /// The number of deposits staged back into the pool that would join a deal of `amount` with `nb_senders`
+    /// senders, to record on-chain when creating the deal
+    fn get_redeemable(amount: U256, nb_senders: U256) -> U256;
+
+    /// Check the key derivation, sign/recover, encrypt/decrypt and shuffle round-trips inside the enclave
+    /// after deployment, returning the canonical encoding of the container (results: List[uint8], passed: uint8)
+    /// followed by the enclave signature
+    fn self_test() -> Vec<u8>;
+
+    /// The build metadata embedded at compile time: the git commit of the sources, the crate version, the
+    /// protocol versions and the Cargo features, canonically encoded as the container (git_commit: List[uint8],
+    /// crate_version: List[uint8], message_version: uint8, capsule_version: uint8, envelope_versions: List[uint8],
+    /// cargo_features: uint8) followed by the signature of the enclave signing key, which also signs the receipts
+    fn get_build_info() -> Vec<u8>;
+
+    /// The effective configuration of the deployment, as the canonical encoding of its snapshot
+    fn get_config() -> Vec<u8>;
+
+    /// The bitfield of the deal features supported by the config of the deployment, to be negotiated
+    /// in `execute_deal`: delayed outputs and memos always, dummy outputs and coalescing once configured
+    fn get_supported_features() -> U256;
+
+    /// The network profile selected at construction, as (id, chain_id, min_deposit_age, deposits_per_task,
+    /// max_execution_window, min_confirmations), for the operator to size its tasks and confirmations by
+    fn get_network_profile() -> Vec<U256>;
+
+    /// The last `n` configuration changes of the admin operations (deployment, threshold configuration,
+    /// relayer registration, operator registration or handoff, standby registration, standby promotion,
+    /// deal value cap override),
+    /// most recent first, each encoded with the snapshot following the operation
+    fn get_config_history(n: U256) -> Vec<Vec<u8>>;
+
+    /// Share the recipient payloads t-of-n across Salad instances, from an oracle-signed `attestation`
+    /// of the threshold, the index of this instance and the encryption public keys of the instances
+    /// (32-byte words then 64-byte keys) after the nonce of the command. Configured once per instance.
+    fn configure_threshold(attestation: Vec<u8>, signature: Vec<u8>);
+
+    /// Register a secret contract relaying deposits, from an oracle-signed `attestation` of the address
+    /// of its enclave signing key and of the Ethereum contract making its deposits (padded 32-byte words)
+    /// after the nonce of the command.
+    /// Its deposits carry the relayed signature mode, the sender being its depositing contract.
+    fn register_relayer(attestation: Vec<u8>, signature: Vec<u8>);
+
+    /// Override the max total value of a deal set at construction, from an oracle-signed `attestation` of the
+    /// new cap (a 32-byte word) after the nonce of the command, zero leaving the deal values unbounded, for the institutional deployments
+    /// mixing larger deals than the cap allows
+    fn override_deal_value_cap(attestation: Vec<u8>, signature: Vec<u8>);
+
+    /// Set the bridge contract the outputs of the deals are relayed through, from an oracle-signed `attestation`
+    /// of its address (a padded 32-byte word) after the nonce of the command, zero paying the outputs directly. The deals with escrowed outputs
+    /// are still paid through the escrow, and the refunds paid back to their senders directly. The bridge is
+    /// committed on the Mixer with `setPayoutBridge`, the Mixer relaying the outputs through no other bridge.
+    fn set_payout_bridge(attestation: Vec<u8>, signature: Vec<u8>);
+
+    /// Set the retention policy enforced by `prune`, from an oracle-signed `attestation` of the epochs the deal
+    /// records and the recipient notifications are kept for (two 32-byte words) after the nonce of the command,
+    /// zero keeping them forever.
+    /// The receipts are kept forever.
+    fn set_retention_policy(attestation: Vec<u8>, signature: Vec<u8>);
+
+    /// Reset the circuit breaker tripped by an anomaly, from an oracle-signed `attestation` of the epoch it
+    /// tripped at (a 32-byte word) after the nonce of the command, as returned by `get_anomaly`, so that the deals execute again once the
+    /// operator pipeline is cleared
+    fn acknowledge_anomaly(attestation: Vec<u8>, signature: Vec<u8>);
+
+    /// Add a token to the multi-asset deals or replace its config, from an oracle-signed `attestation` of the
+    /// token, its decimals (up to 18), its min and max amounts in base units, a zero max leaving them unbounded,
+    /// its fee rate in basis points of the outputs and its adapter contract, zero paying the token directly
+    /// (padded 32-byte words) after the nonce of the command. Once a token is added, the deals refuse the assets
+    /// not in the table.
+    fn set_token_config(attestation: Vec<u8>, signature: Vec<u8>);
+
+    /// Remove a token from the multi-asset deals, from an oracle-signed `attestation` of the token (a padded 32-byte word)
+    /// after the nonce of the command
+    fn remove_token_config(attestation: Vec<u8>, signature: Vec<u8>);
+
+    /// The config of `token`, canonically encoded as the container (token: Bytes20, decimals: uint8,
+    /// min_amount: uint256, max_amount: uint256, fee_rate: uint64, adapter: Bytes20), empty when not supported
+    fn get_token_config(token: H160) -> Vec<u8>;
+
+    /// The tokens supported by the multi-asset deals, in the order they were added
+    fn get_tokens() -> Vec<H160>;
+
+    /// Propose re-pointing the contract at a new Mixer, from an oracle-signed `attestation` of its address
+    /// (a padded 32-byte word) after the nonce of the command, applicable with `apply_mixer_address` once the
+    /// timelock set at construction has elapsed. Replaces the migration pending, if any, restarting its timelock,
+    /// the address pending being refused.
+    fn propose_mixer_address(attestation: Vec<u8>, signature: Vec<u8>);
+
+    /// Re-point the contract at the Mixer address pending, refused until its timelock has elapsed
+    fn apply_mixer_address();
+
+    /// Register the key signing the deal payloads of an operator, from an oracle-signed `attestation`
+    /// of the operator address and of the signer address (padded 32-byte words) after the nonce of the command,
+    /// replacing its previous key
+    fn register_operator(attestation: Vec<u8>, signature: Vec<u8>);
+
+    /// Propose handing the deal payloads of `operator` off to the key of `new_signer_pub_key`, `signature` being
+    /// the signature of the handoff by the current key of the operator
+    fn propose_operator(operator: H160, new_signer_pub_key: Vec<u8>, signature: Vec<u8>);
+
+    /// Accept the handoff pending for `operator`, `signature` being the signature of the handoff by its new key.
+    /// The replaced key keeps signing the deal payloads of the operator during a grace period of HANDOFF_GRACE_DEALS deals.
+    fn accept_operator(operator: H160, signature: Vec<u8>);
+
+    /// The handoff state of `operator`, canonically encoded as the container (nonce: uint64, pending_signer: Bytes20)
+    fn get_operator_handoff(operator: H160) -> Vec<u8>;
+
+    /// Register the cold standby instance of this instance, from an oracle-signed `attestation` of its
+    /// encryption public key after the nonce of the command, replacing the previous standby instance, if any
+    fn register_standby(attestation: Vec<u8>, signature: Vec<u8>);
+
+    /// The state replicated to the standby instance changed after revision `since`, its master seed and
+    /// staged deposits, encrypted to the standby instance, to be imported there with `import_standby_delta`
+    fn export_standby_delta(since: U256) -> Vec<u8>;
+
+    /// Import a delta exported for this standby instance by the instance of `primary_pub_key`, in revision order
+    fn import_standby_delta(primary_pub_key: Vec<u8>, delta: Vec<u8>);
+
+    /// Promote this standby instance in place of the instance it stands by, from an oracle-signed `attestation`
+    /// of the encryption public keys of the replaced instance and of this instance after the nonce of the command,
+    /// restoring the imported state
+    fn promote_standby(attestation: Vec<u8>, signature: Vec<u8>);
+
+    /// Withdraw `amount` of the notes held for `owner` in the ledger to `payout`, `signature` being the
+    /// signature by the owner of the withdrawal, of a `nonce` chosen by the owner. The withdrawal is paid
+    /// out once committed on-chain with the next deal, when that deal is distributed.
+    fn withdraw_notes(owner: H160, payout: H160, amount: U256, nonce: U256, signature: Vec<u8>);
+
+    /// Queue a withdrawal of `amount` of the notes held for `owner` to `payout` for a later payout batch,
+    /// `signature` being the signature by the owner of the withdrawal and of its priority `fee`, spent from
+    /// the notes along with the amount
+    fn queue_withdrawal(owner: H160, payout: H160, amount: U256, fee: U256, nonce: U256, signature: Vec<u8>);
+
+    /// Pay out a batch of at most `max_withdrawals` queued withdrawals, highest priority tier first and in
+    /// random order within a tier, the priority fees of the batch being paid to `operator_address`, a
+    /// registered operator. The batch is paid out with the next deal, like `withdraw_notes`. Returns the
+    /// number of withdrawals paid out.
+    fn process_withdrawals(operator_address: H160, max_withdrawals: U256) -> U256;
+
+    /// Prune the deal records and recipient notifications older than the retention policy allows at the epoch
+    /// `now`, the number of deals executed, at most the current one. The pruned deal records leave a tombstone
+    /// refusing any further execution, challenge or retry of the deal, and the deals not yet settled are kept.
+    /// Each call prunes a bounded number of epochs, returning the number of entries pruned.
+    fn prune(now: U256) -> U256;
+
+    /// The recipient notifications stored for the notification pubKey of `pointer`, its keccak256 hash, oldest
+    /// first. Each is the container (deal_id: Bytes32, recipient: Bytes20, amount: uint256, note: uint8)
+    /// encrypted to the notification pubKey.
+    fn fetch_notifications(pointer: H256) -> Vec<Vec<u8>>;
+
+    /// The claim codes of the notes issued in a deal, one per deposit in the order of the deal record, each
+    /// holding the 32-byte claim secrets of the notes of the deposit encrypted to its public key
+    fn get_claim_codes(deal_id: H256) -> Vec<Vec<u8>>;
+
+    /// The fee invoice of the deposit at `index` in the order of the deal record, the container (deal_id: Bytes32,
+    /// gross: uint256, fee: uint256, net: uint256, relayer_share: uint256) encrypted to the public key of the
+    /// deposit, so that its depositor checks being charged the published rate
+    fn get_fee_invoice(deal_id: H256, index: U256) -> Vec<u8>;
+
+    /// Claim the note of `claim_secret` to `payout`, `signature` being the signature of the claim by the payout
+    /// address, so that notes are withdrawn to fresh addresses without any signature of their owner. The claim
+    /// is paid out with the next deal, like `withdraw_notes`.
+    fn claim(claim_secret: Vec<u8>, payout: H160, signature: Vec<u8>);
+
+    /// The share of this instance in each deposit payload, encrypted to the executing instance
+    /// of the deal, empty for undecryptable payloads
+    fn partial_decrypt(executor_pub_key: Vec<u8>, pub_keys: Vec<Vec<u8>>, enc_recipients: Vec<Vec<u8>>) -> Vec<Vec<u8>>;
+
+    /// Submit the partial decryptions of a peer instance, combined when executing the deal of the deposits
+    fn submit_partials(peer_index: U256, enc_recipients: Vec<Vec<u8>>, partials: Vec<Vec<u8>>);
+
+    /// Replay the verification of a deal from its capsule, returning the unshuffled recipients.
+    /// Only available in contracts built with the "simulation" feature, under a simulation network profile.
+    fn replay_deal(capsule: Vec<u8>) -> Vec<H160>;
+
+    /// Generate `n` deposit test vectors for external client implementations, each matching the keys,
+    /// encrypted payload, signature and digest of a deposit. Only available in simulation builds and profiles.
+    fn generate_test_vectors(n: U256) -> Vec<Vec<u8>>;
 }
 
 struct Contract;
 
 impl Contract {
-    /// Read voting address of MIXER_ETH_ADDR contract
-    fn get_mixer_eth_addr() -> String {
-        read_state!(MIXER_ETH_ADDR).unwrap_or_default()
+    fn get_mixer<R: Runtime>(runtime: &R) -> Option<Address> {
+        runtime.read_state(MIXER_ETH_ADDR)
     }
 
-    fn get_pkey() -> SymmetricKey {
-        let key = read_state!(ENCRYPTION_KEY).unwrap();
-        eprint!("Got key: {:?}", key);
-        key
+    /// Read voting address of MIXER_ETH_ADDR contract, in hex for the callouts
+    fn get_mixer_eth_addr<R: Runtime>(runtime: &R) -> String {
+        Self::get_mixer(runtime).map(|mixer| mixer.0.to_hex()).unwrap_or_default()
     }
 
-    fn get_keypair() -> KeyPair {
-        let key = Self::get_pkey();
-        KeyPair::from_slice(&key).unwrap()
+    fn get_mixer_address<R: Runtime>(runtime: &R) -> H160 {
+        Self::get_mixer(runtime).map_or_else(H160::zero, |mixer| mixer.0)
     }
 
-    fn verify_signature(
-        signature: [u8; SIG_SIZE],
-        sender: &H160,
+    /// The key pair deriving the keys shared with the depositors
+    fn get_keypair<R: Runtime>(runtime: &R) -> KeyPair {
+        kdf::keypair(runtime, kdf::ENCRYPTION_V1)
+    }
+
+    /// The key pair signing the enclave statements
+    fn get_signing_keypair<R: Runtime>(runtime: &R) -> KeyPair {
+        kdf::keypair(runtime, kdf::SIGNING_V1)
+    }
+
+    fn verify_deposits_internal<R: Runtime>(
+        runtime: &R,
+        engine: &dyn ComplianceEngine<R>,
+        amount: U256,
+        pub_keys: Vec<Vec<u8>>,
+        enc_recipients: Vec<Vec<u8>>,
+        senders: Vec<H160>,
+        signatures: Vec<Vec<u8>>,
+        expiries: Vec<U256>,
+        chain_id: U256,
+        aggregate_signature: Vec<u8>,
+        attested_block: u64,
+    ) -> Result<Vec<VerifiedDeposit>, Vec<usize>> {
+        trace!("The number of participants: {}", enc_recipients.len());
+        let (deposits, aggregated, rejected) = Self::verify_deposit_batch(runtime, 
+            engine, &amount, &pub_keys, &enc_recipients, &senders, &signatures, &expiries, &chain_id, attested_block, 0..senders.len());
+        if !rejected.is_empty() {
+            return Err(rejected);
+        }
+        Self::verify_aggregate(&aggregated, &aggregate_signature);
+        Ok(deposits)
+    }
+
+    /// Verify the deposits in the range, returning the verified deposits, the (signer public key, message)
+    /// of the aggregated deposits, and the indices of the rejected deposits
+    fn verify_deposit_batch<R: Runtime>(
+        runtime: &R,
+        engine: &dyn ComplianceEngine<R>,
         amount: &U256,
-        enc_recipient: &[u8],
-        user_pubkey: &[u8; PUB_KEY_SIZE],
+        pub_keys: &[Vec<u8>],
+        enc_recipients: &[Vec<u8>],
+        senders: &[H160],
+        signatures: &[Vec<u8>],
+        expiries: &[U256],
         chain_id: &U256,
-    ) -> H160 {
-        eprint!("Verifying signature: {:?}", signature.as_ref());
-        let mut message: Vec<u8> = Vec::new();
-        // EIP191 header for EIP712 prefix
-        message.extend_from_slice(b"\x19\x01");
-
-        let mut domain_message: Vec<u8> = Vec::new();
-        let eip712_domain_seperator = b"EIP712Domain(string name,string version,uint256 chainId)".keccak256();
-        let domain_name_hash = b"Salad Deposit".keccak256();
-        let domain_version_hash = b"1".keccak256();
-        let chain_id = H256::from(chain_id);
-        domain_message.extend_from_slice(eip712_domain_seperator.as_ref());
-        domain_message.extend_from_slice(domain_name_hash.as_ref());
-        domain_message.extend_from_slice(domain_version_hash.as_ref());
-        domain_message.extend_from_slice(chain_id.as_ref());
-
-        let domain_hash = domain_message.keccak256();
-        message.extend_from_slice(domain_hash.as_ref());
-
-        let mut deposit_message: Vec<u8> = Vec::new();
-        let deposit_seperator_hash = b"Deposit(address sender,uint256 amount,bytes encRecipient,bytes pubKey)".keccak256();
-        deposit_message.extend_from_slice(deposit_seperator_hash.as_ref());
-        eprint!("The sender: {:?}", sender);
-        // addresses must be resized to 32 bytes
-        let mut sender_part = vec![0_u8; 12];
-        sender_part.extend_from_slice(sender.as_ref());
-        eprint!("The resized sender: {:?}", sender_part);
-        deposit_message.extend_from_slice(&sender_part);
-        deposit_message.extend_from_slice(&H256::from(amount));
-        // bytes must be keccak hashes
-        deposit_message.extend_from_slice(enc_recipient.keccak256().as_ref());
-        deposit_message.extend_from_slice(user_pubkey.keccak256().as_ref());
-        eprint!("The typed deposit message: {:?}", deposit_message);
-
-        message.extend_from_slice(deposit_message.keccak256().as_ref());
-        eprint!("The typed data message: {:?}", message);
-
-        let sender_pubkey = KeyPair::recover(&message, signature).unwrap();
-        let mut sender_raw = [0_u8; 20];
-        sender_raw.copy_from_slice(&sender_pubkey.keccak256()[12..32]);
-        let sender = H160::from(&sender_raw);
-        eprint!("Recovered sender: {:?}", sender);
-        sender
-    }
-
-    fn generate_deal_id(
+        attested_block: u64,
+        range: Range<usize>,
+    ) -> (Vec<VerifiedDeposit>, Vec<(Vec<u8>, Vec<u8>)>, Vec<usize>) {
+        let mut deposits: Vec<VerifiedDeposit> = Vec::new();
+        let mut aggregated: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+        let mut rejected: Vec<usize> = Vec::new();
+        let keypair = Self::get_keypair(runtime);
+        let threshold = threshold::config(runtime);
+        let hasher = DepositHasher::new(protocol::message(), chain_id);
+        for input in wire::deposits(pub_keys, enc_recipients, senders, signatures, expiries).skip(range.start).take(range.len()) {
+            let i = input.index;
+            let _participant = trace::participant(&handles::handle(input.pub_key, input.enc_recipient));
+            let opened = metering::measure(runtime, metering::PHASE_DECRYPT, || {
+                verification::open_payload(runtime, engine, threshold.as_ref(), &keypair, i, input.pub_key, input.enc_recipient)
+            });
+            let payload = match opened {
+                Ok(payload) => payload,
+                Err(reason) => {
+                    // The most common user errors must not abort the deal of the other participants
+                    trace!("Refunding unopenable deposit: {}", reason);
+                    deposits.push(VerifiedDeposit::refund(input.sender, amount));
+                    continue;
+                }
+            };
+            let (user_pubkey, envelope_version) = (payload.0, parsing::envelope_version(&payload.1));
+            match verification::verify_deposit(runtime, engine, &keypair, &hasher, &input, amount, payload) {
+                Ok(deposit) => {
+                    if let Some(signed) = &deposit.aggregated {
+                        aggregated.push(signed.clone());
+                    }
+                    if U256::from(attested_block) > *input.expiry {
+                        // The depositor signed the expiry, so that its funds are never mixed under stale terms
+                        trace!("Refunding deposit expired at block {:?}", input.expiry);
+                        deposits.push(VerifiedDeposit::refund(input.sender, amount));
+                        continue;
+                    }
+                    match engine.screen_deposit(runtime, i, input.sender, amount) {
+                        Ok(_) => deposits.push(deposit),
+                        Err(reason) => {
+                            trace!("Refunding screened out deposit: {}", reason);
+                            deposits.push(VerifiedDeposit::refund(input.sender, amount));
+                        }
+                    }
+                }
+                Err(reason) => {
+                    trace!("Rejected deposit: {}", reason);
+                    metrics::record_reject(runtime, envelope_version, verification::message_version(&input, amount, &user_pubkey, chain_id));
+                    rejected.push(i);
+                }
+            }
+        }
+        (deposits, aggregated, rejected)
+    }
+
+    /// Verify the aggregate signature of the deal against the (signer public key, message) of the aggregated deposits
+    fn verify_aggregate(aggregated: &[(Vec<u8>, Vec<u8>)], aggregate_signature: &[u8]) {
+        let (keys, messages): (Vec<Vec<u8>>, Vec<Vec<u8>>) = aggregated.iter().cloned().unzip();
+        signature::verify_aggregate(&keys, &messages, aggregate_signature);
+    }
+
+    /// Refund the deal cancelled by the votes of its senders, or timed out, each deposit being paid back to its sender
+    /// by the `refundDeal` callout, the deal never being committed. The Mixer only refunds the deals past their
+    /// execution window, a refund reverted before being retried with `retry_distribution`.
+    fn refund_deal<R: Runtime>(
+        runtime: &R,
+        deal_id: &H256,
+        operator: &H160,
+        bond: &H256,
         amount: &U256,
-        participants: &Vec<H160>,
-        operator_address: &H160,
-        operator_nonce: &U256,
-    ) -> H256 {
-        let u32_prefix = [0_u8; 4];
-        let mut message: Vec<u8> = Vec::new();
-        message.extend_from_slice(&u32_prefix);
-        message.extend_from_slice(&UNIT256_SIZE.to_be_bytes());
-        message.extend_from_slice(&H256::from(amount));
-        message.extend_from_slice(&u32_prefix);
-        message.extend_from_slice(&participants.len().to_be_bytes());
-        for sender in participants.iter() {
-            message.extend_from_slice(&u32_prefix);
-            message.extend_from_slice(&ADDRESS_SIZE.to_be_bytes());
-            message.extend_from_slice(sender);
-        }
-        message.extend_from_slice(&u32_prefix);
-        message.extend_from_slice(&ADDRESS_SIZE.to_be_bytes());
-        message.extend_from_slice(operator_address);
-        message.extend_from_slice(&u32_prefix);
-        message.extend_from_slice(&UNIT256_SIZE.to_be_bytes());
-        message.extend_from_slice(&H256::from(operator_nonce));
-        eprint!("The DealId message: {:?}", message);
-        let mut hash_raw = [0_u8; 32];
-        hash_raw.copy_from_slice(&message.keccak256().as_ref());
-        H256::from(&hash_raw)
-    }
-
-    fn verify_deposits_internal(
+        senders: &[H160],
+        pub_keys: &[Vec<u8>],
+    ) {
+        let refunds: Vec<(usize, H160, U256)> = senders.iter().enumerate().map(|(i, sender)| (i, *sender, *amount)).collect();
+        // The record of a deal never committed carries no receipt
+        let record = DealRecord::new(
+            DealStatus::Refunded, operator, bond, senders, &[], pub_keys, &refunds, &U256::zero(), &U256::zero(), &H256::zero(), 0);
+        deals::save(runtime, deal_id, &record);
+        runtime.refund_deal(&Self::get_mixer_eth_addr(runtime), deal_id);
+    }
+
+    /// The entries of the participants of a deal in its frozen participant set
+    fn participant_entries(senders: &[H160], pub_keys: &[Vec<u8>], enc_recipients: &[Vec<u8>]) -> Vec<Vec<u8>> {
+        senders.iter().zip(pub_keys.iter()).zip(enc_recipients.iter())
+            .map(|((sender, pub_key), enc_recipient)| freeze::entry(sender, pub_key, enc_recipient))
+            .collect()
+    }
+
+    /// Fail the deal, recording the evidence of the operator committing to the rejected deposits,
+    /// listed by their blinded handles
+    fn fail_deal<R: Runtime>(
+        runtime: &R,
+        deal_id: &H256,
+        operator: &H160,
+        bond: &H256,
+        senders: &[H160],
+        pub_keys: &[Vec<u8>],
+        enc_recipients: &[Vec<u8>],
+        rejected: &[usize],
+    ) {
+        let record = DealRecord::new(DealStatus::Failed, operator, bond, senders, &[], &[], &[], &U256::zero(), &U256::zero(), &H256::zero(), 0);
+        deals::save(runtime, deal_id, &record);
+        anomaly::screen_rejects(runtime, senders.len(), rejected.len());
+        let (handles, senders): (Vec<H256>, Vec<H160>) = handles::sorted(
+            rejected.iter().map(|i| (handles::handle(&pub_keys[*i], &enc_recipients[*i]), senders[*i])).collect()
+        ).into_iter().unzip();
+        evidence::record(runtime, &Misbehavior {
+            kind: evidence::BAD_COMMITMENT,
+            deal_id: *deal_id,
+            operator: *operator,
+            bond: *bond,
+            handles,
+            senders,
+            digests: Vec::new(),
+        }, &Self::get_signing_keypair(runtime));
+    }
+
+    /// Notify the recipients of the deposits carrying a notification pubKey of their outputs paid in the deal,
+    /// leaving out refunds, stipends and fees, and of their outputs held as notes in the enclave ledger
+    fn notify_recipients<R: Runtime>(runtime: &R, deal_id: &H256, deposits: &[VerifiedDeposit], outputs: &[(usize, H160, U256)]) {
+        let keypair = Self::get_keypair(runtime);
+        for (i, deposit) in deposits.iter().enumerate() {
+            let pub_key = match deposit.notification_key {
+                Some(ref pub_key) => pub_key,
+                None => continue,
+            };
+            let paid = outputs.iter()
+                .filter(|(j, recipient, _)| *j == i && deposit.outputs.iter().any(|(split_recipient, _)| split_recipient == recipient))
+                .map(|(_, recipient, amount)| Notification { recipient: *recipient, amount: *amount, note: false });
+            let held = deposit.notes.iter().map(|(owner, amount)| Notification { recipient: *owner, amount: *amount, note: true });
+            notifications::notify(runtime, deal_id, &keypair, pub_key, &paid.chain(held).collect::<Vec<Notification>>());
+        }
+    }
+
+    /// Distribute the outputs of a committed deal, the distribution of a previewed deal being refused
+    /// unless `confirmation` confirms the calldata previewed
+    fn finalize_distribution_internal<R: Runtime>(runtime: &R, deal_id: &H256, confirmation: Option<H256>) {
+        let _deal = trace::deal(deal_id);
+        let mut record = match deals::load(runtime, deal_id) {
+            Some(record) => record,
+            None => panic!("Unknown deal: {:?}", deal_id),
+        };
+        match record.status {
+            DealStatus::Committed => (),
+            // A retried distribution must not pay the outputs twice
+            DealStatus::Distributed => {
+                trace!("Deal already distributed: {:?}", deal_id);
+                return;
+            }
+            _ => panic!("Deal {:?} is not committed: {:?}", deal_id, record.status),
+        }
+        challenge::check_answered(runtime, deal_id);
+        let receipt_hash = record.receipt_hash();
+        let adapter = payout::adapter(runtime, deal_id);
+        distribution::check_confirmed(runtime, deal_id, confirmation.as_ref(), || {
+            adapter.calldata(&Self::deal_payout(deal_id, &record, &receipt_hash))
+        });
+        let outputs = record.outputs();
+        let recipients: Vec<H160> = outputs.iter().map(|(recipient, _)| *recipient).collect();
+        let amounts: Vec<U256> = outputs.iter().map(|(_, amount)| *amount).collect();
+        let mixer_eth_addr: String = Self::get_mixer_eth_addr(runtime);
+        Self::distribute_outputs(runtime, &mixer_eth_addr, deal_id, &record, recipients, amounts, &receipt_hash);
+        Self::pay_note_payouts(runtime, &mixer_eth_addr, deal_id);
+        record.status = DealStatus::Distributed;
+        deals::save(runtime, deal_id, &record);
+    }
+
+    /// Commit the note withdrawals held since the last deal with this deal, before its roots
+    fn commit_note_payouts<R: Runtime>(runtime: &R, mixer_eth_addr: &str, deal_id: &H256) {
+        if let Some((payouts_root, nb_payouts)) = ledger::commit_payouts(runtime, deal_id) {
+            runtime.commit_note_payouts(mixer_eth_addr, deal_id, &payouts_root, nb_payouts);
+        }
+    }
+
+    /// Pay the note withdrawals committed with the deal out of the pool reserve, past its challenge window
+    fn pay_note_payouts<R: Runtime>(runtime: &R, mixer_eth_addr: &str, deal_id: &H256) {
+        for (i, (nullifier, payout, amount, proof)) in ledger::committed_payouts(runtime, deal_id).iter().enumerate() {
+            runtime.withdraw_note(mixer_eth_addr, deal_id, nullifier, payout, amount, i, proof);
+        }
+    }
+
+    /// The payout of the shuffled outputs of the deal, as distributed
+    fn deal_payout<'a>(deal_id: &'a H256, record: &DealRecord, receipt_hash: &'a H256) -> Payout<'a> {
+        let outputs = record.outputs();
+        Payout {
+            deal_id,
+            recipients: outputs.iter().map(|(recipient, _)| *recipient).collect(),
+            amounts: outputs.iter().map(|(_, amount)| *amount).collect(),
+            redeposited: record.redeposited(),
+            redeemed: record.redeemed(),
+            receipt_hash,
+        }
+    }
+
+    /// Distribute the shuffled outputs of the deal through the payout adapter selected for the deal
+    fn distribute_outputs<R: Runtime>(
+        runtime: &R,
+        mixer_eth_addr: &str,
+        deal_id: &H256,
+        record: &DealRecord,
+        recipients: Vec<H160>,
+        amounts: Vec<U256>,
+        receipt_hash: &H256,
+    ) {
+        let adapter = payout::adapter(runtime, deal_id);
+        trace!("Paying out deal {:?} through the {} adapter", deal_id, adapter.name());
+        adapter.pay(runtime, mixer_eth_addr, Payout {
+            deal_id,
+            recipients,
+            amounts,
+            redeposited: record.redeposited(),
+            redeemed: record.redeemed(),
+            receipt_hash,
+        });
+    }
+
+    /// Verify each deposit without aborting on rejections. The result holds, for each deposit, its blinded handle
+    /// followed by either nothing if valid or the rejection reason encrypted to the depositor pubKey, so that the
+    /// operator only learns which deposits were rejected. The results are in the order of the handles, never
+    /// in the order the deposits were submitted in.
+    fn check_deposits_internal<R: Runtime>(
+        runtime: &R,
         amount: U256,
         pub_keys: Vec<Vec<u8>>,
         enc_recipients: Vec<Vec<u8>>,
         senders: Vec<H160>,
         signatures: Vec<Vec<u8>>,
+        expiries: Vec<U256>,
         chain_id: U256,
+        aggregate_signature: Vec<u8>,
+    ) -> Vec<Vec<u8>> {
+        trace!("The number of participants: {}", enc_recipients.len());
+        let mut rejections: Vec<(H256, Vec<u8>)> = Vec::new();
+        let mut aggregated_keys: Vec<Vec<u8>> = Vec::new();
+        let mut aggregated_messages: Vec<Vec<u8>> = Vec::new();
+        let keypair = Self::get_keypair(runtime);
+        let engine = compliance::engine(runtime);
+        let threshold = threshold::config(runtime);
+        let hasher = DepositHasher::new(protocol::message(), &chain_id);
+        for input in wire::deposits(&pub_keys, &enc_recipients, &senders, &signatures, &expiries) {
+            let i = input.index;
+            let handle = handles::handle(input.pub_key, input.enc_recipient);
+            let _participant = trace::participant(&handle);
+            let deposit = verification::open_payload(runtime, &*engine, threshold.as_ref(), &keypair, i, input.pub_key, input.enc_recipient)
+                .and_then(|payload| verification::verify_deposit(runtime, &*engine, &keypair, &hasher, &input, &amount, payload))
+                .and_then(|deposit| engine.screen_deposit(runtime, i, input.sender, &amount).map(|_| deposit));
+            match deposit {
+                Ok(deposit) => {
+                    if let Some((signer_pub_key, message)) = deposit.aggregated {
+                        aggregated_keys.push(signer_pub_key);
+                        aggregated_messages.push(message);
+                    }
+                    rejections.push((handle, Vec::new()));
+                }
+                Err(reason) => {
+                    trace!("Rejected deposit");
+                    rejections.push((handle, Self::encrypt_rejection(runtime, &keypair, input.pub_key, &reason)));
+                }
+            }
+        }
+        signature::verify_aggregate(&aggregated_keys, &aggregated_messages, &aggregate_signature);
+        handles::sorted(rejections).into_iter().map(|(handle, rejection)| {
+            let mut result = handle.to_vec();
+            result.extend(rejection);
+            result
+        }).collect()
+    }
+
+    /// Encrypt the rejection reason with the key shared with the depositor.
+    /// An invalid pubKey is public knowledge, in which case the reason is returned in the clear.
+    fn encrypt_rejection<R: Runtime>(runtime: &R, keypair: &KeyPair, pub_key: &[u8], reason: &str) -> Vec<u8> {
+        if pub_key.len() != PUB_KEY_SIZE {
+            return reason.as_bytes().to_vec();
+        }
+        let mut user_pubkey = [0; PUB_KEY_SIZE];
+        user_pubkey.copy_from_slice(pub_key);
+        match keypair.derive_key(&user_pubkey) {
+            Ok(shared_key) => runtime.encrypt(reason.as_bytes(), &shared_key),
+            Err(_) => reason.as_bytes().to_vec(),
+        }
+    }
+
+    /// Verify the voucher deposits against their commitments and return the outputs of each deposit,
+    /// or the blinded handles of the rejected deposits, in the order of the handles
+    fn verify_voucher_deposits_internal<R: Runtime>(
+        runtime: &R,
+        engine: &dyn ComplianceEngine<R>,
+        amount: &U256,
+        commitments: &[H256],
+        pub_keys: &[Vec<u8>],
+        enc_recipients: &[Vec<u8>],
+    ) -> Result<Vec<Vec<(H160, U256)>>, Vec<H256>> {
+        let keypair = Self::get_keypair(runtime);
+        let threshold = threshold::config(runtime);
+        let mut deposits: Vec<Vec<(H160, U256)>> = Vec::new();
+        let mut rejected: Vec<H256> = Vec::new();
+        for input in wire::vouchers(commitments, pub_keys, enc_recipients) {
+            let i = input.index;
+            let handle = handles::handle(input.pub_key, input.enc_recipient);
+            let _participant = trace::participant(&handle);
+            let deposit = if digest::voucher_commitment(input.pub_key, input.enc_recipient) != *input.commitment {
+                Err(format!("Payload of voucher {} does not match its commitment", i))
+            } else {
+                verification::open_payload(runtime, engine, threshold.as_ref(), &keypair, i, input.pub_key, input.enc_recipient)
+                    .and_then(|(_, plaintext)| parsing::parse_payload(i, &plaintext))
+                    .and_then(|payload| {
+                        let has_notes = payload.splits.iter().any(|(_, percent)| parsing::is_note(*percent));
+                        match (payload.redeposit, payload.guardian) {
+                            // A redeposit would be refunded to a sender the enclave does not know
+                            (Some(_), _) => Err(format!("Redeposit of voucher {} not supported", i)),
+                            (_, Some(_)) => Err(format!("Guardian of voucher {} not supported", i)),
+                            (None, None) if has_notes => Err(format!("Notes of voucher {} not supported", i)),
+                            (None, None) => Ok(parsing::expand_splits(amount, &payload.splits)),
+                        }
+                    })
+            };
+            match deposit {
+                Ok(outputs) => deposits.push(outputs),
+                Err(reason) => {
+                    trace!("Rejected voucher deposit: {}", reason);
+                    rejected.push(handle);
+                }
+            }
+        }
+        rejected.sort();
+        if rejected.is_empty() { Ok(deposits) } else { Err(rejected) }
+    }
+
+    /// Complete the execution of a deal once all its deposits are verified: add the staged deposits joining it,
+    /// shuffle the outputs, commit the Merkle roots of the deal on-chain and record it for distribution
+    fn complete_deal<R: Runtime>(
+        runtime: &R,
+        engine: &dyn ComplianceEngine<R>,
+        deal_id: &H256,
+        operator_address: &H160,
+        operator_bond: &H256,
+        amount: &U256,
+        senders: &[H160],
+        pub_keys: &[Vec<u8>],
+        mut deposits: Vec<VerifiedDeposit>,
+        nb_redeemed: usize,
+        attested_block: u64,
+        features: u64,
     ) -> Vec<H160> {
-        let nb_participants = enc_recipients.len();
-        match nb_participants {
-            l if l != senders.len() => panic!("Mismatching senders list size: {} != {}", l, senders.len()),
-            l if l != pub_keys.len() => panic!("Mismatching pub_keys list size: {} != {}", l, pub_keys.len()),
-            l if l != signatures.len() => panic!("Mismatching signatures list size: {} != {}", l, signatures.len()),
-            l => { eprint!("The number of participants: {}", l); }
-        }
-        let mut recipients: Vec<H160> = Vec::new();
-        let keypair = Self::get_keypair();
+        // Deposits relying on features the deal did not negotiate are refunded rather than mixed under other terms
+        for (i, sender) in senders.iter().enumerate() {
+            if let Err(reason) = features::admit(features, !deposits[i].notes.is_empty(), deposits[i].notification_key.is_some()) {
+                trace!("Refunding deposit {}: {}", i, reason);
+                deposits[i] = VerifiedDeposit::refund(sender, amount);
+            }
+        }
+        // Deposit shares staged back into the pool by previous deals of the same amount join as zero-sender deposits,
+        // as many as the deal redeems on-chain
+        let redeemed = redeposits::take(runtime, amount, nb_redeemed);
+        let mut participants = senders.to_vec();
+        let mut participant_pub_keys = pub_keys.to_vec();
+        for staged in redeemed.iter() {
+            let splits = parsing::parse_splits(deposits.len(), &staged.payload).unwrap();
+            let (mut outputs, mut notes) = parsing::expand_notes(amount, &splits);
+            buckets::round(runtime, &mut outputs, &mut notes);
+            deposits.push(VerifiedDeposit {
+                outputs,
+                aggregated: None,
+                digest: staged.digest(),
+                redeposit: None,
+                guardian: None,
+                notes,
+                notification_key: None,
+            });
+            participants.push(H160::zero());
+            participant_pub_keys.push(staged.pub_key.clone());
+        }
+        let staged_amounts: Vec<U256> = deposits.iter()
+            .map(|deposit| deposit.redeposit.as_ref().map_or_else(U256::zero, |staged| staged.amount()))
+            .collect();
+        let staged: Vec<StagedDeposit> = deposits.iter_mut().filter_map(|deposit| deposit.redeposit.take()).collect();
+        let redeposited = staged.iter().fold(U256::zero(), |total, deposit| amounts::add(&total, &deposit.amount()));
+        let redeemed_amount = amounts::mul(amount, &U256::from(redeemed.len()));
+        // Keeping track of the deposit funding each output to answer inclusion challenges
+        let mut outputs: Vec<(usize, H160, U256)> = Vec::new();
+        for (i, deposit) in deposits.iter().enumerate() {
+            outputs.extend(deposit.outputs.iter().map(|(recipient, amount)| (i, *recipient, *amount)));
+        }
+        // Linkage risks are scored on the outputs of the deposits, before the dust sweep and stipends
+        let risk_scores = risk::score(runtime, &outputs, &participants);
+        // Outputs too small to be worth transferring never reach the distribution
+        let unswept = invoices::tally(&outputs, deposits.len());
+        let (pooled_dust, dust_fee) = if features::enabled(features, features::COALESCING) {
+            fees::sweep_dust(runtime, &mut outputs, &participants)
+        } else {
+            (U256::zero(), U256::zero())
+        };
+        let swept = invoices::tally(&outputs, deposits.len());
+        // Freshness is measured before the stipends mark the recipients of the deal as seen
+        let mixed: Vec<&VerifiedDeposit> = deposits.iter().filter(|deposit| !deposit.digest.is_zero()).collect();
+        let mut mixed_recipients: Vec<H160> = mixed.iter().flat_map(|deposit| deposit.outputs.iter().map(|(recipient, _)| *recipient)).collect();
+        mixed_recipients.sort();
+        mixed_recipients.dedup();
+        let anonymity = metrics::Anonymity {
+            set_size: mixed.len(),
+            refunds: deposits.len() - mixed.len(),
+            recipients: mixed_recipients.len(),
+            fresh_recipients: stipend::count_fresh(runtime, &mixed_recipients),
+        };
+        // Fresh recipients are granted their gas stipend out of the operator fee, attributed to the first deposit
+        let stipends = stipend::grant(runtime, &mut outputs, &participants, &dust_fee);
+        let operator_fee = amounts::sub(&dust_fee, &stipends);
+        if !operator_fee.is_zero() {
+            outputs.push((0, *operator_address, operator_fee));
+        }
+        // The operator fee is shared between the deposits in proportion of their dust swept into the fee
+        let fee_invoices: Vec<Invoice> = deposits.iter().enumerate().map(|(i, deposit)| Invoice {
+            gross: *amount,
+            net: amounts::add(&amounts::add(&swept[i], &staged_amounts[i]), &amounts::sum(deposit.notes.iter().map(|(_, amount)| amount))),
+            relayer_share: if dust_fee.is_zero() {
+                U256::zero()
+            } else {
+                amounts::mul(&amounts::sub(&unswept[i], &swept[i]), &operator_fee) / dust_fee
+            },
+        }).collect();
+        // The outputs held as notes stay in the pool reserve until withdrawn
+        let held = amounts::sum(deposits.iter().flat_map(|deposit| deposit.notes.iter().map(|(_, amount)| amount)));
+        let redeposited = amounts::add(&amounts::add(&redeposited, &pooled_dust), &held);
+        // Dummy outputs are added last, so that only the enclave tells them from the outputs of the deposits
+        let nb_dummies = if features::enabled(features, features::DUMMY_OUTPUTS) { dummies::add(runtime, &mut outputs) } else { 0 };
+        let seed = metering::measure(runtime, metering::PHASE_SHUFFLE, || ordering::order(runtime, &mut outputs, |(_, recipient, _)| *recipient));
+        entropy::commit(runtime, deal_id, seed);
+        let recipients: Vec<H160> = outputs.iter().map(|(_, recipient, _)| *recipient).collect();
+        // Only the outputs paying a recipient of its deposit are held by the guardian, not refunds, stipends or fees
+        let guardians: Vec<H160> = outputs.iter().map(|(i, recipient, _)| {
+            let pays_recipient = deposits[*i].outputs.iter().any(|(split_recipient, _)| split_recipient == recipient);
+            match deposits[*i].guardian {
+                Some(guardian) if pays_recipient => guardian,
+                _ => H160::zero(),
+            }
+        }).collect();
+        let mixer_eth_addr: String = Self::get_mixer_eth_addr(runtime);
+        let receipt_hash = digest::receipt_hash(deal_id, attested_block, &recipients);
+        trace!("The receipt hash: {:?}", receipt_hash);
+        let digests: Vec<H256> = deposits.iter().map(|deposit| deposit.digest).collect();
+        let mut record = DealRecord::new(
+            DealStatus::Committed,
+            operator_address,
+            operator_bond,
+            &participants,
+            &digests,
+            &participant_pub_keys,
+            &outputs,
+            &redeposited,
+            &redeemed_amount,
+            &receipt_hash,
+            nb_dummies);
+        let participants_root = merkle::root(&record.participant_leaves());
+        let outputs_root = merkle::root(&record.output_leaves());
+        trace!("The participants root: {:?}, outputs root: {:?}", participants_root, outputs_root);
+        metering::measure(runtime, metering::PHASE_CALLOUT, || {
+            Self::commit_note_payouts(runtime, &mixer_eth_addr, deal_id);
+            runtime.commit_roots(&mixer_eth_addr, deal_id, &participants_root, &outputs_root)
+        });
+        record.phase_costs = metering::costs();
+        deals::save(runtime, deal_id, &record);
+        escrow::save(runtime, deal_id, &guardians);
+        audit::record(runtime, deal_id, &digest::receipt(deal_id, attested_block, &recipients), &record.encode(), &Self::get_keypair(runtime));
+        let paid_out = amounts::add(&amounts::sum(outputs.iter().map(|(_, _, amount)| amount)), &redeposited);
+        let deposited = amounts::add(&amounts::mul(amount, &U256::from(senders.len())), &redeemed_amount);
+        receipts::record(runtime, &DealStats {
+            deal_id: *deal_id,
+            receipt_hash,
+            nb_participants: participants.len() as u64,
+            denomination: *amount,
+            total_fee: amounts::add(&operator_fee, &if deposited > paid_out { deposited - paid_out } else { U256::zero() }),
+            nb_chunks: outputs.len() as u64,
+            risk_counts: risk::counts(&risk_scores),
+        }, &Self::get_signing_keypair(runtime));
+        let repeated = risk::counts(&risk_scores)[risk::RISK_RECENT_RECIPIENT as usize];
+        anomaly::screen_deal(runtime, attested_block, deposits.len(), risk_scores.len(), repeated);
+        risk::record(runtime, &recipients);
+        redeposits::stage(runtime, staged);
+        let deposit_notes: Vec<&[(H160, U256)]> = deposits.iter().map(|deposit| &deposit.notes[..]).collect();
+        ledger::issue(runtime, deal_id, &Self::get_keypair(runtime), &participant_pub_keys, &deposit_notes);
+        invoices::issue(runtime, deal_id, &Self::get_keypair(runtime), &participant_pub_keys, &fee_invoices);
+        Self::notify_recipients(runtime, deal_id, &deposits, &outputs);
+        engine.commit(runtime);
+        health::record_deal(runtime, attested_block);
+        metrics::record(runtime, senders.len(), outputs.len(), &anonymity);
+        recipients
+    }
+
+    /// Verify the multi-asset deposits and return the (recipient, output asset, output amount) of each participant
+    /// along with the common USD value of the deposits
+    fn verify_multi_asset_deposits_internal<R: Runtime>(
+        runtime: &R,
+        engine: &dyn ComplianceEngine<R>,
+        price_rounds: &[PriceRound],
+        assets: &[H160],
+        amounts: &[U256],
+        pub_keys: &[Vec<u8>],
+        enc_recipients: &[Vec<u8>],
+        senders: &[H160],
+        signatures: &[Vec<u8>],
+        chain_id: &U256,
+    ) -> (Vec<(H160, H160, U256)>, U256) {
+        let nb_participants = validation::check_multi_asset_participants(assets, amounts, pub_keys, enc_recipients, senders, signatures);
+        trace!("The number of participants: {}", nb_participants);
+        let mut value = U256::zero();
+        let mut outputs: Vec<(H160, H160, U256)> = Vec::new();
+        let keypair = Self::get_keypair(runtime);
+        let threshold = threshold::config(runtime);
+        let hasher = DepositHasher::new(protocol::message(), chain_id);
         for i in 0..nb_participants {
-            eprint!("Decrypting recipient {}: {:?}", i, enc_recipients[i]);
-            let user_pubkey = {
-                let mut key = [0; PUB_KEY_SIZE];
-                key.copy_from_slice(&pub_keys[i]);
-                key
-            };
-            eprint!("The user pubKey: {:?}", &user_pubkey[..]);
+            let _participant = trace::participant(&handles::handle(&pub_keys[i], &enc_recipients[i]));
+            let asset_config = tokens::check_asset(runtime, &assets[i]);
+            if let Some(asset_config) = asset_config.as_ref() {
+                tokens::check_amount(asset_config, &amounts[i]);
+            }
+            // Normalized to the same decimals, so that the values of tokens of different decimals compare
+            let price = amounts::mul(&oracle::get_price(price_rounds, &assets[i]), &tokens::scale(asset_config.as_ref()));
+            let (deposit_value, overflow) = amounts[i].overflowing_mul(price);
+            if overflow {
+                panic!("Deposit value overflow for participant {}", i);
+            }
+            if i == 0 {
+                value = deposit_value;
+            } else if deposit_value != value {
+                panic!("Mismatching deposit value for participant {}: {:?} != {:?}", i, deposit_value, value);
+            }
 
-            let shared_key = keypair.derive_key(&user_pubkey).unwrap();
-            let plaintext = decrypt(&enc_recipients[i], &shared_key);
-            eprint!("Successfully decrypted recipient {}", i);
+            let (user_pubkey, plaintext) = verification::decrypt_payload(runtime, engine, threshold.as_ref(), &keypair, i, &pub_keys[i], &enc_recipients[i])
+                .and_then(|(user_pubkey, envelope)| {
+                    let plaintext = engine.open_envelope(i, envelope, &|payload| match payload.len() {
+                        l if l < protocol::envelope().asset_payload_size => Err(format!("Invalid multi-asset payload size for participant {}: {}", i, l)),
+                        _ => Ok(vec![H160::from(&payload[0..20])]),
+                    })?;
+                    Ok((user_pubkey, plaintext))
+                })
+                .unwrap_or_else(|reason| panic!("Rejected deposit {}: {}", i, reason));
+            if plaintext.len() < protocol::envelope().asset_payload_size {
+                panic!("Invalid multi-asset payload size for participant {}: {}", i, plaintext.len());
+            }
             let recipient = H160::from(&plaintext[0..20]);
-
-            let mut signature = [0; SIG_SIZE];
-            signature.copy_from_slice(&signatures[i]);
-
-            let sig_sender = Self::verify_signature(signature,
-                                                    &senders[i],
-                                                    &amount,
-                                                    &enc_recipients[i],
-                                                    &user_pubkey,
-                                                    &chain_id);
-            if sig_sender != senders[i] {
-                panic!(
-                    "Invalid sender recovered from the signature: {:?} != {:?}",
-                    sig_sender, senders[i]
-                );
+            let output_asset = H160::from(&plaintext[20..40]);
+            let output_config = tokens::check_asset(runtime, &output_asset);
+            let output_price = amounts::mul(&oracle::get_price(price_rounds, &output_asset), &tokens::scale(output_config.as_ref()));
+            let output_amount = tokens::net_of_fee(output_config.as_ref(), &(value / output_price));
+            let terms = DepositTerms::Asset(&assets[i]);
+            let (aggregated, _) = verification::verify_sender(runtime, &hasher, &signatures[i], &senders[i], terms, &amounts[i], &enc_recipients[i], &user_pubkey)
+                .and_then(|verified| engine.screen_deposit(runtime, i, &senders[i], &amounts[i]).map(|_| verified))
+                .unwrap_or_else(|reason| panic!("Rejected deposit {}: {}", i, reason));
+            if aggregated.is_some() {
+                panic!("Aggregated signatures are not supported in multi-asset deals");
             }
-            recipients.push(recipient);
+            outputs.push((recipient, output_asset, output_amount));
         }
-        recipients
+        // The outputs in each asset never exceed the deposits in the asset, so that the Mixer never pays a deal
+        // out of the deposits of others, the deals whose recipients pick other assets being refused
+        for (_, asset, _) in outputs.iter() {
+            let deposited = amounts::sum(assets.iter().zip(amounts.iter()).filter(|(deposit_asset, _)| *deposit_asset == asset).map(|(_, amount)| amount));
+            let paid = amounts::sum(outputs.iter().filter(|(_, output_asset, _)| output_asset == asset).map(|(_, _, amount)| amount));
+            if paid > deposited {
+                panic!("Outputs in asset {:?} exceed its deposits: {} > {}", asset, paid, deposited);
+            }
+        }
+        (outputs, value)
     }
 }
 
 impl ContractInterface for Contract {
-    fn construct(mixer_eth_addr: H160) {
-        let mixer_eth_addr_str: String = mixer_eth_addr.to_hex();
-        write_state!(MIXER_ETH_ADDR => mixer_eth_addr_str);
+    fn construct(
+        mixer_eth_addr: H160,
+        oracle_signers: Vec<H160>,
+        output_ordering: U256,
+        recipient_allowlist_root: H256,
+        velocity_epoch_deals: U256,
+        velocity_max_deposits: U256,
+        velocity_max_volume: U256,
+        auditor_pub_keys: Vec<Vec<u8>>,
+        auditor_roles: Vec<U256>,
+        dust_policy: U256,
+        dust_threshold: U256,
+        operator_addresses: Vec<H160>,
+        operator_signers: Vec<H160>,
+        min_amount: U256,
+        max_amount: U256,
+        max_deal_value: U256,
+        gas_stipend: U256,
+        max_dummy_outputs: U256,
+        cancel_quorum: U256,
+        bucket_unit: U256,
+        mixer_timelock: U256,
+        network_profile: U256,
+    ) {
+        deployment::check(&HOST, &mixer_eth_addr, Self::get_mixer(&HOST).is_some());
+        profile::set(&HOST, &network_profile);
+        HOST.write_state(MIXER_ETH_ADDR, Address(mixer_eth_addr));
+        oracle::set_signers(&HOST, &oracle_signers);
+        ordering::set_policy(&HOST, &output_ordering);
+        allowlist::set_root(&HOST, &recipient_allowlist_root);
+        velocity::set_limits(&HOST, &velocity_epoch_deals, &velocity_max_deposits, &velocity_max_volume);
+        fees::set_dust_policy(&HOST, &dust_policy, &dust_threshold);
+        operators::init(&HOST, &operator_addresses, &operator_signers);
+        amounts::set_bounds(&HOST, &min_amount, &max_amount);
+        amounts::set_value_cap(&HOST, &max_deal_value);
+        stipend::set_amount(&HOST, &gas_stipend);
+        dummies::set_max(&HOST, &max_dummy_outputs);
+        cancellation::set_quorum(&HOST, &cancel_quorum);
+        buckets::set_unit(&HOST, &bucket_unit);
+        migration::set_timelock(&HOST, &mixer_timelock);
 
-        // Create new random encryption key
-        let key = generate_key();
-        write_state!(ENCRYPTION_KEY => key);
+        // Create new random master seed of the contract keys
+        kdf::init(&HOST);
+        audit::register(&HOST, &auditor_pub_keys, &auditor_roles, &Self::get_keypair(&HOST));
+        deployment::record(&HOST, &mixer_eth_addr, &Self::get_keypair(&HOST).get_pubkey(), &Self::get_signing_keypair(&HOST).get_pubkey());
+        config::record(&HOST, config::CHANGE_DEPLOYMENT, &mixer_eth_addr);
     }
 
     fn get_pub_key() -> Vec<u8> {
-        let keypair = Self::get_keypair();
+        let keypair = Self::get_keypair(&HOST);
         let pub_key = keypair.get_pubkey();
         let pub_key_text: String = pub_key.to_hex();
-        eprint!("The pubKey hex: {}", pub_key_text);
+        trace!("The pubKey hex: {}", pub_key_text);
         pub_key.to_vec()
     }
 
+    fn get_signing_pub_key() -> Vec<u8> {
+        Self::get_signing_keypair(&HOST).get_pubkey().to_vec()
+    }
+
+    fn is_initialized() -> Vec<u8> {
+        deployment::export(&HOST)
+    }
+
+    fn get_limits() -> Vec<u8> {
+        let mut encoder = codec::Encoder::default();
+        encoder.append_u64(MAX_PARTICIPANTS as u64);
+        encoder.append_u64((protocol::envelope().max_payload_size() + compliance::engine(&HOST).payload_overhead()) as u64);
+        encoder.append_list(&protocol::envelope_versions(), |version| vec![*version]);
+        encoder.append_list(&signature::supported_modes(), |mode| vec![*mode]);
+        encoder.finish()
+    }
+
+    fn get_schema() -> Vec<u8> {
+        schema::export()
+    }
+
+    fn get_metrics() -> Vec<u8> {
+        metrics::export(&HOST)
+    }
+
+    fn health() -> Vec<u8> {
+        health::export(&HOST)
+    }
+
+    fn get_anomaly() -> Vec<u8> {
+        anomaly::export(&HOST)
+    }
+
+    fn schedule_deal(
+        deal_id: H256,
+        window: U256,
+        confirmations: U256,
+        block_attestation: Vec<u8>,
+        block_signature: Vec<u8>,
+        amount: U256,
+        senders: Vec<H160>,
+        operator_address: H160,
+        operator_nonce: U256,
+        signature: Vec<u8>,
+    ) -> U256 {
+        if digest::deal_id(&amount, &senders, &operator_address, &operator_nonce) != deal_id {
+            panic!("Deal inputs not committed to deal {:?}", deal_id);
+        }
+        // Scheduled by its operator only, lest anyone schedule the deal first and lock the operator out
+        operators::verify(&HOST, &operator_address, &schedule::message(&deal_id, &window, &confirmations), &signature);
+        let (attested_block, _) = oracle::verify_block_attestation(&HOST, &block_attestation, &block_signature, &[]);
+        let attested_block = validation::to_u64(&attested_block, "attested block");
+        let window = validation::to_u64(&window, "execution window");
+        let confirmations = validation::to_u64(&confirmations, "confirmation depth");
+        // Bounded by the window so that the deal may still execute once the attested block is confirmed
+        if confirmations > window {
+            panic!("Confirmation depth exceeds the execution window: {} > {}", confirmations, window);
+        }
+        let eligible_block = schedule::schedule(&HOST, &deal_id, attested_block, window);
+        facts::depend(&HOST, &deal_id, &block_attestation, attested_block, confirmations);
+        U256::from(eligible_block)
+    }
+
+    fn invalidate_relayed_fact(fact_id: H256, proof: Vec<u8>) {
+        facts::invalidate(&HOST, &fact_id, &proof);
+    }
+
     fn execute_deal(
         operator_address: H160,
         operator_nonce: U256, // TODO: Try with lower integer
+        operator_bond: H256,
         amount: U256,
         pub_keys: Vec<Vec<u8>>,
         enc_recipients: Vec<Vec<u8>>,
         senders: Vec<H160>,
         signatures: Vec<Vec<u8>>,
+        expiries: Vec<U256>,
         chain_id: U256,
+        features: U256,
+        nb_redeemed: U256,
+        aggregate_signature: Vec<u8>,
+        block_attestation: Vec<u8>,
+        block_signature: Vec<u8>,
+        operator_signature: Vec<u8>,
     ) -> Vec<H160> {
-        eprint!(
-            "In execute_deal({:?}, {:?}, {:?}, {:?}, {:?})",
-            operator_address, operator_nonce, enc_recipients, senders, signatures
-        );
-        let mut recipients = Self::verify_deposits_internal(
+        trace!("In execute_deal({:?}, {:?}) of {} deposits", operator_address, operator_nonce, senders.len());
+        // The deposit lists are checked before indexing them into the payload signed by the operator
+        validation::check_participants(&pub_keys, &enc_recipients, &senders, &signatures, &expiries);
+        profile::check_chain_id(&HOST, &chain_id);
+        let deal_features = features::check(&HOST, &features);
+        let deal_id = digest::deal_id(&amount,
+                                       &senders,
+                                       &operator_address,
+                                       &operator_nonce);
+        trace!("The DealId: {:?}", deal_id);
+        let _deal = trace::deal(&deal_id);
+        let payload_message = digest::payload_message(
+            &deal_id, &pub_keys, &enc_recipients, &signatures, &expiries, &chain_id, &features, &aggregate_signature);
+        operators::verify(&HOST, &operator_address, &payload_message, &operator_signature);
+        amounts::check(&HOST, &amount);
+        amounts::check_value(&HOST, &amount, senders.len());
+        let redeemed = validation::to_u64(&nb_redeemed, "redeemed deposits") as usize;
+        redeposits::check_redeemable(&HOST, &amount, redeemed, MAX_PARTICIPANTS - senders.len());
+        let checkpointed = senders.len() > profile::get(&HOST).deposits_per_task;
+        let capsule = if profile::simulation(&HOST) || checkpointed {
+            let capsule = DealCapsule {
+                operator_address,
+                operator_nonce,
+                amount,
+                pub_keys: pub_keys.clone(),
+                enc_recipients: enc_recipients.clone(),
+                senders: senders.clone(),
+                signatures: signatures.clone(),
+                expiries: expiries.clone(),
+                chain_id,
+                aggregate_signature: aggregate_signature.clone(),
+                block_attestation: block_attestation.clone(),
+                block_signature: block_signature.clone(),
+                features,
+                nb_redeemed,
+            };
+            Some(capsule.encode())
+        } else {
+            None
+        };
+        if profile::simulation(&HOST) {
+            let capsule_hex: String = capsule.as_ref().unwrap().to_hex();
+            trace!("The deal capsule: {}", capsule_hex);
+        }
+        if let Some(record) = deals::load(&HOST, &deal_id) {
+            // A retried execution is answered from the record, without committing the deal twice
+            trace!("Deal already executed with status {:?}", record.status);
+            return record.outputs().iter().map(|(recipient, _)| *recipient).collect();
+        }
+        if checkpoint::load(&HOST, &deal_id).is_some() {
+            trace!("Deal already executing across tasks, resumed with continue_deal");
+            return Vec::new();
+        }
+        if cancellation::cancelled(&HOST, &deal_id, senders.len()) {
+            trace!("Refunding deal {:?} cancelled by its senders", deal_id);
+            Self::refund_deal(&HOST, &deal_id, &operator_address, &operator_bond, &amount, &senders, &pub_keys);
+            return Vec::new();
+        }
+        anomaly::check(&HOST);
+        let (attested_block, immature) = oracle::immature_deposits(&HOST, &block_attestation, &block_signature, &senders);
+        if !immature.is_empty() {
+            // The operator committed to deposits it could see were too recently confirmed
+            trace!("Rejected {} immature deposits", immature.len());
+            Self::fail_deal(&HOST, &deal_id, &operator_address, &operator_bond, &senders, &pub_keys, &enc_recipients, &immature);
+            return Vec::new();
+        }
+        schedule::check(&HOST, &deal_id, attested_block);
+        facts::check(&HOST, &deal_id, &block_attestation, attested_block);
+        // The on-chain commitment of the deal being confirmed, its participants may no longer change
+        freeze::freeze(&HOST, &deal_id, Self::participant_entries(&senders, &pub_keys, &enc_recipients));
+        if checkpointed {
+            // Deals too large for a single task verify the deposits per task of the profile, the first task now
+            checkpoint::save(&HOST, &deal_id, &Checkpoint {
+                capsule: capsule.unwrap(),
+                operator_bond: Hash(operator_bond),
+                attested_block,
+                next: 0,
+                deposits: Vec::new(),
+                aggregated: Vec::new(),
+                rejected: Vec::new(),
+                phase_costs: Vec::new(),
+            });
+            Self::continue_deal(deal_id);
+            return Vec::new();
+        }
+        let engine = compliance::engine(&HOST);
+        let deposits = match Self::verify_deposits_internal(&HOST, 
+            &*engine,
             amount,
-            pub_keys,
-            enc_recipients,
+            pub_keys.clone(),
+            enc_recipients.clone(),
             senders.clone(),
             signatures,
-            chain_id);
-        let seed: u64 = Rand::gen();
-        for i in (0..recipients.len()).rev() {
-            let j = seed as usize % (i + 1);
-            let recipient = recipients[j];
-            recipients[j] = recipients[i];
-            recipients[i] = recipient;
-        }
-        let mixer_eth_addr: String = Self::get_mixer_eth_addr();
-        let prefixed_eth_addr = format!("0x{}", mixer_eth_addr);
-        let eth_contract = EthContract::new(&prefixed_eth_addr);
-        let deal_id = Self::generate_deal_id(&amount,
-                                             &senders,
-                                             &operator_address,
-                                             &operator_nonce);
-        eprint!("The DealId: {:?}", deal_id);
-        // TODO: Converting as a workaround for lack of bytes32 support
-        let deal_id_uint = U256::from(deal_id);
-        eth_contract.distribute(deal_id_uint, recipients.clone());
-        return recipients;
+            expiries,
+            chain_id,
+            aggregate_signature,
+            attested_block) {
+            Ok(deposits) => deposits,
+            Err(rejected) => {
+                // The operator committed to deposits failing verification, stalling the deal
+                Self::fail_deal(&HOST, &deal_id, &operator_address, &operator_bond, &senders, &pub_keys, &enc_recipients, &rejected);
+                return Vec::new();
+            }
+        };
+        Self::complete_deal(&HOST, 
+            &*engine, &deal_id, &operator_address, &operator_bond, &amount, &senders, &pub_keys, deposits, redeemed, attested_block, deal_features)
+    }
+
+    fn continue_deal(deal_id: H256) -> U256 {
+        let _deal = trace::deal(&deal_id);
+        let mut checkpoint = match checkpoint::load(&HOST, &deal_id) {
+            Some(checkpoint) => checkpoint,
+            None => panic!("No deal executing across tasks: {:?}", deal_id),
+        };
+        let capsule = DealCapsule::decode(&checkpoint.capsule);
+        // Deals checkpointed before the participant sets were frozen are frozen on their next task
+        freeze::freeze(&HOST, &deal_id, Self::participant_entries(&capsule.senders, &capsule.pub_keys, &capsule.enc_recipients));
+        let nb_participants = capsule.senders.len();
+        let end = cmp::min(checkpoint.next + profile::get(&HOST).deposits_per_task, nb_participants);
+        trace!("Verifying deposits {} to {} of {}", checkpoint.next, end, nb_participants);
+        metering::resume(&checkpoint.phase_costs);
+        let engine = compliance::engine(&HOST);
+        let (deposits, aggregated, rejected) = Self::verify_deposit_batch(&HOST, 
+            &*engine,
+            &capsule.amount,
+            &capsule.pub_keys,
+            &capsule.enc_recipients,
+            &capsule.senders,
+            &capsule.signatures,
+            &capsule.expiries,
+            &capsule.chain_id,
+            checkpoint.attested_block,
+            checkpoint.next..end);
+        // The deposits screened by each task are recorded at its checkpoint
+        engine.commit(&HOST);
+        checkpoint.deposits.extend(deposits.into_iter().map(VerifiedDeposit::checkpoint));
+        checkpoint.aggregated.extend(aggregated);
+        checkpoint.rejected.extend(rejected);
+        checkpoint.next = end;
+        checkpoint.phase_costs = metering::costs();
+        if end < nb_participants {
+            checkpoint::save(&HOST, &deal_id, &checkpoint);
+            return U256::from(nb_participants - end);
+        }
+        checkpoint::clear(&HOST, &deal_id);
+        let operator_bond = checkpoint.operator_bond.0;
+        if !checkpoint.rejected.is_empty() {
+            trace!("Rejected {} deposits", checkpoint.rejected.len());
+            Self::fail_deal(&HOST, 
+                &deal_id, &capsule.operator_address, &operator_bond, &capsule.senders, &capsule.pub_keys, &capsule.enc_recipients, &checkpoint.rejected);
+            return U256::zero();
+        }
+        Self::verify_aggregate(&checkpoint.aggregated, &capsule.aggregate_signature);
+        let deposits = checkpoint.deposits.into_iter().map(VerifiedDeposit::restore).collect();
+        Self::complete_deal(&HOST, 
+            &*engine,
+            &deal_id,
+            &capsule.operator_address,
+            &operator_bond,
+            &capsule.amount,
+            &capsule.senders,
+            &capsule.pub_keys,
+            deposits,
+            validation::to_u64(&capsule.nb_redeemed, "redeemed deposits") as usize,
+            checkpoint.attested_block,
+            capsule.features.low_u64());
+        U256::zero()
     }
 
     fn verify_deposits(
@@ -265,9 +1523,654 @@ impl ContractInterface for Contract {
         enc_recipients: Vec<Vec<u8>>,
         senders: Vec<H160>,
         signatures: Vec<Vec<u8>>,
+        expiries: Vec<U256>,
+        chain_id: U256,
+        aggregate_signature: Vec<u8>,
+    ) -> Vec<Vec<u8>> {
+        profile::check_chain_id(&HOST, &chain_id);
+        Self::check_deposits_internal(&HOST, amount, pub_keys, enc_recipients, senders, signatures, expiries, chain_id, aggregate_signature)
+    }
+
+    fn preview_deal(
+        amount: U256,
+        amounts: Vec<U256>,
+        pub_keys: Vec<Vec<u8>>,
+        enc_recipients: Vec<Vec<u8>>,
+        senders: Vec<H160>,
+        signatures: Vec<Vec<u8>>,
+        expiries: Vec<U256>,
+    ) -> Vec<u8> {
+        let keypair = Self::get_keypair(&HOST);
+        let engine = compliance::engine(&HOST);
+        let threshold = threshold::config(&HOST);
+        let inputs = wire::deposits(&pub_keys, &enc_recipients, &senders, &signatures, &expiries).collect();
+        let report = preview::preview(&HOST, &amount, &amounts, inputs, |input| {
+            verification::open_payload(&HOST, &*engine, threshold.as_ref(), &keypair, input.index, input.pub_key, input.enc_recipient)
+                .and_then(|_| engine.screen_deposit(&HOST, input.index, input.sender, &amount))
+        });
+        trace!("Previewed deal of {} deposits, passed: {}", senders.len(), report.passed());
+        report.encode()
+    }
+
+    fn execute_multi_asset_deal(
+        operator_address: H160,
+        operator_nonce: U256,
+        price_attestation: Vec<u8>,
+        price_signature: Vec<u8>,
+        assets: Vec<H160>,
+        amounts: Vec<U256>,
+        pub_keys: Vec<Vec<u8>>,
+        enc_recipients: Vec<Vec<u8>>,
+        senders: Vec<H160>,
+        signatures: Vec<Vec<u8>>,
         chain_id: U256,
-    ) -> bool {
-        Self::verify_deposits_internal(amount, pub_keys, enc_recipients, senders, signatures, chain_id);
-        true
+    ) -> Vec<H160> {
+        trace!(
+            "In execute_multi_asset_deal({:?}, {:?}, {:?}, {:?}, {:?})",
+            operator_address, operator_nonce, price_attestation, assets, senders
+        );
+        profile::check_chain_id(&HOST, &chain_id);
+        anomaly::check(&HOST);
+        let price_rounds = oracle::verify_price_attestation(&HOST, &price_attestation, &price_signature);
+        let engine = compliance::engine(&HOST);
+        let (outputs, value) = Self::verify_multi_asset_deposits_internal(&HOST, 
+            &*engine,
+            &price_rounds,
+            &assets,
+            &amounts,
+            &pub_keys,
+            &enc_recipients,
+            &senders,
+            &signatures,
+            &chain_id);
+        // The DealId commits to the common USD value of the deposits in lieu of a single amount,
+        // and to the asset and amount of each deposit locked by the Mixer
+        let deal_id = digest::asset_deal_id(&value, &assets, &amounts, &senders, &operator_address, &operator_nonce);
+        trace!("The DealId: {:?}", deal_id);
+        let _deal = trace::deal(&deal_id);
+        if let Some(record) = deals::load(&HOST, &deal_id) {
+            trace!("Deal already executed with status {:?}", record.status);
+            return record.outputs().iter().map(|(recipient, _)| *recipient).collect();
+        }
+        let mut outputs: Vec<(usize, H160, H160, U256)> = outputs.into_iter().enumerate()
+            .map(|(i, (recipient, asset, amount))| (i, recipient, asset, amount))
+            .collect();
+        let seed = ordering::order(&HOST, &mut outputs, |(_, recipient, _, _)| *recipient);
+        entropy::commit(&HOST, &deal_id, seed);
+        // Group the ordered outputs into per-asset distribution lists, in attested price order
+        let mut output_assets: Vec<(H160, H160)> = Vec::new();
+        let mut record_outputs: Vec<(usize, H160, U256)> = Vec::new();
+        for price_round in price_rounds.iter() {
+            for (i, recipient, asset, amount) in outputs.iter().filter(|(_, _, asset, _)| *asset == price_round.asset) {
+                output_assets.push((*asset, tokens::payee(tokens::get(&HOST, asset).as_ref(), asset)));
+                record_outputs.push((*i, *recipient, *amount));
+            }
+        }
+        let recipients: Vec<H160> = record_outputs.iter().map(|(_, recipient, _)| *recipient).collect();
+        // Multi-asset deposits are not age checked, their receipt binding no attested block
+        let receipt_hash = digest::receipt_hash(&deal_id, 0, &recipients);
+        trace!("The receipt hash: {:?}", receipt_hash);
+        // Multi-asset deals are not bonded, their outputs being paid in their assets by `finalize_distribution`
+        let record = DealRecord::new(
+            DealStatus::Committed,
+            &operator_address,
+            &H256::zero(),
+            &senders,
+            &[],
+            &pub_keys,
+            &record_outputs,
+            &U256::zero(),
+            &U256::zero(),
+            &receipt_hash,
+            0);
+        let participants_root = merkle::root(&record.participant_leaves());
+        let outputs_root = merkle::root(&record.output_leaves());
+        let mixer_eth_addr: String = Self::get_mixer_eth_addr(&HOST);
+        Self::commit_note_payouts(&HOST, &mixer_eth_addr, &deal_id);
+        HOST.commit_roots(&mixer_eth_addr, &deal_id, &participants_root, &outputs_root);
+        payout::save_assets(&HOST, &deal_id, &output_assets);
+        deals::save(&HOST, &deal_id, &record);
+        engine.commit(&HOST);
+        health::record_deal(&HOST, 0);
+        recipients
+    }
+
+    fn execute_voucher_deal(
+        operator_address: H160,
+        operator_nonce: U256,
+        operator_bond: H256,
+        amount: U256,
+        vouchers: Vec<H256>,
+        commitments: Vec<H256>,
+        pub_keys: Vec<Vec<u8>>,
+        enc_recipients: Vec<Vec<u8>>,
+    ) -> Vec<H160> {
+        let nb_participants = vouchers.len();
+        if nb_participants > MAX_PARTICIPANTS {
+            panic!("Too many participants: {} > {}", nb_participants, MAX_PARTICIPANTS);
+        }
+        if commitments.len() != nb_participants || pub_keys.len() != nb_participants || enc_recipients.len() != nb_participants {
+            panic!("Mismatching voucher deposits: {} vouchers, {} commitments, {} pubKeys, {} payloads",
+                   nb_participants, commitments.len(), pub_keys.len(), enc_recipients.len());
+        }
+        amounts::check(&HOST, &amount);
+        amounts::check_value(&HOST, &amount, nb_participants);
+        let deal_id = digest::voucher_deal_id(&amount, &vouchers, &commitments, &operator_address, &operator_nonce);
+        trace!("The voucher DealId: {:?}", deal_id);
+        let _deal = trace::deal(&deal_id);
+        if let Some(record) = deals::load(&HOST, &deal_id) {
+            trace!("Deal already executed with status {:?}", record.status);
+            return record.outputs().iter().map(|(recipient, _)| *recipient).collect();
+        }
+        anomaly::check(&HOST);
+        let entries = vouchers.iter().zip(pub_keys.iter()).zip(enc_recipients.iter())
+            .map(|((voucher, pub_key), enc_recipient)| freeze::entry(voucher, pub_key, enc_recipient))
+            .collect();
+        freeze::freeze(&HOST, &deal_id, entries);
+        let engine = compliance::engine(&HOST);
+        let deposits = Self::verify_voucher_deposits_internal(&HOST, &*engine, &amount, &commitments, &pub_keys, &enc_recipients)
+            .unwrap_or_else(|rejected| panic!("Rejected voucher deposits: {:?}", rejected));
+        let mut voucher_recipients: Vec<H160> = deposits.iter().flat_map(|deposit| deposit.iter().map(|(recipient, _)| *recipient)).collect();
+        voucher_recipients.sort();
+        voucher_recipients.dedup();
+        let anonymity = metrics::Anonymity {
+            set_size: nb_participants,
+            refunds: 0,
+            recipients: voucher_recipients.len(),
+            fresh_recipients: stipend::count_fresh(&HOST, &voucher_recipients),
+        };
+        let mut outputs: Vec<(usize, H160, U256)> = Vec::new();
+        for (i, deposit) in deposits.iter().enumerate() {
+            outputs.extend(deposit.iter().map(|(recipient, amount)| (i, *recipient, *amount)));
+        }
+        let seed = ordering::order(&HOST, &mut outputs, |(_, recipient, _)| *recipient);
+        entropy::commit(&HOST, &deal_id, seed);
+        let recipients: Vec<H160> = outputs.iter().map(|(_, recipient, _)| *recipient).collect();
+        // Voucher deposits are not age checked, their receipt binding no attested block
+        let receipt_hash = digest::receipt_hash(&deal_id, 0, &recipients);
+        // The senders are unknown, the deposits being recorded like staged deposits with their commitment as digest
+        let record = DealRecord::new(
+            DealStatus::Committed,
+            &operator_address,
+            &operator_bond,
+            &vec![H160::zero(); nb_participants],
+            &commitments,
+            &pub_keys,
+            &outputs,
+            &U256::zero(),
+            &U256::zero(),
+            &receipt_hash,
+            0);
+        let participants_root = merkle::root(&record.participant_leaves());
+        let outputs_root = merkle::root(&record.output_leaves());
+        let mixer_eth_addr: String = Self::get_mixer_eth_addr(&HOST);
+        Self::commit_note_payouts(&HOST, &mixer_eth_addr, &deal_id);
+        HOST.commit_roots(&mixer_eth_addr, &deal_id, &participants_root, &outputs_root);
+        deals::save(&HOST, &deal_id, &record);
+        engine.commit(&HOST);
+        health::record_deal(&HOST, 0);
+        metrics::record(&HOST, nb_participants, outputs.len(), &anonymity);
+        recipients
+    }
+
+    fn finalize_distribution(deal_id: H256) {
+        Self::finalize_distribution_internal(&HOST, &deal_id, None);
+    }
+
+    fn preview_distribution(deal_id: H256, operator_pub_key: Vec<u8>, signature: Vec<u8>) -> Vec<u8> {
+        let _deal = trace::deal(&deal_id);
+        let record = match deals::load(&HOST, &deal_id) {
+            Some(record) => record,
+            None => panic!("Unknown deal: {:?}", deal_id),
+        };
+        if record.status != DealStatus::Committed {
+            panic!("Deal {:?} is not committed: {:?}", deal_id, record.status);
+        }
+        if operator_pub_key.len() != PUB_KEY_SIZE {
+            panic!("Invalid operator pubKey size: {}", operator_pub_key.len());
+        }
+        let mut request: Vec<u8> = Vec::new();
+        request.extend_from_slice(&deal_id);
+        request.extend_from_slice(&operator_pub_key);
+        operators::verify(&HOST, &record.operator(), &domains::domain_message(domains::PREVIEW_DOMAIN, &request), &signature);
+        let receipt_hash = record.receipt_hash();
+        let calldata = payout::adapter(&HOST, &deal_id).calldata(&Self::deal_payout(&deal_id, &record, &receipt_hash));
+        distribution::record_preview(&HOST, &deal_id, &calldata);
+        let mut pub_key = [0_u8; PUB_KEY_SIZE];
+        pub_key.copy_from_slice(&operator_pub_key);
+        match Self::get_keypair(&HOST).derive_key(&pub_key) {
+            Ok(shared_key) => HOST.encrypt(&calldata, &shared_key),
+            Err(err) => panic!("Unable to derive a key with the operator pubKey: {:?}", err),
+        }
+    }
+
+    fn confirm_distribution(deal_id: H256, calldata_hash: H256) {
+        Self::finalize_distribution_internal(&HOST, &deal_id, Some(calldata_hash));
+    }
+
+    fn retry_distribution(deal_id: H256) {
+        let _deal = trace::deal(&deal_id);
+        let mut record = match deals::load(&HOST, &deal_id) {
+            Some(record) => record,
+            None => panic!("Unknown deal: {:?}", deal_id),
+        };
+        let mixer_eth_addr: String = Self::get_mixer_eth_addr(&HOST);
+        let retries = match record.status {
+            DealStatus::Distributed => distribution::record_retry(&HOST, &deal_id),
+            // The receipt callout reverts until the distribution lands, the receipt is anchored again after it
+            DealStatus::Anchored => {
+                record.status = DealStatus::Distributed;
+                deals::save(&HOST, &deal_id, &record);
+                distribution::record_retry(&HOST, &deal_id)
+            }
+            // A failed refund of a deal never committed is retried through its own callout
+            DealStatus::Refunded if record.receipt_hash().is_zero() => {
+                trace!("Refunding deal {:?}", deal_id);
+                HOST.refund_deal(&mixer_eth_addr, &deal_id);
+                return;
+            }
+            // A failed refund is retried as is
+            DealStatus::Refunded => u8::max_value(),
+            _ => panic!("Deal {:?} is not distributed: {:?}", deal_id, record.status),
+        };
+        // Multi-asset deals have no compensating refund in ETH, their distribution being retried until it lands
+        if retries <= distribution::MAX_DISTRIBUTION_RETRIES || payout::assets(&HOST, &deal_id).is_some() {
+            trace!("Retrying distribution {} of deal {:?}", retries, deal_id);
+            let outputs = record.outputs();
+            let recipients: Vec<H160> = outputs.iter().map(|(recipient, _)| *recipient).collect();
+            let amounts: Vec<U256> = outputs.iter().map(|(_, amount)| *amount).collect();
+            Self::distribute_outputs(&HOST, &mixer_eth_addr, &deal_id, &record, recipients, amounts, &record.receipt_hash());
+        } else {
+            // The refunds are not shuffled outputs, they carry no receipt
+            trace!("Refunding deal {:?}", deal_id);
+            let refunds = record.refunds();
+            let senders: Vec<H160> = refunds.iter().map(|(sender, _)| *sender).collect();
+            let amounts: Vec<U256> = refunds.iter().map(|(_, amount)| *amount).collect();
+            DirectEth.pay(&HOST, &mixer_eth_addr, Payout {
+                deal_id: &deal_id,
+                recipients: senders,
+                amounts,
+                redeposited: record.redeposited(),
+                redeemed: record.redeemed(),
+                receipt_hash: &H256::zero(),
+            });
+            record.status = DealStatus::Refunded;
+            deals::save(&HOST, &deal_id, &record);
+        }
+    }
+
+    fn vote_cancel_deal(amount: U256, senders: Vec<H160>, operator_address: H160, operator_nonce: U256, signature: Vec<u8>) -> U256 {
+        let deal_id = digest::deal_id(&amount, &senders, &operator_address, &operator_nonce);
+        let _deal = trace::deal(&deal_id);
+        if deals::load(&HOST, &deal_id).is_some() || checkpoint::load(&HOST, &deal_id).is_some() {
+            panic!("Deal already executed: {:?}", deal_id);
+        }
+        U256::from(cancellation::vote(&HOST, &deal_id, &senders, &signature))
+    }
+
+    fn trigger_timeout(
+        deal_id: H256,
+        current_block: U256,
+        block_signature: Vec<u8>,
+        amount: U256,
+        senders: Vec<H160>,
+        operator_address: H160,
+        operator_nonce: U256,
+    ) -> U256 {
+        let _deal = trace::deal(&deal_id);
+        if digest::deal_id(&amount, &senders, &operator_address, &operator_nonce) != deal_id {
+            panic!("Deal inputs not committed to deal {:?}", deal_id);
+        }
+        if deals::load(&HOST, &deal_id).is_some() {
+            panic!("Deal already executed: {:?}", deal_id);
+        }
+        let (current_block, _) = oracle::verify_block_attestation(&HOST, &convert::uint_to_word(&current_block), &block_signature, &[]);
+        schedule::check_timeout(&HOST, &deal_id, validation::to_u64(&current_block, "current block"));
+        // A stalled resumed deal is refunded under the bond its operator executed it with
+        let (bond, pub_keys) = match checkpoint::load(&HOST, &deal_id) {
+            Some(checkpoint) => {
+                checkpoint::clear(&HOST, &deal_id);
+                (checkpoint.operator_bond.0, DealCapsule::decode(&checkpoint.capsule).pub_keys)
+            }
+            None => (H256::zero(), Vec::new()),
+        };
+        trace!("Refunding deal {:?} timed out at block {}", deal_id, current_block);
+        Self::refund_deal(&HOST, &deal_id, &operator_address, &bond, &amount, &senders, &pub_keys);
+        U256::from(senders.len())
+    }
+
+    fn anchor_receipt(deal_id: H256) {
+        let _deal = trace::deal(&deal_id);
+        let mut record = match deals::load(&HOST, &deal_id) {
+            Some(record) => record,
+            None => panic!("Unknown deal: {:?}", deal_id),
+        };
+        match record.status {
+            DealStatus::Distributed => (),
+            DealStatus::Anchored => {
+                let retries = distribution::record_receipt_retry(&HOST, &deal_id);
+                if retries > distribution::MAX_RECEIPT_RETRIES {
+                    panic!("Receipt of deal {:?} not anchored after {} retries", deal_id, distribution::MAX_RECEIPT_RETRIES);
+                }
+                trace!("Retrying receipt {} of deal {:?}", retries, deal_id);
+            }
+            // The refunds carry no receipt
+            _ => panic!("Deal {:?} is not distributed: {:?}", deal_id, record.status),
+        }
+        let mixer_eth_addr: String = Self::get_mixer_eth_addr(&HOST);
+        HOST.record_receipt(&mixer_eth_addr, &deal_id, &record.receipt_hash());
+        record.status = DealStatus::Anchored;
+        deals::save(&HOST, &deal_id, &record);
+    }
+
+    fn open_challenge(deal_id: H256, deposit_digest: H256, proof: Vec<u8>) {
+        challenge::open(&HOST, &deal_id, &deposit_digest, &proof);
+    }
+
+    fn respond_challenge(deal_id: H256, deposit_digest: H256) -> Vec<u8> {
+        challenge::respond(&HOST, &deal_id, &deposit_digest, &Self::get_keypair(&HOST), &Self::get_signing_keypair(&HOST))
+    }
+
+    fn get_open_challenges(deal_id: H256) -> Vec<H256> {
+        challenge::open_challenges(&HOST, &deal_id)
+    }
+
+    fn suggest_batch_size(avg_decrypt_cost: U256, task_gas_limit: U256) -> U256 {
+        metrics::suggest_batch_size(&HOST, &avg_decrypt_cost, &task_gas_limit)
+    }
+
+    fn get_evidence(deal_id: H256, offset: U256, limit: U256) -> Vec<Vec<u8>> {
+        pagination::page(&evidence::load(&HOST, &deal_id), &offset, &limit)
+    }
+
+    fn get_evidence_count(deal_id: H256) -> U256 {
+        U256::from(evidence::load(&HOST, &deal_id).len())
+    }
+
+    fn list_recent_deals(offset: U256, limit: U256) -> Vec<H256> {
+        pagination::page(&deals::recent(&HOST), &offset, &limit)
+    }
+
+    fn get_deal_count() -> U256 {
+        U256::from(deals::recent(&HOST).len())
+    }
+
+    fn get_audit_envelopes(deal_id: H256) -> Vec<Vec<u8>> {
+        audit::load(&HOST, &deal_id)
+    }
+
+    fn get_deal_receipt(deal_id: H256) -> Vec<u8> {
+        receipts::load(&HOST, &deal_id)
+    }
+
+    fn get_shuffle_commitment(deal_id: H256) -> H256 {
+        entropy::commitment(&HOST, &deal_id).unwrap_or_else(H256::zero)
+    }
+
+    fn reveal_shuffle_seed(deal_id: H256) -> Vec<u8> {
+        entropy::reveal(&HOST, &deal_id)
+    }
+
+    fn get_staged_summary() -> Vec<u8> {
+        redeposits::summary(&HOST)
+    }
+
+    fn get_redeemable(amount: U256, nb_senders: U256) -> U256 {
+        let nb_senders = validation::to_u64(&nb_senders, "senders") as usize;
+        U256::from(redeposits::redeemable(&HOST, &amount, MAX_PARTICIPANTS.saturating_sub(nb_senders)))
+    }
+
+    fn self_test() -> Vec<u8> {
+        selftest::run(&HOST, &Self::get_keypair(&HOST), &Self::get_signing_keypair(&HOST))
+    }
+
+    fn get_build_info() -> Vec<u8> {
+        provenance::build_info(&Self::get_signing_keypair(&HOST))
+    }
+
+    fn get_config() -> Vec<u8> {
+        config::snapshot(&HOST, &Self::get_mixer_address(&HOST))
+    }
+
+    fn get_supported_features() -> U256 {
+        U256::from(features::supported(&HOST))
+    }
+
+    fn get_network_profile() -> Vec<U256> {
+        let profile = profile::get(&HOST);
+        vec![
+            U256::from(profile.id),
+            U256::from(profile.chain_id),
+            U256::from(profile.min_deposit_age),
+            U256::from(profile.deposits_per_task),
+            U256::from(profile.max_execution_window),
+            U256::from(profile.min_confirmations),
+        ]
+    }
+
+    fn get_config_history(n: U256) -> Vec<Vec<u8>> {
+        config::history(&HOST, validation::capped_count(&n, usize::max_value()))
+    }
+
+    fn configure_threshold(attestation: Vec<u8>, signature: Vec<u8>) {
+        threshold::configure(&HOST, &attestation, &signature, &Self::get_keypair(&HOST).get_pubkey());
+        config::record(&HOST, config::CHANGE_THRESHOLD, &Self::get_mixer_address(&HOST));
+    }
+
+    fn register_relayer(attestation: Vec<u8>, signature: Vec<u8>) {
+        relay::register(&HOST, &attestation, &signature);
+        config::record(&HOST, config::CHANGE_RELAYER, &Self::get_mixer_address(&HOST));
+    }
+
+    fn override_deal_value_cap(attestation: Vec<u8>, signature: Vec<u8>) {
+        let body = oracle::verify_command(&HOST, domains::ORACLE_VALUE_CAP_DOMAIN, &attestation, &signature);
+        if body.len() != 32 {
+            panic!("Invalid deal value cap attestation size: {}", body.len());
+        }
+        let cap = convert::word_to_uint(body);
+        trace!("Overriding the deal value cap: {}", cap);
+        amounts::set_value_cap(&HOST, &cap);
+        config::record(&HOST, config::CHANGE_VALUE_CAP, &Self::get_mixer_address(&HOST));
+    }
+
+    fn set_payout_bridge(attestation: Vec<u8>, signature: Vec<u8>) {
+        let body = oracle::verify_command(&HOST, domains::ORACLE_BRIDGE_DOMAIN, &attestation, &signature);
+        if body.len() != 32 {
+            panic!("Invalid payout bridge attestation size: {}", body.len());
+        }
+        let bridge = convert::word_to_address(body);
+        trace!("Setting the payout bridge: {:?}", bridge);
+        payout::set_bridge(&HOST, &Self::get_mixer_eth_addr(&HOST), &bridge);
+        config::record(&HOST, config::CHANGE_BRIDGE, &Self::get_mixer_address(&HOST));
+    }
+
+    fn set_retention_policy(attestation: Vec<u8>, signature: Vec<u8>) {
+        let policy = retention::parse_attestation(oracle::verify_command(&HOST, domains::ORACLE_RETENTION_DOMAIN, &attestation, &signature));
+        trace!("Keeping the deal records {} epochs and the notifications {} epochs", policy.deal_epochs, policy.notification_epochs);
+        retention::set(&HOST, policy);
+        config::record(&HOST, config::CHANGE_RETENTION, &Self::get_mixer_address(&HOST));
+    }
+
+    fn acknowledge_anomaly(attestation: Vec<u8>, signature: Vec<u8>) {
+        let body = oracle::verify_command(&HOST, domains::ORACLE_ANOMALY_DOMAIN, &attestation, &signature);
+        if body.len() != 32 {
+            panic!("Invalid anomaly acknowledgment attestation size: {}", body.len());
+        }
+        anomaly::acknowledge(&HOST, validation::to_u64(&convert::word_to_uint(body), "anomaly epoch"));
+        config::record(&HOST, config::CHANGE_ANOMALY, &Self::get_mixer_address(&HOST));
+    }
+
+    fn set_token_config(attestation: Vec<u8>, signature: Vec<u8>) {
+        let config = tokens::parse_attestation(oracle::verify_command(&HOST, domains::ORACLE_TOKEN_CONFIG_DOMAIN, &attestation, &signature));
+        trace!("Setting the config of token {:?}", config.token.0);
+        tokens::set(&HOST, config);
+        config::record(&HOST, config::CHANGE_TOKEN, &Self::get_mixer_address(&HOST));
+    }
+
+    fn remove_token_config(attestation: Vec<u8>, signature: Vec<u8>) {
+        let body = oracle::verify_command(&HOST, domains::ORACLE_TOKEN_REMOVAL_DOMAIN, &attestation, &signature);
+        if body.len() != 32 {
+            panic!("Invalid token removal attestation size: {}", body.len());
+        }
+        let token = convert::word_to_address(body);
+        trace!("Removing token {:?}", token);
+        tokens::remove(&HOST, &token);
+        config::record(&HOST, config::CHANGE_TOKEN, &Self::get_mixer_address(&HOST));
+    }
+
+    fn get_token_config(token: H160) -> Vec<u8> {
+        tokens::get(&HOST, &token).map_or_else(Vec::new, |config| config.encode())
+    }
+
+    fn get_tokens() -> Vec<H160> {
+        tokens::list(&HOST)
+    }
+
+    fn propose_mixer_address(attestation: Vec<u8>, signature: Vec<u8>) {
+        let body = oracle::verify_command(&HOST, domains::ORACLE_MIXER_DOMAIN, &attestation, &signature);
+        if body.len() != 32 {
+            panic!("Invalid Mixer address attestation size: {}", body.len());
+        }
+        migration::propose(&HOST, &convert::word_to_address(body), &Self::get_mixer_address(&HOST));
+        config::record(&HOST, config::CHANGE_MIXER, &Self::get_mixer_address(&HOST));
+    }
+
+    fn apply_mixer_address() {
+        let mixer = migration::apply(&HOST);
+        trace!("Migrating from Mixer {:?} to {:?}", Self::get_mixer_address(&HOST), mixer);
+        HOST.write_state(MIXER_ETH_ADDR, Address(mixer));
+        // The new Mixer relays the outputs through the bridge of the enclave as the previous one
+        let bridge = payout::bridge(&HOST);
+        if !bridge.is_zero() {
+            payout::set_bridge(&HOST, &Self::get_mixer_eth_addr(&HOST), &bridge);
+        }
+        deployment::migrate(&HOST, &mixer);
+        config::record(&HOST, config::CHANGE_MIXER, &mixer);
+    }
+
+    fn register_operator(attestation: Vec<u8>, signature: Vec<u8>) {
+        operators::register(&HOST, &attestation, &signature);
+        config::record(&HOST, config::CHANGE_OPERATOR, &Self::get_mixer_address(&HOST));
+    }
+
+    fn propose_operator(operator: H160, new_signer_pub_key: Vec<u8>, signature: Vec<u8>) {
+        operators::propose(&HOST, &operator, &new_signer_pub_key, &signature);
+    }
+
+    fn accept_operator(operator: H160, signature: Vec<u8>) {
+        operators::accept(&HOST, &operator, &signature);
+        config::record(&HOST, config::CHANGE_OPERATOR, &Self::get_mixer_address(&HOST));
+    }
+
+    fn get_operator_handoff(operator: H160) -> Vec<u8> {
+        operators::handoff(&HOST, &operator)
+    }
+
+    fn register_standby(attestation: Vec<u8>, signature: Vec<u8>) {
+        standby::register(&HOST, &attestation, &signature);
+        config::record(&HOST, config::CHANGE_STANDBY, &Self::get_mixer_address(&HOST));
+    }
+
+    fn export_standby_delta(since: U256) -> Vec<u8> {
+        standby::export_delta(&HOST, &Self::get_keypair(&HOST), validation::to_u64(&since, "standby revision"))
+    }
+
+    fn import_standby_delta(primary_pub_key: Vec<u8>, delta: Vec<u8>) {
+        standby::import_delta(&HOST, &Self::get_keypair(&HOST), &primary_pub_key, &delta);
+    }
+
+    fn promote_standby(attestation: Vec<u8>, signature: Vec<u8>) {
+        standby::promote(&HOST, &attestation, &signature, &Self::get_keypair(&HOST).get_pubkey());
+        config::record(&HOST, config::CHANGE_PROMOTION, &Self::get_mixer_address(&HOST));
+    }
+
+    fn withdraw_notes(owner: H160, payout: H160, amount: U256, nonce: U256, signature: Vec<u8>) {
+        ledger::withdraw(&HOST, &owner, &payout, &amount, &nonce, &signature);
+        ledger::hold_payout(&HOST, &payout, &amount);
+    }
+
+    fn queue_withdrawal(owner: H160, payout: H160, amount: U256, fee: U256, nonce: U256, signature: Vec<u8>) {
+        ledger::enqueue(&HOST, &owner, &payout, &amount, &fee, &nonce, &signature);
+    }
+
+    fn process_withdrawals(operator_address: H160, max_withdrawals: U256) -> U256 {
+        if !operators::is_registered(&HOST, &operator_address) {
+            panic!("Not a registered operator: {:?}", operator_address);
+        }
+        let max = validation::to_u64(&max_withdrawals, "max withdrawals") as usize;
+        let (batch, fees) = ledger::next_batch(&HOST, max, HOST.random_seed());
+        for (payout, amount) in batch.iter() {
+            ledger::hold_payout(&HOST, payout, amount);
+        }
+        if !fees.is_zero() {
+            ledger::hold_payout(&HOST, &operator_address, &fees);
+        }
+        U256::from(batch.len())
+    }
+
+    fn prune(now: U256) -> U256 {
+        U256::from(retention::prune(&HOST, validation::to_u64(&now, "epoch")))
+    }
+
+    fn fetch_notifications(pointer: H256) -> Vec<Vec<u8>> {
+        notifications::fetch(&HOST, &pointer)
+    }
+
+    fn get_claim_codes(deal_id: H256) -> Vec<Vec<u8>> {
+        ledger::claim_codes(&HOST, &deal_id)
+    }
+
+    fn get_fee_invoice(deal_id: H256, index: U256) -> Vec<u8> {
+        invoices::get(&HOST, &deal_id, validation::to_u64(&index, "deposit index") as usize)
+    }
+
+    fn claim(claim_secret: Vec<u8>, payout: H160, signature: Vec<u8>) {
+        let amount = ledger::claim(&HOST, &claim_secret, &payout, &signature);
+        ledger::hold_payout(&HOST, &payout, &amount);
+    }
+
+    fn partial_decrypt(executor_pub_key: Vec<u8>, pub_keys: Vec<Vec<u8>>, enc_recipients: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
+        threshold::partial_decrypt(&HOST, &Self::get_keypair(&HOST), &executor_pub_key, &pub_keys, &enc_recipients)
+    }
+
+    fn submit_partials(peer_index: U256, enc_recipients: Vec<Vec<u8>>, partials: Vec<Vec<u8>>) {
+        threshold::submit_partials(&HOST, &peer_index, &enc_recipients, &partials);
+    }
+
+    fn replay_deal(capsule: Vec<u8>) -> Vec<H160> {
+        if !profile::simulation(&HOST) {
+            panic!("Deal replay is only available in simulation builds");
+        }
+        let capsule = DealCapsule::decode(&capsule);
+        let deal_id = digest::deal_id(&capsule.amount,
+                                       &capsule.senders,
+                                       &capsule.operator_address,
+                                       &capsule.operator_nonce);
+        trace!("Replaying deal: {:?}", deal_id);
+        let _deal = trace::deal(&deal_id);
+        let (attested_block, immature) = oracle::immature_deposits(&HOST, &capsule.block_attestation, &capsule.block_signature, &capsule.senders);
+        if !immature.is_empty() {
+            panic!("Immature deposits: {:?}", immature);
+        }
+        let deposits = Self::verify_deposits_internal(&HOST, 
+            &*compliance::engine(&HOST),
+            capsule.amount,
+            capsule.pub_keys,
+            capsule.enc_recipients,
+            capsule.senders,
+            capsule.signatures,
+            capsule.expiries,
+            capsule.chain_id,
+            capsule.aggregate_signature,
+            attested_block)
+            .unwrap_or_else(|rejected| panic!("Rejected deposits: {:?}", rejected));
+        deposits.iter().flat_map(|deposit| deposit.outputs.iter().map(|(recipient, _)| *recipient)).collect()
+    }
+
+    fn generate_test_vectors(n: U256) -> Vec<Vec<u8>> {
+        if !profile::simulation(&HOST) {
+            panic!("Test vectors are only available in simulation builds");
+        }
+        vectors::generate(&HOST, validation::count(&n, MAX_PARTICIPANTS, "test vectors"), &Self::get_keypair(&HOST))
     }
 }
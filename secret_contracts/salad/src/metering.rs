@@ -0,0 +1,46 @@
+use eng_wasm::Vec;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use crate::runtime::Runtime;
+use crate::trace;
+
+/// Metered phases of the execution of a deal, in their order in the deal record
+pub const PHASE_PARSE: usize = 0;
+pub const PHASE_VERIFY: usize = 1;
+pub const PHASE_DECRYPT: usize = 2;
+pub const PHASE_SHUFFLE: usize = 3;
+pub const PHASE_CALLOUT: usize = 4;
+const NB_PHASES: usize = 5;
+
+/// The costs of each phase accumulated by the current task
+static COSTS: [AtomicU64; NB_PHASES] = [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)];
+static METERED: AtomicBool = AtomicBool::new(false);
+
+/// Run `f` as part of `phase`, adding the cost counted by the runtime meanwhile to the phase, if the runtime counts any,
+/// and tagging the lines it logs with the phase
+pub fn measure<R: Runtime, T, F: FnOnce() -> T>(runtime: &R, phase: usize, f: F) -> T {
+    let _span = trace::phase(phase);
+    let start = runtime.cost_counter();
+    let result = f();
+    if let (Some(start), Some(end)) = (start, runtime.cost_counter()) {
+        COSTS[phase].fetch_add(end.saturating_sub(start), Ordering::Relaxed);
+        METERED.store(true, Ordering::Relaxed);
+    }
+    result
+}
+
+/// Resume the costs of a deal executed across several tasks from the costs of its previous tasks
+pub fn resume(costs: &[u64]) {
+    for (phase, cost) in costs.iter().enumerate().take(NB_PHASES) {
+        COSTS[phase].fetch_add(*cost, Ordering::Relaxed);
+        METERED.store(true, Ordering::Relaxed);
+    }
+}
+
+/// The costs of each phase of the deal so far, empty when the runtime does not expose its meter
+pub fn costs() -> Vec<u64> {
+    if !METERED.load(Ordering::Relaxed) {
+        return Vec::new();
+    }
+    COSTS.iter().map(|cost| cost.load(Ordering::Relaxed)).collect()
+}
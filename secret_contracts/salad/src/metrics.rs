@@ -0,0 +1,164 @@
+use eng_wasm::{Vec, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::MAX_PARTICIPANTS;
+use crate::{amounts, codec};
+use crate::runtime::Runtime;
+
+// State key name "deal_metrics" holding the cost metrics of the executed deals
+static DEAL_METRICS: &str = "deal_metrics";
+// State key name "reject_metrics" holding the (envelope version, message version, count) of the rejected deposits
+static REJECT_METRICS: &str = "reject_metrics";
+
+/// Message version of a rejected deposit whose signature does not recover its sender under any message version
+pub const UNKNOWN_VERSION: u8 = 0xff;
+
+/// Estimated enclave gas of a deal regardless of its size, and of each of its outputs
+/// (ordering, Merkle leaves and distribution callout)
+const DEAL_BASE_GAS_COST: u64 = 5_000_000;
+const OUTPUT_GAS_COST: u64 = 1_000_000;
+
+/// Basis points of the privacy ratios
+const BPS_BASE: u64 = 10_000;
+
+/// Cumulated sizes of the executed deals, along with their privacy statistics
+#[derive(Serialize, Deserialize, Default)]
+struct DealMetrics {
+    deals: u64,
+    participants: u64,
+    outputs: u64,
+    /// The (anonymity set size, number of deals) of the deals executed since the privacy statistics
+    #[serde(default)]
+    anonymity_sets: Vec<(u64, u64)>,
+    #[serde(default)]
+    recipients: u64,
+    #[serde(default)]
+    fresh_recipients: u64,
+    #[serde(default)]
+    deposits: u64,
+    #[serde(default)]
+    refunds: u64,
+}
+
+impl DealMetrics {
+    /// The median anonymity set size of the deals, zero before any is recorded
+    fn median_anonymity_set(&self) -> u64 {
+        let mut sets = self.anonymity_sets.clone();
+        sets.sort();
+        let total: u64 = sets.iter().map(|(_, count)| count).sum();
+        let mut cumulated = 0;
+        for (size, count) in sets {
+            cumulated += count;
+            if 2 * cumulated >= total {
+                return size;
+            }
+        }
+        0
+    }
+}
+
+/// Privacy statistics of an executed deal, as only the enclave knows them
+pub struct Anonymity {
+    /// The deposits mixed together, the refunded ones excluded
+    pub set_size: usize,
+    /// The deposits refunded rather than mixed
+    pub refunds: usize,
+    /// The distinct recipients of the mixed deposits
+    pub recipients: usize,
+    /// The recipients never seen by the enclave as a sender or recipient of a previous deal
+    pub fresh_recipients: usize,
+}
+
+fn bps(part: u64, total: u64) -> u64 {
+    match total {
+        0 => 0,
+        total => part * BPS_BASE / total,
+    }
+}
+
+fn get_metrics<R: Runtime>(runtime: &R) -> DealMetrics {
+    runtime.read_state(DEAL_METRICS).unwrap_or_default()
+}
+
+/// The number of executed deals, counting the epochs of the velocity limits
+pub fn nb_deals<R: Runtime>(runtime: &R) -> u64 {
+    get_metrics(runtime).deals
+}
+
+pub fn record<R: Runtime>(runtime: &R, nb_participants: usize, nb_outputs: usize, anonymity: &Anonymity) {
+    let mut metrics = get_metrics(runtime);
+    metrics.deals += 1;
+    metrics.participants += nb_participants as u64;
+    metrics.outputs += nb_outputs as u64;
+    let set_size = anonymity.set_size as u64;
+    match metrics.anonymity_sets.iter_mut().find(|(size, _)| *size == set_size) {
+        Some((_, count)) => *count += 1,
+        None => metrics.anonymity_sets.push((set_size, 1)),
+    }
+    metrics.recipients += anonymity.recipients as u64;
+    metrics.fresh_recipients += anonymity.fresh_recipients as u64;
+    metrics.deposits += (anonymity.set_size + anonymity.refunds) as u64;
+    metrics.refunds += anonymity.refunds as u64;
+    runtime.write_state(DEAL_METRICS, metrics);
+}
+
+fn get_rejects<R: Runtime>(runtime: &R) -> Vec<(u8, u8, u64)> {
+    runtime.read_state(REJECT_METRICS).unwrap_or_default()
+}
+
+/// Count a rejected deposit under the envelope and message versions of its depositor
+pub fn record_reject<R: Runtime>(runtime: &R, envelope_version: u8, message_version: u8) {
+    let mut rejects = get_rejects(runtime);
+    match rejects.iter_mut().find(|(envelope, message, _)| *envelope == envelope_version && *message == message_version) {
+        Some((_, _, count)) => *count += 1,
+        None => rejects.push((envelope_version, message_version, 1)),
+    }
+    runtime.write_state(REJECT_METRICS, rejects);
+}
+
+/// The aggregated metrics, canonically encoded as the container (deals: uint64, participants: uint64, outputs: uint64,
+/// reject_envelope_versions: List[uint8], reject_message_versions: List[uint8], reject_counts: List[uint64],
+/// median_anonymity_set: uint64, fresh_recipients_bps: uint64, refunds_bps: uint64), free of any per-deposit
+/// information. The privacy statistics only cover the deals executed since they are recorded, their ratios
+/// being in basis points of the recipients and of the deposits respectively.
+pub fn export<R: Runtime>(runtime: &R) -> Vec<u8> {
+    let metrics = get_metrics(runtime);
+    let rejects = get_rejects(runtime);
+    let mut encoder = codec::Encoder::default();
+    encoder.append_u64(metrics.deals);
+    encoder.append_u64(metrics.participants);
+    encoder.append_u64(metrics.outputs);
+    encoder.append_list(&rejects, |(envelope, _, _)| vec![*envelope]);
+    encoder.append_list(&rejects, |(_, message, _)| vec![*message]);
+    encoder.append_list(&rejects, |(_, _, count)| codec::encode_u64(count));
+    encoder.append_u64(metrics.median_anonymity_set());
+    encoder.append_u64(bps(metrics.fresh_recipients, metrics.recipients));
+    encoder.append_u64(bps(metrics.refunds, metrics.deposits));
+    encoder.finish()
+}
+
+/// The max participants per deal fitting in `task_gas_limit`, each participant costing
+/// `avg_decrypt_cost` plus its average number of outputs as recorded over the executed deals
+pub fn suggest_batch_size<R: Runtime>(runtime: &R, avg_decrypt_cost: &U256, task_gas_limit: &U256) -> U256 {
+    let base_cost = U256::from(DEAL_BASE_GAS_COST);
+    if *task_gas_limit <= base_cost {
+        return U256::zero();
+    }
+    let metrics = get_metrics(runtime);
+    // Assuming one output per participant until a deal is recorded
+    let (participants, outputs) = match metrics.participants {
+        0 => (U256::one(), U256::one()),
+        participants => (U256::from(participants), U256::from(metrics.outputs)),
+    };
+    let outputs_cost = amounts::mul(&outputs, &U256::from(OUTPUT_GAS_COST));
+    let participants_cost = amounts::add(&amounts::mul(avg_decrypt_cost, &participants), &outputs_cost);
+    if participants_cost.is_zero() {
+        return U256::from(MAX_PARTICIPANTS);
+    }
+    let batch_size = amounts::mul(&(*task_gas_limit - base_cost), &participants) / participants_cost;
+    if batch_size > U256::from(MAX_PARTICIPANTS) {
+        U256::from(MAX_PARTICIPANTS)
+    } else {
+        batch_size
+    }
+}
@@ -0,0 +1,75 @@
+use eng_wasm::{H160, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::metrics;
+use crate::runtime::Runtime;
+use crate::state::{Address, Word};
+use crate::validation;
+
+// State key name "mixer_timelock" holding the number of deals between the proposal of a Mixer address and its application
+static MIXER_TIMELOCK: &str = "mixer_timelock";
+// State key name "mixer_migration" holding the Mixer address proposed, pending its timelock
+static MIXER_MIGRATION: &str = "mixer_migration";
+
+/// A Mixer address proposed along with the number of deals executed before its proposal
+#[derive(Serialize, Deserialize)]
+struct Migration {
+    mixer: Address,
+    proposed_at: u64,
+}
+
+/// Set the timelock of the Mixer migrations in deals, zero disabling the migrations
+pub fn set_timelock<R: Runtime>(runtime: &R, timelock: &U256) {
+    validation::to_u64(timelock, "Mixer timelock");
+    runtime.write_state(MIXER_TIMELOCK, Word(*timelock));
+}
+
+/// The timelock of the Mixer migrations in deals, zero when disabled, as for the deployments predating the migrations
+pub fn timelock<R: Runtime>(runtime: &R) -> u64 {
+    let timelock: Option<Word> = runtime.read_state(MIXER_TIMELOCK);
+    timelock.map_or(0, |timelock| timelock.0.low_u64())
+}
+
+fn get_migration<R: Runtime>(runtime: &R) -> Option<Migration> {
+    runtime.read_state::<Option<Migration>>(MIXER_MIGRATION).unwrap_or_default()
+}
+
+/// Propose migrating to the Mixer address, replacing the migration pending, if any, and restarting its timelock.
+/// The address pending is refused, so that proposing it again never pushes its timelock back.
+pub fn propose<R: Runtime>(runtime: &R, mixer: &H160, current: &H160) {
+    let timelock = timelock(runtime);
+    if timelock == 0 {
+        panic!("Mixer migrations disabled");
+    }
+    if mixer.is_zero() {
+        panic!("Invalid zero Mixer address");
+    }
+    if mixer == current {
+        panic!("Mixer address unchanged: {:?}", mixer);
+    }
+    if get_migration(runtime).map_or(false, |migration| migration.mixer.0 == *mixer) {
+        panic!("Mixer address already pending: {:?}", mixer);
+    }
+    let migration = Migration { mixer: Address(*mixer), proposed_at: metrics::nb_deals(runtime) };
+    trace!("Proposed Mixer {:?}, applicable once {} deals are executed", mixer, migration.proposed_at + timelock);
+    runtime.write_state(MIXER_MIGRATION, Some(migration));
+}
+
+/// Take the Mixer address pending, refused until its timelock has elapsed
+pub fn apply<R: Runtime>(runtime: &R) -> H160 {
+    let migration = get_migration(runtime).unwrap_or_else(|| panic!("No Mixer migration pending"));
+    let applicable_at = migration.proposed_at + timelock(runtime);
+    if metrics::nb_deals(runtime) < applicable_at {
+        panic!("Mixer migration timelocked until deal {}: {} deals executed", applicable_at, metrics::nb_deals(runtime));
+    }
+    runtime.write_state::<Option<Migration>>(MIXER_MIGRATION, None);
+    migration.mixer.0
+}
+
+/// The (Mixer address, deal count from which it applies) of the migration pending, zero when none
+pub fn pending<R: Runtime>(runtime: &R) -> (H160, u64) {
+    match get_migration(runtime) {
+        Some(migration) => (migration.mixer.0, migration.proposed_at + timelock(runtime)),
+        None => (H160::zero(), 0),
+    }
+}
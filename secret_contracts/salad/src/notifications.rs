@@ -0,0 +1,130 @@
+use eng_wasm::{String, Vec, H160, H256, U256};
+use enigma_crypto::hash::Keccak256;
+use enigma_crypto::KeyPair;
+use rustc_hex::ToHex;
+
+use crate::codec::{self, Encoder};
+use crate::deals;
+use crate::protocol::PUB_KEY_SIZE;
+use crate::runtime::Runtime;
+use crate::state::Hash;
+
+// State key prefix of the notifications of a notification pubKey, followed by its hex pointer
+static NOTIFICATIONS_PREFIX: &str = "notifications_";
+// State key prefix of the epoch each notification of a pointer was stored at, followed by its hex pointer
+static NOTIFICATION_EPOCHS_PREFIX: &str = "notification_epochs_";
+// State key prefix of the pointers notified at an epoch, followed by the epoch
+static NOTIFIED_PREFIX: &str = "notified_";
+
+/// Notifications kept per pointer, the oldest being dropped first
+const MAX_NOTIFICATIONS: usize = 64;
+
+/// An output received by a recipient of a deposit carrying a notification pubKey
+pub struct Notification {
+    pub recipient: H160,
+    pub amount: U256,
+    /// Whether the output is held as a note in the enclave ledger rather than paid out
+    pub note: bool,
+}
+
+impl Notification {
+    /// Canonical encoding of the notification as the container (deal_id: Bytes32, recipient: Bytes20,
+    /// amount: uint256, note: uint8)
+    fn encode(&self, deal_id: &H256) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        encoder.append_bytes(deal_id);
+        encoder.append_bytes(&self.recipient);
+        encoder.append_bytes(&codec::encode_uint256(&self.amount));
+        encoder.append_u8(self.note as u8);
+        encoder.finish()
+    }
+}
+
+/// The pointer of the notifications of a notification pubKey: H(pubKey), only known to its wallet and the enclave
+pub fn pointer(pub_key: &[u8]) -> H256 {
+    H256::from(&pub_key.keccak256()[..])
+}
+
+fn notifications_key(pointer: &H256) -> String {
+    format!("{}{}", NOTIFICATIONS_PREFIX, pointer.to_hex::<String>())
+}
+
+fn notification_epochs_key(pointer: &H256) -> String {
+    format!("{}{}", NOTIFICATION_EPOCHS_PREFIX, pointer.to_hex::<String>())
+}
+
+fn notified_key(epoch: u64) -> String {
+    format!("{}{}", NOTIFIED_PREFIX, epoch)
+}
+
+/// The epochs of the latest notifications of a pointer, oldest first. The notifications stored before their
+/// epochs were tracked have none, the epochs then matching the last notifications only.
+fn get_epochs<R: Runtime>(runtime: &R, pointer: &H256) -> Vec<u64> {
+    runtime.read_state(&notification_epochs_key(pointer)).unwrap_or_default()
+}
+
+fn get_notified<R: Runtime>(runtime: &R, epoch: u64) -> Vec<Hash> {
+    runtime.read_state(&notified_key(epoch)).unwrap_or_default()
+}
+
+/// Store the notifications of the outputs received in a deal by the recipients of a deposit, each encrypted
+/// to the notification pubKey of the deposit so that only its wallet links them to the deal. A pubKey the key
+/// exchange fails with is skipped rather than failing the deal.
+pub fn notify<R: Runtime>(runtime: &R, deal_id: &H256, keypair: &KeyPair, pub_key: &[u8], notifications: &[Notification]) {
+    if notifications.is_empty() {
+        return;
+    }
+    let mut key = [0_u8; PUB_KEY_SIZE];
+    key.copy_from_slice(pub_key);
+    let shared_key = match keypair.derive_key(&key) {
+        Ok(shared_key) => shared_key,
+        Err(err) => {
+            trace!("Skipping the notifications of an invalid notification pubKey: {:?}", err);
+            return;
+        }
+    };
+    let pointer = pointer(pub_key);
+    let epoch = deals::epoch(runtime);
+    let mut stored = fetch(runtime, &pointer);
+    let mut epochs = get_epochs(runtime, &pointer);
+    for notification in notifications.iter() {
+        stored.push(runtime.encrypt(&notification.encode(deal_id), &shared_key));
+        epochs.push(epoch);
+    }
+    let overflow = stored.len().saturating_sub(MAX_NOTIFICATIONS);
+    stored.drain(..overflow);
+    let overflow = epochs.len().saturating_sub(stored.len());
+    epochs.drain(..overflow);
+    runtime.write_state(&notifications_key(&pointer), stored);
+    runtime.write_state(&notification_epochs_key(&pointer), epochs);
+    let mut notified = get_notified(runtime, epoch);
+    if !notified.iter().any(|notified| notified.0 == pointer) {
+        notified.push(Hash(pointer));
+        runtime.write_state(&notified_key(epoch), notified);
+    }
+}
+
+/// Drop the notifications stored before the epoch `cutoff` for the pointers notified at `epoch`, an epoch
+/// before the cutoff, along with the notifications stored before their epochs were tracked. Returns the
+/// number of notifications dropped.
+pub fn prune<R: Runtime>(runtime: &R, epoch: u64, cutoff: u64) -> u64 {
+    let mut pruned = 0;
+    for pointer in get_notified(runtime, epoch).iter() {
+        let mut stored = fetch(runtime, &pointer.0);
+        let mut epochs = get_epochs(runtime, &pointer.0);
+        let untracked = stored.len() - epochs.len();
+        let expired = epochs.iter().take_while(|notified| **notified < cutoff).count();
+        stored.drain(..untracked + expired);
+        epochs.drain(..expired);
+        pruned += (untracked + expired) as u64;
+        runtime.write_state(&notifications_key(&pointer.0), stored);
+        runtime.write_state(&notification_epochs_key(&pointer.0), epochs);
+    }
+    runtime.write_state(&notified_key(epoch), Vec::<Hash>::new());
+    pruned
+}
+
+/// The encrypted notifications of a pointer, oldest first
+pub fn fetch<R: Runtime>(runtime: &R, pointer: &H256) -> Vec<Vec<u8>> {
+    runtime.read_state(&notifications_key(pointer)).unwrap_or_default()
+}
@@ -0,0 +1,187 @@
+use eng_wasm::{Vec, H160, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::codec::Encoder;
+use crate::convert;
+use crate::digest;
+use crate::domains::{self, HANDOFF_DOMAIN, ORACLE_OPERATOR_DOMAIN};
+use crate::protocol::SIG_SIZE;
+use crate::runtime::Runtime;
+use crate::signature;
+use crate::state::Address;
+use crate::{health, metrics, oracle};
+
+// State key name "operator_keys" holding the (operator, signer) address pairs of the registered operator keys
+static OPERATOR_KEYS: &str = "operator_keys";
+// State key name "operator_handoffs" holding the pending and completed handoffs of the operator keys
+static OPERATOR_HANDOFFS: &str = "operator_handoffs";
+
+/// Deals executed after a handoff during which the replaced key still signs the deal payloads of its operator,
+/// so that the deals signed before the handoff are not refused
+const HANDOFF_GRACE_DEALS: u64 = 10;
+
+/// Size of an operator attestation: the operator and signer addresses, padded to 32 bytes
+const OPERATOR_ATTESTATION_SIZE: usize = 64;
+
+fn get_keys<R: Runtime>(runtime: &R) -> Vec<(Address, Address)> {
+    runtime.read_state(OPERATOR_KEYS).unwrap_or_default()
+}
+
+fn set_key<R: Runtime>(runtime: &R, operator: &H160, signer: &H160) {
+    let mut keys: Vec<(Address, Address)> = get_keys(runtime).into_iter().filter(|(registered, _)| registered.0 != *operator).collect();
+    trace!("Registering key {:?} of operator {:?}", signer, operator);
+    keys.push((Address(*operator), Address(*signer)));
+    runtime.write_state(OPERATOR_KEYS, keys);
+}
+
+/// Register the keys signing the deal payloads of the operators, as set by the deployer
+pub fn init<R: Runtime>(runtime: &R, operators: &[H160], signers: &[H160]) {
+    if operators.len() != signers.len() {
+        panic!("Mismatching operator signers list size: {} != {}", operators.len(), signers.len());
+    }
+    for (operator, signer) in operators.iter().zip(signers.iter()) {
+        set_key(runtime, operator, signer);
+    }
+}
+
+/// Register the key signing the deal payloads of an operator from an oracle command attesting the operator
+/// address and the signer address, replacing the key previously registered for the operator, if any
+pub fn register<R: Runtime>(runtime: &R, attestation: &[u8], signature: &[u8]) {
+    let body = oracle::verify_command(runtime, ORACLE_OPERATOR_DOMAIN, attestation, signature);
+    if body.len() != OPERATOR_ATTESTATION_SIZE {
+        panic!("Invalid operator attestation size: {}", body.len());
+    }
+    set_key(runtime, &convert::word_to_address(&body[0..32]), &convert::word_to_address(&body[32..64]));
+    health::record(runtime, health::ACTIVITY_KEY_ROTATION);
+}
+
+/// Handoffs of the operator keys
+#[derive(Serialize, Deserialize, Default)]
+struct Handoffs {
+    /// The (operator, new signer) of the handoffs proposed by the current key, pending acceptance by the new key
+    pending: Vec<(Address, Address)>,
+    /// The (operator, replaced signer, deal count at the handoff) of the keys in their grace period
+    retiring: Vec<(Address, Address, u64)>,
+    /// The (operator, number of completed handoffs) of the operators, signed as the nonce of their next handoff
+    nonces: Vec<(Address, u64)>,
+}
+
+impl Handoffs {
+    fn nonce(&self, operator: &H160) -> u64 {
+        self.nonces.iter().find(|(registered, _)| registered.0 == *operator).map_or(0, |(_, nonce)| *nonce)
+    }
+
+    fn pending_signer(&self, operator: &H160) -> Option<H160> {
+        self.pending.iter().find(|(registered, _)| registered.0 == *operator).map(|(_, signer)| signer.0)
+    }
+}
+
+fn get_handoffs<R: Runtime>(runtime: &R) -> Handoffs {
+    runtime.read_state(OPERATOR_HANDOFFS).unwrap_or_default()
+}
+
+fn get_signer<R: Runtime>(runtime: &R, operator: &H160) -> H160 {
+    match get_keys(runtime).into_iter().find(|(registered, _)| registered.0 == *operator) {
+        Some((_, signer)) => signer.0,
+        None => panic!("Unregistered operator: {:?}", operator),
+    }
+}
+
+/// The address of the key signing the message
+fn recover_signer(message: &[u8], raw_signature: &[u8]) -> H160 {
+    if raw_signature.len() != SIG_SIZE {
+        panic!("Invalid operator signature size: {}", raw_signature.len());
+    }
+    let mut signature = [0; SIG_SIZE];
+    signature.copy_from_slice(raw_signature);
+    digest::recover_address(message, signature).unwrap_or_else(|err| panic!("Invalid operator signature: {}", err))
+}
+
+/// The EIP191 personal message of H(HANDOFF_DOMAIN || operator || new signer || nonce), addresses and nonce
+/// padded to 32 bytes, signed by both the current and the new key of the operator
+fn handoff_message(operator: &H160, new_signer: &H160, nonce: u64) -> Vec<u8> {
+    let mut handoff: Vec<u8> = Vec::new();
+    for address in [operator, new_signer].iter() {
+        handoff.extend_from_slice(&convert::address_to_word(address));
+    }
+    handoff.extend_from_slice(&convert::uint_to_word(&U256::from(nonce)));
+    domains::domain_message(HANDOFF_DOMAIN, &handoff)
+}
+
+/// Propose handing the deal payloads of the operator off to the key of `new_signer_pub_key`, the handoff being
+/// signed by the current key of the operator. Replaces the handoff pending for the operator, if any.
+pub fn propose<R: Runtime>(runtime: &R, operator: &H160, new_signer_pub_key: &[u8], signature: &[u8]) {
+    let new_signer = signature::signer_address(new_signer_pub_key);
+    let mut handoffs = get_handoffs(runtime);
+    let signer = recover_signer(&handoff_message(operator, &new_signer, handoffs.nonce(operator)), signature);
+    if signer != get_signer(runtime, operator) {
+        panic!("Handoff not signed by the key of operator {:?}: {:?}", operator, signer);
+    }
+    trace!("Proposing the handoff of operator {:?} to key {:?}", operator, new_signer);
+    handoffs.pending.retain(|(registered, _)| registered.0 != *operator);
+    handoffs.pending.push((Address(*operator), Address(new_signer)));
+    runtime.write_state(OPERATOR_HANDOFFS, handoffs);
+}
+
+/// Accept the handoff pending for the operator, signed by its new key. The replaced key keeps signing
+/// the deal payloads of the operator for HANDOFF_GRACE_DEALS deals.
+pub fn accept<R: Runtime>(runtime: &R, operator: &H160, signature: &[u8]) {
+    let mut handoffs = get_handoffs(runtime);
+    let new_signer = handoffs.pending_signer(operator)
+        .unwrap_or_else(|| panic!("No handoff pending for operator {:?}", operator));
+    let nonce = handoffs.nonce(operator);
+    let signer = recover_signer(&handoff_message(operator, &new_signer, nonce), signature);
+    if signer != new_signer {
+        panic!("Handoff not accepted by the new key of operator {:?}: {:?}", operator, signer);
+    }
+    let deal_count = metrics::nb_deals(runtime);
+    handoffs.retiring.retain(|(registered, _, handed_off)| {
+        registered.0 != *operator && deal_count < handed_off + HANDOFF_GRACE_DEALS
+    });
+    handoffs.retiring.push((Address(*operator), Address(get_signer(runtime, operator)), deal_count));
+    handoffs.pending.retain(|(registered, _)| registered.0 != *operator);
+    handoffs.nonces.retain(|(registered, _)| registered.0 != *operator);
+    handoffs.nonces.push((Address(*operator), nonce + 1));
+    runtime.write_state(OPERATOR_HANDOFFS, handoffs);
+    set_key(runtime, operator, &new_signer);
+    health::record(runtime, health::ACTIVITY_KEY_ROTATION);
+}
+
+/// The handoff state of the operator, canonically encoded as the container (nonce: uint64, pending_signer: Bytes20),
+/// the pending signer being zero when no handoff is pending
+pub fn handoff<R: Runtime>(runtime: &R, operator: &H160) -> Vec<u8> {
+    let handoffs = get_handoffs(runtime);
+    let pending_signer = handoffs.pending_signer(operator).unwrap_or_else(H160::zero);
+    let mut encoder = Encoder::default();
+    encoder.append_u64(handoffs.nonce(operator));
+    encoder.append_bytes(&pending_signer);
+    encoder.finish()
+}
+
+/// Whether a key is registered for the operator
+pub fn is_registered<R: Runtime>(runtime: &R, operator: &H160) -> bool {
+    get_keys(runtime).iter().any(|(registered, _)| registered.0 == *operator)
+}
+
+pub fn nb_operators<R: Runtime>(runtime: &R) -> usize {
+    get_keys(runtime).len()
+}
+
+/// Verify the deal payload signed by the registered key of the operator, so that the deposits
+/// may not be modified or reordered by whoever submits the deal under the operator's name.
+/// A key replaced by a handoff is accepted during its grace period.
+pub fn verify<R: Runtime>(runtime: &R, operator: &H160, message: &[u8], raw_signature: &[u8]) {
+    let signer = get_signer(runtime, operator);
+    let recovered = recover_signer(message, raw_signature);
+    if recovered == signer {
+        return;
+    }
+    let deal_count = metrics::nb_deals(runtime);
+    let retiring = get_handoffs(runtime).retiring.into_iter().any(|(registered, retired, handed_off)| {
+        registered.0 == *operator && retired.0 == recovered && deal_count < handed_off + HANDOFF_GRACE_DEALS
+    });
+    if !retiring {
+        panic!("Deal payload not signed by the key of operator {:?}: {:?}", operator, recovered);
+    }
+    trace!("Accepting the deal payload signed by the replaced key {:?} of operator {:?}", recovered, operator);
+}
@@ -0,0 +1,28 @@
+use eng_wasm::{Vec, H160, U256};
+
+use crate::runtime::Runtime;
+use crate::shuffle::{self, ORDERING_RANDOM, ORDERING_SORTED_BY_ADDRESS};
+
+// State key name "output_ordering" holding the output ordering policy of the deployment
+static OUTPUT_ORDERING: &str = "output_ordering";
+
+pub fn set_policy<R: Runtime>(runtime: &R, policy: &U256) {
+    if *policy > U256::from(ORDERING_SORTED_BY_ADDRESS) {
+        panic!("Unknown output ordering policy: {:?}", policy);
+    }
+    runtime.write_state(OUTPUT_ORDERING, policy.low_u32() as u8);
+}
+
+/// The policy of the deployment, deployments predating the setting shuffling randomly
+pub fn get_policy<R: Runtime>(runtime: &R) -> u8 {
+    runtime.read_state(OUTPUT_ORDERING).unwrap_or(ORDERING_RANDOM)
+}
+
+/// Reorder the outputs according to the policy of the deployment, returning the seed of the shuffle
+pub fn order<R: Runtime, T: Copy, F: Fn(&T) -> H160>(runtime: &R, outputs: &mut Vec<T>, recipient: F) -> u64 {
+    let policy = get_policy(runtime);
+    let seed = runtime.random_seed();
+    trace!("Ordering {} outputs with policy {}", outputs.len(), policy);
+    shuffle::apply(&*shuffle::shuffler(policy, seed), outputs, recipient);
+    seed
+}
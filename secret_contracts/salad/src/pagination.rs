@@ -0,0 +1,16 @@
+use eng_wasm::{Vec, U256};
+
+use crate::validation;
+
+/// Max items returned by a paginated query, enclave outputs being bounded
+pub const MAX_PAGE_SIZE: usize = 32;
+
+/// The `limit` items from `offset`, at most MAX_PAGE_SIZE
+pub fn page<T: Clone>(items: &[T], offset: &U256, limit: &U256) -> Vec<T> {
+    if *offset >= U256::from(items.len()) {
+        return Vec::new();
+    }
+    let offset = offset.low_u64() as usize;
+    let limit = validation::capped_count(limit, MAX_PAGE_SIZE);
+    items.iter().skip(offset).take(limit).cloned().collect()
+}
@@ -0,0 +1,201 @@
+use eng_wasm::{String, Vec, H160, H256, U256};
+use rustc_hex::ToHex;
+
+use crate::calldata::{self, Token};
+use crate::escrow;
+use crate::runtime::Runtime;
+use crate::state::Address;
+
+// State key name "payout_bridge" holding the bridge contract the outputs of the deals are relayed through
+static PAYOUT_BRIDGE: &str = "payout_bridge";
+// State key prefix of the asset (or adapter) of each output of a multi-asset deal, followed by the hex DealId,
+// as recorded by the earlier versions paying the outputs without their asset
+static ASSETS_PREFIX: &str = "deal_assets_";
+// State key prefix of the (asset, payee) of each output of a multi-asset deal, followed by the hex DealId
+static OUTPUT_ASSETS_PREFIX: &str = "deal_output_assets_";
+
+/// The outputs of a deal paid out in a single Mixer callout, along with the pool movements of the deal
+pub struct Payout<'a> {
+    pub deal_id: &'a H256,
+    pub recipients: Vec<H160>,
+    pub amounts: Vec<U256>,
+    pub redeposited: U256,
+    pub redeemed: U256,
+    pub receipt_hash: &'a H256,
+}
+
+/// Final step of a deal, paying its outputs through the Mixer contract. Each payout mechanism implements
+/// this trait and is selected per deal by `adapter`, so that `execute_deal` pays every deal the same way.
+pub trait PayoutAdapter<R: Runtime> {
+    /// The name of the mechanism, as traced
+    fn name(&self) -> &'static str;
+
+    /// Pay the outputs through the Mixer contract deployed at `mixer_eth_addr` (hex without prefix)
+    fn pay(&self, runtime: &R, mixer_eth_addr: &str, payout: Payout);
+
+    /// The exact ABI-encoded calldata of the Mixer callout of `pay`, previewed by the operator before the call
+    fn calldata(&self, payout: &Payout) -> Vec<u8>;
+}
+
+/// Pays each output in ETH to its recipient
+pub struct DirectEth;
+
+impl<R: Runtime> PayoutAdapter<R> for DirectEth {
+    fn name(&self) -> &'static str {
+        "direct ETH"
+    }
+
+    fn pay(&self, runtime: &R, mixer_eth_addr: &str, payout: Payout) {
+        runtime.distribute(mixer_eth_addr, payout.deal_id, payout.recipients, payout.amounts,
+                        payout.redeposited, payout.redeemed, payout.receipt_hash);
+    }
+
+    fn calldata(&self, payout: &Payout) -> Vec<u8> {
+        calldata::encode("distribute(uint256,address[],uint256[],uint256,uint256,uint256)", &[
+            Token::Uint(U256::from(payout.deal_id)),
+            Token::Addresses(payout.recipients.clone()),
+            Token::Uints(payout.amounts.clone()),
+            Token::Uint(payout.redeposited),
+            Token::Uint(payout.redeemed),
+            Token::Uint(U256::from(payout.receipt_hash)),
+        ])
+    }
+}
+
+/// Pays each output in its asset, through the adapter contract configured for the asset if any.
+/// Multi-asset deals stage nothing back into the pool, their pool movements being left out.
+pub struct Erc20 {
+    pub assets: Vec<H160>,
+    /// The contract paying each output: its asset, or the adapter configured for the asset
+    pub payees: Vec<H160>,
+}
+
+impl<R: Runtime> PayoutAdapter<R> for Erc20 {
+    fn name(&self) -> &'static str {
+        "ERC20"
+    }
+
+    fn pay(&self, runtime: &R, mixer_eth_addr: &str, payout: Payout) {
+        runtime.distribute_assets(mixer_eth_addr, payout.deal_id, self.assets.clone(), self.payees.clone(), payout.recipients,
+                               payout.amounts, payout.receipt_hash);
+    }
+
+    fn calldata(&self, payout: &Payout) -> Vec<u8> {
+        calldata::encode("distributeAssets(uint256,address[],address[],address[],uint256[],uint256)", &[
+            Token::Uint(U256::from(payout.deal_id)),
+            Token::Addresses(self.assets.clone()),
+            Token::Addresses(self.payees.clone()),
+            Token::Addresses(payout.recipients.clone()),
+            Token::Uints(payout.amounts.clone()),
+            Token::Uint(U256::from(payout.receipt_hash)),
+        ])
+    }
+}
+
+/// Holds the outputs with a non-zero guardian in escrow until released by both their recipient and their guardian
+pub struct Escrow {
+    pub guardians: Vec<H160>,
+}
+
+impl<R: Runtime> PayoutAdapter<R> for Escrow {
+    fn name(&self) -> &'static str {
+        "escrow"
+    }
+
+    fn pay(&self, runtime: &R, mixer_eth_addr: &str, payout: Payout) {
+        runtime.distribute_with_escrow(mixer_eth_addr, payout.deal_id, payout.recipients, self.guardians.clone(),
+                                    payout.amounts, payout.redeposited, payout.redeemed, payout.receipt_hash);
+    }
+
+    fn calldata(&self, payout: &Payout) -> Vec<u8> {
+        calldata::encode("distributeWithEscrow(uint256,address[],address[],uint256[],uint256,uint256,uint256)", &[
+            Token::Uint(U256::from(payout.deal_id)),
+            Token::Addresses(payout.recipients.clone()),
+            Token::Addresses(self.guardians.clone()),
+            Token::Uints(payout.amounts.clone()),
+            Token::Uint(payout.redeposited),
+            Token::Uint(payout.redeemed),
+            Token::Uint(U256::from(payout.receipt_hash)),
+        ])
+    }
+}
+
+/// Relays each output in ETH through the bridge contract committed on the Mixer by `set_payout_bridge`,
+/// to its recipient on the other side of the bridge
+pub struct Bridge;
+
+impl<R: Runtime> PayoutAdapter<R> for Bridge {
+    fn name(&self) -> &'static str {
+        "bridge"
+    }
+
+    fn pay(&self, runtime: &R, mixer_eth_addr: &str, payout: Payout) {
+        runtime.distribute_to_bridge(mixer_eth_addr, payout.deal_id, payout.recipients, payout.amounts,
+                                  payout.redeposited, payout.redeemed, payout.receipt_hash);
+    }
+
+    fn calldata(&self, payout: &Payout) -> Vec<u8> {
+        calldata::encode("distributeToBridge(uint256,address[],uint256[],uint256,uint256,uint256)", &[
+            Token::Uint(U256::from(payout.deal_id)),
+            Token::Addresses(payout.recipients.clone()),
+            Token::Uints(payout.amounts.clone()),
+            Token::Uint(payout.redeposited),
+            Token::Uint(payout.redeemed),
+            Token::Uint(U256::from(payout.receipt_hash)),
+        ])
+    }
+}
+
+/// Set the bridge contract the outputs of the deals are relayed through, zero paying them directly, committing it
+/// on the Mixer deployed at `mixer_eth_addr` so that the Mixer only relays the outputs through the bridge of the enclave
+pub fn set_bridge<R: Runtime>(runtime: &R, mixer_eth_addr: &str, bridge: &H160) {
+    runtime.write_state(PAYOUT_BRIDGE, Address(*bridge));
+    runtime.set_payout_bridge(mixer_eth_addr, bridge);
+}
+
+/// The bridge contract the outputs of the deals are relayed through, zero when unset
+pub fn bridge<R: Runtime>(runtime: &R) -> H160 {
+    let bridge: Option<Address> = runtime.read_state(PAYOUT_BRIDGE);
+    bridge.map_or_else(H160::zero, |bridge| bridge.0)
+}
+
+fn assets_key(deal_id: &H256) -> String {
+    format!("{}{}", ASSETS_PREFIX, deal_id.to_hex::<String>())
+}
+
+fn output_assets_key(deal_id: &H256) -> String {
+    format!("{}{}", OUTPUT_ASSETS_PREFIX, deal_id.to_hex::<String>())
+}
+
+/// Record the (asset, payee) of each shuffled output of a multi-asset deal, paid at distribution
+pub fn save_assets<R: Runtime>(runtime: &R, deal_id: &H256, outputs: &[(H160, H160)]) {
+    let outputs: Vec<(Address, Address)> = outputs.iter().map(|(asset, payee)| (Address(*asset), Address(*payee))).collect();
+    runtime.write_state(&output_assets_key(deal_id), outputs);
+}
+
+/// The (asset, payee) of each output of the deal, if it is a multi-asset deal. The deals recorded by the
+/// earlier versions pay each output in the asset recorded, their adapter if any standing for the asset.
+pub fn assets<R: Runtime>(runtime: &R, deal_id: &H256) -> Option<Vec<(H160, H160)>> {
+    let outputs: Option<Vec<(Address, Address)>> = runtime.read_state(&output_assets_key(deal_id));
+    if let Some(outputs) = outputs {
+        return Some(outputs.into_iter().map(|(asset, payee)| (asset.0, payee.0)).collect());
+    }
+    let assets: Option<Vec<Address>> = runtime.read_state(&assets_key(deal_id));
+    assets.map(|assets| assets.into_iter().map(|asset| (asset.0, asset.0)).collect())
+}
+
+/// The payout mechanism of the deal: ERC20 for the multi-asset deals, escrow for the deals with guardians,
+/// the bridge when one is configured, and direct ETH payouts otherwise
+pub fn adapter<R: Runtime>(runtime: &R, deal_id: &H256) -> Box<dyn PayoutAdapter<R>> {
+    if let Some(outputs) = assets(runtime, deal_id) {
+        let (assets, payees) = outputs.into_iter().unzip();
+        return Box::new(Erc20 { assets, payees });
+    }
+    if let Some(guardians) = escrow::load(runtime, deal_id) {
+        return Box::new(Escrow { guardians });
+    }
+    if !bridge(runtime).is_zero() {
+        return Box::new(Bridge);
+    }
+    Box::new(DirectEth)
+}
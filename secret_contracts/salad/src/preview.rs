@@ -0,0 +1,87 @@
+use eng_wasm::{String, Vec, U256};
+
+use crate::amounts;
+use crate::codec::{self, Encoder};
+use crate::protocol::ADDRESS_SIZE;
+use crate::runtime::Runtime;
+use crate::signature;
+use crate::wire::DepositInput;
+
+/// Size of each expiry in the deal inputs
+const EXPIRY_SIZE: usize = 32;
+
+/// The problems a prospective deal would run into, found before the operator commits it on-chain
+pub struct PreviewReport {
+    /// Whether the deal amount is within the deposit bounds of the deployment
+    pub amount_in_bounds: bool,
+    /// The deposits repeating the sender or the encrypted payload of an earlier deposit, the deposits
+    /// bundled by the same aggregator only repeating their payload
+    pub duplicates: Vec<u64>,
+    /// The deposits of an amount other than the deal amount
+    pub mismatched: Vec<u64>,
+    /// The deposits whose payload does not open, or screened out by the compliance policies
+    pub screened: Vec<u64>,
+    /// The size of the deposit inputs of the deal, as submitted to `execute_deal`
+    pub payload_size: u64,
+}
+
+impl PreviewReport {
+    /// Whether the deal would execute without any refund or rejection found by the preview
+    pub fn passed(&self) -> bool {
+        self.amount_in_bounds && self.duplicates.is_empty() && self.mismatched.is_empty() && self.screened.is_empty()
+    }
+
+    /// Canonical encoding of the report as the container (passed: uint8, amount_in_bounds: uint8,
+    /// payload_size: uint64, duplicates: List[uint64], mismatched: List[uint64], screened: List[uint64])
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        encoder.append_u8(self.passed() as u8);
+        encoder.append_u8(self.amount_in_bounds as u8);
+        encoder.append_u64(self.payload_size);
+        encoder.append_list(&self.duplicates, codec::encode_u64);
+        encoder.append_list(&self.mismatched, codec::encode_u64);
+        encoder.append_list(&self.screened, codec::encode_u64);
+        encoder.finish()
+    }
+}
+
+/// Preview the deal of the deposits, opening and screening each deposit with `screen` without recording
+/// anything in state, so that the operator may drop the failing deposits before committing the deal
+pub fn preview<'a, R: Runtime, F>(runtime: &R, amount: &U256, deposit_amounts: &[U256], inputs: Vec<DepositInput<'a>>, screen: F) -> PreviewReport
+where
+    F: Fn(&DepositInput<'a>) -> Result<(), String>,
+{
+    if deposit_amounts.len() != inputs.len() {
+        panic!("Mismatching amounts list size: {} != {}", deposit_amounts.len(), inputs.len());
+    }
+    let (min, max) = amounts::bounds(runtime);
+    let mut report = PreviewReport {
+        amount_in_bounds: !amount.is_zero() && *amount >= min && (max.is_zero() || *amount <= max),
+        duplicates: Vec::new(),
+        mismatched: Vec::new(),
+        screened: Vec::new(),
+        payload_size: 0,
+    };
+    for (input, deposit_amount) in inputs.iter().zip(deposit_amounts.iter()) {
+        let i = input.index;
+        // The deposits bundled by an aggregator share the sender of its depositing contract
+        let bundled = signature::is_bundled(input.signature);
+        let duplicate = inputs[..i].iter().any(|earlier| {
+            (earlier.sender == input.sender && !(bundled && signature::is_bundled(earlier.signature)))
+                || earlier.enc_recipient == input.enc_recipient
+        });
+        if duplicate {
+            report.duplicates.push(i as u64);
+        }
+        if deposit_amount != amount {
+            report.mismatched.push(i as u64);
+        }
+        if let Err(reason) = screen(input) {
+            trace!("Previewed deposit {} screened out: {}", i, reason);
+            report.screened.push(i as u64);
+        }
+        let size = input.pub_key.len() + input.enc_recipient.len() + ADDRESS_SIZE + input.signature.len() + EXPIRY_SIZE;
+        report.payload_size += size as u64;
+    }
+    report
+}
@@ -0,0 +1,43 @@
+use eng_wasm::Vec;
+use enigma_crypto::KeyPair;
+
+use crate::codec::Encoder;
+use crate::protocol;
+
+/// The git commit of the sources the contract was built from, suffixed with `-dirty` when they had
+/// uncommitted changes, `unknown` when built outside of git without SALAD_GIT_COMMIT
+static GIT_COMMIT: &str = env!("SALAD_GIT_COMMIT");
+static CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Cargo features of the build, as bits of the build info
+const FEATURE_SIMULATION: u8 = 1;
+const FEATURE_BLS: u8 = 2;
+
+fn cargo_features() -> u8 {
+    let mut features = 0;
+    if cfg!(feature = "simulation") {
+        features |= FEATURE_SIMULATION;
+    }
+    if cfg!(feature = "bls") {
+        features |= FEATURE_BLS;
+    }
+    features
+}
+
+/// The build metadata, canonically encoded as the container (git_commit: List[uint8], crate_version: List[uint8],
+/// message_version: uint8, capsule_version: uint8, envelope_versions: List[uint8], cargo_features: uint8)
+/// followed by its signature by the enclave signing key, the key signing the deal receipts, so that the
+/// deployed WASM is tied to its audited source revision and to the receipts of its deals
+pub fn build_info(signing_keypair: &KeyPair) -> Vec<u8> {
+    let mut encoder = Encoder::default();
+    encoder.append_list(GIT_COMMIT.as_bytes(), |byte| vec![*byte]);
+    encoder.append_list(CRATE_VERSION.as_bytes(), |byte| vec![*byte]);
+    encoder.append_u8(protocol::message().version);
+    encoder.append_u8(protocol::message().capsule_version);
+    encoder.append_list(&protocol::envelope_versions(), |version| vec![*version]);
+    encoder.append_u8(cargo_features());
+    let mut info = encoder.finish();
+    let signature = signing_keypair.sign(&info).unwrap();
+    info.extend_from_slice(&signature);
+    info
+}
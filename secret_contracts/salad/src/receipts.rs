@@ -0,0 +1,56 @@
+use eng_wasm::{String, Vec, H256, U256};
+use enigma_crypto::KeyPair;
+use rustc_hex::ToHex;
+
+use crate::codec::{self, Encoder};
+use crate::runtime::Runtime;
+
+// State key prefix of the signed receipt of each deal, followed by the hex DealId
+static RECEIPT_PREFIX: &str = "deal_receipt_";
+
+/// Aggregate statistics of a deal, free of any per-participant information
+pub struct DealStats {
+    pub deal_id: H256,
+    pub receipt_hash: H256,
+    pub nb_participants: u64,
+    pub denomination: U256,
+    pub total_fee: U256,
+    /// The number of outputs the deposits were split into
+    pub nb_chunks: u64,
+    /// The number of outputs raising each linkage risk flag
+    pub risk_counts: Vec<u64>,
+}
+
+impl DealStats {
+    /// Canonical encoding of the statistics as the container
+    /// (deal_id: Bytes32, receipt_hash: Bytes32, nb_participants: uint64, denomination: uint256,
+    /// total_fee: uint256, nb_chunks: uint64, risk_counts: List[uint64])
+    fn encode(&self) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        encoder.append_bytes(&self.deal_id);
+        encoder.append_bytes(&self.receipt_hash);
+        encoder.append_u64(self.nb_participants);
+        encoder.append_bytes(&codec::encode_uint256(&self.denomination));
+        encoder.append_bytes(&codec::encode_uint256(&self.total_fee));
+        encoder.append_u64(self.nb_chunks);
+        encoder.append_list(&self.risk_counts, codec::encode_u64);
+        encoder.finish()
+    }
+}
+
+fn receipt_key(deal_id: &H256) -> String {
+    format!("{}{}", RECEIPT_PREFIX, deal_id.to_hex::<String>())
+}
+
+/// Record the statistics of the deal, signed by the enclave as encoding || signature for public dashboards
+pub fn record<R: Runtime>(runtime: &R, stats: &DealStats, keypair: &KeyPair) {
+    trace!("Recording the statistics of deal {:?}: {} participants, {} chunks", stats.deal_id, stats.nb_participants, stats.nb_chunks);
+    let mut receipt = stats.encode();
+    let signature = keypair.sign(&receipt).unwrap();
+    receipt.extend_from_slice(&signature);
+    runtime.write_state(&receipt_key(&stats.deal_id), receipt);
+}
+
+pub fn load<R: Runtime>(runtime: &R, deal_id: &H256) -> Vec<u8> {
+    runtime.read_state(&receipt_key(deal_id)).unwrap_or_default()
+}
@@ -0,0 +1,180 @@
+use eng_wasm::{Vec, H256, U256};
+use enigma_crypto::hash::Keccak256;
+use serde::{Deserialize, Serialize};
+use std::cmp;
+
+use crate::codec::{self, Decoder, Encoder};
+use crate::{health, metrics};
+use crate::protocol::PUB_KEY_SIZE;
+use crate::runtime::Runtime;
+use crate::state::Word;
+
+// State key name "staged_redeposits" holding the deposit shares staged back into the pool, in staging order
+static STAGED_REDEPOSITS: &str = "staged_redeposits";
+
+/// A share of a deposit staged back into the pool, its splits payload decrypted and verified at staging
+#[derive(Serialize, Deserialize)]
+pub struct StagedDeposit {
+    /// Amount of the share
+    amount: Word,
+    pub pub_key: Vec<u8>,
+    pub enc_recipient: Vec<u8>,
+    pub payload: Vec<u8>,
+    /// The staging epoch, as the number of deals executed before staging
+    #[serde(default)]
+    epoch: u64,
+}
+
+impl StagedDeposit {
+    pub fn new(amount: &U256, pub_key: Vec<u8>, enc_recipient: Vec<u8>, payload: Vec<u8>) -> StagedDeposit {
+        StagedDeposit { amount: Word(*amount), pub_key, enc_recipient, payload, epoch: 0 }
+    }
+
+    pub fn amount(&self) -> U256 {
+        self.amount.0
+    }
+
+    /// The digest identifying the staged deposit in challenges: H(pubKey || encrypted splits)
+    pub fn digest(&self) -> H256 {
+        let mut message = self.pub_key.clone();
+        message.extend_from_slice(&self.enc_recipient);
+        H256::from(&message.keccak256()[..])
+    }
+}
+
+fn get_staged<R: Runtime>(runtime: &R) -> Vec<StagedDeposit> {
+    runtime.read_state(STAGED_REDEPOSITS).unwrap_or_default()
+}
+
+/// Stage the deposit shares back into the pool
+pub fn stage<R: Runtime>(runtime: &R, deposits: Vec<StagedDeposit>) {
+    if deposits.is_empty() {
+        return;
+    }
+    trace!("Staging {} re-deposits", deposits.len());
+    health::record(runtime, health::ACTIVITY_STAGED);
+    let epoch = metrics::nb_deals(runtime);
+    let mut staged = get_staged(runtime);
+    staged.extend(deposits.into_iter().map(|deposit| StagedDeposit { epoch, ..deposit }));
+    runtime.write_state(STAGED_REDEPOSITS, staged);
+}
+
+/// The number of staged deposits of the amount joining a deal of the amount, up to `max`
+pub fn redeemable<R: Runtime>(runtime: &R, amount: &U256, max: usize) -> usize {
+    cmp::min(get_staged(runtime).iter().filter(|deposit| deposit.amount() == *amount).count(), max)
+}
+
+/// Refuse a deal redeeming more staged deposits of the amount than are staged, or more than `max`
+pub fn check_redeemable<R: Runtime>(runtime: &R, amount: &U256, nb_redeemed: usize, max: usize) {
+    let available = redeemable(runtime, amount, max);
+    if nb_redeemed > available {
+        panic!("Not enough staged deposits to redeem: {} > {}", nb_redeemed, available);
+    }
+}
+
+/// Take the `count` oldest staged deposits of the amount, to join a deal of the same amount created on-chain
+/// as redeeming that many
+pub fn take<R: Runtime>(runtime: &R, amount: &U256, count: usize) -> Vec<StagedDeposit> {
+    let (mut taken, mut remaining): (Vec<StagedDeposit>, Vec<StagedDeposit>) = get_staged(runtime)
+        .into_iter()
+        .partition(|deposit| deposit.amount() == *amount);
+    if taken.len() < count {
+        panic!("Not enough staged deposits to redeem: {} > {}", count, taken.len());
+    }
+    if taken.len() > count {
+        let mut overflow = taken.split_off(count);
+        overflow.append(&mut remaining);
+        remaining = overflow;
+    }
+    if !taken.is_empty() {
+        trace!("Redeeming {} re-deposits", taken.len());
+        runtime.write_state(STAGED_REDEPOSITS, remaining);
+    }
+    taken
+}
+
+/// The number of staged deposits of each denomination and staging epoch, in staging order, canonically encoded
+/// as the container (denominations: List[uint256], epochs: List[uint64], counts: List[uint64]).
+/// The staged deposits are counted without revealing any of their keys or payloads.
+pub fn summary<R: Runtime>(runtime: &R) -> Vec<u8> {
+    let mut groups: Vec<(U256, u64, u64)> = Vec::new();
+    for deposit in get_staged(runtime).iter() {
+        let amount = deposit.amount();
+        match groups.iter_mut().find(|(denomination, epoch, _)| *denomination == amount && *epoch == deposit.epoch) {
+            Some((_, _, count)) => *count += 1,
+            None => groups.push((amount, deposit.epoch, 1)),
+        }
+    }
+    let denominations: Vec<U256> = groups.iter().map(|(denomination, _, _)| *denomination).collect();
+    let epochs: Vec<u64> = groups.iter().map(|(_, epoch, _)| *epoch).collect();
+    let counts: Vec<u64> = groups.iter().map(|(_, _, count)| *count).collect();
+    let mut encoder = Encoder::default();
+    encoder.append_list(&denominations, codec::encode_uint256);
+    encoder.append_list(&epochs, codec::encode_u64);
+    encoder.append_list(&counts, codec::encode_u64);
+    encoder.finish()
+}
+
+/// Split the concatenated contents of variable-size items along their sizes
+fn split_sizes<'a>(contents: &'a [u8], sizes: &[u8]) -> Vec<&'a [u8]> {
+    let mut start = 0;
+    let items = codec::items(sizes, 8).into_iter().map(|size| {
+        let end = start + codec::decode_u64(size) as usize;
+        if end > contents.len() {
+            panic!("Truncated staged deposits contents: {} > {}", end, contents.len());
+        }
+        let item = &contents[start..end];
+        start = end;
+        item
+    }).collect();
+    if start != contents.len() {
+        panic!("Trailing bytes in staged deposits contents: {}", contents.len() - start);
+    }
+    items
+}
+
+/// The staged deposits replicated to a standby instance, canonically encoded as the container
+/// (amounts: List[uint256], epochs: List[uint64], pub_keys: List[Bytes64], enc_recipient_sizes: List[uint64],
+/// enc_recipients: List[uint8], payload_sizes: List[uint64], payloads: List[uint8])
+pub fn export<R: Runtime>(runtime: &R) -> Vec<u8> {
+    let staged = get_staged(runtime);
+    let amounts: Vec<U256> = staged.iter().map(StagedDeposit::amount).collect();
+    let epochs: Vec<u64> = staged.iter().map(|deposit| deposit.epoch).collect();
+    let enc_recipient_sizes: Vec<u64> = staged.iter().map(|deposit| deposit.enc_recipient.len() as u64).collect();
+    let payload_sizes: Vec<u64> = staged.iter().map(|deposit| deposit.payload.len() as u64).collect();
+    let mut encoder = Encoder::default();
+    encoder.append_list(&amounts, codec::encode_uint256);
+    encoder.append_list(&epochs, codec::encode_u64);
+    encoder.append_list(&staged, |deposit| deposit.pub_key.clone());
+    encoder.append_list(&enc_recipient_sizes, codec::encode_u64);
+    encoder.append_list(&staged, |deposit| deposit.enc_recipient.clone());
+    encoder.append_list(&payload_sizes, codec::encode_u64);
+    encoder.append_list(&staged, |deposit| deposit.payload.clone());
+    encoder.finish()
+}
+
+/// Replace the staged deposits with those exported by the instance this standby instance takes over
+pub fn import<R: Runtime>(runtime: &R, data: &[u8]) {
+    let mut decoder = Decoder::new(data);
+    for _ in 0..7 {
+        decoder.read_offset();
+    }
+    let lists = decoder.finish();
+    let amounts = codec::items(lists[0], 32);
+    let epochs = codec::items(lists[1], 8);
+    let pub_keys = codec::items(lists[2], PUB_KEY_SIZE);
+    let enc_recipients = split_sizes(lists[4], lists[3]);
+    let payloads = split_sizes(lists[6], lists[5]);
+    if [epochs.len(), pub_keys.len(), enc_recipients.len(), payloads.len()].iter().any(|len| *len != amounts.len()) {
+        panic!("Mismatching staged deposits lists");
+    }
+    let staged: Vec<StagedDeposit> = (0..amounts.len()).map(|i| StagedDeposit {
+        amount: Word(codec::decode_uint256(amounts[i])),
+        pub_key: pub_keys[i].to_vec(),
+        enc_recipient: enc_recipients[i].to_vec(),
+        payload: payloads[i].to_vec(),
+        epoch: codec::decode_u64(epochs[i]),
+    }).collect();
+    trace!("Importing {} staged re-deposits", staged.len());
+    runtime.write_state(STAGED_REDEPOSITS, staged);
+}
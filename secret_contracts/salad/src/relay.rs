@@ -0,0 +1,47 @@
+use eng_wasm::{String, Vec, H160};
+
+use crate::convert;
+use crate::domains::ORACLE_RELAYER_DOMAIN;
+use crate::oracle;
+use crate::runtime::Runtime;
+use crate::state::Address;
+
+// State key name "deposit_relayers" holding the (signer, depositing contract) address pairs of the relayers
+static DEPOSIT_RELAYERS: &str = "deposit_relayers";
+
+/// Size of a relayer attestation: the signer and depositing contract addresses, padded to 32 bytes
+const RELAYER_ATTESTATION_SIZE: usize = 64;
+
+fn get_relayers<R: Runtime>(runtime: &R) -> Vec<(Address, Address)> {
+    runtime.read_state(DEPOSIT_RELAYERS).unwrap_or_default()
+}
+
+/// Register a secret contract relaying deposits from an oracle command attesting the address of its
+/// enclave signing key and of the Ethereum contract making its deposits, a signer being registered once
+pub fn register<R: Runtime>(runtime: &R, attestation: &[u8], signature: &[u8]) {
+    let body = oracle::verify_command(runtime, ORACLE_RELAYER_DOMAIN, attestation, signature);
+    if body.len() != RELAYER_ATTESTATION_SIZE {
+        panic!("Invalid relayer attestation size: {}", body.len());
+    }
+    let signer = convert::word_to_address(&body[0..32]);
+    let contract = convert::word_to_address(&body[32..64]);
+    let mut relayers = get_relayers(runtime);
+    if relayers.iter().any(|(registered, _)| registered.0 == signer) {
+        panic!("Relayer signer already registered: {:?}", signer);
+    }
+    trace!("Registering relayer {:?} depositing from {:?}", signer, contract);
+    relayers.push((Address(signer), Address(contract)));
+    runtime.write_state(DEPOSIT_RELAYERS, relayers);
+}
+
+pub fn nb_relayers<R: Runtime>(runtime: &R) -> usize {
+    get_relayers(runtime).len()
+}
+
+/// The depositing contract of the relayer signing the deposit, if registered
+pub fn depositor<R: Runtime>(runtime: &R, signer: &H160) -> Result<H160, String> {
+    get_relayers(runtime).iter()
+        .find(|(registered, _)| registered.0 == *signer)
+        .map(|(_, contract)| contract.0)
+        .ok_or_else(|| format!("Unregistered deposit relayer: {:?}", signer))
+}
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+
+use crate::convert;
+use crate::deals;
+use crate::notifications;
+use crate::runtime::Runtime;
+use crate::validation;
+
+// State key name "retention_policy" holding the number of epochs the deal records and notifications are kept
+static RETENTION_POLICY: &str = "retention_policy";
+// State key name "retention_cursors" holding the epochs up to which the deal records and notifications were pruned
+static RETENTION_CURSORS: &str = "retention_cursors";
+
+/// Epochs visited per pruning, so that a single task never walks the whole lifetime of the contract
+const MAX_PRUNED_EPOCHS: u64 = 64;
+
+/// Epochs the deal records and the notifications are kept for, zero keeping them forever.
+/// The receipts are always kept, the participants proving their outputs from them.
+#[derive(Serialize, Deserialize, Default)]
+pub struct RetentionPolicy {
+    pub deal_epochs: u64,
+    pub notification_epochs: u64,
+}
+
+/// The epochs before which the deal records and the notifications were pruned
+#[derive(Serialize, Deserialize, Default)]
+struct Cursors {
+    deals: u64,
+    notifications: u64,
+}
+
+/// Parse an attestation of the deal and notification epochs (two 32-byte words)
+pub fn parse_attestation(attestation: &[u8]) -> RetentionPolicy {
+    if attestation.len() != 64 {
+        panic!("Invalid retention policy attestation size: {}", attestation.len());
+    }
+    RetentionPolicy {
+        deal_epochs: validation::to_u64(&convert::word_to_uint(&attestation[0..32]), "deal epochs"),
+        notification_epochs: validation::to_u64(&convert::word_to_uint(&attestation[32..64]), "notification epochs"),
+    }
+}
+
+pub fn set<R: Runtime>(runtime: &R, policy: RetentionPolicy) {
+    runtime.write_state(RETENTION_POLICY, policy);
+}
+
+pub fn get<R: Runtime>(runtime: &R) -> RetentionPolicy {
+    runtime.read_state(RETENTION_POLICY).unwrap_or_default()
+}
+
+/// Prune the deal records and notifications older than the policy allows at the epoch `now`, at most
+/// the current epoch, visiting at most MAX_PRUNED_EPOCHS epochs of each. The deal records are replaced
+/// with their tombstone, the pruning stopping at the first deal not yet settled. Returns the number of
+/// deal records and notifications pruned.
+pub fn prune<R: Runtime>(runtime: &R, now: u64) -> u64 {
+    let epoch = deals::epoch(runtime);
+    if now > epoch {
+        panic!("Cannot prune ahead of the current epoch: {} > {}", now, epoch);
+    }
+    let policy = get(runtime);
+    let mut cursors: Cursors = runtime.read_state(RETENTION_CURSORS).unwrap_or_default();
+    let mut pruned = 0;
+    if policy.deal_epochs != 0 {
+        let cutoff = now.saturating_sub(policy.deal_epochs).min(cursors.deals + MAX_PRUNED_EPOCHS);
+        for deal_id in deals::executed_between(runtime, cursors.deals, cutoff).iter() {
+            if !deals::prune(runtime, deal_id) {
+                trace!("Pruning stopped at the unsettled deal {:?}", deal_id);
+                break;
+            }
+            cursors.deals += 1;
+            pruned += 1;
+        }
+    }
+    if policy.notification_epochs != 0 {
+        let cutoff = now.saturating_sub(policy.notification_epochs);
+        let end = cutoff.min(cursors.notifications + MAX_PRUNED_EPOCHS);
+        while cursors.notifications < end {
+            pruned += notifications::prune(runtime, cursors.notifications, cutoff);
+            cursors.notifications += 1;
+        }
+    }
+    trace!("Pruned {} entries, deal records kept from epoch {} and notifications from epoch {}",
+           pruned, cursors.deals, cursors.notifications);
+    runtime.write_state(RETENTION_CURSORS, cursors);
+    pruned
+}
@@ -0,0 +1,54 @@
+use eng_wasm::{Vec, H160, U256};
+
+use crate::runtime::Runtime;
+use crate::state::Address;
+
+// State key name "recent_recipients" holding the recipients of each of the last RECENT_DEALS deals
+static RECENT_RECIPIENTS: &str = "recent_recipients";
+
+/// Deals over which a recipient paid again is flagged
+const RECENT_DEALS: usize = 10;
+
+/// Linkage risks of an output, flagged as bits of its score and counted per flag in the deal receipt
+pub const RISK_SELF_SEND: u8 = 0;
+pub const RISK_RECENT_RECIPIENT: u8 = 1;
+pub const RISK_OTHER_SENDER: u8 = 2;
+const NB_RISKS: usize = 3;
+
+fn get_recent<R: Runtime>(runtime: &R) -> Vec<Vec<Address>> {
+    runtime.read_state(RECENT_RECIPIENTS).unwrap_or_default()
+}
+
+/// The risk score of each output, as the bits of its flags: paying back the sender of its deposit,
+/// paying a recipient of the last RECENT_DEALS deals, or paying the sender of another deposit of the deal
+pub fn score<R: Runtime>(runtime: &R, outputs: &[(usize, H160, U256)], senders: &[H160]) -> Vec<u8> {
+    let recent: Vec<Address> = get_recent(runtime).into_iter().flatten().collect();
+    outputs.iter().map(|(i, recipient, _)| {
+        let mut score = 0_u8;
+        if senders.get(*i) == Some(recipient) {
+            score |= 1 << RISK_SELF_SEND;
+        }
+        if recent.contains(&Address(*recipient)) {
+            score |= 1 << RISK_RECENT_RECIPIENT;
+        }
+        if senders.iter().enumerate().any(|(j, sender)| j != *i && sender == recipient) {
+            score |= 1 << RISK_OTHER_SENDER;
+        }
+        score
+    }).collect()
+}
+
+/// The number of outputs raising each flag, free of any per-participant information
+pub fn counts(scores: &[u8]) -> Vec<u64> {
+    (0..NB_RISKS).map(|flag| scores.iter().filter(|score| **score & (1 << flag) != 0).count() as u64).collect()
+}
+
+/// Remember the recipients of the deal, forgetting those of the deals before the last RECENT_DEALS
+pub fn record<R: Runtime>(runtime: &R, recipients: &[H160]) {
+    let mut recent = get_recent(runtime);
+    recent.push(recipients.iter().cloned().map(Address).collect());
+    if recent.len() > RECENT_DEALS {
+        recent.remove(0);
+    }
+    runtime.write_state(RECENT_RECIPIENTS, recent);
+}
@@ -0,0 +1,101 @@
+use eng_wasm::{String, Vec, H256, U256};
+use rustc_hex::ToHex;
+
+use crate::convert;
+use crate::domains::{self, SCHEDULE_DOMAIN};
+use crate::facts;
+use crate::profile;
+use crate::runtime::Runtime;
+
+// State key prefix of the block from which each scheduled deal may execute, followed by the hex DealId
+static DEAL_SCHEDULE_PREFIX: &str = "deal_schedule_";
+// State key prefix of the last block at which each scheduled deal may execute, followed by the hex DealId
+static DEAL_WINDOW_PREFIX: &str = "deal_window_";
+// State key name "latest_attested_block" holding the latest block attested to the enclave by the operators
+static LATEST_ATTESTED_BLOCK: &str = "latest_attested_block";
+
+/// Blocks over which the execution of a scheduled deal is randomly delayed
+const SCHEDULE_WINDOW: u64 = 20;
+
+fn schedule_key(deal_id: &H256) -> String {
+    format!("{}{}", DEAL_SCHEDULE_PREFIX, deal_id.to_hex::<String>())
+}
+
+fn window_key(deal_id: &H256) -> String {
+    format!("{}{}", DEAL_WINDOW_PREFIX, deal_id.to_hex::<String>())
+}
+
+/// The EIP191 personal message of H(SCHEDULE_DOMAIN || DealId || Window || Confirmations), signed by the operator
+/// of the deal to schedule it, so that the execution window and the confirmation depth are the operator's own
+pub fn message(deal_id: &H256, window: &U256, confirmations: &U256) -> Vec<u8> {
+    let mut schedule: Vec<u8> = Vec::new();
+    schedule.extend_from_slice(deal_id);
+    schedule.extend_from_slice(&convert::uint_to_word(window));
+    schedule.extend_from_slice(&convert::uint_to_word(confirmations));
+    domains::domain_message(SCHEDULE_DOMAIN, &schedule)
+}
+
+/// Record the block attested to the enclave, refusing the blocks older than the latest one attested,
+/// so that an operator cannot replay a stale block attestation to pass for an earlier block
+fn observe<R: Runtime>(runtime: &R, attested_block: u64) {
+    let latest: u64 = runtime.read_state(LATEST_ATTESTED_BLOCK).unwrap_or_default();
+    if attested_block < latest {
+        panic!("Stale attested block: {} < {}", attested_block, latest);
+    }
+    runtime.write_state(LATEST_ATTESTED_BLOCK, attested_block);
+}
+
+/// Draw the block from which the deal may execute, 1 to SCHEDULE_WINDOW blocks after the attested block,
+/// so that observers cannot predict when the outputs of the deal appear. The deal may only execute within
+/// the `window` blocks declared by the operator from then on, bounded by the network profile, so that
+/// the operator cannot hold a prepared deal and execute it much later.
+pub fn schedule<R: Runtime>(runtime: &R, deal_id: &H256, attested_block: u64, window: u64) -> u64 {
+    let key = schedule_key(deal_id);
+    let existing: Option<u64> = runtime.read_state(&key);
+    // Deals depending on a fact invalidated by a reorg are rolled back to be scheduled again
+    if existing.is_some() && !facts::invalidated(runtime, deal_id) {
+        panic!("Deal already scheduled: {:?}", deal_id);
+    }
+    let max_window = profile::get(runtime).max_execution_window;
+    if window == 0 || window > max_window {
+        panic!("Invalid execution window of {} blocks, max {}", window, max_window);
+    }
+    observe(runtime, attested_block);
+    let eligible_block = attested_block + 1 + runtime.random_seed() % SCHEDULE_WINDOW;
+    trace!("Scheduling deal {:?} from block {} to block {}", deal_id, eligible_block, eligible_block + window);
+    runtime.write_state(&key, eligible_block);
+    runtime.write_state(&window_key(deal_id), eligible_block + window);
+    eligible_block
+}
+
+/// Refuse to execute the deal outside of its execution window, the deals scheduled before the windows
+/// only being refused before their scheduled block
+pub fn check<R: Runtime>(runtime: &R, deal_id: &H256, attested_block: u64) {
+    let eligible_block: u64 = match runtime.read_state(&schedule_key(deal_id)) {
+        Some(eligible_block) => eligible_block,
+        None => panic!("Deal not scheduled: {:?}", deal_id),
+    };
+    if attested_block < eligible_block {
+        panic!("Deal {:?} not executable before block {}: {}", deal_id, eligible_block, attested_block);
+    }
+    let last_block: Option<u64> = runtime.read_state(&window_key(deal_id));
+    if let Some(last_block) = last_block {
+        if attested_block > last_block {
+            panic!("Deal {:?} not executable after block {}: {}", deal_id, last_block, attested_block);
+        }
+    }
+    observe(runtime, attested_block);
+}
+
+/// Refuse to time out the deal until its execution window is over, the deals scheduled before the windows
+/// never timing out
+pub fn check_timeout<R: Runtime>(runtime: &R, deal_id: &H256, current_block: u64) {
+    let last_block: u64 = match runtime.read_state(&window_key(deal_id)) {
+        Some(last_block) => last_block,
+        None => panic!("Deal without an execution window: {:?}", deal_id),
+    };
+    if current_block <= last_block {
+        panic!("Deal {:?} executable until block {}: {}", deal_id, last_block, current_block);
+    }
+    observe(runtime, current_block);
+}
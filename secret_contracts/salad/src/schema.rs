@@ -0,0 +1,83 @@
+use eng_wasm::{String, Vec};
+use salad_types::domains::DEPOSIT_DOMAIN_NAME;
+use salad_types::protocol::{
+    self, ASSET_DEPOSIT_FIELDS, DEPOSIT_FIELDS, ENCRYPTION_OVERHEAD, ENVELOPE_VERSIONS, MODE_AGGREGATED,
+    MODE_BLS, MODE_BUNDLED, MODE_ECDSA, MODE_RELAYED, PUB_KEY_SIZE,
+};
+
+use crate::audit::{ROLE_OPERATOR_AUDITOR, ROLE_REGULATOR};
+use crate::evidence::{BAD_COMMITMENT, WITHHELD_DEPOSIT};
+use crate::fees::{DUST_REFUND, DUST_TO_FEE, DUST_TO_POOL};
+use crate::kdf::{DEPOSIT_PAYLOAD_V1, SALTED_PAYLOAD_MAGIC, SALT_SIZE};
+use crate::parsing::{EXTENSIONS_MARKER, EXTENSION_CRITICAL, EXTENSION_NOTIFICATION, EXTENSION_PADDING};
+use crate::risk::{RISK_OTHER_SENDER, RISK_RECENT_RECIPIENT, RISK_SELF_SEND};
+use crate::shuffle::{ORDERING_RANDOM, ORDERING_SORTED_BY_ADDRESS};
+
+fn object(entries: &[(&str, String)]) -> String {
+    let entries: Vec<String> = entries.iter().map(|(key, value)| format!("\"{}\":{}", key, value)).collect();
+    format!("{{{}}}", entries.join(","))
+}
+
+fn array(items: Vec<String>) -> String {
+    format!("[{}]", items.join(","))
+}
+
+fn string(value: &[u8]) -> String {
+    format!("\"{}\"", String::from_utf8_lossy(value))
+}
+
+fn codes(codes: &[(&str, u8)]) -> String {
+    let entries: Vec<(&str, String)> = codes.iter().map(|(name, code)| (*name, code.to_string())).collect();
+    object(&entries)
+}
+
+fn fields(fields: &[(&str, &str)]) -> String {
+    array(fields.iter().map(|(name, kind)| object(&[("name", string(name.as_bytes())), ("type", string(kind.as_bytes()))])).collect())
+}
+
+/// JSON description of the deposit message, the payload envelopes and the codes of the protocol,
+/// generated from the definitions verified by the enclave for the operator and wallet integrations
+pub fn export() -> Vec<u8> {
+    let deposit = object(&[
+        ("domain", object(&[
+            ("name", string(DEPOSIT_DOMAIN_NAME)),
+            ("version", string(protocol::message().deposit_domain_version)),
+        ])),
+        ("primaryType", string(b"Deposit")),
+        ("fields", fields(DEPOSIT_FIELDS)),
+        ("assetFields", fields(ASSET_DEPOSIT_FIELDS)),
+    ]);
+    let envelopes = array(ENVELOPE_VERSIONS.iter().map(|envelope| object(&[
+        ("version", envelope.version.to_string()),
+        ("splitSize", envelope.split_size.to_string()),
+        ("maxSplits", envelope.max_splits.to_string()),
+        ("assetPayloadSize", envelope.asset_payload_size.to_string()),
+        ("redepositSize", envelope.redeposit_size.to_string()),
+        ("guardianSize", envelope.guardian_size.to_string()),
+        ("notes", envelope.notes.to_string()),
+        ("maxExtensionsSize", envelope.max_extensions_size.to_string()),
+        ("maxPayloadSize", envelope.max_payload_size().to_string()),
+    ])).collect());
+    object(&[
+        ("deposit", deposit),
+        ("envelopes", envelopes),
+        ("pubKeySize", PUB_KEY_SIZE.to_string()),
+        ("encryptionOverhead", ENCRYPTION_OVERHEAD.to_string()),
+        ("extensions", object(&[
+            ("marker", EXTENSIONS_MARKER.to_string()),
+            ("criticalFlag", EXTENSION_CRITICAL.to_string()),
+            ("types", codes(&[("padding", EXTENSION_PADDING), ("notification", EXTENSION_NOTIFICATION)])),
+        ])),
+        ("saltedPayload", object(&[
+            ("magic", string(SALTED_PAYLOAD_MAGIC)),
+            ("saltSize", SALT_SIZE.to_string()),
+            ("context", string(DEPOSIT_PAYLOAD_V1)),
+        ])),
+        ("signatureModes", codes(&[("ecdsa", MODE_ECDSA), ("aggregated", MODE_AGGREGATED), ("bls", MODE_BLS), ("relayed", MODE_RELAYED), ("bundled", MODE_BUNDLED)])),
+        ("misbehaviors", codes(&[("badCommitment", BAD_COMMITMENT), ("withheldDeposit", WITHHELD_DEPOSIT)])),
+        ("outputOrderings", codes(&[("random", ORDERING_RANDOM), ("sortedByAddress", ORDERING_SORTED_BY_ADDRESS)])),
+        ("auditorRoles", codes(&[("operatorAuditor", ROLE_OPERATOR_AUDITOR), ("regulator", ROLE_REGULATOR)])),
+        ("dustPolicies", codes(&[("refund", DUST_REFUND), ("toFee", DUST_TO_FEE), ("toPool", DUST_TO_POOL)])),
+        ("riskFlags", codes(&[("selfSend", RISK_SELF_SEND), ("recentRecipient", RISK_RECENT_RECIPIENT), ("otherSender", RISK_OTHER_SENDER)])),
+    ]).into_bytes()
+}
@@ -0,0 +1,84 @@
+use eng_wasm::{Vec, H160};
+use enigma_crypto::KeyPair;
+
+use crate::codec::Encoder;
+use crate::digest;
+use crate::kdf;
+use crate::protocol::SIG_SIZE;
+use crate::runtime::Runtime;
+use crate::shuffle::{self, Shuffler, ORDERING_RANDOM, ORDERING_SORTED_BY_ADDRESS};
+use crate::signature;
+
+/// Number of random recipients shuffled by the check
+const SHUFFLE_SIZE: usize = 16;
+
+/// The keys derived for a purpose are stable, and distinct across purposes
+fn check_key_derivation<R: Runtime>(runtime: &R) -> bool {
+    let encryption = kdf::keypair(runtime, kdf::ENCRYPTION_V1).get_pubkey();
+    let signing = kdf::keypair(runtime, kdf::SIGNING_V1).get_pubkey();
+    encryption[..] == kdf::keypair(runtime, kdf::ENCRYPTION_V1).get_pubkey()[..] && encryption[..] != signing[..]
+}
+
+/// A message signed by the signing key recovers to its address
+fn check_sign_recover<R: Runtime>(runtime: &R, keypair: &KeyPair) -> bool {
+    let message = runtime.generate_key();
+    let mut signature = [0_u8; SIG_SIZE];
+    match keypair.sign(&message) {
+        Ok(raw) => signature.copy_from_slice(&raw),
+        Err(_) => return false,
+    }
+    match digest::recover_address(&message, signature) {
+        Ok(signer) => signer == signature::signer_address(&keypair.get_pubkey()),
+        Err(_) => false,
+    }
+}
+
+/// A payload encrypted by a depositor decrypts with the key the enclave derives, both ends deriving the same key
+fn check_encrypt_decrypt<R: Runtime>(runtime: &R, keypair: &KeyPair) -> bool {
+    let user_keypair = KeyPair::from_slice(&runtime.generate_key()).unwrap();
+    let (user_key, enclave_key) = match (user_keypair.derive_key(&keypair.get_pubkey()), keypair.derive_key(&user_keypair.get_pubkey())) {
+        (Ok(user_key), Ok(enclave_key)) => (user_key, enclave_key),
+        _ => return false,
+    };
+    let payload = runtime.generate_key();
+    let enc_payload = runtime.encrypt(&payload, &user_key);
+    user_key == enclave_key && enc_payload[..] != payload[..] && runtime.decrypt(&enc_payload, &enclave_key).map_or(false, |plaintext| plaintext[..] == payload[..])
+}
+
+/// Each ordering policy reorders random outputs as a permutation, keeping every output
+fn check_shuffle<R: Runtime>(runtime: &R) -> bool {
+    let seed = runtime.generate_key().iter().take(8).fold(0_u64, |seed, byte| seed << 8 | u64::from(*byte));
+    let recipients: Vec<H160> = (0..SHUFFLE_SIZE).map(|_| H160::from(&runtime.generate_key()[12..32])).collect();
+    [ORDERING_RANDOM, ORDERING_SORTED_BY_ADDRESS].iter().all(|policy| {
+        let shuffler: Box<dyn Shuffler> = shuffle::shuffler(*policy, seed);
+        let mut permutation = shuffler.permutation(&recipients);
+        permutation.sort();
+        let mut outputs: Vec<(usize, H160)> = recipients.iter().cloned().enumerate().collect();
+        shuffle::apply(&*shuffler, &mut outputs, |(_, recipient)| *recipient);
+        outputs.sort_by_key(|(i, _)| *i);
+        permutation == (0..SHUFFLE_SIZE).collect::<Vec<usize>>()
+            && outputs.iter().enumerate().all(|(i, (j, recipient))| i == *j && *recipient == recipients[i])
+    })
+}
+
+/// Run the checks of the enclave primitives, returning the report signed by the enclave as
+/// encoding || signature, the report being the container (results: List[uint8], passed: uint8)
+/// of the result of each check (1 passed, 0 failed) and of the overall result. The checks are reported
+/// in order: key derivation, sign/recover, encrypt/decrypt and shuffle.
+pub fn run<R: Runtime>(runtime: &R, keypair: &KeyPair, signing_keypair: &KeyPair) -> Vec<u8> {
+    let results: Vec<u8> = vec![
+        check_key_derivation(runtime),
+        check_sign_recover(runtime, signing_keypair),
+        check_encrypt_decrypt(runtime, keypair),
+        check_shuffle(runtime),
+    ].into_iter().map(|passed| passed as u8).collect();
+    let passed = results.iter().all(|result| *result == 1);
+    trace!("Self-test results: {:?}", results);
+    let mut encoder = Encoder::default();
+    encoder.append_list(&results, |result| vec![*result]);
+    encoder.append_u8(passed as u8);
+    let mut report = encoder.finish();
+    let signature = signing_keypair.sign(&report).unwrap();
+    report.extend_from_slice(&signature);
+    report
+}
@@ -0,0 +1,122 @@
+use eng_wasm::{String, Vec, H160, H256};
+use enigma_crypto::hash::Keccak256;
+
+use crate::domains::{self, BUNDLE_DOMAIN};
+use crate::protocol::{self, BLS_PUB_KEY_SIZE, BLS_SIG_SIZE, MODE_AGGREGATED, MODE_BLS, MODE_BUNDLED, MODE_ECDSA, MODE_RELAYED, SIG_SIZE};
+#[cfg(feature = "bls")]
+use crate::bls;
+
+/// The signature modes accepted by this build
+pub fn supported_modes() -> Vec<u8> {
+    let mut modes = vec![MODE_ECDSA, MODE_AGGREGATED, MODE_RELAYED, MODE_BUNDLED];
+    if cfg!(feature = "bls") {
+        modes.push(MODE_BLS);
+    }
+    modes
+}
+
+pub enum DepositSignature<'a> {
+    /// Individual secp256k1 signature (r || s || v) recovering to the sender, compact signatures being normalized
+    Ecdsa([u8; SIG_SIZE]),
+    /// Member of the deal aggregate signature, carrying the BLS12-381 public key of the signer
+    Aggregated(&'a [u8]),
+    /// Individual BLS12-381 signature along with the signer public key
+    Bls { pub_key: &'a [u8], signature: &'a [u8] },
+    /// secp256k1 signature of a registered relayer secret contract, the sender being its depositing contract
+    Relayed([u8; SIG_SIZE]),
+    /// secp256k1 signatures of a deposit bundled by a registered aggregator, the sender being its depositing
+    /// contract: the depositor signs the deposit message, and the aggregator signs for the depositor
+    Bundled { depositor: [u8; SIG_SIZE], aggregator: [u8; SIG_SIZE] },
+}
+
+/// Parse a deposit signature. The bare 65-byte and compact 64-byte signatures are tried before the mode byte,
+/// so every moded signature has a fixed size other than these two: a mode byte followed by 63 or 64 bytes
+/// would be read as a bare signature.
+pub fn parse(raw: &[u8]) -> Result<DepositSignature, String> {
+    if let Some(signature) = protocol::ecdsa_signature(raw) {
+        return Ok(DepositSignature::Ecdsa(signature));
+    }
+    match raw.first() {
+        Some(&MODE_ECDSA) if raw.len() == SIG_SIZE + 1 => {
+            let mut signature = [0; SIG_SIZE];
+            signature.copy_from_slice(&raw[1..]);
+            Ok(DepositSignature::Ecdsa(signature))
+        }
+        Some(&MODE_AGGREGATED) if raw.len() == 1 + BLS_PUB_KEY_SIZE => Ok(DepositSignature::Aggregated(&raw[1..])),
+        Some(&MODE_BLS) if raw.len() == 1 + BLS_PUB_KEY_SIZE + BLS_SIG_SIZE => Ok(DepositSignature::Bls {
+            pub_key: &raw[1..1 + BLS_PUB_KEY_SIZE],
+            signature: &raw[1 + BLS_PUB_KEY_SIZE..],
+        }),
+        Some(&MODE_RELAYED) if raw.len() == SIG_SIZE + 1 => {
+            let mut signature = [0; SIG_SIZE];
+            signature.copy_from_slice(&raw[1..]);
+            Ok(DepositSignature::Relayed(signature))
+        }
+        Some(&MODE_BUNDLED) if raw.len() == 2 * SIG_SIZE + 1 => {
+            let mut depositor = [0; SIG_SIZE];
+            let mut aggregator = [0; SIG_SIZE];
+            depositor.copy_from_slice(&raw[1..1 + SIG_SIZE]);
+            aggregator.copy_from_slice(&raw[1 + SIG_SIZE..]);
+            Ok(DepositSignature::Bundled { depositor, aggregator })
+        }
+        Some(mode) => Err(format!("Unsupported signature mode {} of size {}", mode, raw.len())),
+        None => Err(String::from("Empty deposit signature")),
+    }
+}
+
+/// Whether the raw signature is the signature of a bundled deposit
+pub fn is_bundled(raw: &[u8]) -> bool {
+    raw.first() == Some(&MODE_BUNDLED) && raw.len() == 2 * SIG_SIZE + 1
+}
+
+/// The message the aggregator signs for the depositor of a bundled deposit:
+/// the EIP191 personal message of H(BUNDLE_DOMAIN || deposit digest || depositor)
+pub fn bundle_message(digest: &H256, depositor: &H160) -> Vec<u8> {
+    let mut body: Vec<u8> = Vec::new();
+    body.extend_from_slice(digest);
+    body.extend_from_slice(depositor);
+    domains::domain_message(BUNDLE_DOMAIN, &body)
+}
+
+/// The depositor address bound to an aggregation scheme public key: H(pub_key)[12..32]
+pub fn signer_address(signer_pub_key: &[u8]) -> H160 {
+    H160::from(&signer_pub_key.keccak256()[12..32])
+}
+
+/// Verify an individual BLS deposit signature, only available in builds with the "bls" feature
+pub fn verify_bls(pub_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), String> {
+    #[cfg(feature = "bls")]
+    {
+        if !bls::verify(pub_key, message, signature) {
+            return Err(format!("Invalid BLS signature of signer: {:?}", pub_key));
+        }
+        Ok(())
+    }
+    #[cfg(not(feature = "bls"))]
+    {
+        trace!("Unable to verify BLS signature of {} bytes over {} bytes", signature.len(), message.len());
+        Err(format!("BLS signatures are not supported in this build: {:?}", pub_key))
+    }
+}
+
+/// Verify the deal aggregate signature over the typed data message of each aggregated deposit.
+///
+/// secp256k1 ECDSA signatures cannot be aggregated, so aggregated deposits are only accepted
+/// in builds with the "bls" feature, where signers hold BLS12-381 keys.
+pub fn verify_aggregate(signer_pub_keys: &[Vec<u8>], messages: &[Vec<u8>], aggregate_signature: &[u8]) {
+    if signer_pub_keys.is_empty() {
+        if !aggregate_signature.is_empty() {
+            panic!("Unexpected aggregate signature without aggregated deposits");
+        }
+        return;
+    }
+    trace!("Verifying aggregate signature of {} deposits over {} messages", signer_pub_keys.len(), messages.len());
+    #[cfg(feature = "bls")]
+    {
+        if !bls::verify_aggregate(signer_pub_keys, messages, aggregate_signature) {
+            panic!("Invalid aggregate signature");
+        }
+    }
+    #[cfg(not(feature = "bls"))]
+    panic!("No aggregate signature scheme available in this build");
+}
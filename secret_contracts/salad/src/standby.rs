@@ -0,0 +1,154 @@
+use eng_wasm::{String, Vec, H256};
+use enigma_crypto::hash::Keccak256;
+use enigma_crypto::KeyPair;
+use serde::{Deserialize, Serialize};
+
+use crate::codec::{self, Decoder, Encoder};
+use crate::domains::{ORACLE_PROMOTION_DOMAIN, ORACLE_STANDBY_DOMAIN};
+use crate::protocol::PUB_KEY_SIZE;
+use crate::runtime::Runtime;
+use crate::state::{self, Hash};
+use crate::{kdf, oracle, redeposits, signature};
+
+// State key name "standby" holding the standby instance registered on this instance, if any
+static STANDBY: &str = "standby";
+// State key prefix of the state imported by this standby instance, followed by the hex address
+// of the public key of the instance it stands by
+static STANDBY_IMPORT_PREFIX: &str = "standby_import_";
+
+/// Replicated sections of the state: the master seed, from which all the contract keys derive,
+/// and the deposits staged back into the pool, only known to the enclave
+const SECTION_SEED: usize = 0;
+const SECTION_REDEPOSITS: usize = 1;
+const NB_SECTIONS: usize = 2;
+
+/// The standby instance registered on this instance, along with the revisions of the replicated sections
+#[derive(Serialize, Deserialize)]
+struct Standby {
+    pub_key: Vec<u8>,
+    /// Revision of the last change of the replicated sections
+    revision: u64,
+    /// The (digest, revision) of the last exported contents of each section
+    sections: Vec<(Hash, u64)>,
+}
+
+/// The sections imported by a standby instance, at the revision of their last delta
+#[derive(Serialize, Deserialize, Default)]
+struct StandbyImport {
+    revision: u64,
+    sections: Vec<Vec<u8>>,
+}
+
+fn shared_key(keypair: &KeyPair, pub_key: &[u8]) -> [u8; 32] {
+    if pub_key.len() != PUB_KEY_SIZE {
+        panic!("Invalid standby public key size: {}", pub_key.len());
+    }
+    let mut key = [0_u8; PUB_KEY_SIZE];
+    key.copy_from_slice(pub_key);
+    keypair.derive_key(&key).unwrap_or_else(|err| panic!("Invalid standby public key: {:?}", err))
+}
+
+fn import_key(pub_key: &[u8]) -> String {
+    state::address_key(STANDBY_IMPORT_PREFIX, &signature::signer_address(pub_key))
+}
+
+fn sections<R: Runtime>(runtime: &R) -> Vec<Vec<u8>> {
+    vec![kdf::seed(runtime).to_vec(), redeposits::export(runtime)]
+}
+
+/// Register the standby instance of this instance from an oracle command `attestation` of its encryption
+/// public key, replacing the previous standby instance, if any, the next delta holding all the sections
+pub fn register<R: Runtime>(runtime: &R, attestation: &[u8], signature: &[u8]) {
+    let pub_key = oracle::verify_command(runtime, ORACLE_STANDBY_DOMAIN, attestation, signature);
+    if pub_key.len() != PUB_KEY_SIZE {
+        panic!("Invalid standby attestation size: {}", pub_key.len());
+    }
+    trace!("Registering standby {:?}", signature::signer_address(pub_key));
+    runtime.write_state(STANDBY, Standby { pub_key: pub_key.to_vec(), revision: 0, sections: Vec::new() });
+}
+
+/// The sections changed after revision `since`, encrypted to the standby instance as the container
+/// (revision: uint64, since: uint64, seed: List[uint8], redeposits: List[uint8]), the sections unchanged
+/// since being empty. Exporting only bumps the revisions of the changed sections, so that a delta
+/// exported but never imported does not leave the standby instance behind.
+pub fn export_delta<R: Runtime>(runtime: &R, keypair: &KeyPair, since: u64) -> Vec<u8> {
+    let mut standby: Standby = runtime.read_state(STANDBY).expect("No standby registered");
+    let contents = sections(runtime);
+    for (i, content) in contents.iter().enumerate() {
+        let digest = Hash(H256::from(&content.keccak256()[..]));
+        match standby.sections.get(i) {
+            Some((exported, _)) if *exported == digest => {}
+            Some(_) => {
+                standby.revision += 1;
+                standby.sections[i] = (digest, standby.revision);
+            }
+            None => {
+                standby.revision += 1;
+                standby.sections.push((digest, standby.revision));
+            }
+        }
+    }
+    let mut encoder = Encoder::default();
+    encoder.append_u64(standby.revision);
+    encoder.append_u64(since);
+    for (content, (_, revision)) in contents.iter().zip(standby.sections.iter()) {
+        let changed: &[u8] = if *revision > since { content } else { &[] };
+        encoder.append_list(changed, |byte| vec![*byte]);
+    }
+    trace!("Exporting standby delta from revision {} to {}", since, standby.revision);
+    let delta = runtime.encrypt(&encoder.finish(), &shared_key(keypair, &standby.pub_key));
+    runtime.write_state(STANDBY, standby);
+    delta
+}
+
+/// Import a delta exported for this standby instance by the instance of `primary_pub_key`, the deltas
+/// being imported in revision order. Stale deltas are ignored.
+pub fn import_delta<R: Runtime>(runtime: &R, keypair: &KeyPair, primary_pub_key: &[u8], delta: &[u8]) {
+    let plaintext = runtime.decrypt(delta, &shared_key(keypair, primary_pub_key))
+        .unwrap_or_else(|err| panic!("Undecryptable standby delta: {}", err));
+    let mut decoder = Decoder::new(&plaintext);
+    let revision = codec::decode_u64(decoder.read_bytes(8));
+    let since = codec::decode_u64(decoder.read_bytes(8));
+    for _ in 0..NB_SECTIONS {
+        decoder.read_offset();
+    }
+    let changed = decoder.finish();
+    let key = import_key(primary_pub_key);
+    let mut import: StandbyImport = runtime.read_state(&key).unwrap_or_default();
+    if revision <= import.revision {
+        trace!("Ignoring stale standby delta of revision {}", revision);
+        return;
+    }
+    if since > import.revision {
+        panic!("Missing standby deltas from revision {} to {}", import.revision, since);
+    }
+    import.sections.resize(NB_SECTIONS, Vec::new());
+    for (i, content) in changed.iter().enumerate().filter(|(_, content)| !content.is_empty()) {
+        import.sections[i] = content.to_vec();
+    }
+    trace!("Imported standby delta from revision {} to {}", since, revision);
+    import.revision = revision;
+    runtime.write_state(&key, import);
+}
+
+/// Promote this standby instance in place of another instance, from an oracle command `attestation` of the
+/// encryption public keys of the replaced instance and of this instance: restore the master seed of the
+/// replaced instance, so that the deposits encrypted to it may be opened, and its staged deposits
+pub fn promote<R: Runtime>(runtime: &R, attestation: &[u8], signature: &[u8], pub_key: &[u8]) {
+    let body = oracle::verify_command(runtime, ORACLE_PROMOTION_DOMAIN, attestation, signature);
+    if body.len() != 2 * PUB_KEY_SIZE || body[PUB_KEY_SIZE..] != *pub_key {
+        panic!("Promotion not attested for this standby");
+    }
+    let key = import_key(&body[..PUB_KEY_SIZE]);
+    let import: StandbyImport = runtime.read_state(&key).unwrap_or_default();
+    match import.sections.get(SECTION_SEED) {
+        Some(seed) if !seed.is_empty() => kdf::restore(runtime, seed),
+        _ => panic!("No master seed imported"),
+    }
+    if !import.sections[SECTION_REDEPOSITS].is_empty() {
+        redeposits::import(runtime, &import.sections[SECTION_REDEPOSITS]);
+    }
+    trace!("Promoted standby at revision {}", import.revision);
+    // Promoted once, later deltas of the replaced instance being imported anew
+    runtime.write_state(&key, StandbyImport::default());
+}
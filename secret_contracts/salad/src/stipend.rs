@@ -0,0 +1,62 @@
+use eng_wasm::{String, Vec, H160, U256};
+
+use crate::amounts;
+use crate::runtime::Runtime;
+use crate::state::{self, Word};
+
+// State key name "gas_stipend" holding the gas stipend of the fresh recipients, if any
+static GAS_STIPEND: &str = "gas_stipend";
+// State key prefix of the addresses seen by the enclave as a sender or recipient, followed by the hex address
+static SEEN_ADDRESS_PREFIX: &str = "seen_address_";
+
+/// Set the gas stipend paid to the fresh recipients, zero disabling the stipends
+pub fn set_amount<R: Runtime>(runtime: &R, stipend: &U256) {
+    if stipend.is_zero() {
+        return;
+    }
+    runtime.write_state(GAS_STIPEND, Word(*stipend));
+}
+
+/// The gas stipend of the deployment, zero when disabled
+pub fn amount<R: Runtime>(runtime: &R) -> U256 {
+    let stipend: Option<Word> = runtime.read_state(GAS_STIPEND);
+    stipend.map_or_else(U256::zero, |stipend| stipend.0)
+}
+
+fn seen_key(address: &H160) -> String {
+    state::address_key(SEEN_ADDRESS_PREFIX, address)
+}
+
+fn is_fresh<R: Runtime>(runtime: &R, address: &H160) -> bool {
+    let seen: Option<bool> = runtime.read_state(&seen_key(address));
+    seen.is_none()
+}
+
+/// The number of the recipients never seen by the enclave as a sender or recipient of a previous deal
+pub fn count_fresh<R: Runtime>(runtime: &R, recipients: &[H160]) -> usize {
+    recipients.iter().filter(|recipient| is_fresh(runtime, recipient)).count()
+}
+
+/// Grant the gas stipend to the recipients never seen by the enclave as a sender or recipient of a previous deal,
+/// as an additional output funded from the operator fee, for as long as the fee covers it. The stipend of each
+/// fresh recipient is attributed to the deposit of its first output. Returns the total of the stipends granted.
+/// The addresses are marked as seen even when the stipends are disabled, for the fresh recipient statistics.
+pub fn grant<R: Runtime>(runtime: &R, outputs: &mut Vec<(usize, H160, U256)>, senders: &[H160], fee: &U256) -> U256 {
+    let stipend = amount(runtime);
+    let mut remaining = *fee;
+    let mut fresh: Vec<(usize, H160)> = Vec::new();
+    if !stipend.is_zero() {
+        for (i, recipient) in outputs.iter().map(|(i, recipient, _)| (*i, *recipient)) {
+            if remaining >= stipend && !fresh.iter().any(|(_, granted)| *granted == recipient) && is_fresh(runtime, &recipient) {
+                remaining = amounts::sub(&remaining, &stipend);
+                fresh.push((i, recipient));
+            }
+        }
+        trace!("Granting the gas stipend to {} fresh recipients", fresh.len());
+    }
+    for address in senders.iter().chain(outputs.iter().map(|(_, recipient, _)| recipient)).filter(|address| !address.is_zero()) {
+        runtime.write_state(&seen_key(address), true);
+    }
+    outputs.extend(fresh.into_iter().map(|(i, recipient)| (i, recipient, stipend)));
+    amounts::sub(fee, &remaining)
+}
@@ -0,0 +1,228 @@
+use eng_wasm::{String, Vec, H256, U256, SymmetricKey};
+use enigma_crypto::hash::Keccak256;
+use enigma_crypto::KeyPair;
+use rustc_hex::ToHex;
+use serde::{Deserialize, Serialize};
+
+use crate::convert;
+use crate::domains::ORACLE_THRESHOLD_DOMAIN;
+use crate::oracle;
+use crate::protocol::{ENCRYPTION_OVERHEAD, PUB_KEY_SIZE};
+use crate::runtime::Runtime;
+
+// State key name "threshold_peers" holding the threshold decryption configuration of the instance, if any
+static THRESHOLD_PEERS: &str = "threshold_peers";
+// State key prefix of the partial decryptions of each deposit, followed by the hex H(encrypted payload)
+static PARTIALS_PREFIX: &str = "threshold_partials_";
+
+/// Max instances sharing the recipient payloads
+const MAX_PEERS: usize = 16;
+
+/// A t-of-n sharing of the recipient payloads across Salad instances. Depositors split their payload
+/// in n Shamir shares over GF(256), the share of each peer at x = index + 1 being encrypted to its
+/// enclave key, the encrypted payload being the concatenation of the n encrypted shares.
+#[derive(Serialize, Deserialize)]
+pub struct ThresholdConfig {
+    /// Encryption public keys of the instances, in share order
+    peers: Vec<Vec<u8>>,
+    threshold: u8,
+    /// Index of this instance among the peers
+    index: u8,
+}
+
+impl ThresholdConfig {
+    pub fn nb_peers(&self) -> usize {
+        self.peers.len()
+    }
+
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    fn peer_key(&self, keypair: &KeyPair, peer: usize) -> Result<SymmetricKey, String> {
+        let mut pub_key = [0_u8; PUB_KEY_SIZE];
+        pub_key.copy_from_slice(&self.peers[peer]);
+        keypair.derive_key(&pub_key).map_err(|err| format!("Unable to derive the key of peer {}: {:?}", peer, err))
+    }
+
+    /// The encrypted share of the peer in the encrypted payload of participant i
+    fn share<'a>(&self, i: usize, enc_recipient: &'a [u8], peer: usize) -> Result<&'a [u8], String> {
+        if enc_recipient.len() % self.nb_peers() != 0 {
+            return Err(format!("Invalid threshold payload size for participant {}: {}", i, enc_recipient.len()));
+        }
+        let share_size = enc_recipient.len() / self.nb_peers();
+        Ok(&enc_recipient[peer * share_size..(peer + 1) * share_size])
+    }
+}
+
+fn read_word(attestation: &[u8], i: usize) -> U256 {
+    convert::word_to_uint(&attestation[i * 32..(i + 1) * 32])
+}
+
+/// Configure the threshold decryption from an oracle command attesting the threshold, the index
+/// of this instance and the encryption public keys of the peers, once
+pub fn configure<R: Runtime>(runtime: &R, attestation: &[u8], signature: &[u8], own_pub_key: &[u8]) {
+    if config(runtime).is_some() {
+        panic!("Threshold decryption already configured");
+    }
+    let body = oracle::verify_command(runtime, ORACLE_THRESHOLD_DOMAIN, attestation, signature);
+    if body.len() < 64 || (body.len() - 64) % PUB_KEY_SIZE != 0 {
+        panic!("Invalid threshold attestation size: {}", body.len());
+    }
+    let peers: Vec<Vec<u8>> = body[64..].chunks(PUB_KEY_SIZE).map(|peer| peer.to_vec()).collect();
+    let threshold = read_word(body, 0);
+    let index = read_word(body, 1);
+    if peers.len() < 2 || peers.len() > MAX_PEERS {
+        panic!("Invalid number of threshold peers: {}", peers.len());
+    }
+    if threshold < U256::from(2) || threshold > U256::from(peers.len()) {
+        panic!("Invalid threshold: {:?} of {}", threshold, peers.len());
+    }
+    if index >= U256::from(peers.len()) || peers[index.low_u64() as usize] != own_pub_key {
+        panic!("This instance is not peer {:?}", index);
+    }
+    trace!("Sharing the recipient payloads {:?} of {} peers", threshold, peers.len());
+    let config = ThresholdConfig { peers, threshold: threshold.low_u32() as u8, index: index.low_u32() as u8 };
+    runtime.write_state(THRESHOLD_PEERS, config);
+}
+
+pub fn config<R: Runtime>(runtime: &R) -> Option<ThresholdConfig> {
+    runtime.read_state(THRESHOLD_PEERS)
+}
+
+fn partials_key(enc_recipient: &[u8]) -> String {
+    format!("{}{}", PARTIALS_PREFIX, H256::from(&enc_recipient.keccak256()[..]).to_hex::<String>())
+}
+
+fn load_partials<R: Runtime>(runtime: &R, enc_recipient: &[u8]) -> Vec<(u8, Vec<u8>)> {
+    runtime.read_state(&partials_key(enc_recipient)).unwrap_or_default()
+}
+
+/// Decrypt the share of this instance in each encrypted payload and encrypt it to the executing peer,
+/// the share of an undecryptable payload being left empty
+pub fn partial_decrypt<R: Runtime>(
+    runtime: &R,
+    keypair: &KeyPair,
+    executor_pub_key: &[u8],
+    pub_keys: &[Vec<u8>],
+    enc_recipients: &[Vec<u8>],
+) -> Vec<Vec<u8>> {
+    let config = config(runtime).expect("Threshold decryption not configured");
+    let executor = config.peers.iter().position(|peer| peer.as_slice() == executor_pub_key)
+        .unwrap_or_else(|| panic!("Unknown executing peer: {:?}", executor_pub_key));
+    if executor == config.index as usize {
+        panic!("Partial decryption for this instance");
+    }
+    let executor_key = config.peer_key(keypair, executor).unwrap();
+    pub_keys.iter().zip(enc_recipients.iter()).enumerate().map(|(i, (pub_key, enc_recipient))| {
+        if pub_key.len() != PUB_KEY_SIZE {
+            return Vec::new();
+        }
+        let mut user_pubkey = [0_u8; PUB_KEY_SIZE];
+        user_pubkey.copy_from_slice(pub_key);
+        match (config.share(i, enc_recipient, config.index as usize), keypair.derive_key(&user_pubkey)) {
+            (Ok(share), Ok(shared_key)) => match share.len() {
+                l if l <= ENCRYPTION_OVERHEAD => Vec::new(),
+                _ => match runtime.decrypt(share, &shared_key) {
+                    Ok(partial) => runtime.encrypt(&partial, &executor_key),
+                    Err(_) => Vec::new(),
+                },
+            },
+            _ => Vec::new(),
+        }
+    }).collect()
+}
+
+/// Store the partial decryptions of the peer for the deposits, to be combined when executing their deal
+pub fn submit_partials<R: Runtime>(runtime: &R, peer: &U256, enc_recipients: &[Vec<u8>], partials: &[Vec<u8>]) {
+    let config = config(runtime).expect("Threshold decryption not configured");
+    if *peer >= U256::from(config.nb_peers()) || peer.low_u32() as u8 == config.index {
+        panic!("Invalid threshold peer: {:?}", peer);
+    }
+    if enc_recipients.len() != partials.len() {
+        panic!("Mismatching partials list size: {} != {}", enc_recipients.len(), partials.len());
+    }
+    let peer = peer.low_u32() as u8;
+    for (enc_recipient, partial) in enc_recipients.iter().zip(partials.iter()).filter(|(_, partial)| !partial.is_empty()) {
+        let mut stored = load_partials(runtime, enc_recipient);
+        stored.retain(|(stored_peer, _)| *stored_peer != peer);
+        stored.push((peer, partial.clone()));
+        runtime.write_state(&partials_key(enc_recipient), stored);
+    }
+}
+
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80;
+        a <<= 1;
+        if carry != 0 {
+            a ^= 0x1b;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// Inverse in GF(256) (AES polynomial) as a^254
+fn gf_inv(a: u8) -> u8 {
+    let mut inverse = 1;
+    let mut base = a;
+    let mut exponent = 254_u8;
+    while exponent != 0 {
+        if exponent & 1 != 0 {
+            inverse = gf_mul(inverse, base);
+        }
+        base = gf_mul(base, base);
+        exponent >>= 1;
+    }
+    inverse
+}
+
+/// Lagrange interpolation at zero of the (x, share) points
+fn combine(shares: &[(u8, Vec<u8>)]) -> Vec<u8> {
+    let mut secret = vec![0_u8; shares[0].1.len()];
+    for (j, (x_j, share)) in shares.iter().enumerate() {
+        let basis = shares.iter().enumerate()
+            .filter(|(m, _)| *m != j)
+            .fold(1, |basis, (_, (x_m, _))| gf_mul(basis, gf_mul(*x_m, gf_inv(x_j ^ x_m))));
+        for (byte, share_byte) in secret.iter_mut().zip(share.iter()) {
+            *byte ^= gf_mul(basis, *share_byte);
+        }
+    }
+    secret
+}
+
+/// Decrypt the payload of participant i from the share of this instance and the partial decryptions
+/// submitted for the threshold of peers
+pub fn decrypt_payload<R: Runtime>(
+    runtime: &R,
+    config: &ThresholdConfig,
+    keypair: &KeyPair,
+    i: usize,
+    shared_key: &SymmetricKey,
+    enc_recipient: &[u8],
+) -> Result<Vec<u8>, String> {
+    let own_share = runtime.decrypt(config.share(i, enc_recipient, config.index as usize)?, shared_key)
+        .map_err(|err| format!("Unable to decrypt own share of participant {}: {}", i, err))?;
+    let mut shares: Vec<(u8, Vec<u8>)> = vec![(config.index + 1, own_share)];
+    for (peer, partial) in load_partials(runtime, enc_recipient).iter() {
+        if shares.len() == config.threshold as usize {
+            break;
+        }
+        if partial.len() != shares[0].1.len() + ENCRYPTION_OVERHEAD {
+            return Err(format!("Invalid partial decryption of peer {} for participant {}", peer, i));
+        }
+        let share = runtime.decrypt(partial, &config.peer_key(keypair, *peer as usize)?)
+            .map_err(|err| format!("Unable to decrypt partial decryption of peer {} for participant {}: {}", peer, i, err))?;
+        shares.push((peer + 1, share));
+    }
+    if shares.len() < config.threshold as usize {
+        return Err(format!("Missing partial decryptions for participant {}: {} of {}", i, shares.len(), config.threshold));
+    }
+    trace!("Combined {} shares of participant {}", shares.len(), i);
+    Ok(combine(&shares))
+}
@@ -0,0 +1,173 @@
+use eng_wasm::{Vec, H160, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::amounts;
+use crate::codec::{self, Encoder};
+use crate::convert;
+use crate::runtime::Runtime;
+use crate::state::{Address, Word};
+
+// State key name "token_configs" holding the config of each supported token of the multi-asset deals
+static TOKEN_CONFIGS: &str = "token_configs";
+
+/// The decimals the deposit values of the multi-asset deals are normalized to
+pub const VALUE_DECIMALS: u8 = 18;
+/// Fee rates are in basis points of the output amounts
+const FEE_RATE_BASE: u64 = 10_000;
+/// Size of a token attestation: the token, decimals, min and max amounts, fee rate and adapter as 32-byte words
+const TOKEN_ATTESTATION_SIZE: usize = 6 * 32;
+
+/// Config of a supported token, its amounts being in its base units, a zero max being unbounded
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TokenConfig {
+    pub token: Address,
+    pub decimals: u8,
+    min_amount: Word,
+    max_amount: Word,
+    /// Fee rate in basis points of the outputs paid in the token, kept by the Mixer contract
+    pub fee_rate: u16,
+    /// The adapter contract the Mixer contract pays the outputs in the token through, zero paying the token directly
+    pub adapter: Address,
+}
+
+impl TokenConfig {
+    pub fn min_amount(&self) -> U256 {
+        self.min_amount.0
+    }
+
+    pub fn max_amount(&self) -> U256 {
+        self.max_amount.0
+    }
+
+    /// Canonical encoding of the config as the container (token: Bytes20, decimals: uint8, min_amount: uint256,
+    /// max_amount: uint256, fee_rate: uint64, adapter: Bytes20)
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        encoder.append_bytes(&self.token.0);
+        encoder.append_u8(self.decimals);
+        encoder.append_bytes(&codec::encode_uint256(&self.min_amount()));
+        encoder.append_bytes(&codec::encode_uint256(&self.max_amount()));
+        encoder.append_u64(self.fee_rate as u64);
+        encoder.append_bytes(&self.adapter.0);
+        encoder.finish()
+    }
+}
+
+/// The token table, none for the deployments predating it, whose assets are unrestricted
+fn get_configs<R: Runtime>(runtime: &R) -> Option<Vec<TokenConfig>> {
+    runtime.read_state(TOKEN_CONFIGS)
+}
+
+/// Parse the oracle-attested config of a token: the token, decimals, min and max amounts, fee rate
+/// and adapter as 32-byte words
+pub fn parse_attestation(attestation: &[u8]) -> TokenConfig {
+    if attestation.len() != TOKEN_ATTESTATION_SIZE {
+        panic!("Invalid token attestation size: {}", attestation.len());
+    }
+    let words: Vec<&[u8]> = attestation.chunks(32).collect();
+    let decimals = convert::word_to_uint(words[1]);
+    if decimals > U256::from(VALUE_DECIMALS) {
+        panic!("Token decimals above {}: {}", VALUE_DECIMALS, decimals);
+    }
+    let min_amount = convert::word_to_uint(words[2]);
+    let max_amount = convert::word_to_uint(words[3]);
+    if !max_amount.is_zero() && min_amount > max_amount {
+        panic!("Min token amount above the max token amount: {} > {}", min_amount, max_amount);
+    }
+    let fee_rate = convert::word_to_uint(words[4]);
+    if fee_rate >= U256::from(FEE_RATE_BASE) {
+        panic!("Token fee rate of at least {} basis points: {}", FEE_RATE_BASE, fee_rate);
+    }
+    TokenConfig {
+        token: Address(convert::word_to_address(words[0])),
+        decimals: decimals.low_u32() as u8,
+        min_amount: Word(min_amount),
+        max_amount: Word(max_amount),
+        fee_rate: fee_rate.low_u32() as u16,
+        adapter: Address(convert::word_to_address(words[5])),
+    }
+}
+
+/// Add the config of a token to the table, or replace its config
+pub fn set<R: Runtime>(runtime: &R, config: TokenConfig) {
+    let mut configs = get_configs(runtime).unwrap_or_default();
+    configs.retain(|listed| listed.token != config.token);
+    configs.push(config);
+    runtime.write_state(TOKEN_CONFIGS, configs);
+}
+
+/// Remove a token from the table, refusing the deposits and outputs in the token from then on
+pub fn remove<R: Runtime>(runtime: &R, token: &H160) {
+    let mut configs = get_configs(runtime).unwrap_or_default();
+    let nb_configs = configs.len();
+    configs.retain(|listed| listed.token.0 != *token);
+    if configs.len() == nb_configs {
+        panic!("Unsupported token: {:?}", token);
+    }
+    runtime.write_state(TOKEN_CONFIGS, configs);
+}
+
+/// The config of the token, none when not in the table
+pub fn get<R: Runtime>(runtime: &R, token: &H160) -> Option<TokenConfig> {
+    get_configs(runtime).unwrap_or_default().into_iter().find(|config| config.token.0 == *token)
+}
+
+/// The supported tokens, in the order they were added
+pub fn list<R: Runtime>(runtime: &R) -> Vec<H160> {
+    get_configs(runtime).unwrap_or_default().iter().map(|config| config.token.0).collect()
+}
+
+/// The config of an asset of a multi-asset deal, refusing the assets not in the table once there is one
+pub fn check_asset<R: Runtime>(runtime: &R, asset: &H160) -> Option<TokenConfig> {
+    match get_configs(runtime) {
+        None => None,
+        Some(configs) => match configs.into_iter().find(|config| config.token.0 == *asset) {
+            Some(config) => Some(config),
+            None => panic!("Unsupported token: {:?}", asset),
+        },
+    }
+}
+
+/// Refuse the amounts in the token that are out of its bounds
+pub fn check_amount(config: &TokenConfig, amount: &U256) {
+    let (min, max) = (config.min_amount(), config.max_amount());
+    if amount.is_zero() || *amount < min {
+        panic!("Amount of token {:?} below its min amount: {} < {}", config.token.0, amount, min);
+    }
+    if !max.is_zero() && *amount > max {
+        panic!("Amount of token {:?} above its max amount: {} > {}", config.token.0, amount, max);
+    }
+}
+
+/// The factor normalizing the amounts in the token to VALUE_DECIMALS, one for the unlisted assets
+pub fn scale(config: Option<&TokenConfig>) -> U256 {
+    match config {
+        Some(config) => U256::from(10).pow(U256::from(VALUE_DECIMALS - config.decimals)),
+        None => U256::one(),
+    }
+}
+
+/// The output amount net of the fee of the token
+pub fn net_of_fee(config: Option<&TokenConfig>, amount: &U256) -> U256 {
+    match config {
+        Some(config) => {
+            let fee = amounts::mul(amount, &U256::from(config.fee_rate)) / U256::from(FEE_RATE_BASE);
+            amounts::sub(amount, &fee)
+        }
+        None => *amount,
+    }
+}
+
+/// The contract the Mixer contract pays the outputs in the asset through: its adapter when configured,
+/// the asset itself otherwise
+pub fn payee(config: Option<&TokenConfig>, asset: &H160) -> H160 {
+    match config {
+        Some(config) if !config.adapter.0.is_zero() => config.adapter.0,
+        _ => *asset,
+    }
+}
+
+/// The number of supported tokens
+pub fn nb_tokens<R: Runtime>(runtime: &R) -> usize {
+    get_configs(runtime).map_or(0, |configs| configs.len())
+}
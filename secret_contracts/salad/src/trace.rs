@@ -0,0 +1,113 @@
+use eng_wasm::{String, Vec, H256, eprint};
+use rustc_hex::ToHex;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+/// Log a line of the enclave tagged with the deal, phase and participant of the current span, so that the
+/// log of a deal greps by `deal=`, `phase=` or `participant=`. Never log secrets: recipients, payloads, keys.
+macro_rules! trace {
+    ($($arg:tt)*) => {
+        $crate::trace::emit(&format!($($arg)*))
+    };
+}
+
+/// Tag of the lines logged outside of a span
+const UNTAGGED: usize = usize::max_value();
+
+/// The spans of the current task, held in atomics rather than thread locals, as the enclave runs single-threaded
+static DEAL_ID: [AtomicU64; 4] = [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)];
+static IN_DEAL: AtomicBool = AtomicBool::new(false);
+static PHASE: AtomicUsize = AtomicUsize::new(UNTAGGED);
+static PARTICIPANT: AtomicUsize = AtomicUsize::new(UNTAGGED);
+
+/// Names of the metered phases of a deal, in the order of their constants in `metering`
+static PHASE_NAMES: &[&str] = &["parse", "verify", "decrypt", "shuffle", "callout"];
+
+/// Span of the deal being executed, restoring the enclosing one, if any, when dropped
+pub struct DealSpan {
+    enclosing: Option<[u64; 4]>,
+}
+
+impl Drop for DealSpan {
+    fn drop(&mut self) {
+        store_deal(self.enclosing);
+    }
+}
+
+fn load_deal() -> Option<[u64; 4]> {
+    if !IN_DEAL.load(Ordering::Relaxed) {
+        return None;
+    }
+    let mut words = [0_u64; 4];
+    for (word, tag) in words.iter_mut().zip(DEAL_ID.iter()) {
+        *word = tag.load(Ordering::Relaxed);
+    }
+    Some(words)
+}
+
+fn store_deal(deal: Option<[u64; 4]>) {
+    if let Some(words) = deal {
+        for (word, tag) in words.iter().zip(DEAL_ID.iter()) {
+            tag.store(*word, Ordering::Relaxed);
+        }
+    }
+    IN_DEAL.store(deal.is_some(), Ordering::Relaxed);
+}
+
+/// Tag the lines logged until the span is dropped with the DealId
+pub fn deal(deal_id: &H256) -> DealSpan {
+    let enclosing = load_deal();
+    let mut words = [0_u64; 4];
+    for (word, bytes) in words.iter_mut().zip(deal_id.chunks(8)) {
+        *word = bytes.iter().fold(0_u64, |word, byte| word << 8 | u64::from(*byte));
+    }
+    store_deal(Some(words));
+    DealSpan { enclosing }
+}
+
+/// Span of a phase or of a participant, restoring the enclosing one when dropped
+pub struct Span {
+    tag: &'static AtomicUsize,
+    enclosing: usize,
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        self.tag.store(self.enclosing, Ordering::Relaxed);
+    }
+}
+
+fn enter(tag: &'static AtomicUsize, value: usize) -> Span {
+    Span { tag, enclosing: tag.swap(value, Ordering::Relaxed) }
+}
+
+/// Tag the lines logged until the span is dropped with the metered phase
+pub fn phase(phase: usize) -> Span {
+    enter(&PHASE, phase)
+}
+
+/// Tag the lines logged until the span is dropped with the leading bytes of the blinded handle of the participant,
+/// never with its index, so that the log does not tell the order the operator submitted the deposits in
+pub fn participant(handle: &H256) -> Span {
+    enter(&PARTICIPANT, handle[..4].iter().fold(0_usize, |tag, byte| tag << 8 | usize::from(*byte)))
+}
+
+fn tag(value: usize, name: impl FnOnce(usize) -> String) -> String {
+    match value {
+        UNTAGGED => String::from("-"),
+        value => name(value),
+    }
+}
+
+/// Log the message prefixed with the tags of the current spans
+pub fn emit(message: &str) {
+    let deal_id = match load_deal() {
+        Some(words) => {
+            let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes().to_vec()).collect();
+            format!("0x{}", bytes.to_hex::<String>())
+        }
+        None => String::from("-"),
+    };
+    let phase = tag(PHASE.load(Ordering::Relaxed), |phase| PHASE_NAMES.get(phase).map_or_else(|| phase.to_string(), |name| name.to_string()));
+    let participant = tag(PARTICIPANT.load(Ordering::Relaxed), |handle| format!("{:08x}", handle));
+    eprint!("deal={} phase={} participant={} {}", deal_id, phase, participant, message);
+}
@@ -0,0 +1,53 @@
+use eng_wasm::{Vec, H160, H256, U256};
+use enigma_crypto::hash::Keccak256;
+use enigma_crypto::KeyPair;
+
+use crate::codec::{self, Encoder};
+use crate::protocol::PUB_KEY_SIZE;
+use crate::digest::{self, DepositTerms};
+use crate::runtime::Runtime;
+use crate::signature;
+
+/// Deposit amount (1 ETH), expiry block and chain id of the generated vectors
+const VECTOR_AMOUNT: u64 = 1_000_000_000_000_000_000;
+const VECTOR_EXPIRES_AT: u64 = 10_000_000;
+const VECTOR_CHAIN_ID: u64 = 1;
+
+/// Generate a deposit with a random depositor key pair and recipient, encrypted to the enclave
+/// and signed through the same code paths as the verification. Each vector is the canonical encoding of
+/// (user_secret: Bytes32, user_pub_key: Bytes64, sender: Bytes20, recipient: Bytes20, amount: uint256,
+/// expires_at: uint256, chain_id: uint256, enc_recipient: List[uint8], signature: Bytes65, digest: Bytes32)
+fn generate_vector<R: Runtime>(runtime: &R, keypair: &KeyPair) -> Vec<u8> {
+    let user_secret = runtime.generate_key();
+    let user_keypair = KeyPair::from_slice(&user_secret).unwrap();
+    let user_pubkey: [u8; PUB_KEY_SIZE] = user_keypair.get_pubkey();
+    let sender = signature::signer_address(&user_pubkey);
+    let recipient = H160::from(&runtime.generate_key()[12..32]);
+    let amount = U256::from(VECTOR_AMOUNT);
+    let expires_at = U256::from(VECTOR_EXPIRES_AT);
+    let chain_id = U256::from(VECTOR_CHAIN_ID);
+
+    let shared_key = user_keypair.derive_key(&keypair.get_pubkey()).unwrap();
+    let enc_recipient = runtime.encrypt(&recipient, &shared_key);
+    let message = digest::deposit_message(&sender, DepositTerms::Expiring(&expires_at), &amount, &enc_recipient, &user_pubkey, &chain_id);
+    let deposit_signature = user_keypair.sign(&message).unwrap();
+    let deposit_digest = H256::from(&message.keccak256()[..]);
+
+    let mut encoder = Encoder::default();
+    encoder.append_bytes(&user_secret);
+    encoder.append_bytes(&user_pubkey);
+    encoder.append_bytes(&sender);
+    encoder.append_bytes(&recipient);
+    encoder.append_bytes(&codec::encode_uint256(&amount));
+    encoder.append_bytes(&codec::encode_uint256(&expires_at));
+    encoder.append_bytes(&codec::encode_uint256(&chain_id));
+    encoder.append_list(&enc_recipient, |byte| vec![*byte]);
+    encoder.append_bytes(&deposit_signature);
+    encoder.append_bytes(&deposit_digest);
+    encoder.finish()
+}
+
+pub fn generate<R: Runtime>(runtime: &R, nb_vectors: usize, keypair: &KeyPair) -> Vec<Vec<u8>> {
+    trace!("Generating {} test vectors", nb_vectors);
+    (0..nb_vectors).map(|_| generate_vector(runtime, keypair)).collect()
+}
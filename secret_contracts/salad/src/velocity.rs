@@ -0,0 +1,115 @@
+use eng_wasm::{String, Vec, H160, U256};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+
+use crate::compliance::ComplianceEngine;
+use crate::metrics;
+use crate::runtime::Runtime;
+use crate::state::{self, Word};
+use crate::validation;
+
+// State key name "velocity_limits" holding the per-sender deposit caps of the deployment, if any
+static VELOCITY_LIMITS: &str = "velocity_limits";
+// State key prefix of the deposits of each sender in its last epoch, followed by the hex sender address
+static SENDER_VELOCITY_PREFIX: &str = "sender_velocity_";
+
+/// Max deposits and volume of each sender per epoch of `epoch_deals` executed deals, a zero cap being unlimited
+#[derive(Serialize, Deserialize)]
+struct VelocityLimits {
+    epoch_deals: u64,
+    max_deposits: u64,
+    max_volume: Word,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SenderVelocity {
+    epoch: u64,
+    deposits: u64,
+    volume: Word,
+}
+
+/// Set the velocity limits of the deployment, zero `epoch_deals` disabling them
+pub fn set_limits<R: Runtime>(runtime: &R, epoch_deals: &U256, max_deposits: &U256, max_volume: &U256) {
+    if epoch_deals.is_zero() {
+        return;
+    }
+    let limits = VelocityLimits {
+        epoch_deals: validation::to_u64(epoch_deals, "velocity epoch"),
+        max_deposits: validation::to_u64(max_deposits, "velocity max deposits"),
+        max_volume: Word(*max_volume),
+    };
+    runtime.write_state(VELOCITY_LIMITS, limits);
+}
+
+/// The (epoch_deals, max_deposits, max_volume) limits of the deployment, zero when disabled
+pub fn limits<R: Runtime>(runtime: &R) -> (u64, u64, U256) {
+    let limits: Option<VelocityLimits> = runtime.read_state(VELOCITY_LIMITS);
+    match limits {
+        Some(limits) => (limits.epoch_deals, limits.max_deposits, limits.max_volume.0),
+        None => (0, 0, U256::zero()),
+    }
+}
+
+fn sender_key(sender: &H160) -> String {
+    state::address_key(SENDER_VELOCITY_PREFIX, sender)
+}
+
+/// The (deposits, volume) of the sender in the epoch
+fn load<R: Runtime>(runtime: &R, sender: &H160, epoch: u64) -> (u64, U256) {
+    let velocity: Option<SenderVelocity> = runtime.read_state(&sender_key(sender));
+    match velocity {
+        Some(ref velocity) if velocity.epoch == epoch => (velocity.deposits, velocity.volume.0),
+        _ => (0, U256::zero()),
+    }
+}
+
+/// Caps the deposits of each sender in the current epoch
+pub struct VelocityLimiter {
+    epoch: u64,
+    max_deposits: u64,
+    max_volume: U256,
+    /// The (sender, deposits, volume) screened in the deal, recorded once the deal commits
+    pending: RefCell<Vec<(H160, u64, U256)>>,
+}
+
+impl<R: Runtime> ComplianceEngine<R> for VelocityLimiter {
+    fn screen_deposit(&self, runtime: &R, i: usize, sender: &H160, amount: &U256) -> Result<(), String> {
+        let mut pending = self.pending.borrow_mut();
+        let position = pending.iter().position(|(pending_sender, _, _)| pending_sender == sender);
+        let (deposits, volume) = match position {
+            Some(position) => (pending[position].1, pending[position].2),
+            None => load(runtime, sender, self.epoch),
+        };
+        let (volume, overflow) = volume.overflowing_add(*amount);
+        if self.max_deposits != 0 && deposits + 1 > self.max_deposits {
+            return Err(format!("Sender of participant {} exceeds {} deposits in epoch {}", i, self.max_deposits, self.epoch));
+        }
+        if overflow || (!self.max_volume.is_zero() && volume > self.max_volume) {
+            return Err(format!("Sender of participant {} exceeds a volume of {} in epoch {}", i, self.max_volume, self.epoch));
+        }
+        match position {
+            Some(position) => pending[position] = (*sender, deposits + 1, volume),
+            None => pending.push((*sender, deposits + 1, volume)),
+        }
+        Ok(())
+    }
+
+    fn commit(&self, runtime: &R) {
+        for (sender, deposits, volume) in self.pending.borrow().iter() {
+            trace!("Recording {} deposits of {:?} in epoch {}", deposits, sender, self.epoch);
+            let velocity = SenderVelocity { epoch: self.epoch, deposits: *deposits, volume: Word(*volume) };
+            runtime.write_state(&sender_key(sender), velocity);
+        }
+    }
+}
+
+/// The velocity limiter of the deployment in the current epoch, if limits are set
+pub fn limiter<R: Runtime>(runtime: &R) -> Option<VelocityLimiter> {
+    let limits: Option<VelocityLimits> = runtime.read_state(VELOCITY_LIMITS);
+    limits.map(|limits| VelocityLimiter {
+        epoch: metrics::nb_deals(runtime) / limits.epoch_deals,
+        max_deposits: limits.max_deposits,
+        max_volume: limits.max_volume.0,
+        pending: RefCell::new(Vec::new()),
+    })
+}
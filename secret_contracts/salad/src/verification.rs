@@ -0,0 +1,241 @@
+use eng_wasm::{String, Vec, H160, H256, U256};
+use enigma_crypto::hash::Keccak256;
+use enigma_crypto::KeyPair;
+
+use crate::checkpoint::CheckpointedDeposit;
+use crate::compliance::ComplianceEngine;
+use crate::digest::{self, DepositHasher, DepositTerms};
+use crate::protocol::{self, ADDRESS_SIZE, ENCRYPTION_OVERHEAD, PUB_KEY_SIZE};
+use crate::redeposits::StagedDeposit;
+use crate::runtime::Runtime;
+use crate::signature::{self, DepositSignature};
+use crate::state::{Address, Hash, Word};
+use crate::threshold::{self, ThresholdConfig};
+use crate::wire::DepositInput;
+use crate::{amounts, buckets, kdf, metering, metrics, parsing, relay};
+
+/// A deposit verified by the enclave
+pub struct VerifiedDeposit {
+    pub outputs: Vec<(H160, U256)>,
+    /// The (signer public key, message) to verify against the deal aggregate signature
+    pub aggregated: Option<(Vec<u8>, Vec<u8>)>,
+    /// Keccak256 digest of the typed data message signed by the depositor
+    pub digest: H256,
+    /// The share of the deposit staged back into the pool
+    pub redeposit: Option<StagedDeposit>,
+    /// The guardian holding the outputs of the deposit in escrow with their recipient
+    pub guardian: Option<H160>,
+    /// The outputs of the deposit held as notes in the enclave ledger instead of being paid out
+    pub notes: Vec<(H160, U256)>,
+    /// The pubKey the recipient notifications of the deposit are encrypted to
+    pub notification_key: Option<Vec<u8>>,
+}
+
+impl VerifiedDeposit {
+    /// A deposit unable to enter the deal, paid back to its sender in the deal outputs.
+    /// Its digest is zero as the deposit may not have been verified.
+    pub fn refund(sender: &H160, amount: &U256) -> VerifiedDeposit {
+        VerifiedDeposit {
+            outputs: vec![(*sender, *amount)],
+            aggregated: None,
+            digest: H256::zero(),
+            redeposit: None,
+            guardian: None,
+            notes: Vec::new(),
+            notification_key: None,
+        }
+    }
+
+    /// The deposit as recorded in the checkpoint of a deal executed across several tasks, its aggregated
+    /// signature being recorded apart
+    pub fn checkpoint(self) -> CheckpointedDeposit {
+        CheckpointedDeposit {
+            outputs: self.outputs.iter().map(|(recipient, amount)| (Address(*recipient), Word(*amount))).collect(),
+            digest: Hash(self.digest),
+            redeposit: self.redeposit,
+            guardian: self.guardian.map(Address),
+            notes: self.notes.iter().map(|(owner, amount)| (Address(*owner), Word(*amount))).collect(),
+            notification_key: self.notification_key,
+        }
+    }
+
+    pub fn restore(deposit: CheckpointedDeposit) -> VerifiedDeposit {
+        VerifiedDeposit {
+            outputs: deposit.outputs.iter().map(|(recipient, amount)| (recipient.0, amount.0)).collect(),
+            aggregated: None,
+            digest: deposit.digest.0,
+            redeposit: deposit.redeposit,
+            guardian: deposit.guardian.map(|guardian| guardian.0),
+            notes: deposit.notes.iter().map(|(owner, amount)| (owner.0, amount.0)).collect(),
+            notification_key: deposit.notification_key,
+        }
+    }
+}
+
+/// The newest message version under which the ECDSA signature of a deposit recovers its sender,
+/// telling the depositors rejected for running an outdated client
+pub fn message_version(input: &DepositInput, amount: &U256, user_pubkey: &[u8; PUB_KEY_SIZE], chain_id: &U256) -> u8 {
+    let signature = match signature::parse(input.signature) {
+        Ok(DepositSignature::Ecdsa(signature)) => signature,
+        _ => return metrics::UNKNOWN_VERSION,
+    };
+    protocol::MESSAGE_VERSIONS.iter().rev()
+        .find(|version| {
+            let terms = DepositTerms::Expiring(input.expiry);
+            let message = digest::versioned_deposit_message(version, input.sender, terms, amount, input.enc_recipient, user_pubkey, chain_id);
+            digest::recover_address(&message, signature).ok() == Some(*input.sender)
+        })
+        .map_or(metrics::UNKNOWN_VERSION, |version| version.version)
+}
+
+/// Verify a single decrypted deposit, returning its outputs, deferred aggregated signature and re-deposit,
+/// or the rejection reason
+pub fn verify_deposit<R: Runtime>(
+    runtime: &R,
+    engine: &dyn ComplianceEngine<R>,
+    keypair: &KeyPair,
+    hasher: &DepositHasher,
+    input: &DepositInput,
+    amount: &U256,
+    payload: ([u8; PUB_KEY_SIZE], Vec<u8>),
+) -> Result<VerifiedDeposit, String> {
+    let i = input.index;
+    let (user_pubkey, plaintext) = payload;
+    let payload = metering::measure(runtime, metering::PHASE_PARSE, || parsing::parse_payload(i, &plaintext))?;
+    let (aggregated, digest) = metering::measure(runtime, metering::PHASE_VERIFY, || verify_sender(runtime, 
+        hasher, input.signature, input.sender, DepositTerms::Expiring(input.expiry), amount, input.enc_recipient, &user_pubkey))?;
+    let notification_key = payload.notification_key().map(|pub_key| pub_key.to_vec());
+    let (payout, redeposit) = match payload.redeposit {
+        Some(redeposit) => {
+            // The re-deposit is opened now, so that it joins a later deal without further checks.
+            // Being only known to this instance, it is encrypted to this instance alone.
+            let (_, redeposit_plaintext) = open_payload(runtime, engine, None, keypair, i, &redeposit.pub_key, &redeposit.enc_recipient)?;
+            parsing::parse_splits(i, &redeposit_plaintext)?;
+            let redeposit_amount = parsing::share(amount, redeposit.percent);
+            let staged = StagedDeposit::new(&redeposit_amount, redeposit.pub_key, redeposit.enc_recipient, redeposit_plaintext);
+            (amounts::sub(amount, &redeposit_amount), Some(staged))
+        }
+        None => (*amount, None),
+    };
+    let (mut outputs, mut notes) = parsing::expand_notes(&payout, &payload.splits);
+    buckets::round(runtime, &mut outputs, &mut notes);
+    Ok(VerifiedDeposit {
+        outputs,
+        aggregated,
+        digest,
+        redeposit,
+        guardian: payload.guardian,
+        notes,
+        notification_key,
+    })
+}
+
+/// Decrypt the payload of a deposit and open its envelope with the compliance engine
+pub fn open_payload<R: Runtime>(
+    runtime: &R,
+    engine: &dyn ComplianceEngine<R>,
+    threshold: Option<&ThresholdConfig>,
+    keypair: &KeyPair,
+    i: usize,
+    pub_key: &[u8],
+    enc_recipient: &[u8],
+) -> Result<([u8; PUB_KEY_SIZE], Vec<u8>), String> {
+    let (user_pubkey, envelope) = decrypt_payload(runtime, engine, threshold, keypair, i, pub_key, enc_recipient)?;
+    let plaintext = engine.open_envelope(i, envelope, &|payload| {
+        parsing::parse_payload(i, payload).map(|payload| payload.splits.iter().map(|(recipient, _)| *recipient).collect())
+    })?;
+    Ok((user_pubkey, plaintext))
+}
+
+/// Decrypt the payload of a deposit, failing instead of aborting on the ciphertexts the runtime cannot decrypt.
+/// Payloads shared across threshold peers are combined from the partial decryptions of the peers.
+pub fn decrypt_payload<R: Runtime>(
+    runtime: &R,
+    engine: &dyn ComplianceEngine<R>,
+    threshold: Option<&ThresholdConfig>,
+    keypair: &KeyPair,
+    i: usize,
+    pub_key: &[u8],
+    enc_recipient: &[u8],
+) -> Result<([u8; PUB_KEY_SIZE], Vec<u8>), String> {
+    trace!("Decrypting recipient {}", i);
+    if pub_key.len() != PUB_KEY_SIZE {
+        return Err(format!("Invalid pubKey size for participant {}: {}", i, pub_key.len()));
+    }
+    // Threshold payloads are shared across the peers under the unsalted key
+    let (salt, enc_recipient) = match threshold {
+        Some(_) => (None, enc_recipient),
+        None => kdf::split_salt(enc_recipient),
+    };
+    let nb_shares = threshold.map_or(1, |config| config.nb_peers());
+    let payload_size = (enc_recipient.len() / nb_shares).saturating_sub(ENCRYPTION_OVERHEAD);
+    if payload_size < ADDRESS_SIZE || payload_size > protocol::envelope().max_payload_size() + engine.payload_overhead() {
+        return Err(format!("Invalid encrypted payload size for participant {}: {}", i, enc_recipient.len()));
+    }
+    let user_pubkey = {
+        let mut key = [0; PUB_KEY_SIZE];
+        key.copy_from_slice(pub_key);
+        key
+    };
+
+    let shared_key = kdf::shared_key(keypair, &user_pubkey, salt)
+        .map_err(|err| format!("Unable to derive shared key for participant {}: {}", i, err))?;
+    let plaintext = match threshold {
+        Some(config) => threshold::decrypt_payload(runtime, config, keypair, i, &shared_key, enc_recipient)?,
+        None => runtime.decrypt(enc_recipient, &shared_key)
+            .map_err(|err| format!("Unable to decrypt payload of participant {}: {}", i, err))?,
+    };
+    trace!("Successfully decrypted recipient {}", i);
+    Ok((user_pubkey, plaintext))
+}
+
+/// Verify the deposit signature of the sender according to its signature mode, returning the digest of the message.
+/// Aggregated deposits return their (signer public key, message) to verify against the deal aggregate signature.
+pub fn verify_sender<R: Runtime>(
+    runtime: &R,
+    hasher: &DepositHasher,
+    raw_signature: &[u8],
+    sender: &H160,
+    terms: DepositTerms,
+    amount: &U256,
+    enc_recipient: &[u8],
+    user_pubkey: &[u8; PUB_KEY_SIZE],
+) -> Result<(Option<(Vec<u8>, Vec<u8>)>, H256), String> {
+    let message = hasher.message(sender, terms, amount, enc_recipient, user_pubkey);
+    let digest = H256::from(&message.keccak256()[..]);
+    let (sig_sender, aggregated) = match signature::parse(raw_signature)? {
+        DepositSignature::Ecdsa(signature) => {
+            trace!("Verifying ECDSA signature");
+            (digest::recover_address(&message, signature)?, None)
+        }
+        DepositSignature::Aggregated(signer_pub_key) => {
+            trace!("Deferring aggregated signature of signer: {:?}", signer_pub_key);
+            (signature::signer_address(signer_pub_key), Some((signer_pub_key.to_vec(), message)))
+        }
+        DepositSignature::Bls { pub_key, signature } => {
+            signature::verify_bls(pub_key, &message, signature)?;
+            (signature::signer_address(pub_key), None)
+        }
+        DepositSignature::Relayed(signature) => {
+            let relayer = digest::recover_address(&message, signature)?;
+            trace!("Verifying deposit relayed by: {:?}", relayer);
+            (relay::depositor(runtime, &relayer)?, None)
+        }
+        DepositSignature::Bundled { depositor, aggregator } => {
+            // The true sender never sent a tx, it is recovered from its signature of the deposit message
+            // naming the aggregator contract, and the aggregator vouches for having bundled its deposit
+            let depositor = digest::recover_address(&message, depositor)?;
+            let aggregator = digest::recover_address(&signature::bundle_message(&digest, &depositor), aggregator)?;
+            trace!("Verifying deposit of {:?} bundled by: {:?}", depositor, aggregator);
+            (relay::depositor(runtime, &aggregator)?, None)
+        }
+    };
+    trace!("Recovered sender: {:?}", sig_sender);
+    if sig_sender != *sender {
+        return Err(format!(
+            "Invalid sender recovered from the signature: {:?} != {:?}",
+            sig_sender, sender
+        ));
+    }
+    Ok((aggregated, digest))
+}
@@ -0,0 +1,219 @@
+use salad_types::U256;
+use serde::{Deserialize, Serialize};
+
+use crate::runtime::Runtime;
+use crate::state::Word;
+
+// State key name "amount_bounds" holding the min and max deal amounts of the deployment
+static AMOUNT_BOUNDS: &str = "amount_bounds";
+// State key name "deal_value_cap" holding the max total value of a deal
+static DEAL_VALUE_CAP: &str = "deal_value_cap";
+
+/// Min and max deal amounts, a zero max being unbounded
+#[derive(Serialize, Deserialize)]
+struct AmountBounds {
+    min: Word,
+    max: Word,
+}
+
+/// Set the min and max deal amounts of the deployment, a zero max leaving the amounts unbounded
+pub fn set_bounds<R: Runtime>(runtime: &R, min: &U256, max: &U256) {
+    if !max.is_zero() && min > max {
+        panic!("Min amount above the max amount: {} > {}", min, max);
+    }
+    runtime.write_state(AMOUNT_BOUNDS, AmountBounds { min: Word(*min), max: Word(*max) });
+}
+
+/// The (min, max) deal amounts of the deployment, a zero max when unbounded
+pub fn bounds<R: Runtime>(runtime: &R) -> (U256, U256) {
+    let bounds: Option<AmountBounds> = runtime.read_state(AMOUNT_BOUNDS);
+    match bounds {
+        Some(bounds) => (bounds.min.0, bounds.max.0),
+        None => (U256::zero(), U256::zero()),
+    }
+}
+
+/// Refuse deal amounts that are zero or out of the bounds of the deployment
+pub fn check<R: Runtime>(runtime: &R, amount: &U256) {
+    let (min, max) = bounds(runtime);
+    if amount.is_zero() || *amount < min {
+        panic!("Deal amount below the min amount: {} < {}", amount, min);
+    }
+    if !max.is_zero() && *amount > max {
+        panic!("Deal amount above the max amount: {} > {}", amount, max);
+    }
+}
+
+/// Set the max total value of a deal (amount × participants), a zero cap leaving the deal values unbounded
+pub fn set_value_cap<R: Runtime>(runtime: &R, cap: &U256) {
+    runtime.write_state(DEAL_VALUE_CAP, Word(*cap));
+}
+
+/// The max total value of a deal, zero when unbounded, as for the deployments predating the cap
+pub fn value_cap<R: Runtime>(runtime: &R) -> U256 {
+    let cap: Option<Word> = runtime.read_state(DEAL_VALUE_CAP);
+    cap.map_or_else(U256::zero, |cap| cap.0)
+}
+
+/// Refuse deals whose total value is above the cap of the deployment, bounding what any single faulty
+/// or malicious distribution may pay out
+pub fn check_value<R: Runtime>(runtime: &R, amount: &U256, nb_participants: usize) {
+    let cap = value_cap(runtime);
+    let value = mul(amount, &U256::from(nb_participants));
+    if !cap.is_zero() && value > cap {
+        panic!("Deal value above the value cap: {} > {}", value, cap);
+    }
+}
+
+/// The sum of the amounts, refusing to wrap around on overflow
+pub fn add(a: &U256, b: &U256) -> U256 {
+    match a.overflowing_add(*b) {
+        (_, true) => panic!("Amount overflow: {} + {}", a, b),
+        (total, false) => total,
+    }
+}
+
+/// The difference of the amounts, refusing to wrap around on underflow
+pub fn sub(a: &U256, b: &U256) -> U256 {
+    match a.overflowing_sub(*b) {
+        (_, true) => panic!("Amount underflow: {} - {}", a, b),
+        (difference, false) => difference,
+    }
+}
+
+/// The product of the amounts, refusing to wrap around on overflow
+pub fn mul(a: &U256, b: &U256) -> U256 {
+    match a.overflowing_mul(*b) {
+        (_, true) => panic!("Amount overflow: {} * {}", a, b),
+        (product, false) => product,
+    }
+}
+
+/// The total of the amounts, refusing to wrap around on overflow
+pub fn sum<'a, I: IntoIterator<Item = &'a U256>>(amounts: I) -> U256 {
+    amounts.into_iter().fold(U256::zero(), |total, amount| add(&total, amount))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockRuntime;
+
+    #[test]
+    fn checks_the_bounds_inclusively() {
+        let runtime = MockRuntime::new(1);
+        set_bounds(&runtime, &U256::from(10), &U256::from(20));
+        assert_eq!(bounds(&runtime), (U256::from(10), U256::from(20)));
+        check(&runtime, &U256::from(10));
+        check(&runtime, &U256::from(20));
+    }
+
+    #[test]
+    #[should_panic(expected = "below the min amount")]
+    fn refuses_amounts_below_the_min() {
+        let runtime = MockRuntime::new(1);
+        set_bounds(&runtime, &U256::from(10), &U256::from(20));
+        check(&runtime, &U256::from(9));
+    }
+
+    #[test]
+    #[should_panic(expected = "above the max amount")]
+    fn refuses_amounts_above_the_max() {
+        let runtime = MockRuntime::new(1);
+        set_bounds(&runtime, &U256::from(10), &U256::from(20));
+        check(&runtime, &U256::from(21));
+    }
+
+    #[test]
+    #[should_panic(expected = "below the min amount")]
+    fn refuses_zero_amounts_when_unbounded() {
+        check(&MockRuntime::new(1), &U256::zero());
+    }
+
+    #[test]
+    fn leaves_the_amounts_unbounded_with_a_zero_max() {
+        let runtime = MockRuntime::new(1);
+        check(&runtime, &U256::max_value());
+        set_bounds(&runtime, &U256::from(10), &U256::zero());
+        check(&runtime, &U256::max_value());
+    }
+
+    #[test]
+    #[should_panic(expected = "Min amount above the max amount")]
+    fn refuses_inverted_bounds() {
+        set_bounds(&MockRuntime::new(1), &U256::from(21), &U256::from(20));
+    }
+
+    #[test]
+    fn checks_the_value_cap_inclusively() {
+        let runtime = MockRuntime::new(1);
+        assert_eq!(value_cap(&runtime), U256::zero());
+        check_value(&runtime, &U256::max_value(), 1);
+        set_value_cap(&runtime, &U256::from(300));
+        check_value(&runtime, &U256::from(100), 3);
+    }
+
+    #[test]
+    fn reads_the_bounds_and_cap_stored_in_decimal() {
+        #[derive(Serialize)]
+        struct LegacyBounds {
+            min: String,
+            max: String,
+        }
+        let runtime = MockRuntime::new(1);
+        runtime.write_state(AMOUNT_BOUNDS, LegacyBounds { min: "10".to_string(), max: "20".to_string() });
+        runtime.write_state(DEAL_VALUE_CAP, "300".to_string());
+        assert_eq!(bounds(&runtime), (U256::from(10), U256::from(20)));
+        assert_eq!(value_cap(&runtime), U256::from(300));
+    }
+
+    #[test]
+    #[should_panic(expected = "above the value cap")]
+    fn refuses_deals_above_the_value_cap() {
+        let runtime = MockRuntime::new(1);
+        set_value_cap(&runtime, &U256::from(300));
+        check_value(&runtime, &U256::from(100), 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "Amount overflow")]
+    fn refuses_deal_values_overflowing() {
+        let runtime = MockRuntime::new(1);
+        set_value_cap(&runtime, &U256::from(300));
+        check_value(&runtime, &U256::max_value(), 2);
+    }
+
+    #[test]
+    fn computes_up_to_the_boundaries() {
+        let max = U256::max_value();
+        assert_eq!(add(&(max - U256::from(1)), &U256::from(1)), max);
+        assert_eq!(sub(&U256::from(1), &U256::from(1)), U256::zero());
+        assert_eq!(mul(&(max / U256::from(2)), &U256::from(2)), max - U256::from(1));
+        assert_eq!(sum(&[U256::from(1), U256::from(2), max - U256::from(3)]), max);
+        assert_eq!(sum(&[] as &[U256]), U256::zero());
+    }
+
+    #[test]
+    #[should_panic(expected = "Amount overflow")]
+    fn refuses_overflowing_sums() {
+        add(&U256::max_value(), &U256::from(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Amount underflow")]
+    fn refuses_underflowing_differences() {
+        sub(&U256::zero(), &U256::from(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Amount overflow")]
+    fn refuses_overflowing_products() {
+        mul(&(U256::max_value() / U256::from(2) + U256::from(1)), &U256::from(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Amount overflow")]
+    fn refuses_overflowing_totals() {
+        sum(&[U256::max_value(), U256::from(1)]);
+    }
+}
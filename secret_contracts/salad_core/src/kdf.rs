@@ -0,0 +1,91 @@
+use enigma_crypto::hash::Sha256;
+use enigma_crypto::KeyPair;
+use salad_types::protocol::PUB_KEY_SIZE;
+
+use crate::runtime::{Runtime, SymmetricKey};
+
+// State key name "master_seed" holding the seed all the contract keys are derived from
+static MASTER_SEED: &str = "master_seed";
+
+/// HKDF salt and purpose labels of the derived keys, a new version deriving a new key
+static KDF_SALT: &[u8] = b"Salad KDF";
+pub static ENCRYPTION_V1: &[u8] = b"encryption/v1";
+pub static SIGNING_V1: &[u8] = b"signing/v1";
+/// HKDF context of the keys shared with the depositors of salted payloads
+pub static DEPOSIT_PAYLOAD_V1: &[u8] = b"deposit-payload/v1";
+
+/// Magic prefixing the encrypted payloads salted by their depositor, followed by the salt
+pub static SALTED_PAYLOAD_MAGIC: &[u8] = b"SALADKS1";
+pub const SALT_SIZE: usize = 16;
+
+const BLOCK_SIZE: usize = 64;
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> SymmetricKey {
+    let mut padded_key = [0_u8; BLOCK_SIZE];
+    padded_key[..key.len()].copy_from_slice(key);
+    let mut inner: Vec<u8> = padded_key.iter().map(|b| b ^ 0x36).collect();
+    inner.extend_from_slice(message);
+    let mut outer: Vec<u8> = padded_key.iter().map(|b| b ^ 0x5c).collect();
+    outer.extend_from_slice(inner.sha256().as_ref());
+    let mut mac = [0_u8; 32];
+    mac.copy_from_slice(outer.sha256().as_ref());
+    mac
+}
+
+/// HKDF-SHA256 (RFC 5869) of a single output block: HMAC(HMAC(salt, seed), info || 0x01)
+fn hkdf(salt: &[u8], seed: &[u8], info: &[u8]) -> SymmetricKey {
+    let prk = hmac_sha256(salt, seed);
+    let mut block: Vec<u8> = info.to_vec();
+    block.push(1);
+    hmac_sha256(&prk, &block)
+}
+
+/// Generate and store the master seed, once at deployment
+pub fn init<R: Runtime>(runtime: &R) {
+    let seed = runtime.generate_key();
+    runtime.write_state(MASTER_SEED, seed);
+}
+
+/// The master seed, replicated to a standby instance
+pub fn seed<R: Runtime>(runtime: &R) -> SymmetricKey {
+    runtime.read_state(MASTER_SEED).unwrap()
+}
+
+/// Replace the master seed with the seed of the instance this standby instance takes over,
+/// so that it derives the same keys
+pub fn restore<R: Runtime>(runtime: &R, seed: &[u8]) {
+    if seed.len() != 32 {
+        panic!("Invalid master seed size: {}", seed.len());
+    }
+    let mut restored: SymmetricKey = [0_u8; 32];
+    restored.copy_from_slice(seed);
+    runtime.write_state(MASTER_SEED, restored);
+}
+
+/// The key derived from the master seed for the purpose label
+fn derive_key<R: Runtime>(runtime: &R, purpose: &[u8]) -> SymmetricKey {
+    hkdf(KDF_SALT, &seed(runtime), purpose)
+}
+
+pub fn keypair<R: Runtime>(runtime: &R, purpose: &[u8]) -> KeyPair {
+    KeyPair::from_slice(&derive_key(runtime, purpose)).unwrap()
+}
+
+/// The salt of a salted encrypted payload along with its ciphertext, the legacy payloads carrying no salt
+pub fn split_salt(enc_payload: &[u8]) -> (Option<&[u8]>, &[u8]) {
+    let header_size = SALTED_PAYLOAD_MAGIC.len() + SALT_SIZE;
+    if enc_payload.len() <= header_size || !enc_payload.starts_with(SALTED_PAYLOAD_MAGIC) {
+        return (None, enc_payload);
+    }
+    (Some(&enc_payload[SALTED_PAYLOAD_MAGIC.len()..header_size]), &enc_payload[header_size..])
+}
+
+/// The key shared with a depositor: the ECDH key of its pubKey, expanded by HKDF under DEPOSIT_PAYLOAD_V1
+/// with the salt of its payload, if any, so that deposits under the same pubKey never share a key
+pub fn shared_key(keypair: &KeyPair, user_pubkey: &[u8; PUB_KEY_SIZE], salt: Option<&[u8]>) -> Result<SymmetricKey, String> {
+    let ecdh_key = keypair.derive_key(user_pubkey).map_err(|err| format!("{:?}", err))?;
+    Ok(match salt {
+        Some(salt) => hkdf(salt, &ecdh_key, DEPOSIT_PAYLOAD_V1),
+        None => ecdh_key,
+    })
+}
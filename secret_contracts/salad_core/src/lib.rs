@@ -0,0 +1,27 @@
+//! The deal core of the Salad secret contracts, built against the `Runtime` of either the Enigma runtime
+//! or the Secret Network (CosmWasm) runtime: the deposit lists and payloads, the keys, the oracle attestations,
+//! the network profiles, the output ordering and the state encodings.
+
+/// Log a line through the runtime, tagged with the spans of the runtime
+macro_rules! trace {
+    ($runtime:expr, $($arg:tt)*) => {
+        $crate::runtime::Runtime::trace($runtime, &format!($($arg)*))
+    };
+}
+
+pub mod amounts;
+pub mod kdf;
+/// In-memory runtime of the host-side tests, exported to the tests of the contracts with the "mock" feature
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+pub mod oracle;
+pub mod parsing;
+pub mod profile;
+pub mod runtime;
+pub mod shuffle;
+pub mod state;
+pub mod validation;
+pub mod wire;
+
+/// Max participants of a deal, bounded by the uint8 participation threshold of the Salad contract
+pub const MAX_PARTICIPANTS: usize = 255;
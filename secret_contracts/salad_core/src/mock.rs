@@ -0,0 +1,242 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+use aes_gcm::aead::generic_array::GenericArray;
+use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::Aes256Gcm;
+use enigma_crypto::hash::Keccak256;
+use salad_types::{H160, H256, U256};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::runtime::{Runtime, SymmetricKey};
+
+/// Size of the AES-GCM IV appended to the ciphertexts after the tag, as in the Enigma runtime
+const IV_SIZE: usize = 12;
+
+/// A Mixer callout made through the mock runtime
+#[derive(Clone, Debug, PartialEq)]
+pub struct Callout {
+    pub name: &'static str,
+    pub deal_id: Option<H256>,
+    pub recipients: Vec<H160>,
+    pub amounts: Vec<U256>,
+}
+
+impl Callout {
+    fn new(name: &'static str, deal_id: Option<&H256>) -> Callout {
+        Callout { name, deal_id: deal_id.cloned(), recipients: Vec::new(), amounts: Vec::new() }
+    }
+}
+
+/// Host-side runtime of the tests: the state is held in memory, the randomness is drawn deterministically
+/// from a seed so that the executions replay, the payloads are encrypted with AES-256-GCM in the format of
+/// the Enigma runtime, and the Mixer callouts and traces are recorded for inspection.
+pub struct MockRuntime {
+    state: RefCell<HashMap<String, Vec<u8>>>,
+    seed: u64,
+    draws: Cell<u64>,
+    pub callouts: RefCell<Vec<Callout>>,
+    pub traces: RefCell<Vec<String>>,
+}
+
+impl MockRuntime {
+    pub fn new(seed: u64) -> MockRuntime {
+        MockRuntime {
+            state: RefCell::new(HashMap::new()),
+            seed,
+            draws: Cell::new(0),
+            callouts: RefCell::new(Vec::new()),
+            traces: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// The names of the callouts made so far, in order
+    pub fn callout_names(&self) -> Vec<&'static str> {
+        self.callouts.borrow().iter().map(|callout| callout.name).collect()
+    }
+
+    fn record(&self, callout: Callout) {
+        self.callouts.borrow_mut().push(callout);
+    }
+}
+
+impl Runtime for MockRuntime {
+    fn random_seed(&self) -> u64 {
+        let mut seed = [0_u8; 8];
+        seed.copy_from_slice(&self.generate_key()[..8]);
+        u64::from_be_bytes(seed)
+    }
+
+    /// keccak256(seed || draw), each draw incrementing the counter
+    fn generate_key(&self) -> SymmetricKey {
+        let draw = self.draws.get();
+        self.draws.set(draw + 1);
+        let mut message = self.seed.to_be_bytes().to_vec();
+        message.extend_from_slice(&draw.to_be_bytes());
+        let mut key = [0_u8; 32];
+        key.copy_from_slice(&message.keccak256()[..]);
+        key
+    }
+
+    fn encrypt(&self, plaintext: &[u8], key: &SymmetricKey) -> Vec<u8> {
+        let iv = self.generate_key();
+        let cipher = Aes256Gcm::new(GenericArray::from_slice(key));
+        let mut ciphertext = cipher.encrypt(GenericArray::from_slice(&iv[..IV_SIZE]), plaintext).unwrap();
+        ciphertext.extend_from_slice(&iv[..IV_SIZE]);
+        ciphertext
+    }
+
+    fn decrypt(&self, ciphertext: &[u8], key: &SymmetricKey) -> Result<Vec<u8>, String> {
+        if ciphertext.len() < IV_SIZE {
+            return Err(format!("Ciphertext too short: {}", ciphertext.len()));
+        }
+        let (ciphertext, iv) = ciphertext.split_at(ciphertext.len() - IV_SIZE);
+        let cipher = Aes256Gcm::new(GenericArray::from_slice(key));
+        cipher.decrypt(GenericArray::from_slice(iv), ciphertext).map_err(|_| "Ciphertext failing authentication".to_string())
+    }
+
+    fn trace(&self, line: &str) {
+        self.traces.borrow_mut().push(line.to_string());
+    }
+
+    fn cost_counter(&self) -> Option<u64> {
+        None
+    }
+
+    fn read_state<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.state.borrow().get(key).map(|value| serde_json::from_slice(value).unwrap())
+    }
+
+    fn write_state<T: Serialize>(&self, key: &str, value: T) {
+        self.state.borrow_mut().insert(key.to_string(), serde_json::to_vec(&value).unwrap());
+    }
+
+    fn commit_roots(&self, _mixer_eth_addr: &str, deal_id: &H256, _participants_root: &H256, _outputs_root: &H256) {
+        self.record(Callout::new("commitRoots", Some(deal_id)));
+    }
+
+    fn distribute(
+        &self,
+        _mixer_eth_addr: &str,
+        deal_id: &H256,
+        recipients: Vec<H160>,
+        amounts: Vec<U256>,
+        _redeposited: U256,
+        _redeemed: U256,
+        _receipt_hash: &H256,
+    ) {
+        self.record(Callout { recipients, amounts, ..Callout::new("distribute", Some(deal_id)) });
+    }
+
+    fn distribute_with_escrow(
+        &self,
+        _mixer_eth_addr: &str,
+        deal_id: &H256,
+        recipients: Vec<H160>,
+        _guardians: Vec<H160>,
+        amounts: Vec<U256>,
+        _redeposited: U256,
+        _redeemed: U256,
+        _receipt_hash: &H256,
+    ) {
+        self.record(Callout { recipients, amounts, ..Callout::new("distributeWithEscrow", Some(deal_id)) });
+    }
+
+    fn distribute_assets(
+        &self,
+        _mixer_eth_addr: &str,
+        deal_id: &H256,
+        _assets: Vec<H160>,
+        _payees: Vec<H160>,
+        recipients: Vec<H160>,
+        amounts: Vec<U256>,
+        _receipt_hash: &H256,
+    ) {
+        self.record(Callout { recipients, amounts, ..Callout::new("distributeAssets", Some(deal_id)) });
+    }
+
+    fn distribute_to_bridge(
+        &self,
+        _mixer_eth_addr: &str,
+        deal_id: &H256,
+        recipients: Vec<H160>,
+        amounts: Vec<U256>,
+        _redeposited: U256,
+        _redeemed: U256,
+        _receipt_hash: &H256,
+    ) {
+        self.record(Callout { recipients, amounts, ..Callout::new("distributeToBridge", Some(deal_id)) });
+    }
+
+    fn set_payout_bridge(&self, _mixer_eth_addr: &str, bridge: &H160) {
+        self.record(Callout { recipients: vec![*bridge], ..Callout::new("setPayoutBridge", None) });
+    }
+
+    fn record_receipt(&self, _mixer_eth_addr: &str, deal_id: &H256, _receipt_hash: &H256) {
+        self.record(Callout::new("recordReceipt", Some(deal_id)));
+    }
+
+    fn refund_deal(&self, _mixer_eth_addr: &str, deal_id: &H256) {
+        self.record(Callout::new("refundDeal", Some(deal_id)));
+    }
+
+    fn commit_note_payouts(&self, _mixer_eth_addr: &str, deal_id: &H256, _payouts_root: &H256, _nb_payouts: usize) {
+        self.record(Callout::new("commitNotePayouts", Some(deal_id)));
+    }
+
+    fn withdraw_note(
+        &self,
+        _mixer_eth_addr: &str,
+        deal_id: &H256,
+        _nullifier: &H256,
+        payout: &H160,
+        amount: &U256,
+        _index: usize,
+        _proof: &[H256],
+    ) {
+        self.record(Callout { recipients: vec![*payout], amounts: vec![*amount], ..Callout::new("withdrawNote", Some(deal_id)) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_replay_from_the_seed() {
+        let (runtime, replay) = (MockRuntime::new(7), MockRuntime::new(7));
+        let keys: Vec<SymmetricKey> = (0..3).map(|_| runtime.generate_key()).collect();
+        assert_eq!(keys, (0..3).map(|_| replay.generate_key()).collect::<Vec<SymmetricKey>>());
+        assert_ne!(keys[0], keys[1]);
+        assert_ne!(MockRuntime::new(8).generate_key(), keys[0]);
+    }
+
+    #[test]
+    fn decrypts_what_it_encrypts() {
+        let runtime = MockRuntime::new(1);
+        let key = runtime.generate_key();
+        let ciphertext = runtime.encrypt(b"recipient", &key);
+        assert_eq!(ciphertext.len(), b"recipient".len() + salad_types::protocol::ENCRYPTION_OVERHEAD);
+        assert_eq!(runtime.decrypt(&ciphertext, &key), Ok(b"recipient".to_vec()));
+    }
+
+    #[test]
+    fn refuses_unauthenticated_ciphertexts() {
+        let runtime = MockRuntime::new(1);
+        let key = runtime.generate_key();
+        let mut ciphertext = runtime.encrypt(b"recipient", &key);
+        ciphertext[0] ^= 1;
+        assert!(runtime.decrypt(&ciphertext, &key).is_err());
+        assert!(runtime.decrypt(&ciphertext[..4], &key).is_err());
+        assert!(runtime.decrypt(&runtime.encrypt(b"recipient", &key), &runtime.generate_key()).is_err());
+    }
+
+    #[test]
+    fn round_trips_the_state() {
+        let runtime = MockRuntime::new(1);
+        assert_eq!(runtime.read_state::<u64>("counter"), None);
+        runtime.write_state("counter", 3_u64);
+        assert_eq!(runtime.read_state::<u64>("counter"), Some(3));
+    }
+}
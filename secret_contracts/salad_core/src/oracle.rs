@@ -0,0 +1,292 @@
+use rustc_hex::ToHex;
+use salad_types::protocol::SIG_SIZE;
+use salad_types::{convert, digest, domains, H160, U256};
+
+use crate::profile;
+use crate::runtime::Runtime;
+use crate::state::{self, Address, Word};
+use crate::validation;
+use crate::MAX_PARTICIPANTS;
+
+// State key name "oracle_signers" holding the eth addresses allowed to sign the oracle attestations
+static ORACLE_SIGNERS: &str = "oracle_signers";
+// State key prefix of the last accepted oracle round of each asset
+static ORACLE_ROUND_PREFIX: &str = "oracle_round_";
+// State key prefix of the last consumed nonce of the oracle commands of each domain, followed by the hex domain
+static ORACLE_NONCE_PREFIX: &str = "oracle_nonce_";
+
+/// Size of an attested round: asset (padded), roundId, answer, updatedAt
+const ROUND_SIZE: usize = 128;
+/// Size of an attested deposit block: sender (padded), confirmation block number
+const DEPOSIT_BLOCK_SIZE: usize = 64;
+
+/// Chainlink round data of an asset USD price, relayed by the operator
+pub struct PriceRound {
+    pub asset: H160,
+    pub round_id: U256,
+    pub price: U256,
+    pub updated_at: U256,
+}
+
+pub fn set_signers<R: Runtime>(runtime: &R, signers: &[H160]) {
+    let signers: Vec<Address> = signers.iter().cloned().map(Address).collect();
+    runtime.write_state(ORACLE_SIGNERS, signers);
+}
+
+fn get_signers<R: Runtime>(runtime: &R) -> Vec<Address> {
+    runtime.read_state(ORACLE_SIGNERS).unwrap_or_default()
+}
+
+pub fn signers<R: Runtime>(runtime: &R) -> Vec<H160> {
+    get_signers(runtime).into_iter().map(|signer| signer.0).collect()
+}
+
+fn get_last_round<R: Runtime>(runtime: &R, asset: &H160) -> U256 {
+    let key = state::address_key(ORACLE_ROUND_PREFIX, asset);
+    let round_id: Option<Word> = runtime.read_state(&key);
+    round_id.map_or_else(U256::zero, |round_id| round_id.0)
+}
+
+fn set_last_round<R: Runtime>(runtime: &R, asset: &H160, round_id: &U256) {
+    let key = state::address_key(ORACLE_ROUND_PREFIX, asset);
+    runtime.write_state(&key, Word(*round_id));
+}
+
+/// Parse the concatenated 32-byte words of each attested round
+fn parse_rounds(attestation: &[u8]) -> Vec<PriceRound> {
+    if attestation.is_empty() || attestation.len() % ROUND_SIZE != 0 {
+        panic!("Invalid price attestation size: {}", attestation.len());
+    }
+    attestation.chunks(ROUND_SIZE).map(|round| PriceRound {
+        asset: convert::word_to_address(&round[0..32]),
+        round_id: convert::word_to_uint(&round[32..64]),
+        price: convert::word_to_uint(&round[64..96]),
+        updated_at: convert::word_to_uint(&round[96..128]),
+    }).collect()
+}
+
+/// Verify the attestation signed, as an EIP191 personal message of H(domain || attestation), by an allowed oracle
+/// signer, the domain of each kind of attestation keeping an attestation of one kind from passing for another
+pub fn verify_signer<R: Runtime>(runtime: &R, domain: &[u8], attestation: &[u8], raw_signature: &[u8]) {
+    let kind = String::from_utf8_lossy(domain);
+    let message = domains::domain_message(domain, attestation);
+
+    if raw_signature.len() != SIG_SIZE {
+        panic!("Invalid {} attestation signature size: {}", kind, raw_signature.len());
+    }
+    let mut signature = [0; SIG_SIZE];
+    signature.copy_from_slice(raw_signature);
+    let signer = digest::recover_address(&message, signature)
+        .unwrap_or_else(|err| panic!("Invalid {} attestation signature: {}", kind, err));
+    if !get_signers(runtime).contains(&Address(signer)) {
+        panic!("{} attestation signer not allowed: {:?}", kind, signer);
+    }
+}
+
+fn nonce_key(domain: &[u8]) -> String {
+    format!("{}{}", ORACLE_NONCE_PREFIX, domain.to_hex::<String>())
+}
+
+/// Verify the command attestation signed by an allowed oracle signer under its domain and return its body,
+/// the attestation holding the nonce of the command followed by the body. The oracle signers number the
+/// commands of each domain in increasing order, a nonce not above the last one consumed in the domain being
+/// rejected, so that a command is carried out once and an older command is never replayed over a newer one.
+pub fn verify_command<'a, R: Runtime>(runtime: &R, domain: &[u8], attestation: &'a [u8], raw_signature: &[u8]) -> &'a [u8] {
+    if attestation.len() < 32 {
+        panic!("Invalid {} attestation size: {}", String::from_utf8_lossy(domain), attestation.len());
+    }
+    verify_signer(runtime, domain, attestation, raw_signature);
+    let nonce = convert::word_to_uint(&attestation[0..32]);
+    let key = nonce_key(domain);
+    let last_nonce: Option<Word> = runtime.read_state(&key);
+    if let Some(Word(last_nonce)) = last_nonce {
+        if nonce <= last_nonce {
+            panic!("{} attestation already consumed: nonce {} <= {}", String::from_utf8_lossy(domain), nonce, last_nonce);
+        }
+    }
+    runtime.write_state(&key, Word(nonce));
+    &attestation[32..]
+}
+
+/// Verify the price attestation signed by an allowed oracle signer and return its rounds.
+/// Rounds older than the last accepted round of their asset are rejected to prevent replays. The last round
+/// itself is accepted again: it attests the current price of the asset until the feed publishes the next
+/// round, which may take longer than between two deals, so that replaying it only prices a deal as the
+/// next attestation of the oracle signers would.
+pub fn verify_price_attestation<R: Runtime>(runtime: &R, attestation: &[u8], raw_signature: &[u8]) -> Vec<PriceRound> {
+    let rounds = parse_rounds(attestation);
+    verify_signer(runtime, domains::ORACLE_PRICE_DOMAIN, attestation, raw_signature);
+    for round in rounds.iter() {
+        if round.price.is_zero() {
+            panic!("Invalid zero price for asset: {:?}", round.asset);
+        }
+        let last_round = get_last_round(runtime, &round.asset);
+        if round.round_id < last_round {
+            panic!("Stale oracle round for asset {:?}: {} < {}", round.asset, round.round_id, last_round);
+        }
+        trace!(runtime, "Accepted oracle round {} for asset {:?} updated at {}", round.round_id, round.asset, round.updated_at);
+        set_last_round(runtime, &round.asset, &round.round_id);
+    }
+    rounds
+}
+
+pub fn get_price(rounds: &[PriceRound], asset: &H160) -> U256 {
+    match rounds.iter().find(|round| &round.asset == asset) {
+        Some(round) => round.price,
+        None => panic!("No attested price for asset: {:?}", asset),
+    }
+}
+
+/// Verify the block attestation signed by an allowed oracle signer and return the attested block number along
+/// with the confirmation block number of each deposit. The attestation holds the attested block number followed,
+/// for each sender in deal order, by the sender (padded) and the block number confirming its deposit.
+/// Block attestations attest facts rather than commands and carry no nonce: a replayed attestation attests
+/// an earlier block, which matures no more deposits, and the contracts refuse the blocks older than the
+/// latest one attested before scheduling, executing or timing out a deal.
+pub fn verify_block_attestation<R: Runtime>(runtime: &R, attestation: &[u8], raw_signature: &[u8], senders: &[H160]) -> (U256, Vec<U256>) {
+    // The deposit lists are checked later, the size of the attestation being bounded first
+    if senders.len() > MAX_PARTICIPANTS {
+        panic!("Too many participants: {} > {}", senders.len(), MAX_PARTICIPANTS);
+    }
+    if attestation.len() != 32 + senders.len() * DEPOSIT_BLOCK_SIZE {
+        panic!("Invalid block attestation size: {}", attestation.len());
+    }
+    verify_signer(runtime, domains::ORACLE_BLOCK_DOMAIN, attestation, raw_signature);
+    let attested_block = convert::word_to_uint(&attestation[0..32]);
+    let deposit_blocks = attestation[32..].chunks(DEPOSIT_BLOCK_SIZE).zip(senders.iter()).map(|(entry, sender)| {
+        let entry_sender = convert::word_to_address(&entry[0..32]);
+        if entry_sender != *sender {
+            panic!("Block attestation entry for {:?} instead of {:?}", entry_sender, sender);
+        }
+        convert::word_to_uint(&entry[32..64])
+    }).collect();
+    trace!(runtime, "Accepted block attestation at block {}", attested_block);
+    (attested_block, deposit_blocks)
+}
+
+/// The block attested by an oracle signer, along with the deposits it attests were confirmed less than
+/// the min deposit age of the network profile before
+pub fn immature_deposits<R: Runtime>(runtime: &R, block_attestation: &[u8], block_signature: &[u8], senders: &[H160]) -> (u64, Vec<usize>) {
+    let (attested_block, deposit_blocks) = verify_block_attestation(runtime, block_attestation, block_signature, senders);
+    let immature = deposit_blocks.iter().enumerate()
+        .filter(|(_, block)| attested_block < **block || attested_block - **block < U256::from(profile::get(runtime).min_deposit_age))
+        .map(|(i, _)| i)
+        .collect();
+    (validation::to_u64(&attested_block, "attested block"), immature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockRuntime;
+    use enigma_crypto::hash::Keccak256;
+    use enigma_crypto::KeyPair;
+
+    fn oracle() -> KeyPair {
+        KeyPair::from_slice(&b"oracle".keccak256()[..]).unwrap()
+    }
+
+    fn runtime() -> MockRuntime {
+        let runtime = MockRuntime::new(1);
+        set_signers(&runtime, &[H160::from(&oracle().get_pubkey().keccak256()[12..32])]);
+        runtime
+    }
+
+    fn sign(domain: &[u8], attestation: &[u8]) -> Vec<u8> {
+        oracle().sign(&domains::domain_message(domain, attestation)).unwrap().to_vec()
+    }
+
+    fn command(nonce: u64, body: &[u8]) -> Vec<u8> {
+        let mut attestation = convert::uint_to_word(&U256::from(nonce)).to_vec();
+        attestation.extend_from_slice(body);
+        attestation
+    }
+
+    fn price(round_id: u64) -> Vec<u8> {
+        let mut attestation = convert::address_to_word(&H160::from(&[0x11; 20][..])).to_vec();
+        for value in [round_id, 2000, 1_600_000_000].iter() {
+            attestation.extend_from_slice(&convert::uint_to_word(&U256::from(*value)));
+        }
+        attestation
+    }
+
+    #[test]
+    fn accepts_the_attestations_signed_under_their_domain() {
+        let runtime = runtime();
+        verify_signer(&runtime, domains::ORACLE_OPERATOR_DOMAIN, b"attested", &sign(domains::ORACLE_OPERATOR_DOMAIN, b"attested"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Salad Oracle Mixer attestation signer not allowed")]
+    fn rejects_the_attestations_signed_under_another_domain() {
+        let runtime = runtime();
+        let word = convert::uint_to_word(&U256::from(7));
+        verify_signer(&runtime, domains::ORACLE_MIXER_DOMAIN, &word, &sign(domains::ORACLE_BLOCK_DOMAIN, &word));
+    }
+
+    #[test]
+    fn returns_the_body_of_the_commands_in_nonce_order() {
+        let runtime = runtime();
+        let domain = domains::ORACLE_VALUE_CAP_DOMAIN;
+        for nonce in [0, 1, 5].iter() {
+            let attestation = command(*nonce, b"body");
+            assert_eq!(verify_command(&runtime, domain, &attestation, &sign(domain, &attestation)), &b"body"[..]);
+        }
+        // The nonces of each domain are consumed apart
+        let attestation = command(1, b"body");
+        verify_command(&runtime, domains::ORACLE_ANOMALY_DOMAIN, &attestation, &sign(domains::ORACLE_ANOMALY_DOMAIN, &attestation));
+    }
+
+    #[test]
+    #[should_panic(expected = "Salad Oracle Value Cap attestation already consumed: nonce 3 <= 3")]
+    fn rejects_the_replayed_commands() {
+        let runtime = runtime();
+        let domain = domains::ORACLE_VALUE_CAP_DOMAIN;
+        let attestation = command(3, b"body");
+        let signature = sign(domain, &attestation);
+        verify_command(&runtime, domain, &attestation, &signature);
+        verify_command(&runtime, domain, &attestation, &signature);
+    }
+
+    #[test]
+    #[should_panic(expected = "Salad Oracle Value Cap attestation already consumed: nonce 2 <= 3")]
+    fn rejects_the_commands_older_than_the_last_consumed() {
+        let runtime = runtime();
+        let domain = domains::ORACLE_VALUE_CAP_DOMAIN;
+        for nonce in [3, 2].iter() {
+            let attestation = command(*nonce, b"body");
+            verify_command(&runtime, domain, &attestation, &sign(domain, &attestation));
+        }
+    }
+
+    #[test]
+    fn accepts_the_last_price_round_again() {
+        let runtime = runtime();
+        for round_id in [4, 4, 5].iter() {
+            let attestation = price(*round_id);
+            let rounds = verify_price_attestation(&runtime, &attestation, &sign(domains::ORACLE_PRICE_DOMAIN, &attestation));
+            assert_eq!(rounds[0].round_id, U256::from(*round_id));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Stale oracle round")]
+    fn reads_the_last_round_stored_in_decimal() {
+        let runtime = runtime();
+        let asset = H160::from(&[0x11; 20][..]);
+        runtime.write_state(&state::address_key(ORACLE_ROUND_PREFIX, &asset), "5".to_string());
+        assert_eq!(get_last_round(&runtime, &asset), U256::from(5));
+        let attestation = price(4);
+        verify_price_attestation(&runtime, &attestation, &sign(domains::ORACLE_PRICE_DOMAIN, &attestation));
+    }
+
+    #[test]
+    #[should_panic(expected = "Stale oracle round")]
+    fn rejects_the_price_rounds_older_than_the_last_accepted() {
+        let runtime = runtime();
+        for round_id in [5, 4].iter() {
+            let attestation = price(*round_id);
+            verify_price_attestation(&runtime, &attestation, &sign(domains::ORACLE_PRICE_DOMAIN, &attestation));
+        }
+    }
+}
@@ -0,0 +1,404 @@
+use enigma_crypto::hash::Keccak256;
+use salad_types::protocol::{self, ADDRESS_SIZE, PUB_KEY_SIZE};
+use salad_types::{H160, U256};
+
+use crate::amounts;
+use crate::wire::{self, Reader};
+
+/// Flag of the split percentages whose output is held as a note in the enclave ledger, in v4 envelopes
+pub const NOTE_FLAG: u8 = 0x80;
+
+/// Whether the split percentage is flagged with NOTE_FLAG
+pub fn is_note(percent: u8) -> bool {
+    percent & NOTE_FLAG != 0
+}
+
+/// Parse the recipient splits of a decrypted payload: either a single recipient address receiving
+/// the full amount, or up to `max_splits` (recipient address, percentage) entries summing to 100%,
+/// each percentage possibly flagged with NOTE_FLAG
+pub fn parse_splits(i: usize, plaintext: &[u8]) -> Result<Vec<(H160, u8)>, String> {
+    if plaintext.len() == ADDRESS_SIZE {
+        return Ok(vec![(H160::from(plaintext), 100)]);
+    }
+    let envelope = protocol::envelope();
+    let nb_splits = plaintext.len() / envelope.split_size;
+    if plaintext.len() % envelope.split_size != 0 || nb_splits == 0 || nb_splits > envelope.max_splits {
+        return Err(format!("Invalid recipient splits size for participant {}: {}", i, plaintext.len()));
+    }
+    let splits: Vec<(H160, u8)> = wire::splits(plaintext, envelope.split_size)
+        .map(|split| (split.recipient, split.percent))
+        .collect();
+    if splits.iter().any(|(_, percent)| *percent & !NOTE_FLAG == 0) {
+        return Err(format!("Invalid zero percent split for participant {}", i));
+    }
+    if !envelope.notes && splits.iter().any(|(_, percent)| is_note(*percent)) {
+        return Err(format!("Notes not supported for participant {}", i));
+    }
+    let total: u32 = splits.iter().map(|(_, percent)| (*percent & !NOTE_FLAG) as u32).sum();
+    if total != 100 {
+        return Err(format!("Recipient splits of participant {} do not sum to 100%: {}", i, total));
+    }
+    Ok(splits)
+}
+
+/// A share of a deposit staged back into the pool as a fresh deposit, with its own pubKey and encrypted splits
+pub struct Redeposit {
+    pub percent: u8,
+    pub pub_key: Vec<u8>,
+    pub enc_recipient: Vec<u8>,
+}
+
+/// Flag of the splits size byte of the envelopes designating a guardian
+pub const GUARDIAN_FLAG: u8 = 0x80;
+
+/// First byte of the v5 envelopes prefixed with a TLV extension area, never a valid splits size
+pub const EXTENSIONS_MARKER: u8 = 0x40;
+/// Extension types: padding entries are skipped, so that short envelopes outgrow the largest splits payload.
+/// Unknown types are skipped as well, unless flagged with EXTENSION_CRITICAL, rejecting the deposit instead.
+pub const EXTENSION_PADDING: u8 = 0x00;
+pub const EXTENSION_CRITICAL: u8 = 0x80;
+/// The pubKey of the recipient notifications of the deposit, encrypted to it after the deal executes
+pub const EXTENSION_NOTIFICATION: u8 = 0x01;
+/// The extension types known to this version, besides padding
+pub static KNOWN_EXTENSIONS: &[u8] = &[EXTENSION_NOTIFICATION];
+
+/// A decrypted deposit payload
+pub struct Payload {
+    pub splits: Vec<(H160, u8)>,
+    pub redeposit: Option<Redeposit>,
+    /// Address of the guardian releasing the outputs of the deposit along with their recipient, if any
+    pub guardian: Option<H160>,
+    /// The (type, value) of the known extensions of the envelope, in order
+    pub extensions: Vec<(u8, Vec<u8>)>,
+}
+
+impl Payload {
+    /// The pubKey the recipient notifications of the deposit are encrypted to, if any
+    pub fn notification_key(&self) -> Option<&[u8]> {
+        self.extensions.iter().find(|(kind, _)| *kind == EXTENSION_NOTIFICATION).map(|(_, value)| &value[..])
+    }
+}
+
+/// Parse a TLV extension area: entries of a type (1 byte), a value size (1 byte) and the value
+pub fn parse_extensions(i: usize, area: &[u8]) -> Result<Vec<(u8, Vec<u8>)>, String> {
+    let mut extensions: Vec<(u8, Vec<u8>)> = Vec::new();
+    let mut reader = Reader::new(area);
+    while let Some(kind) = reader.byte() {
+        let truncated = || format!("Truncated extension {} for participant {}", kind, i);
+        let size = reader.byte().ok_or_else(truncated)?;
+        let value = reader.bytes(size as usize).ok_or_else(truncated)?;
+        match kind {
+            EXTENSION_NOTIFICATION if value.len() != PUB_KEY_SIZE => {
+                return Err(format!("Invalid notification pubKey size for participant {}: {}", i, value.len()));
+            }
+            _ if KNOWN_EXTENSIONS.contains(&kind) => extensions.push((kind, value.to_vec())),
+            _ if kind & EXTENSION_CRITICAL != 0 => {
+                return Err(format!("Unknown critical extension {} for participant {}", kind, i));
+            }
+            // Padding and the extensions of later versions
+            _ => {}
+        }
+    }
+    Ok(extensions)
+}
+
+/// Parse the recipient splits of a decrypted payload along with its re-deposit, guardian and extensions, if any.
+/// Payloads longer than the splits are v2 envelopes: the splits size (1 byte), the splits,
+/// the re-deposited percentage (1 byte), the re-deposit pubKey and its encrypted splits.
+/// In v3 envelopes, the splits size is flagged with GUARDIAN_FLAG when the pubKey of a guardian
+/// follows the splits, the re-deposit being optional.
+/// In v5 envelopes, EXTENSIONS_MARKER, the extension area size (2 bytes, big-endian) and the extension area
+/// prefix any of the above payloads, padded so that the envelope is longer than the largest splits payload.
+pub fn parse_payload(i: usize, plaintext: &[u8]) -> Result<Payload, String> {
+    let envelope = protocol::envelope();
+    if plaintext.len() <= envelope.max_splits_size() || plaintext[0] != EXTENSIONS_MARKER || envelope.max_extensions_size == 0 {
+        return parse_envelope(i, plaintext);
+    }
+    let truncated = || format!("Truncated extension area for participant {}", i);
+    let mut reader = Reader::new(&plaintext[1..]);
+    let size = reader.bytes(2).ok_or_else(truncated)?;
+    let size = (size[0] as usize) << 8 | size[1] as usize;
+    if size > envelope.max_extensions_size {
+        return Err(format!("Extension area too large for participant {}: {}", i, size));
+    }
+    let extensions = parse_extensions(i, reader.bytes(size).ok_or_else(truncated)?)?;
+    let payload = parse_envelope(i, reader.rest())?;
+    Ok(Payload { extensions, ..payload })
+}
+
+/// Parse a payload without extension area
+fn parse_envelope(i: usize, plaintext: &[u8]) -> Result<Payload, String> {
+    if plaintext.len() <= protocol::envelope().max_splits_size() {
+        return Ok(Payload { splits: parse_splits(i, plaintext)?, redeposit: None, guardian: None, extensions: Vec::new() });
+    }
+    let truncated = || format!("Truncated envelope for participant {}", i);
+    let mut reader = Reader::new(plaintext);
+    let size = reader.byte().ok_or_else(truncated)?;
+    let guarded = size & GUARDIAN_FLAG != 0;
+    let splits = parse_splits(i, reader.bytes((size & !GUARDIAN_FLAG) as usize).ok_or_else(truncated)?)?;
+    let guardian = if guarded {
+        let guardian_pub_key = reader.bytes(PUB_KEY_SIZE).ok_or_else(truncated)?;
+        Some(H160::from(&guardian_pub_key.keccak256()[12..32]))
+    } else {
+        None
+    };
+    if guarded && reader.rest().is_empty() {
+        return Ok(Payload { splits, redeposit: None, guardian, extensions: Vec::new() });
+    }
+    let truncated_redeposit = || format!("Truncated re-deposit envelope for participant {}", i);
+    let percent = reader.byte().ok_or_else(truncated_redeposit)?;
+    let pub_key = reader.bytes(PUB_KEY_SIZE).ok_or_else(truncated_redeposit)?;
+    if percent == 0 || percent >= 100 {
+        return Err(format!("Invalid re-deposit percent for participant {}: {}", i, percent));
+    }
+    Ok(Payload {
+        splits,
+        redeposit: Some(Redeposit {
+            percent,
+            pub_key: pub_key.to_vec(),
+            enc_recipient: reader.rest().to_vec(),
+        }),
+        guardian,
+        extensions: Vec::new(),
+    })
+}
+
+/// The oldest envelope version with the shape of a decrypted payload, regardless of its validity:
+/// a bare recipient address (v0), splits or an asset payload (v1), a re-deposit (v2), a guardian (v3)
+/// or an extension area (v5)
+pub fn envelope_version(plaintext: &[u8]) -> u8 {
+    match plaintext.first() {
+        _ if plaintext.len() == ADDRESS_SIZE => 0,
+        _ if plaintext.len() <= protocol::envelope().max_splits_size() => 1,
+        Some(&EXTENSIONS_MARKER) => 5,
+        Some(size) if size & GUARDIAN_FLAG != 0 => 3,
+        _ => 2,
+    }
+}
+
+/// The percentage share of the amount
+pub fn share(amount: &U256, percent: u8) -> U256 {
+    let percent = U256::from(percent);
+    // The share of the remainder is below 100 * 255, only the share of the hundredths may overflow
+    amounts::add(&amounts::mul(&(*amount / U256::from(100)), &percent), &(*amount % U256::from(100) * percent / U256::from(100)))
+}
+
+/// Expand the splits into outputs, the last split receiving the rounding remainder
+pub fn expand_splits(amount: &U256, splits: &[(H160, u8)]) -> Vec<(H160, U256)> {
+    let mut outputs: Vec<(H160, U256)> = Vec::new();
+    let mut remainder = *amount;
+    for (j, (recipient, percent)) in splits.iter().enumerate() {
+        let split_amount = if j == splits.len() - 1 {
+            remainder
+        } else {
+            share(amount, *percent & !NOTE_FLAG)
+        };
+        remainder = amounts::sub(&remainder, &split_amount);
+        outputs.push((*recipient, split_amount));
+    }
+    outputs
+}
+
+/// Expand the splits like `expand_splits`, returning apart the outputs held as notes
+pub fn expand_notes(amount: &U256, splits: &[(H160, u8)]) -> (Vec<(H160, U256)>, Vec<(H160, U256)>) {
+    let (mut outputs, mut notes) = (Vec::new(), Vec::new());
+    for ((_, percent), output) in splits.iter().zip(expand_splits(amount, splits)) {
+        if is_note(*percent) {
+            notes.push(output);
+        } else {
+            outputs.push(output);
+        }
+    }
+    (outputs, notes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address(byte: u8) -> H160 {
+        H160::from(&[byte; ADDRESS_SIZE][..])
+    }
+
+    fn splits(splits: &[(u8, u8)]) -> Vec<u8> {
+        splits.iter().flat_map(|(byte, percent)| {
+            let mut split = vec![*byte; ADDRESS_SIZE];
+            split.push(*percent);
+            split
+        }).collect()
+    }
+
+    #[test]
+    fn parses_a_bare_recipient() {
+        assert_eq!(parse_splits(0, &[0x11; ADDRESS_SIZE]), Ok(vec![(address(0x11), 100)]));
+    }
+
+    #[test]
+    fn parses_splits_summing_to_100() {
+        assert_eq!(parse_splits(0, &splits(&[(0x11, 60), (0x22, 40)])), Ok(vec![(address(0x11), 60), (address(0x22), 40)]));
+        assert_eq!(parse_splits(0, &splits(&[(0x11, 50), (0x22, NOTE_FLAG | 50)])), Ok(vec![(address(0x11), 50), (address(0x22), NOTE_FLAG | 50)]));
+    }
+
+    #[test]
+    fn refuses_invalid_splits() {
+        assert!(parse_splits(0, &splits(&[(0x11, 60), (0x22, 30)])).is_err());
+        assert!(parse_splits(0, &splits(&[(0x11, 100), (0x22, 0)])).is_err());
+        assert!(parse_splits(0, &splits(&[(0x11, 100), (0x22, NOTE_FLAG)])).is_err());
+        assert!(parse_splits(0, &splits(&[(0x11, 20); 5])).is_err());
+        assert!(parse_splits(0, &splits(&[(0x11, 100)])[..ADDRESS_SIZE - 1]).is_err());
+        assert!(parse_splits(0, &[]).is_err());
+    }
+
+    #[test]
+    fn parses_a_redeposit_envelope() {
+        let mut plaintext = vec![2 * 21];
+        plaintext.extend(splits(&[(0x11, 60), (0x22, 40)]));
+        plaintext.push(10);
+        plaintext.extend_from_slice(&[0x44; PUB_KEY_SIZE]);
+        plaintext.extend_from_slice(&[0x55; 48]);
+        let payload = parse_payload(0, &plaintext).unwrap();
+        assert_eq!(payload.splits, vec![(address(0x11), 60), (address(0x22), 40)]);
+        let redeposit = payload.redeposit.unwrap();
+        assert_eq!((redeposit.percent, redeposit.pub_key, redeposit.enc_recipient), (10, vec![0x44; PUB_KEY_SIZE], vec![0x55; 48]));
+        assert_eq!(payload.guardian, None);
+        assert_eq!(envelope_version(&plaintext), 2);
+    }
+
+    #[test]
+    fn parses_a_guarded_envelope() {
+        let mut plaintext = vec![GUARDIAN_FLAG | 21];
+        plaintext.extend(splits(&[(0x11, 100)]));
+        plaintext.extend_from_slice(&[0x44; PUB_KEY_SIZE]);
+        let payload = parse_payload(0, &plaintext).unwrap();
+        assert_eq!(payload.guardian, Some(H160::from(&[0x44_u8; PUB_KEY_SIZE][..].keccak256()[12..32])));
+        assert!(payload.redeposit.is_none());
+        assert_eq!(envelope_version(&plaintext), 3);
+    }
+
+    #[test]
+    fn refuses_truncated_envelopes() {
+        let mut plaintext = vec![2 * 21];
+        plaintext.extend(splits(&[(0x11, 60), (0x22, 40)]));
+        plaintext.push(10);
+        plaintext.extend_from_slice(&[0x44; PUB_KEY_SIZE - 1]);
+        assert!(parse_payload(0, &plaintext).is_err());
+        assert!(parse_payload(0, &plaintext[..2 * 21 + 1]).is_err());
+    }
+
+    #[test]
+    fn refuses_invalid_redeposit_percents() {
+        for percent in [0, 100].iter() {
+            let mut plaintext = vec![21];
+            plaintext.extend(splits(&[(0x11, 100)]));
+            plaintext.push(*percent);
+            plaintext.extend_from_slice(&[0x44; PUB_KEY_SIZE]);
+            assert!(parse_payload(0, &plaintext).is_err());
+        }
+    }
+
+    fn extended(area: &[u8], payload: &[u8]) -> Vec<u8> {
+        let mut plaintext = vec![EXTENSIONS_MARKER, (area.len() >> 8) as u8, area.len() as u8];
+        plaintext.extend_from_slice(area);
+        plaintext.extend_from_slice(payload);
+        plaintext
+    }
+
+    fn notification(key: &[u8]) -> Vec<u8> {
+        let mut extension = vec![EXTENSION_NOTIFICATION, key.len() as u8];
+        extension.extend_from_slice(key);
+        extension
+    }
+
+    #[test]
+    fn parses_the_known_extensions_skipping_the_others() {
+        let mut area = vec![EXTENSION_PADDING, 3, 0, 0, 0];
+        area.extend(notification(&[0x66; PUB_KEY_SIZE]));
+        area.extend_from_slice(&[0x7f, 2, 0xaa, 0xbb]);
+        let plaintext = extended(&area, &splits(&[(0x11, 100)]));
+        let payload = parse_payload(0, &plaintext).unwrap();
+        assert_eq!(payload.splits, vec![(address(0x11), 100)]);
+        assert_eq!(payload.extensions, vec![(EXTENSION_NOTIFICATION, vec![0x66; PUB_KEY_SIZE])]);
+        assert_eq!(payload.notification_key(), Some(&[0x66; PUB_KEY_SIZE][..]));
+        assert_eq!(envelope_version(&plaintext), 5);
+    }
+
+    #[test]
+    fn parses_the_envelope_following_the_extensions() {
+        let mut envelope = vec![GUARDIAN_FLAG | 21];
+        envelope.extend(splits(&[(0x11, 100)]));
+        envelope.extend_from_slice(&[0x44; PUB_KEY_SIZE]);
+        let payload = parse_payload(0, &extended(&[EXTENSION_PADDING, 0], &envelope)).unwrap();
+        assert_eq!(payload.guardian, Some(H160::from(&[0x44_u8; PUB_KEY_SIZE][..].keccak256()[12..32])));
+        assert!(payload.extensions.is_empty());
+        assert_eq!(payload.notification_key(), None);
+    }
+
+    #[test]
+    fn parses_an_empty_extension_area() {
+        assert_eq!(parse_extensions(0, &[]), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn refuses_unknown_critical_extensions() {
+        let error = parse_extensions(0, &[EXTENSION_CRITICAL | 0x02, 1, 0]).unwrap_err();
+        assert!(error.starts_with("Unknown critical extension"), "{}", error);
+        assert!(parse_extensions(0, &[EXTENSION_CRITICAL, 0]).is_err());
+    }
+
+    #[test]
+    fn refuses_notification_keys_of_the_wrong_size() {
+        for size in [0, PUB_KEY_SIZE - 1, PUB_KEY_SIZE + 1].iter() {
+            let error = parse_extensions(0, &notification(&vec![0x66; *size])).unwrap_err();
+            assert!(error.starts_with("Invalid notification pubKey size"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn refuses_truncated_extensions() {
+        let extension = notification(&[0x66; PUB_KEY_SIZE]);
+        for len in [1, 2, extension.len() - 1].iter() {
+            let error = parse_extensions(0, &extension[..*len]).unwrap_err();
+            assert!(error.starts_with("Truncated extension"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn refuses_truncated_extension_areas() {
+        let mut plaintext = extended(&notification(&[0x66; PUB_KEY_SIZE]), &splits(&[(0x11, 100)]));
+        plaintext[2] = 200;
+        assert_eq!(parse_payload(0, &plaintext).err(), Some("Truncated extension area for participant 0".to_string()));
+    }
+
+    #[test]
+    fn refuses_extension_areas_above_the_max_size() {
+        let max_size = protocol::envelope().max_extensions_size;
+        let mut area = vec![EXTENSION_PADDING, (max_size - 2) as u8];
+        area.resize(max_size, 0);
+        assert!(parse_payload(0, &extended(&area, &splits(&[(0x11, 100)]))).is_ok());
+        area.push(0);
+        let error = parse_payload(0, &extended(&area, &splits(&[(0x11, 100)]))).err().unwrap();
+        assert!(error.starts_with("Extension area too large"), "{}", error);
+    }
+
+    #[test]
+    fn tells_the_envelope_versions() {
+        assert_eq!(envelope_version(&[0x11; ADDRESS_SIZE]), 0);
+        assert_eq!(envelope_version(&splits(&[(0x11, 60), (0x22, 40)])), 1);
+        assert_eq!(envelope_version(&[EXTENSIONS_MARKER; 90]), 5);
+    }
+
+    #[test]
+    fn expands_the_splits_with_the_remainder_last() {
+        let outputs = expand_splits(&U256::from(1001), &[(address(0x11), 33), (address(0x22), 67)]);
+        assert_eq!(outputs, vec![(address(0x11), U256::from(330)), (address(0x22), U256::from(671))]);
+        let (outputs, notes) = expand_notes(&U256::from(1000), &[(address(0x11), 40), (address(0x22), NOTE_FLAG | 60)]);
+        assert_eq!(outputs, vec![(address(0x11), U256::from(400))]);
+        assert_eq!(notes, vec![(address(0x22), U256::from(600))]);
+    }
+
+    #[test]
+    fn shares_do_not_overflow() {
+        assert_eq!(share(&U256::max_value(), 100), U256::max_value());
+        assert_eq!(share(&U256::from(199), 50), U256::from(99));
+    }
+}
@@ -0,0 +1,93 @@
+use salad_types::U256;
+
+use crate::runtime::Runtime;
+use crate::validation;
+
+// State key name "network_profile" holding the network profile selected at construction
+static NETWORK_PROFILE: &str = "network_profile";
+
+/// Network profiles selectable at construction
+pub const PROFILE_MAINNET: u8 = 0;
+pub const PROFILE_ROPSTEN: u8 = 1;
+pub const PROFILE_LOCAL_SIM: u8 = 2;
+
+/// The assumptions a deployment makes about the Ethereum network of its Mixer
+pub struct NetworkProfile {
+    pub id: u8,
+    pub name: &'static str,
+    /// The chain id signed in the deposit messages and the deal payloads
+    pub chain_id: u64,
+    /// Blocks a deposit must be confirmed for before it is mixed
+    pub min_deposit_age: u64,
+    /// Deposits verified per task, deals with more deposits being verified across several tasks
+    pub deposits_per_task: usize,
+    /// Max blocks after its scheduled block during which a deal may execute
+    pub max_execution_window: u64,
+    /// Min blocks the relayed on-chain facts a deal depends on must be confirmed for before it executes
+    pub min_confirmations: u64,
+    /// Whether the simulation entry points of simulation builds are enabled
+    pub simulation: bool,
+}
+
+pub static PROFILES: &[NetworkProfile] = &[
+    NetworkProfile { id: PROFILE_MAINNET, name: "mainnet", chain_id: 1, min_deposit_age: 20, deposits_per_task: 32, max_execution_window: 240, min_confirmations: 12, simulation: false },
+    NetworkProfile { id: PROFILE_ROPSTEN, name: "ropsten", chain_id: 3, min_deposit_age: 12, deposits_per_task: 32, max_execution_window: 240, min_confirmations: 6, simulation: false },
+    NetworkProfile { id: PROFILE_LOCAL_SIM, name: "local-sim", chain_id: 4447, min_deposit_age: 1, deposits_per_task: 32, max_execution_window: 100, min_confirmations: 1, simulation: true },
+];
+
+/// Select the network profile of the deployment
+pub fn set<R: Runtime>(runtime: &R, profile: &U256) {
+    let id = validation::to_u64(profile, "network profile");
+    let profile = PROFILES.iter().find(|candidate| u64::from(candidate.id) == id)
+        .unwrap_or_else(|| panic!("Unknown network profile: {}", id));
+    trace!(runtime, "Selecting the {} network profile", profile.name);
+    runtime.write_state(NETWORK_PROFILE, profile.id);
+}
+
+/// The profile selected at construction, if any
+fn selected<R: Runtime>(runtime: &R) -> Option<&'static NetworkProfile> {
+    let id: Option<u8> = runtime.read_state(NETWORK_PROFILE);
+    id.and_then(|id| PROFILES.iter().find(|profile| profile.id == id))
+}
+
+/// The network profile of the deployment, contracts constructed before the profiles running as mainnet
+pub fn get<R: Runtime>(runtime: &R) -> &'static NetworkProfile {
+    selected(runtime).unwrap_or(&PROFILES[PROFILE_MAINNET as usize])
+}
+
+/// Whether the simulation entry points are available: only in builds with the "simulation" feature of this
+/// crate, forwarded by the one of the contract, and unless a profile without simulation was selected
+pub fn simulation<R: Runtime>(runtime: &R) -> bool {
+    cfg!(feature = "simulation") && selected(runtime).map_or(true, |profile| profile.simulation)
+}
+
+/// Refuse the chain ids other than the one of the selected profile, contracts constructed before the
+/// profiles accepting any chain id
+pub fn check_chain_id<R: Runtime>(runtime: &R, chain_id: &U256) {
+    if let Some(profile) = selected(runtime) {
+        if *chain_id != U256::from(profile.chain_id) {
+            panic!("Invalid chain id for the {} network profile: {}", profile.name, chain_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockRuntime;
+
+    #[test]
+    fn simulation_follows_the_feature_of_the_build() {
+        let runtime = MockRuntime::new(1);
+        assert_eq!(simulation(&runtime), cfg!(feature = "simulation"));
+        set(&runtime, &U256::from(PROFILE_LOCAL_SIM));
+        assert_eq!(simulation(&runtime), cfg!(feature = "simulation"));
+    }
+
+    #[test]
+    fn simulation_is_disabled_by_profiles_without_it() {
+        let runtime = MockRuntime::new(1);
+        set(&runtime, &U256::from(PROFILE_MAINNET));
+        assert!(!simulation(&runtime));
+    }
+}
@@ -0,0 +1,110 @@
+use salad_types::{H160, H256, U256};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// The AES-256-GCM key of the payload encryption, as in the Enigma runtime
+pub type SymmetricKey = [u8; 32];
+
+/// Seam over the runtime services used by the contract logic: the contract state, the enclave
+/// randomness and encryption, and the callouts to the Mixer contract. The deal logic only reaches
+/// the runtime through this trait, so that it may compile against another confidential-compute runtime.
+pub trait Runtime {
+    fn random_seed(&self) -> u64;
+
+    /// A random 32-byte key
+    fn generate_key(&self) -> SymmetricKey;
+
+    fn encrypt(&self, plaintext: &[u8], key: &SymmetricKey) -> Vec<u8>;
+
+    /// Fails on the ciphertexts not authenticated under the key, instead of aborting the execution
+    fn decrypt(&self, ciphertext: &[u8], key: &SymmetricKey) -> Result<Vec<u8>, String>;
+
+    /// Log a line of the execution, the runtime tagging it like its own traces
+    fn trace(&self, line: &str);
+
+    /// The cost consumed by the execution so far, in the units of the runtime, if it exposes its meter to the contract
+    fn cost_counter(&self) -> Option<u64>;
+
+    /// The value stored under the state key, if any
+    fn read_state<T: DeserializeOwned>(&self, key: &str) -> Option<T>;
+
+    fn write_state<T: Serialize>(&self, key: &str, value: T);
+
+    /// Call `commitRoots` on the Mixer contract deployed at `mixer_eth_addr` (hex without prefix)
+    fn commit_roots(&self, mixer_eth_addr: &str, deal_id: &H256, participants_root: &H256, outputs_root: &H256);
+
+    /// Call `distribute` on the Mixer contract deployed at `mixer_eth_addr` (hex without prefix)
+    fn distribute(
+        &self,
+        mixer_eth_addr: &str,
+        deal_id: &H256,
+        recipients: Vec<H160>,
+        amounts: Vec<U256>,
+        redeposited: U256,
+        redeemed: U256,
+        receipt_hash: &H256,
+    );
+
+    /// Call `distributeWithEscrow` on the Mixer contract deployed at `mixer_eth_addr` (hex without prefix)
+    fn distribute_with_escrow(
+        &self,
+        mixer_eth_addr: &str,
+        deal_id: &H256,
+        recipients: Vec<H160>,
+        guardians: Vec<H160>,
+        amounts: Vec<U256>,
+        redeposited: U256,
+        redeemed: U256,
+        receipt_hash: &H256,
+    );
+
+    /// Call `distributeAssets` on the Mixer contract deployed at `mixer_eth_addr` (hex without prefix)
+    fn distribute_assets(
+        &self,
+        mixer_eth_addr: &str,
+        deal_id: &H256,
+        assets: Vec<H160>,
+        payees: Vec<H160>,
+        recipients: Vec<H160>,
+        amounts: Vec<U256>,
+        receipt_hash: &H256,
+    );
+
+    /// Call `distributeToBridge` on the Mixer contract deployed at `mixer_eth_addr` (hex without prefix)
+    fn distribute_to_bridge(
+        &self,
+        mixer_eth_addr: &str,
+        deal_id: &H256,
+        recipients: Vec<H160>,
+        amounts: Vec<U256>,
+        redeposited: U256,
+        redeemed: U256,
+        receipt_hash: &H256,
+    );
+
+    /// Call `setPayoutBridge` on the Mixer contract deployed at `mixer_eth_addr` (hex without prefix)
+    fn set_payout_bridge(&self, mixer_eth_addr: &str, bridge: &H160);
+
+    /// Call `recordReceipt` on the Mixer contract deployed at `mixer_eth_addr` (hex without prefix)
+    fn record_receipt(&self, mixer_eth_addr: &str, deal_id: &H256, receipt_hash: &H256);
+
+    /// Call `refundDeal` on the Mixer contract deployed at `mixer_eth_addr` (hex without prefix)
+    fn refund_deal(&self, mixer_eth_addr: &str, deal_id: &H256);
+
+    /// Call `commitNotePayouts` on the Mixer contract deployed at `mixer_eth_addr` (hex without prefix)
+    fn commit_note_payouts(&self, mixer_eth_addr: &str, deal_id: &H256, payouts_root: &H256, nb_payouts: usize);
+
+    /// Call `withdrawNote` on the Mixer contract deployed at `mixer_eth_addr` (hex without prefix),
+    /// paying `amount` out of the pool reserve to `payout`, from the withdrawal at `index` of the note
+    /// payouts committed with the deal
+    fn withdraw_note(
+        &self,
+        mixer_eth_addr: &str,
+        deal_id: &H256,
+        nullifier: &H256,
+        payout: &H160,
+        amount: &U256,
+        index: usize,
+        proof: &[H256],
+    );
+}
@@ -0,0 +1,106 @@
+use salad_types::H160;
+
+/// Output ordering policies, selected at deployment
+pub const ORDERING_RANDOM: u8 = 0;
+pub const ORDERING_SORTED_BY_ADDRESS: u8 = 1;
+
+/// Policy ordering the outputs of a deal before distribution
+pub trait Shuffler {
+    /// The order in which to distribute the outputs, as indices into the given recipients
+    fn permutation(&self, recipients: &[H160]) -> Vec<usize>;
+}
+
+/// Fisher–Yates shuffle from a random seed
+pub struct RandomShuffler {
+    pub seed: u64,
+}
+
+impl Shuffler for RandomShuffler {
+    fn permutation(&self, recipients: &[H160]) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..recipients.len()).collect();
+        for i in (0..indices.len()).rev() {
+            // The low 32 bits of the seed, as read by the wasm32 enclave, so that the hosts replay its permutations
+            let j = self.seed as u32 as usize % (i + 1);
+            indices.swap(i, j);
+        }
+        indices
+    }
+}
+
+/// Outputs sorted by recipient address, the order being independent of the deposits altogether
+pub struct SortedShuffler;
+
+impl Shuffler for SortedShuffler {
+    fn permutation(&self, recipients: &[H160]) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..recipients.len()).collect();
+        indices.sort_by(|a, b| recipients[*a].cmp(&recipients[*b]));
+        indices
+    }
+}
+
+pub fn shuffler(policy: u8, seed: u64) -> Box<dyn Shuffler> {
+    match policy {
+        ORDERING_SORTED_BY_ADDRESS => Box::new(SortedShuffler),
+        _ => Box::new(RandomShuffler { seed }),
+    }
+}
+
+/// Reorder the outputs with the shuffler
+pub fn apply<T: Copy, F: Fn(&T) -> H160>(shuffler: &dyn Shuffler, outputs: &mut Vec<T>, recipient: F) {
+    let recipients: Vec<H160> = outputs.iter().map(|output| recipient(output)).collect();
+    let permutation = shuffler.permutation(&recipients);
+    *outputs = permutation.iter().map(|i| outputs[*i]).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipients() -> Vec<H160> {
+        [0x33, 0x11, 0x44, 0x22].iter().map(|byte| H160::from(&[*byte; 20][..])).collect()
+    }
+
+    fn is_permutation(mut permutation: Vec<usize>, len: usize) -> bool {
+        permutation.sort();
+        permutation == (0..len).collect::<Vec<usize>>()
+    }
+
+    #[test]
+    fn random_shuffle_is_a_permutation_replayed_from_its_seed() {
+        for seed in [0, 1, 7, u64::max_value()].iter() {
+            let permutation = shuffler(ORDERING_RANDOM, *seed).permutation(&recipients());
+            assert!(is_permutation(permutation.clone(), 4));
+            assert_eq!(permutation, shuffler(ORDERING_RANDOM, *seed).permutation(&recipients()));
+        }
+        assert_eq!(RandomShuffler { seed: 0 }.permutation(&recipients()), vec![1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn random_shuffle_reads_the_low_32_bits_of_its_seed() {
+        let seed = 0x0123_4567_89ab_cdef_u64;
+        assert_eq!(
+            RandomShuffler { seed }.permutation(&recipients()),
+            RandomShuffler { seed: seed & 0xffff_ffff }.permutation(&recipients())
+        );
+    }
+
+    #[test]
+    fn sorted_shuffle_orders_by_address() {
+        assert_eq!(shuffler(ORDERING_SORTED_BY_ADDRESS, 7).permutation(&recipients()), vec![1, 3, 0, 2]);
+    }
+
+    #[test]
+    fn apply_reorders_the_outputs_along_their_recipient() {
+        let mut outputs: Vec<(H160, u64)> = recipients().into_iter().zip(0..).collect();
+        apply(&SortedShuffler, &mut outputs, |(recipient, _)| *recipient);
+        assert_eq!(outputs.iter().map(|(_, amount)| *amount).collect::<Vec<u64>>(), vec![1, 3, 0, 2]);
+        let mut sorted = recipients();
+        sorted.sort();
+        assert_eq!(outputs.iter().map(|(recipient, _)| *recipient).collect::<Vec<H160>>(), sorted);
+    }
+
+    #[test]
+    fn shuffles_empty_deals() {
+        assert!(shuffler(ORDERING_RANDOM, 1).permutation(&[]).is_empty());
+    }
+}
@@ -0,0 +1,178 @@
+use rustc_hex::{FromHex, ToHex};
+use salad_types::{H160, H256, U256};
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+
+/// An address held in the state as its 20 raw bytes, its canonical form, so that the tables keyed by address
+/// (operators, relayers, tokens, Mixer) compare addresses regardless of the case they were given in. Values
+/// stored as hex by earlier versions are read as well, and rewritten in binary on their next write.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Address(pub H160);
+
+/// A hash held in the state as its 32 raw bytes, read from hex like `Address`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Hash(pub H256);
+
+/// An amount held in the state as its 32-byte big-endian word, so that reading it back never parses it.
+/// Values stored in decimal by earlier versions are read as well.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Word(pub U256);
+
+/// The state key of the address under the prefix: the lowercase hex of its 20 raw bytes without `0x`. Addresses
+/// given as hex strings are decoded to their raw bytes where they enter (the legacy state values read by
+/// `Address`, the messages of the CosmWasm backend), so that their mixed-case (EIP-55 checksummed) and
+/// lowercase spellings key the same entry.
+pub fn address_key(prefix: &str, address: &H160) -> String {
+    format!("{}{}", prefix, address.to_hex::<String>())
+}
+
+impl From<H160> for Address {
+    fn from(address: H160) -> Address {
+        Address(address)
+    }
+}
+
+impl From<H256> for Hash {
+    fn from(hash: H256) -> Hash {
+        Hash(hash)
+    }
+}
+
+impl From<U256> for Word {
+    fn from(value: U256) -> Word {
+        Word(value)
+    }
+}
+
+impl Serialize for Address {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl Serialize for Hash {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.0)
+    }
+}
+
+impl Serialize for Word {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut word = [0_u8; 32];
+        self.0.to_big_endian(&mut word);
+        serializer.serialize_bytes(&word)
+    }
+}
+
+impl<'de> Deserialize<'de> for Address {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Address, D::Error> {
+        deserializer.deserialize_any(FixedBytes(20)).map(|bytes| Address(H160::from(&bytes[..])))
+    }
+}
+
+impl<'de> Deserialize<'de> for Hash {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Hash, D::Error> {
+        deserializer.deserialize_any(FixedBytes(32)).map(|bytes| Hash(H256::from(&bytes[..])))
+    }
+}
+
+impl<'de> Deserialize<'de> for Word {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Word, D::Error> {
+        deserializer.deserialize_any(WordVisitor)
+    }
+}
+
+/// Visitor of an amount, stored either as a raw 32-byte word or as a legacy decimal string
+struct WordVisitor;
+
+impl<'de> Visitor<'de> for WordVisitor {
+    type Value = Word;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "a 32-byte word, raw or in decimal")
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Word, E> {
+        FixedBytes(32).check(value.to_vec()).map(|word| Word(U256::from_big_endian(&word)))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Word, E> {
+        U256::from_dec_str(value).map(Word).map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Word, A::Error> {
+        FixedBytes(32).visit_seq(seq).map(|word| Word(U256::from_big_endian(&word)))
+    }
+}
+
+/// Visitor of a value of the size, stored either as raw bytes or as a legacy hex string
+struct FixedBytes(usize);
+
+impl FixedBytes {
+    fn check<E: de::Error>(&self, bytes: Vec<u8>) -> Result<Vec<u8>, E> {
+        if bytes.len() != self.0 {
+            return Err(E::invalid_length(bytes.len(), self));
+        }
+        Ok(bytes)
+    }
+}
+
+impl<'de> Visitor<'de> for FixedBytes {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{} bytes, raw or in hex", self.0)
+    }
+
+    fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Vec<u8>, E> {
+        self.check(value.to_vec())
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Vec<u8>, E> {
+        let bytes = value.trim_start_matches("0x").from_hex().map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))?;
+        self.check(bytes)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+        let mut bytes: Vec<u8> = Vec::new();
+        while let Some(byte) = seq.next_element()? {
+            bytes.push(byte);
+        }
+        self.check(bytes)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::MockRuntime;
+    use crate::runtime::Runtime;
+
+    /// An EIP-55 example address, checksummed and in lowercase
+    const CHECKSUMMED: &str = "0x52908400098527886E0F7030069857D2E4169EE7";
+    const LOWERCASE: &str = "0x52908400098527886e0f7030069857d2e4169ee7";
+
+    fn parse(value: &str) -> H160 {
+        H160::from(&value.trim_start_matches("0x").from_hex::<Vec<u8>>().unwrap()[..])
+    }
+
+    #[test]
+    fn keys_the_spellings_of_an_address_alike() {
+        let key = address_key("operator_", &parse(CHECKSUMMED));
+        assert_eq!(key, address_key("operator_", &parse(LOWERCASE)));
+        assert_eq!(key, format!("operator_{}", &LOWERCASE[2..]));
+    }
+
+    #[test]
+    fn reads_the_hex_addresses_in_any_case() {
+        let runtime = MockRuntime::new(1);
+        runtime.write_state("checksummed", CHECKSUMMED.to_string());
+        runtime.write_state("lowercase", LOWERCASE.to_string());
+        let checksummed: Address = runtime.read_state("checksummed").unwrap();
+        let lowercase: Address = runtime.read_state("lowercase").unwrap();
+        assert_eq!(checksummed, lowercase);
+        assert_eq!(checksummed, Address(parse(LOWERCASE)));
+    }
+}
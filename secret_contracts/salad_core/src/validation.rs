@@ -0,0 +1,148 @@
+use salad_types::{H160, U256};
+
+use crate::MAX_PARTICIPANTS;
+
+/// The count `n` as a usize, refused above `max` instead of truncated to its low bits
+pub fn count(n: &U256, max: usize, what: &str) -> usize {
+    if *n > U256::from(max) {
+        panic!("Too many {}: {} > {}", what, n, max);
+    }
+    n.low_u64() as usize
+}
+
+/// The count `n` as a usize, capped at `max`
+pub fn capped_count(n: &U256, max: usize) -> usize {
+    if *n > U256::from(max) {
+        max
+    } else {
+        n.low_u64() as usize
+    }
+}
+
+/// The value as a u64, refused above 64 bits instead of truncated to its low bits
+pub fn to_u64(value: &U256, what: &str) -> u64 {
+    if *value > U256::from(u64::max_value()) {
+        panic!("Invalid {}: {} exceeds 64 bits", what, value);
+    }
+    value.low_u64()
+}
+
+/// Check the consistency of the deposit lists, returning the number of participants
+pub fn check_participants(
+    pub_keys: &[Vec<u8>],
+    enc_recipients: &[Vec<u8>],
+    senders: &[H160],
+    signatures: &[Vec<u8>],
+    expiries: &[U256],
+) -> usize {
+    try_check_participants(pub_keys, enc_recipients, senders, signatures, expiries).unwrap_or_else(|err| panic!("{}", err))
+}
+
+/// Check the consistency of the deposit lists like `check_participants`, returning the inconsistency
+/// rather than aborting, for the runtimes reporting errors to the caller
+pub fn try_check_participants(
+    pub_keys: &[Vec<u8>],
+    enc_recipients: &[Vec<u8>],
+    senders: &[H160],
+    signatures: &[Vec<u8>],
+    expiries: &[U256],
+) -> Result<usize, String> {
+    let nb_participants = enc_recipients.len();
+    match nb_participants {
+        l if l != senders.len() => Err(format!("Mismatching senders list size: {} != {}", l, senders.len())),
+        l if l != pub_keys.len() => Err(format!("Mismatching pub_keys list size: {} != {}", l, pub_keys.len())),
+        l if l != signatures.len() => Err(format!("Mismatching signatures list size: {} != {}", l, signatures.len())),
+        l if l != expiries.len() => Err(format!("Mismatching expiries list size: {} != {}", l, expiries.len())),
+        l if l > MAX_PARTICIPANTS => Err(format!("Too many participants: {} > {}", l, MAX_PARTICIPANTS)),
+        _ => Ok(nb_participants),
+    }
+}
+
+/// Check the consistency of the multi-asset deposit lists, returning the number of participants
+pub fn check_multi_asset_participants(
+    assets: &[H160],
+    amounts: &[U256],
+    pub_keys: &[Vec<u8>],
+    enc_recipients: &[Vec<u8>],
+    senders: &[H160],
+    signatures: &[Vec<u8>],
+) -> usize {
+    let nb_participants = enc_recipients.len();
+    match nb_participants {
+        l if l != senders.len() => panic!("Mismatching senders list size: {} != {}", l, senders.len()),
+        l if l != assets.len() => panic!("Mismatching assets list size: {} != {}", l, assets.len()),
+        l if l != amounts.len() => panic!("Mismatching amounts list size: {} != {}", l, amounts.len()),
+        l if l != pub_keys.len() => panic!("Mismatching pub_keys list size: {} != {}", l, pub_keys.len()),
+        l if l != signatures.len() => panic!("Mismatching signatures list size: {} != {}", l, signatures.len()),
+        0 => panic!("Empty deal"),
+        l if l > MAX_PARTICIPANTS => panic!("Too many participants: {} > {}", l, MAX_PARTICIPANTS),
+        _ => {}
+    }
+    nb_participants
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lists(n: usize) -> (Vec<Vec<u8>>, Vec<Vec<u8>>, Vec<H160>, Vec<Vec<u8>>, Vec<U256>) {
+        (vec![Vec::new(); n], vec![Vec::new(); n], vec![H160::zero(); n], vec![Vec::new(); n], vec![U256::zero(); n])
+    }
+
+    #[test]
+    fn counts_consistent_participants() {
+        let (pub_keys, enc_recipients, senders, signatures, expiries) = lists(3);
+        assert_eq!(try_check_participants(&pub_keys, &enc_recipients, &senders, &signatures, &expiries), Ok(3));
+        assert_eq!(check_participants(&pub_keys, &enc_recipients, &senders, &signatures, &expiries), 3);
+    }
+
+    #[test]
+    fn refuses_mismatching_lists() {
+        let (pub_keys, enc_recipients, senders, signatures, expiries) = lists(3);
+        assert!(try_check_participants(&pub_keys[1..], &enc_recipients, &senders, &signatures, &expiries).is_err());
+        assert!(try_check_participants(&pub_keys, &enc_recipients, &senders[1..], &signatures, &expiries).is_err());
+        assert!(try_check_participants(&pub_keys, &enc_recipients, &senders, &signatures[1..], &expiries).is_err());
+        assert!(try_check_participants(&pub_keys, &enc_recipients, &senders, &signatures, &expiries[1..]).is_err());
+    }
+
+    #[test]
+    fn refuses_too_many_participants() {
+        let (pub_keys, enc_recipients, senders, signatures, expiries) = lists(MAX_PARTICIPANTS + 1);
+        assert!(try_check_participants(&pub_keys, &enc_recipients, &senders, &signatures, &expiries).is_err());
+        let (pub_keys, enc_recipients, senders, signatures, expiries) = lists(MAX_PARTICIPANTS);
+        assert_eq!(try_check_participants(&pub_keys, &enc_recipients, &senders, &signatures, &expiries), Ok(MAX_PARTICIPANTS));
+    }
+
+    #[test]
+    #[should_panic(expected = "Mismatching senders list size")]
+    fn check_participants_aborts_on_mismatching_lists() {
+        let (pub_keys, enc_recipients, senders, signatures, expiries) = lists(2);
+        check_participants(&pub_keys, &enc_recipients, &senders[1..], &signatures, &expiries);
+    }
+
+    #[test]
+    fn counts_without_truncating() {
+        assert_eq!(count(&U256::from(255), 255, "participants"), 255);
+        assert_eq!(capped_count(&(U256::from(1) << 64), 10), 10);
+        assert_eq!(capped_count(&U256::from(3), 10), 3);
+        assert_eq!(to_u64(&U256::from(u64::max_value()), "block"), u64::max_value());
+    }
+
+    #[test]
+    #[should_panic(expected = "Too many participants")]
+    fn count_refuses_the_high_bits() {
+        count(&((U256::from(1) << 64) + U256::from(1)), 255, "participants");
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds 64 bits")]
+    fn to_u64_refuses_the_high_bits() {
+        to_u64(&(U256::from(1) << 64), "block");
+    }
+
+    #[test]
+    #[should_panic(expected = "Empty deal")]
+    fn refuses_empty_multi_asset_deals() {
+        check_multi_asset_participants(&[], &[], &[], &[], &[], &[]);
+    }
+}
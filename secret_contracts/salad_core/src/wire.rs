@@ -0,0 +1,187 @@
+use salad_types::protocol::ADDRESS_SIZE;
+use salad_types::{H160, H256, U256};
+
+use crate::validation;
+
+/// The inputs of a deposit, borrowed from the deposit lists of the deal
+pub struct DepositInput<'a> {
+    /// Position of the deposit in the deal
+    pub index: usize,
+    pub pub_key: &'a [u8],
+    pub enc_recipient: &'a [u8],
+    pub sender: &'a H160,
+    pub signature: &'a [u8],
+    pub expiry: &'a U256,
+}
+
+/// The inputs of each deposit, in deal order, the deposit lists being checked for consistency first
+pub fn deposits<'a>(
+    pub_keys: &'a [Vec<u8>],
+    enc_recipients: &'a [Vec<u8>],
+    senders: &'a [H160],
+    signatures: &'a [Vec<u8>],
+    expiries: &'a [U256],
+) -> impl Iterator<Item = DepositInput<'a>> {
+    validation::check_participants(pub_keys, enc_recipients, senders, signatures, expiries);
+    pub_keys.iter()
+        .zip(enc_recipients.iter())
+        .zip(senders.iter().zip(signatures.iter().zip(expiries.iter())))
+        .enumerate()
+        .map(|(index, ((pub_key, enc_recipient), (sender, (signature, expiry))))| DepositInput {
+            index,
+            pub_key,
+            enc_recipient,
+            sender,
+            signature,
+            expiry,
+        })
+}
+
+/// The inputs of a voucher deposit, borrowed from the voucher lists of the deal
+pub struct VoucherInput<'a> {
+    /// Position of the voucher in the deal
+    pub index: usize,
+    pub commitment: &'a H256,
+    pub pub_key: &'a [u8],
+    pub enc_recipient: &'a [u8],
+}
+
+/// The inputs of each voucher deposit, in deal order
+pub fn vouchers<'a>(
+    commitments: &'a [H256],
+    pub_keys: &'a [Vec<u8>],
+    enc_recipients: &'a [Vec<u8>],
+) -> impl Iterator<Item = VoucherInput<'a>> {
+    match commitments.len() {
+        l if l != pub_keys.len() => panic!("Mismatching pub_keys list size: {} != {}", l, pub_keys.len()),
+        l if l != enc_recipients.len() => panic!("Mismatching enc_recipients list size: {} != {}", l, enc_recipients.len()),
+        _ => {}
+    }
+    commitments.iter()
+        .zip(pub_keys.iter().zip(enc_recipients.iter()))
+        .enumerate()
+        .map(|(index, (commitment, (pub_key, enc_recipient)))| VoucherInput { index, commitment, pub_key, enc_recipient })
+}
+
+/// A (recipient address, percentage) split of a deposit payload
+pub struct Split {
+    pub recipient: H160,
+    pub percent: u8,
+}
+
+/// The splits of a payload made of whole `split_size` entries, the trailing bytes of a partial entry being
+/// left to the caller to reject
+pub fn splits(payload: &[u8], split_size: usize) -> impl Iterator<Item = Split> + '_ {
+    payload.chunks_exact(split_size).map(|split| Split {
+        recipient: H160::from(&split[..ADDRESS_SIZE]),
+        percent: split[ADDRESS_SIZE],
+    })
+}
+
+/// Cursor over the consecutive fields of an envelope, each read failing instead of indexing past its end
+pub struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data }
+    }
+
+    /// The next byte, if any
+    pub fn byte(&mut self) -> Option<u8> {
+        self.bytes(1).map(|byte| byte[0])
+    }
+
+    /// The next `len` bytes, if as many remain
+    pub fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.data.len() < len {
+            return None;
+        }
+        let (field, rest) = self.data.split_at(len);
+        self.data = rest;
+        Some(field)
+    }
+
+    /// The remaining bytes, left unread
+    pub fn rest(&self) -> &'a [u8] {
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yields_each_deposit_in_deal_order() {
+        let pub_keys = vec![vec![1], vec![2], vec![3]];
+        let enc_recipients = vec![vec![4], vec![5], vec![6]];
+        let senders: Vec<H160> = (7..10).map(|byte| H160::from(&[byte; ADDRESS_SIZE][..])).collect();
+        let signatures = vec![vec![10], vec![11], vec![12]];
+        let expiries: Vec<U256> = (13..16_u64).map(U256::from).collect();
+        let inputs: Vec<DepositInput> = deposits(&pub_keys, &enc_recipients, &senders, &signatures, &expiries).collect();
+        assert_eq!(inputs.len(), 3);
+        for (i, input) in inputs.iter().enumerate() {
+            assert_eq!(input.index, i);
+            assert_eq!(input.pub_key, &[1 + i as u8][..]);
+            assert_eq!(input.enc_recipient, &[4 + i as u8][..]);
+            assert_eq!(*input.sender, senders[i]);
+            assert_eq!(input.signature, &[10 + i as u8][..]);
+            assert_eq!(*input.expiry, U256::from(13 + i));
+        }
+    }
+
+    #[test]
+    fn yields_no_deposit_of_an_empty_deal() {
+        assert_eq!(deposits(&[], &[], &[], &[], &[]).count(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Mismatching expiries list size")]
+    fn refuses_mismatching_deposit_lists() {
+        deposits(&[vec![1]], &[vec![2]], &[H160::zero()], &[vec![3]], &[]).count();
+    }
+
+    #[test]
+    fn yields_each_voucher_in_deal_order() {
+        let commitments = vec![H256::from(&[1; 32][..]), H256::from(&[2; 32][..])];
+        let (pub_keys, enc_recipients) = (vec![vec![3], vec![4]], vec![vec![5], vec![6]]);
+        let inputs: Vec<VoucherInput> = vouchers(&commitments, &pub_keys, &enc_recipients).collect();
+        assert_eq!(inputs.iter().map(|input| input.index).collect::<Vec<usize>>(), vec![0, 1]);
+        assert_eq!(*inputs[1].commitment, commitments[1]);
+        assert_eq!((inputs[1].pub_key, inputs[1].enc_recipient), (&[4_u8][..], &[6_u8][..]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Mismatching enc_recipients list size")]
+    fn refuses_mismatching_voucher_lists() {
+        vouchers(&[H256::zero()], &[vec![1]], &[]).count();
+    }
+
+    #[test]
+    fn reads_whole_splits_only() {
+        let mut payload: Vec<u8> = Vec::new();
+        for (byte, percent) in [(0x11, 60), (0x22, 40)].iter() {
+            payload.extend_from_slice(&[*byte; ADDRESS_SIZE]);
+            payload.push(*percent);
+        }
+        let splits_of = |payload: &[u8]| splits(payload, ADDRESS_SIZE + 1).map(|split| (split.recipient, split.percent)).collect::<Vec<(H160, u8)>>();
+        let expected = vec![(H160::from(&[0x11; ADDRESS_SIZE][..]), 60), (H160::from(&[0x22; ADDRESS_SIZE][..]), 40)];
+        assert_eq!(splits_of(&payload), expected);
+        assert_eq!(splits_of(&payload[..payload.len() - 1]), expected[..1].to_vec());
+        assert!(splits_of(&[]).is_empty());
+    }
+
+    #[test]
+    fn reads_fields_without_indexing_past_the_end() {
+        let mut reader = Reader::new(&[1, 2, 3]);
+        assert_eq!(reader.byte(), Some(1));
+        assert_eq!(reader.bytes(3), None);
+        assert_eq!(reader.rest(), &[2, 3]);
+        assert_eq!(reader.bytes(2), Some(&[2_u8, 3][..]));
+        assert_eq!(reader.byte(), None);
+        assert_eq!(reader.bytes(0), Some(&[][..]));
+        assert!(reader.rest().is_empty());
+    }
+}
@@ -0,0 +1,79 @@
+//! The deposit digests and payload envelopes of the JS client, pinned by the fixtures of test/fixtures/compat.json
+//! that `yarn compat-test` checks the client against: a format change on either side fails one of the two tests.
+
+use enigma_crypto::hash::Keccak256;
+use rustc_hex::{FromHex, ToHex};
+use salad_core::parsing;
+use salad_types::digest::{self, DepositTerms};
+use salad_types::protocol::PUB_KEY_SIZE;
+use salad_types::{H160, U256};
+use serde::Deserialize;
+
+/// A deposit signed by the JS client: its fields, in hex or decimal as sent to the operator, and the hex keccak256
+/// of its EIP712 typed data
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DepositVector {
+    sender: String,
+    amount: String,
+    enc_recipient: String,
+    pub_key: String,
+    expires_at: String,
+    chain_id: u64,
+    digest: String,
+}
+
+/// A payload envelope built by the JS client around a recipient, with the notification pubKey extension if any
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EnvelopeVector {
+    recipient: String,
+    notification_key: Option<String>,
+    payload: String,
+}
+
+#[derive(Deserialize)]
+struct Fixtures {
+    deposits: Vec<DepositVector>,
+    envelopes: Vec<EnvelopeVector>,
+}
+
+fn fixtures() -> Fixtures {
+    serde_json::from_str(include_str!("../../../test/fixtures/compat.json")).unwrap()
+}
+
+fn bytes(hex: &str) -> Vec<u8> {
+    hex.trim_start_matches("0x").from_hex().unwrap()
+}
+
+#[test]
+fn hashes_the_deposits_to_the_digests_signed_by_the_client() {
+    let deposits = fixtures().deposits;
+    assert!(!deposits.is_empty());
+    for vector in deposits.iter() {
+        let mut pub_key = [0_u8; PUB_KEY_SIZE];
+        pub_key.copy_from_slice(&bytes(&vector.pub_key));
+        let expires_at = U256::from_dec_str(&vector.expires_at).unwrap();
+        let message = digest::deposit_message(
+            &H160::from(&bytes(&vector.sender)[..]),
+            DepositTerms::Expiring(&expires_at),
+            &U256::from_dec_str(&vector.amount).unwrap(),
+            &bytes(&vector.enc_recipient),
+            &pub_key,
+            &U256::from(vector.chain_id),
+        );
+        assert_eq!(format!("0x{}", message.keccak256().to_hex::<String>()), vector.digest);
+    }
+}
+
+#[test]
+fn parses_the_envelopes_built_by_the_client() {
+    let envelopes = fixtures().envelopes;
+    assert!(!envelopes.is_empty());
+    for vector in envelopes.iter() {
+        let payload = parsing::parse_payload(0, &bytes(&vector.payload)).unwrap();
+        assert_eq!(payload.splits, vec![(H160::from(&bytes(&vector.recipient)[..]), 100)]);
+        assert_eq!(payload.notification_key().map(|key| key.to_vec()), vector.notification_key.as_ref().map(|key| bytes(key)));
+        assert_eq!(parsing::envelope_version(&bytes(&vector.payload)), 5);
+    }
+}
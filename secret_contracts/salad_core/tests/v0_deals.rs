@@ -0,0 +1,144 @@
+//! Regression corpus of v0 deal payloads in test/fixtures/v0_deals.json, generated by scripts/generate_v0_deals.py:
+//! bare recipient addresses encrypted without salt to the enclave and signed under message version 0. The digests,
+//! keys, recipients and permutations are locked byte for byte, so that a change of the wire behavior fails here.
+
+use aes_gcm::aead::generic_array::GenericArray;
+use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::Aes256Gcm;
+use enigma_crypto::hash::Keccak256;
+use enigma_crypto::KeyPair;
+use rustc_hex::{FromHex, ToHex};
+use salad_core::{kdf, parsing, shuffle};
+use salad_types::digest::{self, DepositTerms};
+use salad_types::protocol::{self, PUB_KEY_SIZE, SIG_SIZE};
+use salad_types::{H160, U256};
+use serde::Deserialize;
+
+/// Size of the AES-GCM IV appended to the ciphertexts after the tag
+const IV_SIZE: usize = 12;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DepositFixture {
+    user_secret: String,
+    pub_key: String,
+    sender: String,
+    recipient: String,
+    key: String,
+    enc_recipient: String,
+    signature: String,
+    digest: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DealFixture {
+    amount: String,
+    chain_id: u64,
+    operator: String,
+    operator_nonce: String,
+    deal_id: String,
+    seed: String,
+    deposits: Vec<DepositFixture>,
+    permutation: Vec<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Corpus {
+    enclave_secret: String,
+    enclave_pub_key: String,
+    deals: Vec<DealFixture>,
+}
+
+fn corpus() -> Corpus {
+    serde_json::from_str(include_str!("../../../test/fixtures/v0_deals.json")).unwrap()
+}
+
+fn bytes(hex: &str) -> Vec<u8> {
+    hex.from_hex().unwrap()
+}
+
+fn pub_key(hex: &str) -> [u8; PUB_KEY_SIZE] {
+    let mut pub_key = [0_u8; PUB_KEY_SIZE];
+    pub_key.copy_from_slice(&bytes(hex));
+    pub_key
+}
+
+fn uint(decimal: &str) -> U256 {
+    U256::from_dec_str(decimal).unwrap()
+}
+
+#[test]
+fn hashes_the_deposits_and_deals_to_their_digests() {
+    let version = &protocol::MESSAGE_VERSIONS[0];
+    assert_eq!(version.version, 0);
+    for deal in corpus().deals.iter() {
+        let amount = uint(&deal.amount);
+        for deposit in deal.deposits.iter() {
+            let sender = H160::from(&bytes(&deposit.sender)[..]);
+            // v0 messages do not sign the expiry
+            let message = digest::versioned_deposit_message(
+                version,
+                &sender,
+                DepositTerms::Expiring(&U256::zero()),
+                &amount,
+                &bytes(&deposit.enc_recipient),
+                &pub_key(&deposit.pub_key),
+                &U256::from(deal.chain_id),
+            );
+            assert_eq!(message.keccak256().to_hex::<String>(), deposit.digest);
+            let mut signature = [0_u8; SIG_SIZE];
+            signature.copy_from_slice(&bytes(&deposit.signature));
+            assert_eq!(digest::recover_address(&message, signature), Ok(sender));
+        }
+        let senders: Vec<H160> = deal.deposits.iter().map(|deposit| H160::from(&bytes(&deposit.sender)[..])).collect();
+        let deal_id = digest::deal_id(&amount, &senders, &H160::from(&bytes(&deal.operator)[..]), &uint(&deal.operator_nonce));
+        assert_eq!(deal_id.to_hex::<String>(), deal.deal_id);
+    }
+}
+
+#[test]
+fn derives_the_fixture_keys_from_the_enclave_key() {
+    let corpus = corpus();
+    let enclave = KeyPair::from_slice(&bytes(&corpus.enclave_secret)).unwrap();
+    assert_eq!(enclave.get_pubkey()[..], bytes(&corpus.enclave_pub_key)[..]);
+    for deposit in corpus.deals.iter().flat_map(|deal| deal.deposits.iter()) {
+        let user = KeyPair::from_slice(&bytes(&deposit.user_secret)).unwrap();
+        assert_eq!(user.get_pubkey()[..], bytes(&deposit.pub_key)[..]);
+        let enc_recipient = bytes(&deposit.enc_recipient);
+        let (salt, _) = kdf::split_salt(&enc_recipient);
+        assert_eq!(salt, None);
+        let key = kdf::shared_key(&enclave, &pub_key(&deposit.pub_key), salt).unwrap();
+        assert_eq!(key[..], bytes(&deposit.key)[..]);
+        assert_eq!(user.derive_key(&enclave.get_pubkey()).unwrap(), key);
+    }
+}
+
+#[test]
+fn decrypts_the_recipients_with_the_fixture_keys() {
+    for deposit in corpus().deals.iter().flat_map(|deal| deal.deposits.iter()) {
+        let enc_recipient = bytes(&deposit.enc_recipient);
+        let (ciphertext, iv) = enc_recipient.split_at(enc_recipient.len() - IV_SIZE);
+        let cipher = Aes256Gcm::new(GenericArray::from_slice(&bytes(&deposit.key)));
+        let plaintext = cipher.decrypt(GenericArray::from_slice(iv), ciphertext).unwrap();
+        assert_eq!(plaintext, bytes(&deposit.recipient));
+        assert_eq!(parsing::envelope_version(&plaintext), 0);
+        let payload = parsing::parse_payload(0, &plaintext).unwrap();
+        assert_eq!(payload.splits, vec![(H160::from(&bytes(&deposit.recipient)[..]), 100)]);
+        assert!(payload.redeposit.is_none() && payload.guardian.is_none() && payload.extensions.is_empty());
+    }
+}
+
+#[test]
+fn replays_the_permutations_of_the_deals() {
+    for deal in corpus().deals.iter() {
+        let recipients: Vec<H160> = deal.deposits.iter().map(|deposit| H160::from(&bytes(&deposit.recipient)[..])).collect();
+        let shuffler = shuffle::shuffler(shuffle::ORDERING_RANDOM, deal.seed.parse().unwrap());
+        assert_eq!(shuffler.permutation(&recipients), deal.permutation);
+        let mut outputs = recipients.clone();
+        shuffle::apply(&*shuffler, &mut outputs, |recipient| *recipient);
+        let expected: Vec<H160> = deal.permutation.iter().map(|i| recipients[*i]).collect();
+        assert_eq!(outputs, expected);
+    }
+}
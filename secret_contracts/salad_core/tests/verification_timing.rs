@@ -0,0 +1,100 @@
+//! Host-side timing of the deposit verification of a full deal of MAX_PARTICIPANTS deposits, comparing the
+//! deposit messages hashed by a DepositHasher built once per deal with the messages rebuilding the EIP712 domain
+//! and type hashes per deposit. The timings are ignored by default, being meaningful in release builds only:
+//!
+//!     cargo test --release --test verification_timing -- --ignored --nocapture
+
+use std::time::{Duration, Instant};
+
+use enigma_crypto::hash::Keccak256;
+use enigma_crypto::KeyPair;
+use salad_core::MAX_PARTICIPANTS;
+use salad_types::digest::{self, DepositHasher, DepositTerms};
+use salad_types::protocol::{self, PUB_KEY_SIZE, SIG_SIZE};
+use salad_types::{H160, U256};
+
+/// Runs of each measure, the fastest run being kept to leave out the noise of the host
+const RUNS: usize = 5;
+
+/// The deposits of a deal, each signed by its own depositor
+struct Deposit {
+    sender: H160,
+    pub_key: [u8; PUB_KEY_SIZE],
+    enc_recipient: Vec<u8>,
+    expiry: U256,
+    signature: [u8; SIG_SIZE],
+}
+
+fn chain_id() -> U256 {
+    U256::from(1)
+}
+
+fn amount() -> U256 {
+    U256::from(1_000_000_000_000_000_000_u64)
+}
+
+fn deposits() -> Vec<Deposit> {
+    (0..MAX_PARTICIPANTS).map(|i| {
+        let keypair = KeyPair::from_slice(&(i as u64 + 1).to_be_bytes().keccak256()[..]).unwrap();
+        let pub_key = keypair.get_pubkey();
+        let sender = H160::from(&pub_key[..].keccak256()[12..32]);
+        let enc_recipient = (i as u64).to_le_bytes().keccak256()[..].repeat(3);
+        let expiry = U256::from(10_000_000 + i as u64);
+        let message = digest::deposit_message(&sender, DepositTerms::Expiring(&expiry), &amount(), &enc_recipient, &pub_key, &chain_id());
+        let signature = keypair.sign(&message).unwrap();
+        Deposit { sender, pub_key, enc_recipient, expiry, signature }
+    }).collect()
+}
+
+/// The fastest of RUNS runs of `f`
+fn fastest<F: FnMut()>(mut f: F) -> Duration {
+    (0..RUNS).map(|_| {
+        let start = Instant::now();
+        f();
+        start.elapsed()
+    }).min().unwrap()
+}
+
+#[test]
+fn shared_hasher_hashes_the_messages_of_a_full_deal() {
+    let hasher = DepositHasher::new(protocol::message(), &chain_id());
+    for deposit in deposits().iter() {
+        let terms = DepositTerms::Expiring(&deposit.expiry);
+        let message = hasher.message(&deposit.sender, terms, &amount(), &deposit.enc_recipient, &deposit.pub_key);
+        assert_eq!(message, digest::deposit_message(&deposit.sender, terms, &amount(), &deposit.enc_recipient, &deposit.pub_key, &chain_id()));
+        assert_eq!(digest::recover_address(&message, deposit.signature), Ok(deposit.sender));
+    }
+}
+
+#[test]
+#[ignore]
+fn times_the_verification_of_a_full_deal() {
+    let deposits = deposits();
+    // The messages are kept so that their hashing is not optimized away
+    let per_deposit = fastest(|| {
+        let messages: Vec<Vec<u8>> = deposits.iter().map(|deposit| {
+            let terms = DepositTerms::Expiring(&deposit.expiry);
+            digest::deposit_message(&deposit.sender, terms, &amount(), &deposit.enc_recipient, &deposit.pub_key, &chain_id())
+        }).collect();
+        assert_eq!(messages.len(), MAX_PARTICIPANTS);
+    });
+    let per_deal = fastest(|| {
+        let hasher = DepositHasher::new(protocol::message(), &chain_id());
+        let messages: Vec<Vec<u8>> = deposits.iter().map(|deposit| {
+            let terms = DepositTerms::Expiring(&deposit.expiry);
+            hasher.message(&deposit.sender, terms, &amount(), &deposit.enc_recipient, &deposit.pub_key)
+        }).collect();
+        assert_eq!(messages.len(), MAX_PARTICIPANTS);
+    });
+    let verification = fastest(|| {
+        let hasher = DepositHasher::new(protocol::message(), &chain_id());
+        for deposit in deposits.iter() {
+            let terms = DepositTerms::Expiring(&deposit.expiry);
+            let message = hasher.message(&deposit.sender, terms, &amount(), &deposit.enc_recipient, &deposit.pub_key);
+            assert_eq!(digest::recover_address(&message, deposit.signature), Ok(deposit.sender));
+        }
+    });
+    println!("{} deposits: messages hashed per deposit {:?}, per deal {:?}", deposits.len(), per_deposit, per_deal);
+    println!("{} deposits: messages hashed per deal and signers recovered {:?}", deposits.len(), verification);
+    assert!(per_deal < per_deposit, "{:?} >= {:?}", per_deal, per_deposit);
+}
@@ -0,0 +1,310 @@
+use std::cell::RefCell;
+use std::ops::Deref;
+
+use aes_gcm::aead::generic_array::GenericArray;
+use aes_gcm::aead::{Aead, NewAead};
+use aes_gcm::Aes256Gcm;
+use cosmwasm_std::{attr, from_slice, to_vec, Attribute, Env, StdError, StdResult, Storage};
+use rustc_hex::{FromHex, ToHex};
+use salad_types::calldata::{self, Token};
+use salad_types::{convert, H160, H256, U256};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::kdf;
+use crate::runtime::{Runtime, SymmetricKey};
+
+/// Size of the AES-GCM IV appended to the ciphertexts after the tag, as in the Enigma runtime
+const IV_SIZE: usize = 12;
+
+// State key name "entropy_counter" holding the number of keys drawn from the block randomness, so that the keys
+// drawn by the executions of the same block never repeat
+static ENTROPY_COUNTER: &str = "entropy_counter";
+// State key name "callout_nonce" holding the number of callouts signed by the contract, each relayed once by the Mixer
+static CALLOUT_NONCE: &str = "callout_nonce";
+
+/// Comma-separated hex of the addresses or hashes
+fn join<T: Deref<Target = [u8]>>(values: &[T]) -> String {
+    values.iter().map(|value| value.to_hex::<String>()).collect::<Vec<String>>().join(",")
+}
+
+/// The Secret Network runtime of a contract execution. The randomness is derived from the randomness of the
+/// block, secret to the enclaves of the network, and the Mixer callouts are signed by the contract and logged
+/// for the operator to relay through `relayCallout` on the Mixer.
+pub struct SecretRuntime<'a> {
+    storage: RefCell<&'a mut dyn Storage>,
+    entropy: Vec<u8>,
+    callouts: RefCell<Vec<Attribute>>,
+}
+
+impl<'a> SecretRuntime<'a> {
+    /// Refused on the chains without block randomness, the keys of the contract never being derived from
+    /// entropy known outside of the enclaves
+    pub fn new(storage: &'a mut dyn Storage, env: &Env) -> StdResult<SecretRuntime<'a>> {
+        let random = env.block.random.as_ref()
+            .ok_or_else(|| StdError::generic_err("No block randomness on this chain"))?;
+        let mut entropy: Vec<u8> = random.0.clone();
+        entropy.extend_from_slice(env.contract.address.as_bytes());
+        Ok(SecretRuntime { storage: RefCell::new(storage), entropy, callouts: RefCell::new(Vec::new()) })
+    }
+
+    /// The Mixer callouts logged by the execution
+    pub fn into_log(self) -> Vec<Attribute> {
+        self.callouts.into_inner()
+    }
+
+    /// Log the callout `signature` of the Mixer contract deployed at `mixer_eth_addr`, along with its calldata
+    /// signed by the signing key of the contract under the next callout nonce:
+    /// sign(keccak256(mixer || nonce || calldata)), as checked by `relayCallout` on the Mixer
+    fn log_callout(&self, signature: &str, mixer_eth_addr: &str, deal_id: Option<&H256>, tokens: &[Token], fields: Vec<Attribute>) {
+        let nonce: u64 = self.read_state(CALLOUT_NONCE).unwrap_or(0);
+        self.write_state(CALLOUT_NONCE, nonce + 1);
+        let calldata = calldata::encode(signature, tokens);
+        let mut message: Vec<u8> = mixer_eth_addr.from_hex().unwrap_or_default();
+        message.extend_from_slice(&convert::uint_to_word(&U256::from(nonce)));
+        message.extend_from_slice(&calldata);
+        let callout_signature = kdf::keypair(self, kdf::SIGNING_V1).sign(&message).unwrap();
+        let mut callouts = self.callouts.borrow_mut();
+        callouts.push(attr("callout", &signature[..signature.find('(').unwrap_or(signature.len())]));
+        callouts.push(attr("mixer", mixer_eth_addr));
+        if let Some(deal_id) = deal_id {
+            callouts.push(attr("deal_id", deal_id.to_hex::<String>()));
+        }
+        callouts.extend(fields);
+        callouts.push(attr("nonce", nonce.to_string()));
+        callouts.push(attr("calldata", calldata.to_hex::<String>()));
+        callouts.push(attr("signature", callout_signature.to_hex::<String>()));
+    }
+}
+
+impl<'a> Runtime for SecretRuntime<'a> {
+    fn random_seed(&self) -> u64 {
+        let mut seed = [0_u8; 8];
+        seed.copy_from_slice(&self.generate_key()[..8]);
+        u64::from_be_bytes(seed)
+    }
+
+    /// SHA256(block randomness || contract || counter), each draw incrementing the counter held in the state
+    fn generate_key(&self) -> SymmetricKey {
+        let counter: u64 = self.read_state(ENTROPY_COUNTER).unwrap_or(0);
+        self.write_state(ENTROPY_COUNTER, counter + 1);
+        let mut hasher = Sha256::new();
+        hasher.update(&self.entropy);
+        hasher.update(&counter.to_be_bytes());
+        let mut key = [0_u8; 32];
+        key.copy_from_slice(&hasher.finalize());
+        key
+    }
+
+    fn encrypt(&self, plaintext: &[u8], key: &SymmetricKey) -> Vec<u8> {
+        let iv = self.generate_key();
+        let cipher = Aes256Gcm::new(GenericArray::from_slice(key));
+        let mut ciphertext = cipher.encrypt(GenericArray::from_slice(&iv[..IV_SIZE]), plaintext).unwrap();
+        ciphertext.extend_from_slice(&iv[..IV_SIZE]);
+        ciphertext
+    }
+
+    fn decrypt(&self, ciphertext: &[u8], key: &SymmetricKey) -> Result<Vec<u8>, String> {
+        if ciphertext.len() < IV_SIZE {
+            return Err(format!("Ciphertext too short: {}", ciphertext.len()));
+        }
+        let (ciphertext, iv) = ciphertext.split_at(ciphertext.len() - IV_SIZE);
+        let cipher = Aes256Gcm::new(GenericArray::from_slice(key));
+        cipher.decrypt(GenericArray::from_slice(iv), ciphertext).map_err(|_| "Ciphertext failing authentication".to_string())
+    }
+
+    /// The CosmWasm runtime has no debug output
+    fn trace(&self, _line: &str) {}
+
+    /// The gas meter of the Secret Network runtime is not exposed to the contract
+    fn cost_counter(&self) -> Option<u64> {
+        None
+    }
+
+    fn read_state<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.storage.borrow().get(key.as_bytes()).map(|value| from_slice(&value).unwrap())
+    }
+
+    fn write_state<T: Serialize>(&self, key: &str, value: T) {
+        self.storage.borrow_mut().set(key.as_bytes(), &to_vec(&value).unwrap());
+    }
+
+    fn commit_roots(&self, mixer_eth_addr: &str, deal_id: &H256, participants_root: &H256, outputs_root: &H256) {
+        self.log_callout("commitRoots(uint256,uint256,uint256)", mixer_eth_addr, Some(deal_id), &[
+            Token::Uint(convert::hash_to_uint(deal_id)),
+            Token::Uint(convert::hash_to_uint(participants_root)),
+            Token::Uint(convert::hash_to_uint(outputs_root)),
+        ], vec![
+            attr("participants_root", participants_root.to_hex::<String>()),
+            attr("outputs_root", outputs_root.to_hex::<String>()),
+        ]);
+    }
+
+    fn distribute(
+        &self,
+        mixer_eth_addr: &str,
+        deal_id: &H256,
+        recipients: Vec<H160>,
+        amounts: Vec<U256>,
+        redeposited: U256,
+        redeemed: U256,
+        receipt_hash: &H256,
+    ) {
+        let fields = vec![
+            attr("recipients", join(&recipients)),
+            attr("amounts", amounts.iter().map(|amount| amount.to_string()).collect::<Vec<String>>().join(",")),
+            attr("redeposited", redeposited.to_string()),
+            attr("redeemed", redeemed.to_string()),
+            attr("receipt_hash", receipt_hash.to_hex::<String>()),
+        ];
+        self.log_callout("distribute(uint256,address[],uint256[],uint256,uint256,uint256)", mixer_eth_addr, Some(deal_id), &[
+            Token::Uint(convert::hash_to_uint(deal_id)),
+            Token::Addresses(recipients),
+            Token::Uints(amounts),
+            Token::Uint(redeposited),
+            Token::Uint(redeemed),
+            Token::Uint(convert::hash_to_uint(receipt_hash)),
+        ], fields);
+    }
+
+    fn distribute_with_escrow(
+        &self,
+        mixer_eth_addr: &str,
+        deal_id: &H256,
+        recipients: Vec<H160>,
+        guardians: Vec<H160>,
+        amounts: Vec<U256>,
+        redeposited: U256,
+        redeemed: U256,
+        receipt_hash: &H256,
+    ) {
+        let fields = vec![
+            attr("recipients", join(&recipients)),
+            attr("guardians", join(&guardians)),
+            attr("amounts", amounts.iter().map(|amount| amount.to_string()).collect::<Vec<String>>().join(",")),
+            attr("redeposited", redeposited.to_string()),
+            attr("redeemed", redeemed.to_string()),
+            attr("receipt_hash", receipt_hash.to_hex::<String>()),
+        ];
+        self.log_callout("distributeWithEscrow(uint256,address[],address[],uint256[],uint256,uint256,uint256)", mixer_eth_addr, Some(deal_id), &[
+            Token::Uint(convert::hash_to_uint(deal_id)),
+            Token::Addresses(recipients),
+            Token::Addresses(guardians),
+            Token::Uints(amounts),
+            Token::Uint(redeposited),
+            Token::Uint(redeemed),
+            Token::Uint(convert::hash_to_uint(receipt_hash)),
+        ], fields);
+    }
+
+    fn distribute_assets(
+        &self,
+        mixer_eth_addr: &str,
+        deal_id: &H256,
+        assets: Vec<H160>,
+        payees: Vec<H160>,
+        recipients: Vec<H160>,
+        amounts: Vec<U256>,
+        receipt_hash: &H256,
+    ) {
+        let fields = vec![
+            attr("assets", join(&assets)),
+            attr("payees", join(&payees)),
+            attr("recipients", join(&recipients)),
+            attr("amounts", amounts.iter().map(|amount| amount.to_string()).collect::<Vec<String>>().join(",")),
+            attr("receipt_hash", receipt_hash.to_hex::<String>()),
+        ];
+        self.log_callout("distributeAssets(uint256,address[],address[],address[],uint256[],uint256)", mixer_eth_addr, Some(deal_id), &[
+            Token::Uint(convert::hash_to_uint(deal_id)),
+            Token::Addresses(assets),
+            Token::Addresses(payees),
+            Token::Addresses(recipients),
+            Token::Uints(amounts),
+            Token::Uint(convert::hash_to_uint(receipt_hash)),
+        ], fields);
+    }
+
+    fn distribute_to_bridge(
+        &self,
+        mixer_eth_addr: &str,
+        deal_id: &H256,
+        recipients: Vec<H160>,
+        amounts: Vec<U256>,
+        redeposited: U256,
+        redeemed: U256,
+        receipt_hash: &H256,
+    ) {
+        let fields = vec![
+            attr("recipients", join(&recipients)),
+            attr("amounts", amounts.iter().map(|amount| amount.to_string()).collect::<Vec<String>>().join(",")),
+            attr("redeposited", redeposited.to_string()),
+            attr("redeemed", redeemed.to_string()),
+            attr("receipt_hash", receipt_hash.to_hex::<String>()),
+        ];
+        self.log_callout("distributeToBridge(uint256,address[],uint256[],uint256,uint256,uint256)", mixer_eth_addr, Some(deal_id), &[
+            Token::Uint(convert::hash_to_uint(deal_id)),
+            Token::Addresses(recipients),
+            Token::Uints(amounts),
+            Token::Uint(redeposited),
+            Token::Uint(redeemed),
+            Token::Uint(convert::hash_to_uint(receipt_hash)),
+        ], fields);
+    }
+
+    fn set_payout_bridge(&self, mixer_eth_addr: &str, bridge: &H160) {
+        self.log_callout("setPayoutBridge(address)", mixer_eth_addr, None, &[Token::Address(*bridge)], vec![
+            attr("bridge", bridge.to_hex::<String>()),
+        ]);
+    }
+
+    fn record_receipt(&self, mixer_eth_addr: &str, deal_id: &H256, receipt_hash: &H256) {
+        self.log_callout("recordReceipt(uint256,uint256)", mixer_eth_addr, Some(deal_id), &[
+            Token::Uint(convert::hash_to_uint(deal_id)),
+            Token::Uint(convert::hash_to_uint(receipt_hash)),
+        ], vec![
+            attr("receipt_hash", receipt_hash.to_hex::<String>()),
+        ]);
+    }
+
+    fn refund_deal(&self, mixer_eth_addr: &str, deal_id: &H256) {
+        self.log_callout("refundDeal(uint256)", mixer_eth_addr, Some(deal_id), &[Token::Uint(convert::hash_to_uint(deal_id))], Vec::new());
+    }
+
+    fn commit_note_payouts(&self, mixer_eth_addr: &str, deal_id: &H256, payouts_root: &H256, nb_payouts: usize) {
+        self.log_callout("commitNotePayouts(uint256,uint256,uint256)", mixer_eth_addr, Some(deal_id), &[
+            Token::Uint(convert::hash_to_uint(deal_id)),
+            Token::Uint(convert::hash_to_uint(payouts_root)),
+            Token::Uint(U256::from(nb_payouts)),
+        ], vec![
+            attr("payouts_root", payouts_root.to_hex::<String>()),
+            attr("nb_payouts", nb_payouts.to_string()),
+        ]);
+    }
+
+    fn withdraw_note(
+        &self,
+        mixer_eth_addr: &str,
+        deal_id: &H256,
+        nullifier: &H256,
+        payout: &H160,
+        amount: &U256,
+        index: usize,
+        proof: &[H256],
+    ) {
+        self.log_callout("withdrawNote(uint256,uint256,address,uint256,uint256,uint256[])", mixer_eth_addr, Some(deal_id), &[
+            Token::Uint(convert::hash_to_uint(deal_id)),
+            Token::Uint(convert::hash_to_uint(nullifier)),
+            Token::Address(*payout),
+            Token::Uint(*amount),
+            Token::Uint(U256::from(index)),
+            Token::Uints(proof.iter().map(convert::hash_to_uint).collect()),
+        ], vec![
+            attr("nullifier", nullifier.to_hex::<String>()),
+            attr("payout", payout.to_hex::<String>()),
+            attr("amount", amount.to_string()),
+            attr("index", index.to_string()),
+            attr("proof", join(proof)),
+        ]);
+    }
+}
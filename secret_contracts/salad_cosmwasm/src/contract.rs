@@ -0,0 +1,370 @@
+use cosmwasm_std::{
+    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+};
+use enigma_crypto::hash::Keccak256;
+use enigma_crypto::KeyPair;
+use rustc_hex::{FromHex, ToHex};
+use salad_types::{H160, H256, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::backend::SecretRuntime;
+use crate::digest::{self, DepositHasher, DepositTerms};
+use crate::msg::{HandleMsg, InitMsg, QueryMsg};
+use crate::protocol::{self, ADDRESS_SIZE, ENCRYPTION_OVERHEAD, PUB_KEY_SIZE};
+use crate::runtime::Runtime;
+use crate::state::Address;
+use crate::wire::{self, DepositInput};
+use crate::{amounts, kdf, merkle, oracle, parsing, profile, shuffle, validation};
+
+// State key name "mixer_eth_addr" holding the hex address of the Mixer contract on Ethereum
+static MIXER_ETH_ADDR: &str = "mixer_eth_addr";
+// State key name "output_ordering" holding the output ordering policy of the deployment
+static OUTPUT_ORDERING: &str = "output_ordering";
+// State key name "operator_keys" holding the (operator, signer) addresses of the operators allowed to execute deals
+static OPERATOR_KEYS: &str = "operator_keys";
+// State key name "encryption_pub_key" holding the public key the deposit payloads are encrypted to
+static ENCRYPTION_PUB_KEY: &str = "encryption_pub_key";
+// State key name "signing_address" holding the Ethereum address of the key signing the Mixer callouts
+static SIGNING_ADDRESS: &str = "signing_address";
+// State key name "challenge_window" holding the blocks between the commitment of a deal and its distribution
+static CHALLENGE_WINDOW: &str = "challenge_window";
+// State key prefix of the shuffled outputs of each committed deal, followed by the hex DealId
+static DEAL_PREFIX: &str = "deal_";
+
+/// The shuffled outputs of a deal committed on the Mixer contract, until distribution
+#[derive(Serialize, Deserialize)]
+struct CommittedDeal {
+    recipients: Vec<String>,
+    amounts: Vec<String>,
+    receipt_hash: String,
+    distributed: bool,
+    /// The account which executed the deal, alone allowed to finalize its distribution
+    executor: String,
+    /// The block the deal was committed at, the distribution waiting for the challenge window past it
+    commit_height: u64,
+}
+
+/// Parse a hex address, with or without `0x`, into its raw bytes, the mixed-case (checksummed) and lowercase
+/// spellings of an address parsing to the same address
+fn parse_address(value: &str) -> StdResult<H160> {
+    let raw: Vec<u8> = value.trim_start_matches("0x").from_hex().map_err(|_| StdError::generic_err(format!("Invalid address: {}", value)))?;
+    if raw.len() != ADDRESS_SIZE {
+        return Err(StdError::generic_err(format!("Invalid address: {}", value)));
+    }
+    Ok(H160::from(&raw[..]))
+}
+
+fn parse_addresses(values: &[String]) -> StdResult<Vec<H160>> {
+    values.iter().map(|value| parse_address(value)).collect()
+}
+
+fn parse_amount(value: &str) -> StdResult<U256> {
+    U256::from_dec_str(value).map_err(|_| StdError::generic_err(format!("Invalid amount: {}", value)))
+}
+
+fn parse_hash(value: &str) -> StdResult<H256> {
+    let raw: Vec<u8> = value.trim_start_matches("0x").from_hex().map_err(|_| StdError::generic_err(format!("Invalid hash: {}", value)))?;
+    if raw.len() != 32 {
+        return Err(StdError::generic_err(format!("Invalid hash: {}", value)));
+    }
+    Ok(H256::from(&raw[..]))
+}
+
+fn deal_key(deal_id: &H256) -> String {
+    format!("{}{}", DEAL_PREFIX, deal_id.to_hex::<String>())
+}
+
+/// The key pair the deposit payloads are encrypted to, derived as in the Enigma secret contract
+fn keypair<R: Runtime>(runtime: &R) -> KeyPair {
+    kdf::keypair(runtime, kdf::ENCRYPTION_V1)
+}
+
+/// Refuse the features of the Enigma secret contract this build does not run, rather than deploying without them
+fn check_supported(msg: &InitMsg) -> StdResult<()> {
+    let unsupported = [
+        ("recipient allowlist", msg.recipient_allowlist_root.is_some()),
+        ("velocity limits", msg.velocity_max_deposits.is_some() || msg.velocity_max_volume.is_some()),
+        ("auditors", msg.auditor_pub_keys.is_some()),
+        ("dust coalescing", msg.dust_threshold.is_some()),
+        ("dummy outputs", msg.max_dummy_outputs.is_some()),
+        ("buckets", msg.bucket_unit.is_some()),
+        ("cancellations", msg.cancel_quorum.is_some()),
+    ];
+    match unsupported.iter().find(|(_, set)| *set) {
+        Some((feature, _)) => Err(StdError::generic_err(format!("Unsupported on Secret Network: {}", feature))),
+        None => Ok(()),
+    }
+}
+
+#[entry_point]
+pub fn instantiate(deps: DepsMut, env: Env, _info: MessageInfo, msg: InitMsg) -> StdResult<Response> {
+    check_supported(&msg)?;
+    if msg.output_ordering > shuffle::ORDERING_SORTED_BY_ADDRESS {
+        return Err(StdError::generic_err(format!("Unknown output ordering policy: {}", msg.output_ordering)));
+    }
+    if msg.operator_addresses.len() != msg.operator_signers.len() {
+        return Err(StdError::generic_err("Operator addresses and signers of different lengths"));
+    }
+    let mixer_eth_addr: String = parse_address(&msg.mixer_eth_addr)?.to_hex();
+    let oracle_signers = parse_addresses(&msg.oracle_signers)?;
+    let operator_keys: Vec<(Address, Address)> = parse_addresses(&msg.operator_addresses)?.into_iter()
+        .zip(parse_addresses(&msg.operator_signers)?)
+        .map(|(operator, signer)| (Address(operator), Address(signer)))
+        .collect();
+    let runtime = SecretRuntime::new(deps.storage, &env)?;
+    profile::set(&runtime, &U256::from(msg.network_profile));
+    oracle::set_signers(&runtime, &oracle_signers);
+    amounts::set_bounds(&runtime, &parse_amount(&msg.min_amount)?, &parse_amount(&msg.max_amount)?);
+    amounts::set_value_cap(&runtime, &parse_amount(&msg.max_deal_value)?);
+    runtime.write_state(OPERATOR_KEYS, operator_keys);
+    runtime.write_state(MIXER_ETH_ADDR, mixer_eth_addr);
+    runtime.write_state(OUTPUT_ORDERING, msg.output_ordering);
+    runtime.write_state(CHALLENGE_WINDOW, msg.challenge_window);
+    kdf::init(&runtime);
+    runtime.write_state(ENCRYPTION_PUB_KEY, keypair(&runtime).get_pubkey().to_vec());
+    let signing_address = H160::from(&kdf::keypair(&runtime, kdf::SIGNING_V1).get_pubkey()[..].keccak256()[12..32]);
+    runtime.write_state(SIGNING_ADDRESS, signing_address.to_hex::<String>());
+    Ok(Response::default())
+}
+
+#[entry_point]
+pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: HandleMsg) -> StdResult<Response> {
+    let runtime = SecretRuntime::new(deps.storage, &env)?;
+    match msg {
+        HandleMsg::ExecuteDeal {
+            operator_address,
+            operator_nonce,
+            amount,
+            pub_keys,
+            enc_recipients,
+            senders,
+            signatures,
+            expiries,
+            chain_id,
+            block_attestation,
+            block_signature,
+            operator_signature,
+        } => {
+            let senders = parse_addresses(&senders)?;
+            let expiries = expiries.iter().map(|expiry| parse_amount(expiry)).collect::<StdResult<Vec<U256>>>()?;
+            let pub_keys: Vec<Vec<u8>> = pub_keys.into_iter().map(|pub_key| pub_key.0).collect();
+            let enc_recipients: Vec<Vec<u8>> = enc_recipients.into_iter().map(|enc_recipient| enc_recipient.0).collect();
+            let signatures: Vec<Vec<u8>> = signatures.into_iter().map(|signature| signature.0).collect();
+            execute_deal(
+                &runtime,
+                &info,
+                &env,
+                &parse_address(&operator_address)?,
+                &parse_amount(&operator_nonce)?,
+                &parse_amount(&amount)?,
+                &pub_keys,
+                &enc_recipients,
+                &senders,
+                &signatures,
+                &expiries,
+                &parse_amount(&chain_id)?,
+                &block_attestation.0,
+                &block_signature.0,
+                &operator_signature.0,
+            )?;
+        }
+        HandleMsg::FinalizeDistribution { deal_id } => {
+            finalize_distribution(&runtime, &info, &env, &parse_hash(&deal_id)?)?;
+        }
+    }
+    Ok(Response::new().add_attributes(runtime.into_log()))
+}
+
+#[entry_point]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetPubKey {} => {
+            let pub_key = deps.storage.get(ENCRYPTION_PUB_KEY.as_bytes())
+                .ok_or_else(|| StdError::not_found("encryption public key"))?;
+            to_binary(&Binary(cosmwasm_std::from_slice::<Vec<u8>>(&pub_key)?))
+        }
+        QueryMsg::GetSigningAddress {} => {
+            let signing_address = deps.storage.get(SIGNING_ADDRESS.as_bytes())
+                .ok_or_else(|| StdError::not_found("signing address"))?;
+            to_binary(&cosmwasm_std::from_slice::<String>(&signing_address)?)
+        }
+    }
+}
+
+/// Verify the deal payload signed by the registered key of the operator, as the Enigma secret contract does,
+/// so that the deposits may not be modified or reordered by whoever submits the deal under the operator's name
+fn verify_operator<R: Runtime>(runtime: &R, operator: &H160, message: &[u8], raw_signature: &[u8]) -> StdResult<()> {
+    let operator_keys: Vec<(Address, Address)> = runtime.read_state(OPERATOR_KEYS).unwrap_or_default();
+    let signer = operator_keys.into_iter().find(|(registered, _)| registered.0 == *operator)
+        .map(|(_, signer)| signer.0)
+        .ok_or_else(|| StdError::generic_err(format!("Unregistered operator: {:?}", operator)))?;
+    let signature = protocol::ecdsa_signature(raw_signature)
+        .ok_or_else(|| StdError::generic_err(format!("Invalid operator signature size: {}", raw_signature.len())))?;
+    let recovered = digest::recover_address(message, signature)
+        .map_err(|err| StdError::generic_err(format!("Invalid operator signature: {}", err)))?;
+    if recovered != signer {
+        return Err(StdError::generic_err(format!("Deal payload not signed by the key of operator {:?}: {:?}", operator, recovered)));
+    }
+    Ok(())
+}
+
+/// Decrypt the payload of a deposit as the Enigma secret contract, salted payloads included, failing on the
+/// payloads the runtime cannot decrypt
+fn open_deposit<R: Runtime>(runtime: &R, keypair: &KeyPair, input: &DepositInput) -> Result<([u8; PUB_KEY_SIZE], Vec<u8>), String> {
+    let i = input.index;
+    if input.pub_key.len() != PUB_KEY_SIZE {
+        return Err(format!("Invalid pubKey size for participant {}: {}", i, input.pub_key.len()));
+    }
+    let (salt, enc_recipient) = kdf::split_salt(input.enc_recipient);
+    let payload_size = enc_recipient.len().saturating_sub(ENCRYPTION_OVERHEAD);
+    if payload_size < ADDRESS_SIZE || payload_size > protocol::envelope().max_payload_size() {
+        return Err(format!("Invalid encrypted payload size for participant {}: {}", i, enc_recipient.len()));
+    }
+    let mut user_pubkey = [0_u8; PUB_KEY_SIZE];
+    user_pubkey.copy_from_slice(input.pub_key);
+    let shared_key = kdf::shared_key(keypair, &user_pubkey, salt)
+        .map_err(|err| format!("Unable to derive shared key for participant {}: {}", i, err))?;
+    let plaintext = runtime.decrypt(enc_recipient, &shared_key)
+        .map_err(|err| format!("Unable to decrypt payload of participant {}: {}", i, err))?;
+    Ok((user_pubkey, plaintext))
+}
+
+/// Verify a decrypted deposit, returning its outputs. The deposits relying on redeposits, guardians, notes or
+/// the non-ECDSA signature modes, which this build does not run, are refunded to their sender.
+fn verify_deposit(
+    hasher: &DepositHasher,
+    input: &DepositInput,
+    amount: &U256,
+    payload: ([u8; PUB_KEY_SIZE], Vec<u8>),
+) -> Result<Vec<(H160, U256)>, String> {
+    let i = input.index;
+    let (user_pubkey, plaintext) = payload;
+    let payload = parsing::parse_payload(i, &plaintext)?;
+    let raw_signature = match protocol::ecdsa_signature(input.signature) {
+        Some(raw_signature) => raw_signature,
+        None => return Ok(vec![(*input.sender, *amount)]),
+    };
+    let message = hasher.message(input.sender, DepositTerms::Expiring(input.expiry), amount, input.enc_recipient, &user_pubkey);
+    let sig_sender = digest::recover_address(&message, raw_signature)?;
+    if sig_sender != *input.sender {
+        return Err(format!("Invalid sender recovered from the signature: {:?} != {:?}", sig_sender, input.sender));
+    }
+    let notes = payload.splits.iter().any(|(_, percent)| parsing::is_note(*percent));
+    if payload.redeposit.is_some() || payload.guardian.is_some() || notes {
+        return Ok(vec![(*input.sender, *amount)]);
+    }
+    Ok(parsing::expand_splits(amount, &payload.splits))
+}
+
+/// Verify and shuffle the deposits, and commit the Merkle roots of the deal on the Mixer contract. The deal is
+/// checked as in the Enigma secret contract: the deposits that cannot be decrypted or are expired at the attested
+/// block are refunded to their sender among the deal outputs, and the deal fails on the deposits failing
+/// verification or too recently confirmed, the operator having committed to them.
+fn execute_deal<R: Runtime>(
+    runtime: &R,
+    info: &MessageInfo,
+    env: &Env,
+    operator_address: &H160,
+    operator_nonce: &U256,
+    amount: &U256,
+    pub_keys: &[Vec<u8>],
+    enc_recipients: &[Vec<u8>],
+    senders: &[H160],
+    signatures: &[Vec<u8>],
+    expiries: &[U256],
+    chain_id: &U256,
+    block_attestation: &[u8],
+    block_signature: &[u8],
+    operator_signature: &[u8],
+) -> StdResult<()> {
+    validation::try_check_participants(pub_keys, enc_recipients, senders, signatures, expiries)
+        .map_err(StdError::generic_err)?;
+    profile::check_chain_id(runtime, chain_id);
+    let deal_id = digest::deal_id(amount, &senders.to_vec(), operator_address, operator_nonce);
+    // No deal features nor aggregate signature on Secret Network
+    let payload_message = digest::payload_message(&deal_id, pub_keys, enc_recipients, signatures, expiries, chain_id, &U256::zero(), &[]);
+    verify_operator(runtime, operator_address, &payload_message, operator_signature)?;
+    amounts::check(runtime, amount);
+    amounts::check_value(runtime, amount, senders.len());
+    if runtime.read_state::<CommittedDeal>(&deal_key(&deal_id)).is_some() {
+        return Err(StdError::generic_err(format!("Deal already executed: {}", deal_id.to_hex::<String>())));
+    }
+    let (attested_block, immature) = oracle::immature_deposits(runtime, block_attestation, block_signature, senders);
+    if !immature.is_empty() {
+        return Err(StdError::generic_err(format!("Immature deposits of participants {:?}", immature)));
+    }
+    let keypair = keypair(runtime);
+    let hasher = DepositHasher::new(protocol::message(), chain_id);
+    let mut outputs: Vec<(H160, U256)> = Vec::new();
+    for input in wire::deposits(pub_keys, enc_recipients, senders, signatures, expiries) {
+        let payload = match open_deposit(runtime, &keypair, &input) {
+            Ok(payload) => payload,
+            Err(_) => {
+                outputs.push((*input.sender, *amount));
+                continue;
+            }
+        };
+        match verify_deposit(&hasher, &input, amount, payload) {
+            // The depositor signed the expiry, so that its funds are never mixed under stale terms
+            Ok(_) if U256::from(attested_block) > *input.expiry => outputs.push((*input.sender, *amount)),
+            Ok(deposit_outputs) => outputs.extend(deposit_outputs),
+            Err(reason) => return Err(StdError::generic_err(format!("Rejected deposit of participant {}: {}", input.index, reason))),
+        }
+    }
+    let ordering: u8 = runtime.read_state(OUTPUT_ORDERING).unwrap_or(shuffle::ORDERING_RANDOM);
+    let shuffler = shuffle::shuffler(ordering, runtime.random_seed());
+    shuffle::apply(&*shuffler, &mut outputs, |(recipient, _)| *recipient);
+    let recipients: Vec<H160> = outputs.iter().map(|(recipient, _)| *recipient).collect();
+    let receipt_hash = digest::receipt_hash(&deal_id, attested_block, &recipients);
+    let participant_leaves: Vec<Vec<u8>> = senders.iter().map(|sender| sender.to_vec()).collect();
+    let output_leaves: Vec<Vec<u8>> = outputs.iter().map(|(recipient, amount)| {
+        let mut leaf = recipient.to_vec();
+        leaf.extend_from_slice(&H256::from(amount));
+        leaf
+    }).collect();
+    let mixer_eth_addr = mixer_eth_addr(runtime)?;
+    runtime.commit_roots(&mixer_eth_addr, &deal_id, &merkle::root(&participant_leaves), &merkle::root(&output_leaves));
+    runtime.write_state(&deal_key(&deal_id), CommittedDeal {
+        recipients: recipients.iter().map(|recipient| recipient.to_hex()).collect(),
+        amounts: outputs.iter().map(|(_, amount)| amount.to_string()).collect(),
+        receipt_hash: receipt_hash.to_hex(),
+        distributed: false,
+        executor: info.sender.to_string(),
+        commit_height: env.block.height,
+    });
+    Ok(())
+}
+
+fn mixer_eth_addr<R: Runtime>(runtime: &R) -> StdResult<String> {
+    runtime.read_state(MIXER_ETH_ADDR).ok_or_else(|| StdError::not_found("Mixer address"))
+}
+
+/// Distribute a committed deal once the challenge window past its commitment has elapsed, leaving the
+/// participants the time to check the committed roots on the Mixer contract before their funds move.
+/// Only the account which executed the deal may finalize it.
+fn finalize_distribution<R: Runtime>(runtime: &R, info: &MessageInfo, env: &Env, deal_id: &H256) -> StdResult<()> {
+    let mut deal: CommittedDeal = runtime.read_state(&deal_key(deal_id))
+        .ok_or_else(|| StdError::generic_err(format!("Unknown deal: {}", deal_id.to_hex::<String>())))?;
+    if deal.distributed {
+        return Err(StdError::generic_err(format!("Deal already distributed: {}", deal_id.to_hex::<String>())));
+    }
+    if info.sender.as_str() != deal.executor {
+        return Err(StdError::generic_err(format!("Deal not executed by {}: {}", info.sender, deal_id.to_hex::<String>())));
+    }
+    let challenge_window: u64 = runtime.read_state(CHALLENGE_WINDOW).unwrap_or(0);
+    if env.block.height < deal.commit_height.saturating_add(challenge_window) {
+        return Err(StdError::generic_err(format!(
+            "Challenge window of deal {} open until block {}",
+            deal_id.to_hex::<String>(),
+            deal.commit_height.saturating_add(challenge_window),
+        )));
+    }
+    let recipients = parse_addresses(&deal.recipients)?;
+    let amounts = deal.amounts.iter().map(|amount| parse_amount(amount)).collect::<StdResult<Vec<U256>>>()?;
+    let receipt_hash = parse_hash(&deal.receipt_hash)?;
+    let mixer_eth_addr = mixer_eth_addr(runtime)?;
+    runtime.distribute(&mixer_eth_addr, deal_id, recipients, amounts, U256::zero(), U256::zero(), &receipt_hash);
+    deal.distributed = true;
+    runtime.write_state(&deal_key(deal_id), deal);
+    Ok(())
+}
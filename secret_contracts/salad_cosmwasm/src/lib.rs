@@ -0,0 +1,13 @@
+//! Secret Network (CosmWasm) build of the Salad deal execution, running the deal core shared with
+//! the Enigma secret contract against the CosmWasm runtime. The Mixer contract stays on Ethereum,
+//! the operator relaying the Mixer callouts signed by the contract. The features of the Enigma secret contract
+//! this build does not run (compliance policies, auditors, dust coalescing, dummy outputs, buckets, cancellations)
+//! are refused at instantiation, and the deposits relying on redeposits, guardians, notes or the non-ECDSA
+//! signature modes are refunded.
+
+use salad_core::{amounts, kdf, oracle, parsing, profile, runtime, shuffle, state, validation, wire};
+use salad_types::{calldata, digest, merkle, protocol};
+
+mod backend;
+pub mod contract;
+pub mod msg;
@@ -0,0 +1,77 @@
+use cosmwasm_std::Binary;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Addresses are hex without prefix and amounts decimal strings, as in the Enigma task arguments.
+/// The contract keys are derived from the randomness of the instantiation block, which the deployer cannot know.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InitMsg {
+    pub mixer_eth_addr: String,
+    /// Signers of the block attestations the expiries and ages of the deposits are checked against
+    pub oracle_signers: Vec<String>,
+    /// 0 for a random shuffle, 1 for outputs sorted by recipient address
+    pub output_ordering: u8,
+    /// Operators allowed to execute deals, each with the key signing its deal payloads in `operator_signers`
+    pub operator_addresses: Vec<String>,
+    pub operator_signers: Vec<String>,
+    pub min_amount: String,
+    /// Zero leaving the amounts unbounded
+    pub max_amount: String,
+    /// Max amount × participants of a deal, zero leaving the deal values unbounded
+    pub max_deal_value: String,
+    /// The network profile of the Mixer, as in the Enigma secret contract
+    pub network_profile: u8,
+    /// Blocks past the commitment of a deal before its distribution may be finalized
+    pub challenge_window: u64,
+    /// Features of the Enigma secret contract not run by this build, refused when set
+    #[serde(default)]
+    pub recipient_allowlist_root: Option<String>,
+    #[serde(default)]
+    pub velocity_max_deposits: Option<String>,
+    #[serde(default)]
+    pub velocity_max_volume: Option<String>,
+    #[serde(default)]
+    pub auditor_pub_keys: Option<Vec<Binary>>,
+    #[serde(default)]
+    pub dust_threshold: Option<String>,
+    #[serde(default)]
+    pub max_dummy_outputs: Option<String>,
+    #[serde(default)]
+    pub bucket_unit: Option<String>,
+    #[serde(default)]
+    pub cancel_quorum: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HandleMsg {
+    /// Verify and shuffle the deposits of a deal, logging the `commitRoots` callout of the Mixer contract.
+    /// The deal payload is signed by the operator as in the Enigma secret contract, without deal features
+    /// nor aggregate signature, and the block attestation is signed by an oracle signer.
+    ExecuteDeal {
+        operator_address: String,
+        operator_nonce: String,
+        amount: String,
+        pub_keys: Vec<Binary>,
+        enc_recipients: Vec<Binary>,
+        senders: Vec<String>,
+        signatures: Vec<Binary>,
+        expiries: Vec<String>,
+        chain_id: String,
+        block_attestation: Binary,
+        block_signature: Binary,
+        operator_signature: Binary,
+    },
+    /// Log the `distribute` callout of the Mixer contract for a committed deal, by the account which executed
+    /// it and once the challenge window past its commitment has elapsed
+    FinalizeDistribution { deal_id: String },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// The encryption public key of the contract
+    GetPubKey {},
+    /// The Ethereum address of the key signing the Mixer callouts, to be set as `secretSigner` on the Mixer
+    GetSigningAddress {},
+}
@@ -0,0 +1,49 @@
+use enigma_crypto::hash::Keccak256;
+use ethereum_types::{H160, U256};
+
+use crate::convert;
+
+/// An argument of a Mixer callout, as ABI encoded
+pub enum Token {
+    Uint(U256),
+    Address(H160),
+    Uints(Vec<U256>),
+    Addresses(Vec<H160>),
+}
+
+fn uint_word(value: &U256) -> Vec<u8> {
+    convert::uint_to_word(value).to_vec()
+}
+
+fn address_word(address: &H160) -> Vec<u8> {
+    convert::address_to_word(address).to_vec()
+}
+
+/// The ABI-encoded calldata of the Mixer callout of canonical signature `name(type,...)`,
+/// the selector followed by the head and the tail of the arguments
+pub fn encode(signature: &str, tokens: &[Token]) -> Vec<u8> {
+    let mut calldata = signature.as_bytes().keccak256()[..4].to_vec();
+    let mut head: Vec<u8> = Vec::new();
+    let mut tail: Vec<u8> = Vec::new();
+    for token in tokens.iter() {
+        let items: Vec<Vec<u8>> = match token {
+            Token::Uint(value) => {
+                head.extend(uint_word(value));
+                continue;
+            }
+            Token::Address(address) => {
+                head.extend(address_word(address));
+                continue;
+            }
+            Token::Uints(values) => values.iter().map(uint_word).collect(),
+            Token::Addresses(addresses) => addresses.iter().map(address_word).collect(),
+        };
+        // Dynamic arrays are referenced from the head by their offset from the start of the arguments
+        head.extend(uint_word(&U256::from(tokens.len() * 32 + tail.len())));
+        tail.extend(uint_word(&U256::from(items.len())));
+        tail.extend(items.into_iter().flatten());
+    }
+    calldata.extend(head);
+    calldata.extend(tail);
+    calldata
+}
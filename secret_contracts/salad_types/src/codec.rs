@@ -0,0 +1,265 @@
+use ethereum_types::{H160, H256, U256};
+
+const OFFSET_SIZE: usize = 4;
+
+/// SSZ-style canonical encoder of the receipts and state records.
+/// A container is serialized as its fixed-size part followed by the contents of its variable-size fields:
+/// fixed-size fields (uint8, uint64, uint256 in little-endian and byte vectors) are inlined in order,
+/// each variable-size field (a list of fixed-size items) being replaced in the fixed-size part by the
+/// uint32 little-endian offset of its contents from the start of the container.
+#[derive(Default)]
+pub struct Encoder {
+    fixed: Vec<u8>,
+    variable: Vec<Vec<u8>>,
+    /// Position in the fixed-size part of the offset of each variable-size field
+    offset_positions: Vec<usize>,
+}
+
+impl Encoder {
+    pub fn append_u8(&mut self, value: u8) {
+        self.fixed.push(value);
+    }
+
+    pub fn append_u64(&mut self, value: u64) {
+        self.fixed.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn append_bytes(&mut self, value: &[u8]) {
+        self.fixed.extend_from_slice(value);
+    }
+
+    /// Append a variable-size list of fixed-size items, each serialized by `encode`
+    pub fn append_list<T, F: Fn(&T) -> Vec<u8>>(&mut self, items: &[T], encode: F) {
+        let mut contents: Vec<u8> = Vec::new();
+        for item in items.iter() {
+            contents.extend_from_slice(&encode(item));
+        }
+        self.offset_positions.push(self.fixed.len());
+        self.fixed.extend_from_slice(&[0_u8; OFFSET_SIZE]);
+        self.variable.push(contents);
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        let mut data = self.fixed;
+        let mut offset = data.len();
+        for (position, contents) in self.offset_positions.iter().zip(self.variable.iter()) {
+            data[*position..*position + OFFSET_SIZE].copy_from_slice(&(offset as u32).to_le_bytes());
+            offset += contents.len();
+        }
+        for contents in self.variable.iter() {
+            data.extend_from_slice(contents);
+        }
+        data
+    }
+}
+
+pub struct Decoder<'a> {
+    data: &'a [u8],
+    position: usize,
+    offsets: Vec<usize>,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(data: &'a [u8]) -> Decoder<'a> {
+        Decoder { data, position: 0, offsets: Vec::new() }
+    }
+
+    fn take(&mut self, len: usize) -> &'a [u8] {
+        if self.data.len() - self.position < len {
+            panic!("Truncated encoding at offset {}", self.position);
+        }
+        let value = &self.data[self.position..self.position + len];
+        self.position += len;
+        value
+    }
+
+    pub fn read_u8(&mut self) -> u8 {
+        self.take(1)[0]
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> &'a [u8] {
+        self.take(len)
+    }
+
+    /// Read the offset of the next variable-size field, its contents being returned by `finish`
+    pub fn read_offset(&mut self) {
+        let mut offset = [0_u8; OFFSET_SIZE];
+        offset.copy_from_slice(self.take(OFFSET_SIZE));
+        self.offsets.push(u32::from_le_bytes(offset) as usize);
+    }
+
+    /// Whether the fixed-size part holds more fields, telling the containers encoded before a field
+    /// was appended to them
+    pub fn has_more(&self) -> bool {
+        self.position < self.offsets.first().cloned().unwrap_or_else(|| self.data.len())
+    }
+
+    /// Return the contents of the variable-size fields, in order, once the fixed-size part is read
+    pub fn finish(self) -> Vec<&'a [u8]> {
+        let mut bounds = self.offsets.clone();
+        bounds.push(self.data.len());
+        match self.offsets.first() {
+            Some(first) if *first != self.position => panic!("Invalid first offset: {} != {}", first, self.position),
+            None if self.position != self.data.len() => panic!("Trailing bytes in encoding: {}", self.data.len() - self.position),
+            _ => {}
+        }
+        let data = self.data;
+        bounds.windows(2).map(move |bound| {
+            if bound[0] > bound[1] {
+                panic!("Decreasing offsets in encoding: {} > {}", bound[0], bound[1]);
+            }
+            &data[bound[0]..bound[1]]
+        }).collect()
+    }
+}
+
+/// Split the contents of a list into its items of `size` bytes
+pub fn items(contents: &[u8], size: usize) -> Vec<&[u8]> {
+    if contents.len() % size != 0 {
+        panic!("Invalid list size for items of {} bytes: {}", size, contents.len());
+    }
+    contents.chunks(size).collect()
+}
+
+pub fn encode_u64(value: &u64) -> Vec<u8> {
+    value.to_le_bytes().to_vec()
+}
+
+pub fn decode_u64(item: &[u8]) -> u64 {
+    let mut value = [0_u8; 8];
+    value.copy_from_slice(item);
+    u64::from_le_bytes(value)
+}
+
+pub fn encode_uint256(value: &U256) -> Vec<u8> {
+    let mut item = [0_u8; 32];
+    value.to_little_endian(&mut item);
+    item.to_vec()
+}
+
+pub fn decode_uint256(item: &[u8]) -> U256 {
+    U256::from_little_endian(item)
+}
+
+pub fn encode_address(value: &H160) -> Vec<u8> {
+    value.to_vec()
+}
+
+pub fn encode_hash(value: &H256) -> Vec<u8> {
+    value.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_a_container() {
+        let recipients = [H160::from(&[0x11; 20][..]), H160::from(&[0x22; 20][..])];
+        let amounts = [U256::from(1), U256::max_value()];
+        let mut encoder = Encoder::default();
+        encoder.append_u8(4);
+        encoder.append_list(&recipients, encode_address);
+        encoder.append_u64(7);
+        encoder.append_list(&amounts, encode_uint256);
+        let data = encoder.finish();
+
+        let mut decoder = Decoder::new(&data);
+        assert_eq!(decoder.read_u8(), 4);
+        decoder.read_offset();
+        assert_eq!(decode_u64(decoder.read_bytes(8)), 7);
+        decoder.read_offset();
+        assert!(!decoder.has_more());
+        let lists = decoder.finish();
+        let decoded: Vec<H160> = items(lists[0], 20).into_iter().map(H160::from).collect();
+        assert_eq!(decoded, recipients);
+        let decoded: Vec<U256> = items(lists[1], 32).into_iter().map(decode_uint256).collect();
+        assert_eq!(decoded, amounts);
+    }
+
+    #[test]
+    fn encodes_the_golden_container() {
+        let mut encoder = Encoder::default();
+        encoder.append_u8(1);
+        encoder.append_list(&[3_u64, 4], encode_u64);
+        encoder.append_bytes(&[0xab, 0xcd]);
+        encoder.append_list(&[] as &[u64], encode_u64);
+        let data = encoder.finish();
+        assert_eq!(data, vec![
+            0x01,
+            0x0b, 0x00, 0x00, 0x00,
+            0xab, 0xcd,
+            0x1b, 0x00, 0x00, 0x00,
+            0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ]);
+
+        let mut decoder = Decoder::new(&data);
+        assert_eq!(decoder.read_u8(), 1);
+        decoder.read_offset();
+        assert_eq!(decoder.read_bytes(2), &[0xab, 0xcd]);
+        decoder.read_offset();
+        let lists = decoder.finish();
+        assert_eq!(items(lists[0], 8).into_iter().map(decode_u64).collect::<Vec<u64>>(), vec![3, 4]);
+        assert!(lists[1].is_empty());
+    }
+
+    #[test]
+    fn encodes_the_integers_little_endian() {
+        assert_eq!(encode_u64(&0x0102), vec![0x02, 0x01, 0, 0, 0, 0, 0, 0]);
+        let mut expected = vec![0_u8; 32];
+        expected[0] = 0x02;
+        expected[1] = 0x01;
+        assert_eq!(encode_uint256(&U256::from(0x0102)), expected);
+        assert_eq!(decode_uint256(&expected), U256::from(0x0102));
+        assert_eq!(decode_uint256(&encode_uint256(&U256::max_value())), U256::max_value());
+    }
+
+    #[test]
+    #[should_panic(expected = "Truncated encoding")]
+    fn refuses_truncated_encodings() {
+        Decoder::new(&[1, 2]).read_bytes(3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Trailing bytes")]
+    fn refuses_trailing_bytes() {
+        let mut decoder = Decoder::new(&[1, 2]);
+        decoder.read_u8();
+        decoder.finish();
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid first offset")]
+    fn refuses_a_first_offset_past_the_fixed_part() {
+        let mut decoder = Decoder::new(&[0x05, 0x00, 0x00, 0x00, 0xff]);
+        decoder.read_offset();
+        decoder.finish();
+    }
+
+    #[test]
+    #[should_panic(expected = "Decreasing offsets")]
+    fn refuses_decreasing_offsets() {
+        let mut decoder = Decoder::new(&[0x08, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00]);
+        decoder.read_offset();
+        decoder.read_offset();
+        decoder.finish();
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid list size")]
+    fn refuses_partial_items() {
+        items(&[0; 5], 2);
+    }
+
+    #[test]
+    fn has_more_tells_the_fields_appended_later() {
+        let mut encoder = Encoder::default();
+        encoder.append_u64(1);
+        let data = encoder.finish();
+        let mut decoder = Decoder::new(&data);
+        decoder.read_bytes(8);
+        assert!(!decoder.has_more());
+        assert!(decoder.finish().is_empty());
+    }
+}
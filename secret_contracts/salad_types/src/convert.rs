@@ -0,0 +1,106 @@
+use ethereum_types::{H160, H256, U256};
+
+/// Size of an ABI word, and of the leading padding of an address in its word
+const WORD_SIZE: usize = 32;
+const ADDRESS_PADDING: usize = 12;
+
+/// The 32-byte word read as a big-endian uint256, as the Mixer contract and the EIP712 encoding do,
+/// refusing a word of another size instead of padding or truncating it
+pub fn word_to_uint(word: &[u8]) -> U256 {
+    if word.len() != WORD_SIZE {
+        panic!("Invalid uint256 word size: {}", word.len());
+    }
+    U256::from(word)
+}
+
+/// The uint256 as a big-endian 32-byte word (never the little-endian SSZ encoding of `codec`)
+pub fn uint_to_word(value: &U256) -> H256 {
+    H256::from(value)
+}
+
+/// The bytes32 (deal id, receipt hash or root) as the uint256 passed in its place to the Mixer contract,
+/// reading it big-endian so that the contract sees the same 32 bytes
+pub fn hash_to_uint(hash: &H256) -> U256 {
+    U256::from(hash)
+}
+
+/// The address of an ABI-encoded address word, refusing a word with non-zero padding instead of
+/// truncating it to its low 20 bytes
+pub fn word_to_address(word: &[u8]) -> H160 {
+    if word.len() != WORD_SIZE {
+        panic!("Invalid address word size: {}", word.len());
+    }
+    if word[..ADDRESS_PADDING].iter().any(|byte| *byte != 0) {
+        panic!("Invalid address word padding: {:?}", &word[..ADDRESS_PADDING]);
+    }
+    H160::from(&word[ADDRESS_PADDING..])
+}
+
+/// The address as an ABI word, left-padded with zeros
+pub fn address_to_word(address: &H160) -> H256 {
+    let mut word = [0_u8; WORD_SIZE];
+    word[ADDRESS_PADDING..].copy_from_slice(address);
+    H256::from(&word[..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value() -> U256 {
+        U256::from(0x0102_0304_0506_0708_u64) << 128 | U256::from(0x090a_0b0c_u64)
+    }
+
+    #[test]
+    fn reads_words_big_endian() {
+        let mut word = [0_u8; WORD_SIZE];
+        word[0] = 1;
+        assert_eq!(word_to_uint(&word), U256::from(1) << 248);
+        assert_eq!(uint_to_word(&U256::from(1))[WORD_SIZE - 1], 1);
+        assert_eq!(uint_to_word(&U256::from(1))[..WORD_SIZE - 1], [0_u8; WORD_SIZE - 1][..]);
+    }
+
+    #[test]
+    fn round_trips_uints_and_hashes_through_their_words() {
+        assert_eq!(word_to_uint(&uint_to_word(&value())), value());
+        assert_eq!(word_to_uint(&uint_to_word(&U256::max_value())), U256::max_value());
+        let hash = uint_to_word(&value());
+        assert_eq!(uint_to_word(&hash_to_uint(&hash)), hash);
+        assert_eq!(hash_to_uint(&hash), word_to_uint(&hash));
+    }
+
+    #[test]
+    fn round_trips_addresses_through_their_words() {
+        let address = H160::from(&uint_to_word(&value())[ADDRESS_PADDING..]);
+        let word = address_to_word(&address);
+        assert_eq!(word[..ADDRESS_PADDING], [0_u8; ADDRESS_PADDING][..]);
+        assert_eq!(word[ADDRESS_PADDING..], address[..]);
+        assert_eq!(word_to_address(&word), address);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid uint256 word size: 31")]
+    fn refuses_short_uint_words() {
+        word_to_uint(&[0; WORD_SIZE - 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid uint256 word size: 33")]
+    fn refuses_long_uint_words() {
+        word_to_uint(&[0; WORD_SIZE + 1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid address word size: 20")]
+    fn refuses_bare_addresses_as_words() {
+        word_to_address(&[0x11; 20]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid address word padding")]
+    fn refuses_address_words_with_dirty_padding() {
+        let mut word = [0_u8; WORD_SIZE];
+        word[0] = 1;
+        word_to_address(&word);
+    }
+}
@@ -0,0 +1,360 @@
+use ethereum_types::{H160, H256, U256};
+use enigma_crypto::hash::Keccak256;
+use enigma_crypto::KeyPair;
+
+use crate::codec;
+use crate::convert;
+use crate::domains::{self, DEPOSIT_DOMAIN_NAME, EIP712_DOMAIN_TYPE, EIP712_PREFIX};
+use crate::protocol::{self, MessageVersion, ADDRESS_SIZE, PUB_KEY_SIZE, SIG_SIZE, UNIT256_SIZE};
+
+/// The terms signed by the depositor besides the common deposit fields, depending on the kind of deal
+#[derive(Clone, Copy)]
+pub enum DepositTerms<'a> {
+    /// A deposit of the pool asset, expiring after the block number
+    Expiring(&'a U256),
+    /// A deposit of the asset in a multi-asset deal
+    Asset(&'a H160),
+}
+
+/// The EIP712 typed data message signed by the depositor
+pub fn deposit_message(
+    sender: &H160,
+    terms: DepositTerms,
+    amount: &U256,
+    enc_recipient: &[u8],
+    user_pubkey: &[u8; PUB_KEY_SIZE],
+    chain_id: &U256,
+) -> Vec<u8> {
+    DepositHasher::new(protocol::message(), chain_id).message(sender, terms, amount, enc_recipient, user_pubkey)
+}
+
+/// The EIP712 typed data message signed by a depositor running the message version `version`
+pub fn versioned_deposit_message(
+    version: &MessageVersion,
+    sender: &H160,
+    terms: DepositTerms,
+    amount: &U256,
+    enc_recipient: &[u8],
+    user_pubkey: &[u8; PUB_KEY_SIZE],
+    chain_id: &U256,
+) -> Vec<u8> {
+    DepositHasher::new(version, chain_id).message(sender, terms, amount, enc_recipient, user_pubkey)
+}
+
+/// Hashes the deposit messages of a deal. The EIP712 domain and the type hashes, common to the deposits
+/// of the deal, are hashed once, leaving the hashes of the deposit fields and of the struct to each deposit.
+pub struct DepositHasher<'a> {
+    version: &'a MessageVersion,
+    /// The EIP191 header for EIP712 followed by the domain hash
+    prefix: Vec<u8>,
+    /// The type hashes of the deposits of the pool asset, and of the deposits of multi-asset deals
+    expiring_type_hash: H256,
+    asset_type_hash: H256,
+}
+
+impl<'a> DepositHasher<'a> {
+    pub fn new(version: &'a MessageVersion, chain_id: &U256) -> DepositHasher<'a> {
+        let mut domain_message: Vec<u8> = Vec::with_capacity(4 * UNIT256_SIZE);
+        domain_message.extend_from_slice(EIP712_DOMAIN_TYPE.keccak256().as_ref());
+        domain_message.extend_from_slice(DEPOSIT_DOMAIN_NAME.keccak256().as_ref());
+        domain_message.extend_from_slice(version.deposit_domain_version.keccak256().as_ref());
+        domain_message.extend_from_slice(convert::uint_to_word(chain_id).as_ref());
+
+        let mut prefix: Vec<u8> = Vec::with_capacity(EIP712_PREFIX.len() + UNIT256_SIZE);
+        prefix.extend_from_slice(EIP712_PREFIX);
+        prefix.extend_from_slice(domain_message.keccak256().as_ref());
+        let expiring_fields = if version.signs_expiry { protocol::DEPOSIT_FIELDS } else { protocol::LEGACY_DEPOSIT_FIELDS };
+        DepositHasher {
+            version,
+            prefix,
+            expiring_type_hash: H256::from(&protocol::deposit_type(expiring_fields).as_bytes().keccak256()[..]),
+            asset_type_hash: H256::from(&protocol::deposit_type(protocol::ASSET_DEPOSIT_FIELDS).as_bytes().keccak256()[..]),
+        }
+    }
+
+    /// The EIP712 typed data message of the deposit
+    pub fn message(
+        &self,
+        sender: &H160,
+        terms: DepositTerms,
+        amount: &U256,
+        enc_recipient: &[u8],
+        user_pubkey: &[u8; PUB_KEY_SIZE],
+    ) -> Vec<u8> {
+        let mut deposit_message: Vec<u8> = Vec::with_capacity(7 * UNIT256_SIZE);
+        let type_hash = match terms {
+            DepositTerms::Asset(_) => &self.asset_type_hash,
+            DepositTerms::Expiring(_) => &self.expiring_type_hash,
+        };
+        deposit_message.extend_from_slice(type_hash);
+        // addresses must be resized to 32 bytes
+        deposit_message.extend_from_slice(&[0_u8; 12]);
+        deposit_message.extend_from_slice(sender.as_ref());
+        if let DepositTerms::Asset(asset) = terms {
+            deposit_message.extend_from_slice(&[0_u8; 12]);
+            deposit_message.extend_from_slice(asset.as_ref());
+        }
+        deposit_message.extend_from_slice(&convert::uint_to_word(amount));
+        // bytes must be keccak hashes
+        deposit_message.extend_from_slice(enc_recipient.keccak256().as_ref());
+        deposit_message.extend_from_slice(user_pubkey.keccak256().as_ref());
+        if let DepositTerms::Expiring(expires_at) = terms {
+            if self.version.signs_expiry {
+                deposit_message.extend_from_slice(&convert::uint_to_word(expires_at));
+            }
+        }
+
+        let mut message: Vec<u8> = Vec::with_capacity(self.prefix.len() + UNIT256_SIZE);
+        message.extend_from_slice(&self.prefix);
+        message.extend_from_slice(deposit_message.keccak256().as_ref());
+        message
+    }
+}
+
+/// DealId of a deal as generated by the Mixer contract, H(amount || senders || operator || nonce),
+/// each field and list prefixed with its length as a big-endian u64
+pub fn deal_id(
+    amount: &U256,
+    participants: &Vec<H160>,
+    operator_address: &H160,
+    operator_nonce: &U256,
+) -> H256 {
+    let mut message: Vec<u8> = Vec::new();
+    message.extend_from_slice(&(UNIT256_SIZE as u64).to_be_bytes());
+    message.extend_from_slice(&convert::uint_to_word(amount));
+    message.extend_from_slice(&(participants.len() as u64).to_be_bytes());
+    for sender in participants.iter() {
+        message.extend_from_slice(&(ADDRESS_SIZE as u64).to_be_bytes());
+        message.extend_from_slice(sender);
+    }
+    message.extend_from_slice(&(ADDRESS_SIZE as u64).to_be_bytes());
+    message.extend_from_slice(operator_address);
+    message.extend_from_slice(&(UNIT256_SIZE as u64).to_be_bytes());
+    message.extend_from_slice(&convert::uint_to_word(operator_nonce));
+    let mut hash_raw = [0_u8; 32];
+    hash_raw.copy_from_slice(&message.keccak256().as_ref());
+    H256::from(&hash_raw)
+}
+
+/// DealId of a voucher deal, binding each voucher id to the commitment of its deposit payload
+/// as computed by the Mixer contract from the commitments it recorded at deposit time
+pub fn voucher_deal_id(
+    amount: &U256,
+    vouchers: &[H256],
+    commitments: &[H256],
+    operator_address: &H160,
+    operator_nonce: &U256,
+) -> H256 {
+    let mut message: Vec<u8> = Vec::new();
+    message.extend_from_slice(&(UNIT256_SIZE as u64).to_be_bytes());
+    message.extend_from_slice(&convert::uint_to_word(amount));
+    for items in [vouchers, commitments].iter() {
+        message.extend_from_slice(&(items.len() as u64).to_be_bytes());
+        for item in items.iter() {
+            message.extend_from_slice(&(UNIT256_SIZE as u64).to_be_bytes());
+            message.extend_from_slice(item);
+        }
+    }
+    message.extend_from_slice(&(ADDRESS_SIZE as u64).to_be_bytes());
+    message.extend_from_slice(operator_address);
+    message.extend_from_slice(&(UNIT256_SIZE as u64).to_be_bytes());
+    message.extend_from_slice(&convert::uint_to_word(operator_nonce));
+    H256::from(&message.keccak256()[..])
+}
+
+/// DealId of a multi-asset deal, binding the common USD value of the deposits and the asset and amount
+/// of each deposit as locked by the Mixer contract when the deal was created
+pub fn asset_deal_id(
+    value: &U256,
+    assets: &[H160],
+    amounts: &[U256],
+    participants: &[H160],
+    operator_address: &H160,
+    operator_nonce: &U256,
+) -> H256 {
+    let mut message: Vec<u8> = Vec::new();
+    message.extend_from_slice(&(UNIT256_SIZE as u64).to_be_bytes());
+    message.extend_from_slice(&convert::uint_to_word(value));
+    for addresses in [assets, participants].iter() {
+        message.extend_from_slice(&(addresses.len() as u64).to_be_bytes());
+        for address in addresses.iter() {
+            message.extend_from_slice(&(ADDRESS_SIZE as u64).to_be_bytes());
+            message.extend_from_slice(address);
+        }
+    }
+    message.extend_from_slice(&(amounts.len() as u64).to_be_bytes());
+    for amount in amounts.iter() {
+        message.extend_from_slice(&(UNIT256_SIZE as u64).to_be_bytes());
+        message.extend_from_slice(&convert::uint_to_word(amount));
+    }
+    message.extend_from_slice(&(ADDRESS_SIZE as u64).to_be_bytes());
+    message.extend_from_slice(operator_address);
+    message.extend_from_slice(&(UNIT256_SIZE as u64).to_be_bytes());
+    message.extend_from_slice(&convert::uint_to_word(operator_nonce));
+    H256::from(&message.keccak256()[..])
+}
+
+/// Commitment of a voucher deposit to its payload: H(pub_key || enc_recipient)
+pub fn voucher_commitment(pub_key: &[u8], enc_recipient: &[u8]) -> H256 {
+    let mut message: Vec<u8> = pub_key.to_vec();
+    message.extend_from_slice(enc_recipient);
+    H256::from(&message.keccak256()[..])
+}
+
+/// Canonical encoding of the deal receipt as the container
+/// (deal_id: Bytes32, attested_block: uint64, recipients: List[Bytes20])
+pub fn receipt(deal_id: &H256, attested_block: u64, recipients: &[H160]) -> Vec<u8> {
+    let mut encoder = codec::Encoder::default();
+    encoder.append_bytes(deal_id);
+    encoder.append_u64(attested_block);
+    encoder.append_list(recipients, codec::encode_address);
+    encoder.finish()
+}
+
+/// Hash committing to the seed a deal was shuffled with, H(deal_id || seed || salt), the seed as 8 big-endian bytes.
+/// The random salt keeps the 64-bit seed from being found by enumerating the seeds before it is revealed.
+pub fn shuffle_commitment(deal_id: &H256, seed: u64, salt: &H256) -> H256 {
+    let mut message: Vec<u8> = Vec::with_capacity(2 * UNIT256_SIZE + 8);
+    message.extend_from_slice(deal_id);
+    message.extend_from_slice(&seed.to_be_bytes());
+    message.extend_from_slice(salt);
+    H256::from(&message.keccak256()[..])
+}
+
+/// Hash binding the DealId to the attested block and the ordered recipients, emitted on-chain as an opaque receipt
+pub fn receipt_hash(deal_id: &H256, attested_block: u64, recipients: &[H160]) -> H256 {
+    let message = receipt(deal_id, attested_block, recipients);
+    let mut hash_raw = [0_u8; 32];
+    hash_raw.copy_from_slice(&message.keccak256().as_ref());
+    H256::from(&hash_raw)
+}
+
+/// The address of the key signing the message
+pub fn recover_address(message: &[u8], signature: [u8; SIG_SIZE]) -> Result<H160, String> {
+    let pubkey = KeyPair::recover(message, signature)
+        .map_err(|err| format!("Unable to recover signer: {:?}", err))?;
+    let mut address_raw = [0_u8; 20];
+    address_raw.copy_from_slice(&pubkey.keccak256()[12..32]);
+    Ok(H160::from(&address_raw))
+}
+
+/// The EIP191 personal message of H(payload) signed by the operator, the payload being the DealId then,
+/// for each deposit in order, H(pubKey) || H(encRecipient) || H(signature) || expiry, followed by
+/// the chain id, the deal features and the aggregate signature. The DealId binds the amount, the senders
+/// and their order.
+pub fn payload_message(
+    deal_id: &H256,
+    pub_keys: &[Vec<u8>],
+    enc_recipients: &[Vec<u8>],
+    signatures: &[Vec<u8>],
+    expiries: &[U256],
+    chain_id: &U256,
+    features: &U256,
+    aggregate_signature: &[u8],
+) -> Vec<u8> {
+    let mut payload: Vec<u8> = Vec::new();
+    payload.extend_from_slice(deal_id);
+    for i in 0..enc_recipients.len() {
+        payload.extend_from_slice(pub_keys[i].keccak256().as_ref());
+        payload.extend_from_slice(enc_recipients[i].keccak256().as_ref());
+        payload.extend_from_slice(signatures[i].keccak256().as_ref());
+        payload.extend_from_slice(&convert::uint_to_word(&expiries[i]));
+    }
+    payload.extend_from_slice(&convert::uint_to_word(chain_id));
+    payload.extend_from_slice(&convert::uint_to_word(features));
+    payload.extend_from_slice(aggregate_signature);
+    domains::personal_message(&payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustc_hex::FromHex;
+
+    fn hex(value: &str) -> Vec<u8> {
+        value.from_hex().unwrap()
+    }
+
+    fn address(byte: u8) -> H160 {
+        H160::from(&[byte; ADDRESS_SIZE][..])
+    }
+
+    fn ether() -> U256 {
+        U256::from(1_000_000_000_000_000_000_u64)
+    }
+
+    #[test]
+    fn deal_id_matches_the_mixer() {
+        let deal_id = deal_id(&ether(), &vec![address(0x11), address(0x22)], &address(0x33), &U256::from(7));
+        assert_eq!(deal_id[..], hex("752bb53bf268687afd3418dcb4f3a1fc973f48250da64025d7d3b6c31a0f4536")[..]);
+    }
+
+    #[test]
+    fn deal_id_binds_the_order_of_the_senders() {
+        let ids: Vec<H256> = [vec![address(0x11), address(0x22)], vec![address(0x22), address(0x11)]].iter()
+            .map(|senders| deal_id(&ether(), senders, &address(0x33), &U256::from(7)))
+            .collect();
+        assert_ne!(ids[0], ids[1]);
+    }
+
+    #[test]
+    fn receipt_is_the_canonical_container() {
+        let receipt = receipt(&H256::from(&[0xaa; 32][..]), 5, &[address(0x11)]);
+        let mut expected = vec![0xaa; 32];
+        expected.extend_from_slice(&5_u64.to_le_bytes());
+        expected.extend_from_slice(&44_u32.to_le_bytes());
+        expected.extend_from_slice(&[0x11; ADDRESS_SIZE]);
+        assert_eq!(receipt, expected);
+        assert_eq!(
+            receipt_hash(&H256::from(&[0xaa; 32][..]), 5, &[address(0x11)])[..],
+            hex("0e2396c266638ac0be4c1289ad4a0afadd1fa7b6b0900fabe584554682a9cb0e")[..],
+        );
+    }
+
+    #[test]
+    fn commitments_match_their_vectors() {
+        assert_eq!(
+            voucher_commitment(&[1; PUB_KEY_SIZE], &[2; 3])[..],
+            hex("d647370f49e9162137b75795af4fb55a6014da0125305596105ad99d747634db")[..],
+        );
+        assert_eq!(
+            shuffle_commitment(&H256::from(&[0xaa; 32][..]), 42, &H256::from(&[0xbb; 32][..]))[..],
+            hex("475f054bb4ef9163537786f80f1950553689fd17b6d6243c2dd438d081b098c9")[..],
+        );
+    }
+
+    #[test]
+    fn deposit_messages_are_the_eip712_typed_data() {
+        let (pub_key, enc_recipient) = ([0x44; PUB_KEY_SIZE], [0x55; 48]);
+        let expiring = deposit_message(&address(0x11), DepositTerms::Expiring(&U256::from(100)), &ether(), &enc_recipient, &pub_key, &U256::from(1));
+        assert_eq!(expiring, hex(concat!(
+            "1901302cd66d2f544412805ecf8576d0f51e4a09398e70201a455144c88d60cbffa1",
+            "2de9cfe715aeb21fa6a0671ec1fac6ac4966b8a046c82260e2d5f87488ca7daf",
+        )));
+        let asset = deposit_message(&address(0x11), DepositTerms::Asset(&address(0x22)), &ether(), &enc_recipient, &pub_key, &U256::from(1));
+        assert_eq!(asset, hex(concat!(
+            "1901302cd66d2f544412805ecf8576d0f51e4a09398e70201a455144c88d60cbffa1",
+            "e7ccd8ca47e39091e8366cacbeddc9446df36d4ed18ccfb05931ae5c90278e74",
+        )));
+    }
+
+    #[test]
+    fn legacy_deposit_messages_do_not_sign_the_expiry() {
+        let (pub_key, enc_recipient) = ([0x44; PUB_KEY_SIZE], [0x55; 48]);
+        let legacy = &protocol::MESSAGE_VERSIONS[1];
+        let messages: Vec<Vec<u8>> = [100, 200].iter().map(|expiry| {
+            versioned_deposit_message(legacy, &address(0x11), DepositTerms::Expiring(&U256::from(*expiry)), &ether(), &enc_recipient, &pub_key, &U256::from(1))
+        }).collect();
+        assert_eq!(messages[0], messages[1]);
+    }
+
+    #[test]
+    fn recovers_the_signer() {
+        let keypair = KeyPair::from_slice(&[1; 32]).unwrap();
+        let signer = H160::from(&keypair.get_pubkey()[..].keccak256()[12..32]);
+        let message = payload_message(&H256::from(&[0xaa; 32][..]), &[vec![0x44; PUB_KEY_SIZE]], &[vec![0x55; 48]], &[vec![0x66; SIG_SIZE]], &[U256::from(100)], &U256::from(1), &U256::zero(), &[]);
+        assert!(message.starts_with(domains::EIP191_PERSONAL_PREFIX));
+        assert_eq!(recover_address(&message, keypair.sign(&message).unwrap()), Ok(signer));
+        assert_ne!(recover_address(&message[1..], keypair.sign(&message).unwrap()), Ok(signer));
+    }
+}
@@ -0,0 +1,162 @@
+use enigma_crypto::hash::Keccak256;
+
+/// Prefixes of the messages signed by the Ethereum wallets
+pub static EIP191_PERSONAL_PREFIX: &[u8] = b"\x19Ethereum Signed Message:\n32";
+pub static EIP712_PREFIX: &[u8] = b"\x19\x01";
+pub static EIP712_DOMAIN_TYPE: &[u8] = b"EIP712Domain(string name,string version,uint256 chainId)";
+
+/// The EIP712 domain name of the deposit messages
+pub static DEPOSIT_DOMAIN_NAME: &[u8] = b"Salad Deposit";
+
+/// Domains prefixing the hashed body of each EIP191 personal message, one per purpose
+pub static CHALLENGE_DOMAIN: &[u8] = b"Salad Challenge";
+pub static HANDOFF_DOMAIN: &[u8] = b"Salad Operator Handoff";
+pub static NOTE_WITHDRAWAL_DOMAIN: &[u8] = b"Salad Note Withdrawal";
+pub static NOTE_CLAIM_DOMAIN: &[u8] = b"Salad Note Claim";
+pub static QUEUED_WITHDRAWAL_DOMAIN: &[u8] = b"Salad Queued Withdrawal";
+/// The aggregators vouching for the deposits they bundled on behalf of their depositors
+pub static BUNDLE_DOMAIN: &[u8] = b"Salad Bundled Deposit";
+/// The cancel votes of the senders of a deal
+pub static CANCEL_DOMAIN: &[u8] = b"Salad Cancel";
+/// The reorg proofs of the oracle signers invalidating a relayed fact
+pub static REORG_DOMAIN: &[u8] = b"Salad Reorg";
+/// The requests of the operators previewing the distribution calldata of their deals
+pub static PREVIEW_DOMAIN: &[u8] = b"Salad Distribution Preview";
+/// The operators scheduling their deals
+pub static SCHEDULE_DOMAIN: &[u8] = b"Salad Schedule";
+/// Reserved for the administrative operations signed by the deployment owner
+pub static ADMIN_DOMAIN: &[u8] = b"Salad Admin Operation";
+
+/// The attestations of the oracle signers, one domain per kind so that an attestation of one kind never
+/// passes for another: the facts (prices, blocks) and the commands configuring the enclave
+pub static ORACLE_PRICE_DOMAIN: &[u8] = b"Salad Oracle Price";
+pub static ORACLE_BLOCK_DOMAIN: &[u8] = b"Salad Oracle Block";
+pub static ORACLE_OPERATOR_DOMAIN: &[u8] = b"Salad Oracle Operator";
+pub static ORACLE_RELAYER_DOMAIN: &[u8] = b"Salad Oracle Relayer";
+pub static ORACLE_THRESHOLD_DOMAIN: &[u8] = b"Salad Oracle Threshold";
+pub static ORACLE_STANDBY_DOMAIN: &[u8] = b"Salad Oracle Standby";
+pub static ORACLE_PROMOTION_DOMAIN: &[u8] = b"Salad Oracle Promotion";
+pub static ORACLE_VALUE_CAP_DOMAIN: &[u8] = b"Salad Oracle Value Cap";
+pub static ORACLE_BRIDGE_DOMAIN: &[u8] = b"Salad Oracle Payout Bridge";
+pub static ORACLE_RETENTION_DOMAIN: &[u8] = b"Salad Oracle Retention";
+pub static ORACLE_ANOMALY_DOMAIN: &[u8] = b"Salad Oracle Anomaly";
+pub static ORACLE_TOKEN_CONFIG_DOMAIN: &[u8] = b"Salad Oracle Token Config";
+pub static ORACLE_TOKEN_REMOVAL_DOMAIN: &[u8] = b"Salad Oracle Token Removal";
+pub static ORACLE_MIXER_DOMAIN: &[u8] = b"Salad Oracle Mixer";
+
+/// Domain separation tag of the hash of the BLS messages to G1, for the contracts built with the "bls" feature
+pub static BLS_HASH_DST: &[u8] = b"SALAD-BLS12381G1-KECCAK-TAI-V1";
+
+/// The domains of the personal messages, none being a prefix of another so that the bodies of two
+/// purposes never hash the same. The operator payloads predate the domains and stay untagged, their
+/// bodies starting with a DealId rather than a domain.
+pub static PERSONAL_DOMAINS: &[&[u8]] = &[
+    CHALLENGE_DOMAIN,
+    HANDOFF_DOMAIN,
+    NOTE_WITHDRAWAL_DOMAIN,
+    NOTE_CLAIM_DOMAIN,
+    QUEUED_WITHDRAWAL_DOMAIN,
+    BUNDLE_DOMAIN,
+    CANCEL_DOMAIN,
+    REORG_DOMAIN,
+    PREVIEW_DOMAIN,
+    SCHEDULE_DOMAIN,
+    ADMIN_DOMAIN,
+    ORACLE_PRICE_DOMAIN,
+    ORACLE_BLOCK_DOMAIN,
+    ORACLE_OPERATOR_DOMAIN,
+    ORACLE_RELAYER_DOMAIN,
+    ORACLE_THRESHOLD_DOMAIN,
+    ORACLE_STANDBY_DOMAIN,
+    ORACLE_PROMOTION_DOMAIN,
+    ORACLE_VALUE_CAP_DOMAIN,
+    ORACLE_BRIDGE_DOMAIN,
+    ORACLE_RETENTION_DOMAIN,
+    ORACLE_ANOMALY_DOMAIN,
+    ORACLE_TOKEN_CONFIG_DOMAIN,
+    ORACLE_TOKEN_REMOVAL_DOMAIN,
+    ORACLE_MIXER_DOMAIN,
+];
+
+/// The EIP191 personal message of H(data)
+pub fn personal_message(data: &[u8]) -> Vec<u8> {
+    let mut message: Vec<u8> = Vec::new();
+    message.extend_from_slice(EIP191_PERSONAL_PREFIX);
+    message.extend_from_slice(data.keccak256().as_ref());
+    message
+}
+
+/// The EIP191 personal message of H(domain || body)
+pub fn domain_message(domain: &[u8], body: &[u8]) -> Vec<u8> {
+    let mut data: Vec<u8> = Vec::new();
+    data.extend_from_slice(domain);
+    data.extend_from_slice(body);
+    personal_message(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustc_hex::ToHex;
+
+    /// Golden vectors of the hex keccak256 of each domain, pinning the bytes signed by the wallets and the
+    /// operators: changing a domain invalidates every message signed for it
+    const DOMAIN_VECTORS: &[(&[u8], &str)] = &[
+        (EIP712_DOMAIN_TYPE, "c2f8787176b8ac6bf7215b4adcc1e069bf4ab82d9ab1df05a57a91d425935b6e"),
+        (DEPOSIT_DOMAIN_NAME, "36b8abd4914327cba8565e713506ff5b353ec1fc1780e68a9a1b103052525906"),
+        (CHALLENGE_DOMAIN, "3aff461e1f81ede4421938bcd19ade9499f6e532ad3fddac3063b1fc0ba0bf36"),
+        (HANDOFF_DOMAIN, "42ad999c9dc5a0a5e10a5ec7ee6e0c1f2024107daa9e82eba4ff28278867cf46"),
+        (NOTE_WITHDRAWAL_DOMAIN, "148eebe8cf41d0974b0cae2424285511edba2e58d6ce5387a17eb4059fe0a9c1"),
+        (NOTE_CLAIM_DOMAIN, "c7e14dac8701003d39bb7d65b299d0a754285f622694c5f881878809ca25291b"),
+        (QUEUED_WITHDRAWAL_DOMAIN, "71cbb5c9dab8a4645c2c328ede0f6a82e77a8de409712122941cf641f6b9576c"),
+        (BUNDLE_DOMAIN, "1bad8422aa2b17520ac367d1ea269918ca855d5496143ebcc6f90908f7da917a"),
+        (CANCEL_DOMAIN, "d822b34462abdad15d4b7e9bf6f5e353a9b1c07f109073d2da1acae2608f3242"),
+        (REORG_DOMAIN, "a9aa8978d1b36a0f4a62a2edd8a38b2d7d3ac14fa929a75bec44e440cedd20d2"),
+        (PREVIEW_DOMAIN, "714687580a6b2cea0f0903aeb154167206add3717d65d85c08fc50ebe680bb69"),
+        (SCHEDULE_DOMAIN, "be7c03fb67d176dd0c086e69375ace9614b6ff397500b9d63532a41c3867a854"),
+        (ADMIN_DOMAIN, "75c40122faaed0f25be8e9dbfacf7f2b3c2ea2e07273edd030874cad0549ebe3"),
+        (ORACLE_PRICE_DOMAIN, "ad965e9214436e6cf842e616c874ff51589278f58adc3501f51971a3f258939c"),
+        (ORACLE_BLOCK_DOMAIN, "bc84c6a531560a57777ec27bb1e2060bd7a432eb4eaa20b0432776bf77933b11"),
+        (ORACLE_OPERATOR_DOMAIN, "48bfb7ba38daf1fedea94adcd596aafc15811c0156e99d5dbd20643c3602f43a"),
+        (ORACLE_RELAYER_DOMAIN, "ce84c3f1c6bc51af5877fc8ba8af428e14f867ef7079cc20269c8ffd0a22dd7e"),
+        (ORACLE_THRESHOLD_DOMAIN, "9295c548be2e04acbd4bf603e008c94f6064bc79c15a47172640e0d94935745e"),
+        (ORACLE_STANDBY_DOMAIN, "b785edd60f1c669678d135ba236d4530ea0aa5afa258c3e2ec6621991d4b1afd"),
+        (ORACLE_PROMOTION_DOMAIN, "7c937852cbc4b93de1fc1b9d7cc4215647de1340c37f08aabc6038426918e877"),
+        (ORACLE_VALUE_CAP_DOMAIN, "b6406123d60fc502b936cd33b5ef75f4b4be480b66992386176e84afb32ee4b1"),
+        (ORACLE_BRIDGE_DOMAIN, "163aab4f7bfab5a8e3f2cd055bb5989939623c8266fc042d194f1710f50cfcfa"),
+        (ORACLE_RETENTION_DOMAIN, "1f6ce7a627f5066779bbde406ab20032bd340262dd2badc1da847a6daa2c857b"),
+        (ORACLE_ANOMALY_DOMAIN, "4722531dd9e86985395bd35662489f2a5d0b2151c155b34419044b26e766ddb7"),
+        (ORACLE_TOKEN_CONFIG_DOMAIN, "1249aa708f0c0ed9d80bad80954d166811a7b3031394b1b2ffe440d073efdd8c"),
+        (ORACLE_TOKEN_REMOVAL_DOMAIN, "80e6843d9b219024bf8a04cd5994f2d350e035872879d8a482730bfd0ac5c511"),
+        (ORACLE_MIXER_DOMAIN, "77721c5b3d3f68ed691d969629c3f5cbf2fbd591109634fb0fa4ae29f3aeba86"),
+    ];
+
+    #[test]
+    fn hashes_the_domains_to_their_golden_vectors() {
+        for (domain, hash) in DOMAIN_VECTORS.iter() {
+            assert_eq!(domain.keccak256().to_hex::<String>(), *hash, "{}", String::from_utf8_lossy(domain));
+        }
+    }
+
+    #[test]
+    fn pins_every_personal_domain() {
+        assert!(PERSONAL_DOMAINS.iter().all(|domain| DOMAIN_VECTORS.iter().any(|(pinned, _)| pinned == domain)));
+    }
+
+    #[test]
+    fn no_personal_domain_prefixes_another() {
+        for (i, domain) in PERSONAL_DOMAINS.iter().enumerate() {
+            for (j, other) in PERSONAL_DOMAINS.iter().enumerate() {
+                assert!(i == j || !other.starts_with(domain), "{} prefixes {}", String::from_utf8_lossy(domain), String::from_utf8_lossy(other));
+            }
+        }
+    }
+
+    #[test]
+    fn prefixes_the_hashed_body_with_its_domain() {
+        assert_eq!(domain_message(CANCEL_DOMAIN, b"body"), personal_message(b"Salad Cancelbody"));
+        let message = personal_message(b"");
+        assert_eq!(&message[..EIP191_PERSONAL_PREFIX.len()], EIP191_PERSONAL_PREFIX);
+        assert_eq!(message[EIP191_PERSONAL_PREFIX.len()..].to_hex::<String>(), "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470");
+    }
+}
@@ -0,0 +1,13 @@
+//! The wire formats of the Salad protocol shared by the secret contracts and their verifiers: the canonical
+//! encoding of the receipts and records, the ABI words, the deposit and deal digests, the signing domains,
+//! the Merkle trees the Mixer contract checks, the calldata of the Mixer callouts, and the protocol constants.
+
+pub mod calldata;
+pub mod codec;
+pub mod convert;
+pub mod digest;
+pub mod domains;
+pub mod merkle;
+pub mod protocol;
+
+pub use ethereum_types::{H160, H256, U256};
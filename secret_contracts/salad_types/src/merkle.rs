@@ -0,0 +1,92 @@
+use ethereum_types::{H160, H256, U256};
+use enigma_crypto::hash::Keccak256;
+
+use crate::convert;
+
+fn hash(data: &[u8]) -> H256 {
+    let mut hash_raw = [0_u8; 32];
+    hash_raw.copy_from_slice(&data.keccak256().as_ref());
+    H256::from(&hash_raw)
+}
+
+fn hash_pair(left: &H256, right: &H256) -> H256 {
+    let mut message: Vec<u8> = Vec::new();
+    message.extend_from_slice(left);
+    message.extend_from_slice(right);
+    hash(&message)
+}
+
+fn next_level(level: &[H256]) -> Vec<H256> {
+    level
+        .chunks(2)
+        .map(|pair| if pair.len() == 2 { hash_pair(&pair[0], &pair[1]) } else { pair[0] })
+        .collect()
+}
+
+/// Keccak256 Merkle root of the leaves, hashing each leaf then each pair of nodes as H(left || right).
+/// The last node of an odd level is carried up unchanged; the root of no leaves is zero.
+pub fn root(leaves: &[Vec<u8>]) -> H256 {
+    if leaves.is_empty() {
+        return H256::zero();
+    }
+    let mut level: Vec<H256> = leaves.iter().map(|leaf| hash(leaf)).collect();
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Sibling hashes from the leaf at `index` up to the root, skipping the levels where the node is carried up
+pub fn proof(leaves: &[Vec<u8>], index: usize) -> Vec<H256> {
+    let mut path: Vec<H256> = Vec::new();
+    let mut level: Vec<H256> = leaves.iter().map(|leaf| hash(leaf)).collect();
+    let mut index = index;
+    while level.len() > 1 {
+        let sibling = index ^ 1;
+        if sibling < level.len() {
+            path.push(level[sibling]);
+        }
+        level = next_level(&level);
+        index /= 2;
+    }
+    path
+}
+
+/// Whether the sibling hashes of `proof` link the leaf at `index` of the `nb_leaves` leaves to the root,
+/// only checked off-chain by salad-verify, the enclave building the proofs
+#[allow(dead_code)]
+pub fn verify(leaf: &[u8], index: usize, nb_leaves: usize, proof: &[H256], root: &H256) -> bool {
+    if index >= nb_leaves {
+        return false;
+    }
+    let mut node = hash(leaf);
+    let mut siblings = proof.iter();
+    let (mut index, mut size) = (index, nb_leaves);
+    while size > 1 {
+        if index ^ 1 < size {
+            let sibling = match siblings.next() {
+                Some(sibling) => sibling,
+                None => return false,
+            };
+            node = if index % 2 == 0 { hash_pair(&node, sibling) } else { hash_pair(sibling, &node) };
+        }
+        size = (size + 1) / 2;
+        index /= 2;
+    }
+    siblings.next().is_none() && node == *root
+}
+
+/// Merkle leaf of a shuffled output: recipient || amount
+pub fn output_leaf(recipient: &H160, amount: &U256) -> Vec<u8> {
+    let mut leaf = recipient.to_vec();
+    leaf.extend_from_slice(&convert::uint_to_word(amount));
+    leaf
+}
+
+/// Merkle leaf of a note withdrawal committed with a deal: nullifier || payout || amount
+pub fn note_payout_leaf(nullifier: &H256, payout: &H160, amount: &U256) -> Vec<u8> {
+    let mut leaf = nullifier.to_vec();
+    leaf.extend_from_slice(payout);
+    leaf.extend_from_slice(&convert::uint_to_word(amount));
+    leaf
+}
@@ -0,0 +1,271 @@
+/// Sizes of the wire primitives
+pub const PUB_KEY_SIZE: usize = 64;
+pub const UNIT256_SIZE: usize = 32;
+pub const SIG_SIZE: usize = 65;
+/// Size of the EIP-2098 compact secp256k1 signatures (r || yParityAndS)
+pub const COMPACT_SIG_SIZE: usize = 64;
+pub const ADDRESS_SIZE: usize = 20;
+/// Sizes of the BLS12-381 compressed G2 public key and G1 signature (minimal-signature-size variant)
+pub const BLS_PUB_KEY_SIZE: usize = 96;
+pub const BLS_SIG_SIZE: usize = 48;
+/// The AES-GCM tag and IV appended to the encrypted payloads
+pub const ENCRYPTION_OVERHEAD: usize = 28;
+
+/// Magic of the deal capsules
+pub static CAPSULE_MAGIC: &[u8] = b"SALADCAP";
+
+/// EIP712 fields of the deposit messages signed for the pool asset, and for multi-asset deals,
+/// the legacy fields being those of the pool asset deposits predating the signed expiry
+pub static DEPOSIT_FIELDS: &[(&str, &str)] = &[
+    ("sender", "address"),
+    ("amount", "uint256"),
+    ("encRecipient", "bytes"),
+    ("pubKey", "bytes"),
+    ("expiresAt", "uint256"),
+];
+pub static LEGACY_DEPOSIT_FIELDS: &[(&str, &str)] = &[
+    ("sender", "address"),
+    ("amount", "uint256"),
+    ("encRecipient", "bytes"),
+    ("pubKey", "bytes"),
+];
+pub static ASSET_DEPOSIT_FIELDS: &[(&str, &str)] = &[
+    ("sender", "address"),
+    ("asset", "address"),
+    ("amount", "uint256"),
+    ("encRecipient", "bytes"),
+    ("pubKey", "bytes"),
+];
+
+/// The EIP712 type encoding of the deposit messages with the fields
+pub fn deposit_type(fields: &[(&str, &str)]) -> String {
+    let fields: Vec<String> = fields.iter().map(|(name, kind)| format!("{} {}", kind, name)).collect();
+    format!("Deposit({})", fields.join(","))
+}
+
+/// The r || s || v secp256k1 signature of a 65-byte signature, or of an EIP-2098 compact signature whose
+/// top bit of s holds the y parity, normalized with v being 27 or 28
+pub fn ecdsa_signature(raw: &[u8]) -> Option<[u8; SIG_SIZE]> {
+    let mut signature = [0_u8; SIG_SIZE];
+    match raw.len() {
+        SIG_SIZE => signature.copy_from_slice(raw),
+        COMPACT_SIG_SIZE => {
+            signature[..COMPACT_SIG_SIZE].copy_from_slice(raw);
+            signature[32] &= 0x7f;
+            signature[COMPACT_SIG_SIZE] = 27 + (raw[32] >> 7);
+        }
+        _ => return None,
+    }
+    Some(signature)
+}
+
+/// Signature mode byte prefixing each deposit signature.
+/// A bare 65-byte or EIP-2098 compact 64-byte signature is read as a legacy `MODE_ECDSA` signature, so that
+/// no moded signature may be 64 or 65 bytes long.
+pub const MODE_ECDSA: u8 = 0;
+pub const MODE_AGGREGATED: u8 = 1;
+pub const MODE_BLS: u8 = 2;
+/// Signature of a deposit relayed by another secret contract, by its enclave signing key
+pub const MODE_RELAYED: u8 = 3;
+/// Signature of a deposit bundled by an on-chain aggregator: the signature of the depositor, then the
+/// signature of the aggregator signing key
+pub const MODE_BUNDLED: u8 = 4;
+
+/// Format of a deposit payload envelope
+pub struct EnvelopeVersion {
+    pub version: u8,
+    /// Size of each (recipient address, percentage) split, if any
+    pub split_size: usize,
+    pub max_splits: usize,
+    /// Size of the (recipient address, output asset) payload of multi-asset deals, if any
+    pub asset_payload_size: usize,
+    /// Max size of the share of the deposit staged back into the pool, if any:
+    /// percentage, pubKey and encrypted splits payload
+    pub redeposit_size: usize,
+    /// Size of the pubKey of the guardian releasing the outputs along with their recipient, if any
+    pub guardian_size: usize,
+    /// Whether the splits may be held as notes in the enclave ledger
+    pub notes: bool,
+    /// Max size of the TLV extension area prefixing the envelope, if any
+    pub max_extensions_size: usize,
+}
+
+impl EnvelopeVersion {
+    pub fn max_splits_size(&self) -> usize {
+        self.max_splits * self.split_size
+    }
+
+    pub fn max_payload_size(&self) -> usize {
+        let extensions_size = match self.max_extensions_size {
+            0 => 0,
+            // The marker and the u16 size of the extension area
+            max_extensions_size => 3 + max_extensions_size,
+        };
+        extensions_size + match (self.max_splits, self.redeposit_size) {
+            (0, _) => ADDRESS_SIZE,
+            (_, 0) => self.max_splits_size(),
+            (_, redeposit_size) => 1 + self.max_splits_size() + self.guardian_size + redeposit_size,
+        }
+    }
+}
+
+/// Deposit payload envelope versions: v0 holds the recipient address, v1 the recipient address
+/// or up to 4 splits, or the recipient address and output asset in multi-asset deals,
+/// v2 the v1 splits along with a share of the deposit staged back into the pool,
+/// v3 the v2 envelope along with the pubKey of a guardian holding the outputs in escrow with their recipient,
+/// v4 the v3 envelope, its split percentages possibly flagged to hold their output as a note in the enclave ledger,
+/// v5 the v4 envelope possibly prefixed with a TLV extension area, so that fields are added without a new version.
+/// A new version is a new entry, the current version being the last.
+pub static ENVELOPE_VERSIONS: &[EnvelopeVersion] = &[
+    EnvelopeVersion {
+        version: 0,
+        split_size: 0,
+        max_splits: 0,
+        asset_payload_size: 0,
+        redeposit_size: 0,
+        guardian_size: 0,
+        notes: false,
+        max_extensions_size: 0,
+    },
+    EnvelopeVersion {
+        version: 1,
+        split_size: 21,
+        max_splits: 4,
+        asset_payload_size: 40,
+        redeposit_size: 0,
+        guardian_size: 0,
+        notes: false,
+        max_extensions_size: 0,
+    },
+    EnvelopeVersion {
+        version: 2,
+        split_size: 21,
+        max_splits: 4,
+        asset_payload_size: 40,
+        redeposit_size: 1 + PUB_KEY_SIZE + 4 * 21 + ENCRYPTION_OVERHEAD,
+        guardian_size: 0,
+        notes: false,
+        max_extensions_size: 0,
+    },
+    EnvelopeVersion {
+        version: 3,
+        split_size: 21,
+        max_splits: 4,
+        asset_payload_size: 40,
+        redeposit_size: 1 + PUB_KEY_SIZE + 4 * 21 + ENCRYPTION_OVERHEAD,
+        guardian_size: PUB_KEY_SIZE,
+        notes: false,
+        max_extensions_size: 0,
+    },
+    EnvelopeVersion {
+        version: 4,
+        split_size: 21,
+        max_splits: 4,
+        asset_payload_size: 40,
+        redeposit_size: 1 + PUB_KEY_SIZE + 4 * 21 + ENCRYPTION_OVERHEAD,
+        guardian_size: PUB_KEY_SIZE,
+        notes: true,
+        max_extensions_size: 0,
+    },
+    EnvelopeVersion {
+        version: 5,
+        split_size: 21,
+        max_splits: 4,
+        asset_payload_size: 40,
+        redeposit_size: 1 + PUB_KEY_SIZE + 4 * 21 + ENCRYPTION_OVERHEAD,
+        guardian_size: PUB_KEY_SIZE,
+        notes: true,
+        max_extensions_size: 256,
+    },
+];
+
+/// Format of a message signed by the depositors
+pub struct MessageVersion {
+    pub version: u8,
+    /// EIP712 domain version of the deposit messages
+    pub deposit_domain_version: &'static [u8],
+    /// Version of the deal capsule format
+    pub capsule_version: u8,
+    /// Whether the deposit messages of the pool asset sign their expiry block
+    pub signs_expiry: bool,
+}
+
+/// Message versions: v0 predates the attested deposit blocks in the deal capsules, v1 carries them,
+/// v2 adds the expiry signed in the deposit messages. A new version is a new entry, the current version being the last.
+pub static MESSAGE_VERSIONS: &[MessageVersion] = &[
+    MessageVersion { version: 0, deposit_domain_version: b"1", capsule_version: 2, signs_expiry: false },
+    MessageVersion { version: 1, deposit_domain_version: b"1", capsule_version: 3, signs_expiry: false },
+    MessageVersion { version: 2, deposit_domain_version: b"2", capsule_version: 4, signs_expiry: true },
+];
+
+pub fn envelope() -> &'static EnvelopeVersion {
+    ENVELOPE_VERSIONS.last().unwrap()
+}
+
+pub fn message() -> &'static MessageVersion {
+    MESSAGE_VERSIONS.last().unwrap()
+}
+
+pub fn envelope_versions() -> Vec<u8> {
+    ENVELOPE_VERSIONS.iter().map(|envelope| envelope.version).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::digest;
+    use crate::H160;
+    use rustc_hex::FromHex;
+
+    fn hex(value: &str) -> Vec<u8> {
+        value.from_hex().unwrap()
+    }
+
+    /// The EIP-2098 examples, signed by the private key 0x1234567890...1234 (address 0x2e988a38...aabfb) over the
+    /// personal messages: (message, r, s, v, yParityAndS)
+    const EIP2098_VECTORS: &[(&[u8], &str, &str, u8, &str)] = &[
+        (
+            b"Hello World",
+            "68a020a209d3d56c46f38cc50a33f704f4a9a10a59377f8dd762ac66910e9b90",
+            "7e865ad05c4035ab5792787d4a0297a43617ae897930a6fe4d822b8faea52064",
+            27,
+            "7e865ad05c4035ab5792787d4a0297a43617ae897930a6fe4d822b8faea52064",
+        ),
+        (
+            b"It's a small(er) world",
+            "9328da16089fcba9bececa81663203989f2df5fe1faa6291a45381c81bd17f76",
+            "139c6d6b623b42da56557e5e734a43dc83345ddfadec52cbe24d0cc64f550793",
+            28,
+            "939c6d6b623b42da56557e5e734a43dc83345ddfadec52cbe24d0cc64f550793",
+        ),
+    ];
+
+    fn personal_message(message: &[u8]) -> Vec<u8> {
+        let mut personal = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+        personal.extend_from_slice(message);
+        personal
+    }
+
+    #[test]
+    fn normalizes_the_compact_signatures_to_their_golden_vectors() {
+        let signer = H160::from(&hex("2e988a386a799f506693793c6a5af6b54dfaabfb")[..]);
+        for (message, r, s, v, y_parity_and_s) in EIP2098_VECTORS.iter() {
+            let mut expected = hex(r);
+            expected.extend_from_slice(&hex(s));
+            expected.push(*v);
+            let mut compact = hex(r);
+            compact.extend_from_slice(&hex(y_parity_and_s));
+            let signature = ecdsa_signature(&compact).unwrap();
+            assert_eq!(&signature[..], &expected[..]);
+            assert_eq!(&ecdsa_signature(&expected).unwrap()[..], &expected[..]);
+            assert_eq!(digest::recover_address(&personal_message(message), signature), Ok(signer));
+        }
+    }
+
+    #[test]
+    fn refuses_the_signatures_of_other_sizes() {
+        for size in [0, COMPACT_SIG_SIZE - 1, SIG_SIZE + 1].iter() {
+            assert!(ecdsa_signature(&vec![0x1b; *size]).is_none());
+        }
+    }
+}
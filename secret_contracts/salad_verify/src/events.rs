@@ -0,0 +1,20 @@
+use salad_types::{H160, H256, U256};
+
+/// The `CommitRoots(dealId, participantsRoot, outputsRoot)` event of the Mixer contract, emitted before the
+/// challenge window of a deal
+pub struct CommitRootsEvent {
+    pub deal_id: H256,
+    pub participants_root: H256,
+    pub outputs_root: H256,
+}
+
+/// The `Distribute(dealId, amount, recipients, receiptHash)` event of the Mixer contract, emitted when the
+/// outputs of a deal are paid out. Refunds carry a zero receipt hash.
+pub struct DistributeEvent {
+    pub deal_id: H256,
+    /// The deposit amount of the deal
+    pub amount: U256,
+    /// The shuffled recipients, in the order of the outputs Merkle tree
+    pub recipients: Vec<H160>,
+    pub receipt_hash: H256,
+}
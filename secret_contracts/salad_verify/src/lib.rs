@@ -0,0 +1,10 @@
+//! Independent verification of the Salad deals for explorers and auditors: checks the `CommitRoots` and
+//! `Distribute` events of the Mixer contract against the receipts and Merkle proofs issued by the enclave,
+//! with the receipt, Merkle and encoding definitions the secret contract commits with.
+
+mod events;
+mod verify;
+
+pub use salad_types::{H160, H256, U256};
+pub use events::{CommitRootsEvent, DistributeEvent};
+pub use verify::{verify_output, verify_participant, verify_receipt, verify_shuffle_seed, verify_stats, DealStats, Receipt};
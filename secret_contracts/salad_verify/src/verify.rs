@@ -0,0 +1,142 @@
+use std::panic::{self, AssertUnwindSafe};
+
+use enigma_crypto::KeyPair;
+use salad_types::codec::{self, Decoder};
+use salad_types::protocol::{ADDRESS_SIZE, PUB_KEY_SIZE, SIG_SIZE};
+use salad_types::{digest, merkle, H160, H256, U256};
+
+use crate::events::{CommitRootsEvent, DistributeEvent};
+
+/// A deal receipt, as disclosed to the auditors along with the deal record
+pub struct Receipt {
+    pub deal_id: H256,
+    pub attested_block: u64,
+    pub recipients: Vec<H160>,
+}
+
+/// The statistics of a deal signed by the enclave, as returned by `get_deal_receipt`
+pub struct DealStats {
+    pub deal_id: H256,
+    pub receipt_hash: H256,
+    pub nb_participants: u64,
+    pub denomination: U256,
+    pub total_fee: U256,
+    pub nb_chunks: u64,
+    pub risk_counts: Vec<u64>,
+}
+
+/// Decode an encoding of the secret contract, its decoder panicking on malformed encodings
+fn decode<T, F: FnOnce() -> T>(kind: &str, decode: F) -> Result<T, String> {
+    panic::catch_unwind(AssertUnwindSafe(decode)).map_err(|_| format!("Malformed {} encoding", kind))
+}
+
+fn check<T: PartialEq + std::fmt::Debug>(kind: &str, expected: &T, found: &T) -> Result<(), String> {
+    if expected != found {
+        return Err(format!("Mismatching {}: {:?} != {:?}", kind, expected, found));
+    }
+    Ok(())
+}
+
+/// Verify the canonical encoding of a deal receipt (deal_id: Bytes32, attested_block: uint64, recipients: List[Bytes20])
+/// against the `Distribute` event of its deal: the DealId, the recipients in their shuffled order and the
+/// receipt hash emitted on-chain. Returns the decoded receipt.
+pub fn verify_receipt(distribute: &DistributeEvent, receipt: &[u8]) -> Result<Receipt, String> {
+    let receipt = decode("receipt", || {
+        let mut decoder = Decoder::new(receipt);
+        let deal_id = H256::from(decoder.read_bytes(32));
+        let attested_block = codec::decode_u64(decoder.read_bytes(8));
+        decoder.read_offset();
+        let recipients = codec::items(decoder.finish()[0], ADDRESS_SIZE).iter().map(|item| H160::from(*item)).collect();
+        Receipt { deal_id, attested_block, recipients }
+    })?;
+    check("DealId", &distribute.deal_id, &receipt.deal_id)?;
+    check("recipients", &distribute.recipients, &receipt.recipients)?;
+    let receipt_hash = digest::receipt_hash(&receipt.deal_id, receipt.attested_block, &receipt.recipients);
+    check("receipt hash", &distribute.receipt_hash, &receipt_hash)?;
+    Ok(receipt)
+}
+
+/// Verify the Merkle proof of the output of `amount` paid to the recipient at `index` of the `Distribute`
+/// event against the outputs root committed in the `CommitRoots` event of the deal
+pub fn verify_output(
+    commit: &CommitRootsEvent,
+    distribute: &DistributeEvent,
+    index: usize,
+    amount: &U256,
+    proof: &[H256],
+) -> Result<(), String> {
+    check("DealId", &commit.deal_id, &distribute.deal_id)?;
+    let recipient = distribute.recipients.get(index).ok_or_else(|| format!("Unknown output: {}", index))?;
+    let leaf = merkle::output_leaf(recipient, amount);
+    if !merkle::verify(&leaf, index, distribute.recipients.len(), proof, &commit.outputs_root) {
+        return Err(format!("Invalid proof of output {} to {:?}", index, recipient));
+    }
+    Ok(())
+}
+
+/// Verify the Merkle proof of `sender` as the participant at `index` of the `nb_participants` participants
+/// of the deal, against the participants root committed in its `CommitRoots` event. Staged deposits joining
+/// the deal are participants of zero sender.
+pub fn verify_participant(
+    commit: &CommitRootsEvent,
+    sender: &H160,
+    index: usize,
+    nb_participants: usize,
+    proof: &[H256],
+) -> Result<(), String> {
+    if !merkle::verify(sender, index, nb_participants, proof, &commit.participants_root) {
+        return Err(format!("Invalid proof of participant {}: {:?}", index, sender));
+    }
+    Ok(())
+}
+
+/// Verify the statistics signed by the enclave (encoding || signature) of the deal of the `Distribute` event:
+/// the signature by the enclave signing key of `signing_pub_key`, as returned by `get_signing_pub_key`,
+/// the DealId, the receipt hash and the denomination. Returns the decoded statistics.
+pub fn verify_stats(distribute: &DistributeEvent, signed_stats: &[u8], signing_pub_key: &[u8]) -> Result<DealStats, String> {
+    if signing_pub_key.len() != PUB_KEY_SIZE {
+        return Err(format!("Invalid signing pubKey size: {}", signing_pub_key.len()));
+    }
+    if signed_stats.len() < SIG_SIZE {
+        return Err(format!("Invalid signed statistics size: {}", signed_stats.len()));
+    }
+    let (encoding, raw_signature) = signed_stats.split_at(signed_stats.len() - SIG_SIZE);
+    let mut signature = [0_u8; SIG_SIZE];
+    signature.copy_from_slice(raw_signature);
+    let signer = KeyPair::recover(encoding, signature).map_err(|err| format!("Invalid statistics signature: {:?}", err))?;
+    if signer[..] != signing_pub_key[..] {
+        return Err(String::from("Statistics not signed by the enclave signing key"));
+    }
+    let stats = decode("statistics", || {
+        let mut decoder = Decoder::new(encoding);
+        let deal_id = H256::from(decoder.read_bytes(32));
+        let receipt_hash = H256::from(decoder.read_bytes(32));
+        let nb_participants = codec::decode_u64(decoder.read_bytes(8));
+        let denomination = codec::decode_uint256(decoder.read_bytes(32));
+        let total_fee = codec::decode_uint256(decoder.read_bytes(32));
+        let nb_chunks = codec::decode_u64(decoder.read_bytes(8));
+        decoder.read_offset();
+        let risk_counts = codec::items(decoder.finish()[0], 8).iter().map(|item| codec::decode_u64(item)).collect();
+        DealStats { deal_id, receipt_hash, nb_participants, denomination, total_fee, nb_chunks, risk_counts }
+    })?;
+    check("DealId", &distribute.deal_id, &stats.deal_id)?;
+    check("receipt hash", &distribute.receipt_hash, &stats.receipt_hash)?;
+    check("denomination", &distribute.amount, &stats.denomination)?;
+    Ok(stats)
+}
+
+/// Verify the shuffle seed revealed by the enclave for a distributed deal, canonically encoded as
+/// (seed: uint64, salt: Bytes32, commitment: Bytes32), against the commitment returned by `get_shuffle_commitment`
+/// before its distribution. Returns the seed, to recompute the permutation of the outputs.
+pub fn verify_shuffle_seed(deal_id: &H256, commitment: &H256, revealed: &[u8]) -> Result<u64, String> {
+    let (seed, salt, revealed_commitment) = decode("shuffle seed", || {
+        let mut decoder = Decoder::new(revealed);
+        let seed = codec::decode_u64(decoder.read_bytes(8));
+        let salt = H256::from(decoder.read_bytes(32));
+        let revealed_commitment = H256::from(decoder.read_bytes(32));
+        (seed, salt, revealed_commitment)
+    })?;
+    check("shuffle commitment", commitment, &revealed_commitment)?;
+    check("shuffle commitment", commitment, &digest::shuffle_commitment(deal_id, seed, &salt))?;
+    Ok(seed)
+}
@@ -0,0 +1,5 @@
+pragma solidity ^0.5.1;
+
+interface IPayoutBridge {
+    function relay(address _recipient) external payable;
+}
@@ -1,5 +1,14 @@
 pragma solidity ^0.5.1;
 
 interface ISalad {
-    function distribute(uint256 _dealId, address payable[] calldata _recipients) external;
+    function commitRoots(uint256 _dealId, uint256 _participantsRoot, uint256 _outputsRoot) external;
+    function distribute(uint256 _dealId, address payable[] calldata _recipients, uint[] calldata _amounts, uint _redeposited, uint _redeemed, uint256 _receiptHash) external;
+    function distributeWithEscrow(uint256 _dealId, address payable[] calldata _recipients, address[] calldata _guardians, uint[] calldata _amounts, uint _redeposited, uint _redeemed, uint256 _receiptHash) external;
+    function setPayoutBridge(address _bridge) external;
+    function distributeToBridge(uint256 _dealId, address[] calldata _recipients, uint[] calldata _amounts, uint _redeposited, uint _redeemed, uint256 _receiptHash) external;
+    function distributeAssets(uint256 _dealId, address[] calldata _assets, address[] calldata _payees, address payable[] calldata _recipients, uint[] calldata _amounts, uint256 _receiptHash) external;
+    function recordReceipt(uint256 _dealId, uint256 _receiptHash) external;
+    function refundDeal(uint256 _dealId) external;
+    function commitNotePayouts(uint256 _dealId, uint256 _payoutsRoot, uint _nbPayouts) external;
+    function withdrawNote(uint256 _dealId, uint256 _nullifier, address payable _payout, uint _amount, uint _index, uint256[] calldata _proof) external;
 }
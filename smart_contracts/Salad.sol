@@ -1,15 +1,18 @@
 pragma solidity ^0.5.1;
 
 import "./ISalad.sol";
+import "./IPayoutBridge.sol";
 import {SaladCommon} from "./utils/SaladCommon.sol";
 import "openzeppelin-solidity/contracts/math/SafeMath.sol";
 import {Bytes} from "./utils/Bytes.sol";
 import "openzeppelin-solidity/contracts/ownership/Ownable.sol";
+import "openzeppelin-solidity/contracts/token/ERC20/IERC20.sol";
 
 contract Salad is ISalad, Ownable {
     using SafeMath for uint256;
     using Bytes for address;
     using Bytes for uint256;
+    using Bytes for bytes32;
 
     struct Deal {
         address organizer;
@@ -18,33 +21,108 @@ contract Salad is ISalad, Ownable {
         address[] participants;
         address[] recipients;
         DealStatus status;
+        bytes32 participantsRoot;
+        bytes32 outputsRoot;
+        uint commitBlockNumber;
+        // The vouchers of the deposits of a voucher deal, in lieu of participants
+        bytes32[] vouchers;
+        // The Merkle root of the note withdrawals committed by the enclave with the deal, and their number
+        bytes32 notePayoutsRoot;
+        uint nbNotePayouts;
+        // The total paid out of the pool to the staged deposits joining the deal, set at creation
+        uint redeemed;
+        uint startBlockNumber;
+        // The asset and amount of each deposit of a multi-asset deal, locked in the asset balances of its participants
+        address[] assets;
+        uint[] assetAmounts;
     }
-    enum DealStatus {Undefined, Executable, Executed}
+    enum DealStatus {Undefined, Executable, Executed, Committed, Refunded}
 
     struct Balance {
         uint amount;
         uint lastDepositBlockNumber;
+        // The deposits of the pending deals of the participant, never withdrawn before the deals settle
+        uint locked;
     }
 
     mapping(bytes32 => Deal) public deals;
     mapping(address => Balance) public balances;
+    // The balances of each participant in each asset for the multi-asset deals, the zero asset holding ETH
+    mapping(address => mapping(address => Balance)) public assetBalances;
+    // The total deposited in each asset by the participants of each multi-asset deal, bounding its outputs in the asset
+    mapping(bytes32 => mapping(address => uint)) public assetDealDeposits;
+    // Deposits identified by opaque voucher ids, bound to the commitment of their payload
+    struct Voucher {
+        uint amount;
+        bytes32 commitment;
+        uint depositBlockNumber;
+        address payable depositor;
+        // The deposits of the pending deals of the voucher, never withdrawn before the deals settle
+        uint locked;
+    }
+    mapping(bytes32 => Voucher) public vouchers;
+    uint public voucherCount;
+    // Outputs held until released by both their recipient and the guardian designated by the depositor
+    struct Escrow {
+        uint amount;
+        address payable recipient;
+        address guardian;
+        bool releasedByRecipient;
+        bool releasedByGuardian;
+    }
+    mapping(bytes32 => Escrow) public escrows;
+    uint public escrowCount;
+    // Enclave receipt hashes of the distributed deals, timestamped when recorded
+    struct Receipt {
+        bytes32 receiptHash;
+        uint timestamp;
+        uint blockNumber;
+    }
+    mapping(bytes32 => Receipt) public receipts;
     address[] authorizedRelayers;
     bytes32[] public dealIds;
     uint8 public depositLockPeriodInBlocks;
     uint8 public dealIntervalInBlocks;
     uint8 public relayerFeePercent;
     uint8 public participationThreshold;
+    uint8 public challengeWindowInBlocks;
+    // Blocks after its creation past which a deal never committed may be refunded to its senders
+    uint public executionWindowInBlocks;
     uint public lastExecutionBlockNumber;
+    // The Enigma contract, the only caller of the callouts of the Salad secret contract
+    address public enigma;
+    // The signing key of the Salad contract on Secret Network, whose signed callouts are relayed through `relayCallout`
+    address public secretSigner;
+    // Nonces of the signed callouts already relayed
+    mapping(uint => bool) public relayedCallouts;
+    // The bridge contract the outputs of `distributeToBridge` are relayed through, committed by the secret contract
+    address public payoutBridge;
+    // Shares of the distributed deposits staged back into the pool, until paid out by later deals,
+    // and outputs held as notes by the enclave, until withdrawn
+    uint public redepositPool;
+    // Nullifiers of the note withdrawals paid out of the pool reserve
+    mapping(bytes32 => bool) public notePayoutNullifiers;
     // TODO: Should the contract validate a fix deposit amount for all deals?
 
     event NewDeal(address indexed user, bytes32 indexed _dealId, uint _startTime, uint _deposit, uint _numParticipants);
     event Deposit(address indexed _depositor, uint _value, uint _balance);
     event Withdraw(address indexed _depositor, uint _value);
-    event Distribute(bytes32 indexed _dealId, uint _amount, address[] _recipients);
+    event AssetDeposit(address indexed _depositor, address indexed _asset, uint _value, uint _balance);
+    event AssetWithdraw(address indexed _depositor, address indexed _asset, uint _value);
+    event VoucherDeposit(bytes32 indexed _voucherId, bytes32 _commitment, uint _value);
+    event CommitRoots(bytes32 indexed _dealId, bytes32 _participantsRoot, bytes32 _outputsRoot);
+    event Distribute(bytes32 indexed _dealId, uint _amount, address[] _recipients, bytes32 _receiptHash);
+    event NewEscrow(bytes32 indexed _escrowId, address indexed _recipient, address indexed _guardian, uint _value);
+    event ReleaseEscrow(bytes32 indexed _escrowId, address _releaser);
+    event RefundDeal(bytes32 indexed _dealId, uint _nbDeposits);
+    event RecordReceipt(bytes32 indexed _dealId, bytes32 _receiptHash, uint _timestamp);
+    event CommitNotePayouts(bytes32 indexed _dealId, bytes32 _payoutsRoot, uint _nbPayouts);
+    event WithdrawNote(bytes32 indexed _nullifier, address indexed _payout, uint _value);
 
     modifier onlyEnigma {
-        // TODO: Verify the calling fn in addition to the Enigma contract address
-        // require(msg.sender == owner, "Only owner can call this function.");
+        // TODO: Verify the calling secret contract in addition to the Enigma contract address
+        // The signed callouts of the Secret Network contract are called by this contract from `relayCallout`
+        require((enigma != address(0) && msg.sender == enigma) || msg.sender == address(this), "Only the Enigma contract can call this function.");
         _;
     }
 
@@ -60,8 +138,10 @@ contract Salad is ISalad, Ownable {
         _;
     }
 
-    constructor(uint8 _depositLockPeriodInBlocks, uint8 _dealIntervalInBlocks, address _relayer, uint8 _relayerFeePercent, uint8 _participationThreshold) public {
+    constructor(uint8 _depositLockPeriodInBlocks, uint8 _dealIntervalInBlocks, address _relayer, uint8 _relayerFeePercent, uint8 _participationThreshold, uint _executionWindowInBlocks) public {
+        require(_executionWindowInBlocks > 0, "Execution window must be positive.");
         depositLockPeriodInBlocks = _depositLockPeriodInBlocks;
+        executionWindowInBlocks = _executionWindowInBlocks;
         dealIntervalInBlocks = _dealIntervalInBlocks;
         relayerFeePercent = _relayerFeePercent;
         participationThreshold = _participationThreshold;
@@ -69,6 +149,35 @@ contract Salad is ISalad, Ownable {
         authorizedRelayers.push(_relayer);
     }
 
+    function setEnigma(address _enigma) public onlyOwner {
+        require(_enigma != address(0), "Invalid Enigma contract.");
+        enigma = _enigma;
+    }
+
+    function setSecretSigner(address _signer) public onlyOwner {
+        require(_signer != address(0), "Invalid secret signer.");
+        secretSigner = _signer;
+    }
+
+    /**
+    * Relay a callout signed by the Salad contract on Secret Network, which logs its callouts instead of calling
+    * the Mixer. Anyone may relay a callout, each nonce once.
+    *
+    * @param _nonce The callout nonce of the secret contract
+    * @param _calldata The ABI-encoded call of the callout
+    * @param _v, _r, _s The signature of keccak256(mixer || nonce || calldata) by the signing key of the secret contract
+    */
+    function relayCallout(uint _nonce, bytes memory _calldata, uint8 _v, bytes32 _r, bytes32 _s)
+    public {
+        require(secretSigner != address(0), "Secret signer not set.");
+        require(!relayedCallouts[_nonce], "Callout already relayed.");
+        bytes32 _hash = keccak256(abi.encodePacked(address(this), _nonce, _calldata));
+        require(ecrecover(_hash, _v, _r, _s) == secretSigner, "Callout not signed by the secret signer.");
+        relayedCallouts[_nonce] = true;
+        (bool _success, ) = address(this).call(_calldata);
+        require(_success, "Relayed callout failed.");
+    }
+
     function setDealInterval(uint8 _intervalInBlocks) public onlyOwner {
         dealIntervalInBlocks = _intervalInBlocks;
     }
@@ -77,6 +186,15 @@ contract Salad is ISalad, Ownable {
         participationThreshold = _nbParticipants;
     }
 
+    function setChallengeWindow(uint8 _windowInBlocks) public onlyOwner {
+        challengeWindowInBlocks = _windowInBlocks;
+    }
+
+    function setExecutionWindow(uint _windowInBlocks) public onlyOwner {
+        require(_windowInBlocks > 0, "Execution window must be positive.");
+        executionWindowInBlocks = _windowInBlocks;
+    }
+
     function authorizeRelayer(address _relayer ) public onlyOwner {
         authorizedRelayers.push(_relayer);
     }
@@ -95,25 +213,98 @@ contract Salad is ISalad, Ownable {
     * @param _amount The required deposit amount (in Wei)
     * @param _participants The sender addresses of Deal participants
     * @param _nonce The nonce (operator's transaction count)
+    * @param _nbRedeemed The number of deposits staged back into the pool joining the Deal
     */
-    function newDeal(uint _amount, address[] memory _participants, uint _nonce)
+    function newDeal(uint _amount, address[] memory _participants, uint _nonce, uint _nbRedeemed)
     public onlyRelayer {
         uint newDealBlockNumber = lastExecutionBlockNumber.add(dealIntervalInBlocks);
         require(newDealBlockNumber < block.number, "Deal creation interval not reached");
+        bytes32 _dealId = generateDealId(_amount, _participants, _nonce);
+        require(deals[_dealId].status == DealStatus.Undefined, "Deal already exists.");
         for (uint i = 0; i < _participants.length; i++) {
-            require(balances[_participants[i]].amount >= _amount, "Participant balance(s) insufficient");
+            Balance storage _balance = balances[_participants[i]];
+            require(_balance.amount.sub(_balance.locked) >= _amount, "Participant balance(s) insufficient");
+            _balance.locked = _balance.locked.add(_amount);
         }
-        bytes32 _dealId = generateDealId(_amount, _participants, _nonce);
+        uint _redeemed = _amount.mul(_nbRedeemed);
+        require(redepositPool >= _redeemed, "Not enough funds in the pool reserve.");
         dealIds.push(_dealId);
         deals[_dealId].organizer = msg.sender;
         deals[_dealId].startTime = now;
         deals[_dealId].deposit = _amount;
+        deals[_dealId].redeemed = _redeemed;
+        deals[_dealId].startBlockNumber = block.number;
         deals[_dealId].participants = _participants;
         deals[_dealId].recipients = new address[](_participants.length);
         deals[_dealId].status = DealStatus.Executable;
         emit NewDeal(msg.sender, _dealId, now, _amount, _participants.length);
     }
 
+    /**
+    * Create a new Pending Deal of voucher deposits, the deal listing no sender address
+    *
+    * @param _amount The required deposit amount (in Wei)
+    * @param _vouchers The voucher ids of the Deal deposits
+    * @param _nonce The nonce (operator's transaction count)
+    */
+    function newVoucherDeal(uint _amount, bytes32[] memory _vouchers, uint _nonce)
+    public onlyRelayer {
+        uint newDealBlockNumber = lastExecutionBlockNumber.add(dealIntervalInBlocks);
+        require(newDealBlockNumber < block.number, "Deal creation interval not reached");
+        bytes32 _dealId = generateVoucherDealId(_amount, _vouchers, _nonce);
+        require(deals[_dealId].status == DealStatus.Undefined, "Deal already exists.");
+        for (uint i = 0; i < _vouchers.length; i++) {
+            Voucher storage _voucher = vouchers[_vouchers[i]];
+            require(_voucher.amount.sub(_voucher.locked) >= _amount, "Voucher balance(s) insufficient");
+            _voucher.locked = _voucher.locked.add(_amount);
+        }
+        dealIds.push(_dealId);
+        deals[_dealId].organizer = msg.sender;
+        deals[_dealId].startTime = now;
+        deals[_dealId].deposit = _amount;
+        deals[_dealId].startBlockNumber = block.number;
+        deals[_dealId].vouchers = _vouchers;
+        deals[_dealId].recipients = new address[](_vouchers.length);
+        deals[_dealId].status = DealStatus.Executable;
+        emit NewDeal(msg.sender, _dealId, now, _amount, _vouchers.length);
+    }
+
+    /**
+    * Create a new Pending Deal of deposits in (possibly different) assets of equal USD value,
+    * the deposit of each participant being locked in its asset balance
+    *
+    * @param _value The common USD value of the deposits, as computed by the secret contract from attested prices
+    * @param _assets The asset of each deposit, zero for ETH
+    * @param _amounts The amount of each deposit, in the units of its asset
+    * @param _participants The sender addresses of Deal participants
+    * @param _nonce The nonce (operator's transaction count)
+    */
+    function newAssetDeal(uint _value, address[] memory _assets, uint[] memory _amounts, address[] memory _participants, uint _nonce)
+    public onlyRelayer {
+        uint newDealBlockNumber = lastExecutionBlockNumber.add(dealIntervalInBlocks);
+        require(newDealBlockNumber < block.number, "Deal creation interval not reached");
+        require(_assets.length == _participants.length && _amounts.length == _participants.length, "Mismatching assets, amounts and participants.");
+        bytes32 _dealId = generateAssetDealId(_value, _assets, _amounts, _participants, _nonce);
+        require(deals[_dealId].status == DealStatus.Undefined, "Deal already exists.");
+        for (uint i = 0; i < _participants.length; i++) {
+            Balance storage _balance = assetBalances[_participants[i]][_assets[i]];
+            require(_amounts[i] > 0, "Deposit amount must be positive.");
+            require(_balance.amount.sub(_balance.locked) >= _amounts[i], "Participant balance(s) insufficient");
+            _balance.locked = _balance.locked.add(_amounts[i]);
+            assetDealDeposits[_dealId][_assets[i]] = assetDealDeposits[_dealId][_assets[i]].add(_amounts[i]);
+        }
+        dealIds.push(_dealId);
+        deals[_dealId].organizer = msg.sender;
+        deals[_dealId].startTime = now;
+        deals[_dealId].startBlockNumber = block.number;
+        deals[_dealId].participants = _participants;
+        deals[_dealId].assets = _assets;
+        deals[_dealId].assetAmounts = _amounts;
+        deals[_dealId].recipients = new address[](_participants.length);
+        deals[_dealId].status = DealStatus.Executable;
+        emit NewDeal(msg.sender, _dealId, now, _value, _participants.length);
+    }
+
     /**
     * Make deposit to own balance for participation in Deals
     */
@@ -127,16 +318,87 @@ contract Salad is ISalad, Ownable {
     }
 
     /**
-    * Withdraw from own balance
+    * Make a deposit identified by an opaque voucher id, bound to the commitment of its encrypted payload
+    *
+    * @param _commitment The keccak256 hash of the deposit public key and encrypted payload
+    */
+    function makeVoucherDeposit(bytes32 _commitment)
+    public
+    payable
+    returns (bytes32) {
+        require(msg.value > 0, "Deposit value must be positive.");
+        bytes32 _voucherId = keccak256(abi.encodePacked(address(this), voucherCount));
+        voucherCount = voucherCount.add(1);
+        vouchers[_voucherId] = Voucher(msg.value, _commitment, block.number, msg.sender, 0);
+        emit VoucherDeposit(_voucherId, _commitment, msg.value);
+        return _voucherId;
+    }
+
+    /**
+    * Withdraw the unmixed balance of an own voucher, less the deposits of its pending deals
+    *
+    * @param _voucherId The voucher id
+    */
+    function withdrawVoucher(bytes32 _voucherId)
+    public {
+        Voucher storage _voucher = vouchers[_voucherId];
+        require(_voucher.depositor == msg.sender, "Not the voucher depositor");
+        require(_voucher.depositBlockNumber.add(depositLockPeriodInBlocks) < block.number, "Deposit not yet available for withdrawal");
+        uint amount = _voucher.amount.sub(_voucher.locked);
+        _voucher.amount = _voucher.locked;
+        msg.sender.transfer(amount);
+        emit Withdraw(msg.sender, amount);
+    }
+
+    /**
+    * Make a deposit to own balance in an asset for participation in multi-asset Deals, transferred from
+    * the ERC20 allowance of the sender, or in ETH for the zero asset
+    *
+    * @param _asset The asset, zero for ETH
+    * @param _amount The amount, in the units of the asset
+    */
+    function makeAssetDeposit(address _asset, uint _amount)
+    public
+    payable {
+        require(_amount > 0, "Deposit value must be positive.");
+        if (_asset == address(0)) {
+            require(msg.value == _amount, "Mismatching deposit value.");
+        } else {
+            require(msg.value == 0, "ETH sent with an asset deposit.");
+            require(IERC20(_asset).transferFrom(msg.sender, address(this), _amount), "Unable to transfer asset");
+        }
+        Balance storage _balance = assetBalances[msg.sender][_asset];
+        _balance.amount = _balance.amount.add(_amount);
+        _balance.lastDepositBlockNumber = block.number;
+        emit AssetDeposit(msg.sender, _asset, _amount, _balance.amount);
+    }
+
+    /**
+    * Withdraw from own balance in an asset, less the deposits of the pending multi-asset deals
+    *
+    * @param _asset The asset, zero for ETH
+    */
+    function withdrawAsset(address _asset)
+    public {
+        Balance storage _balance = assetBalances[msg.sender][_asset];
+        require(_balance.lastDepositBlockNumber.add(depositLockPeriodInBlocks) < block.number, "Deposit not yet available for withdrawal");
+        uint amount = _balance.amount.sub(_balance.locked);
+        _balance.amount = _balance.locked;
+        require(_payAsset(_asset, msg.sender, amount), "Unable to withdraw asset");
+        emit AssetWithdraw(msg.sender, _asset, amount);
+    }
+
+    /**
+    * Withdraw from own balance, less the deposits of the pending deals
     */
     function withdraw()
     public
     payable {
         uint withdrawBlockNumber = balances[msg.sender].lastDepositBlockNumber.add(depositLockPeriodInBlocks);
         require(withdrawBlockNumber < block.number, "Deposit not yet available for withdrawal");
-        uint amount = balances[msg.sender].amount;
+        uint amount = balances[msg.sender].amount.sub(balances[msg.sender].locked);
+        balances[msg.sender].amount = balances[msg.sender].locked;
         msg.sender.transfer(amount);
-        balances[msg.sender].amount = 0;
         emit Withdraw(msg.sender, amount);
     }
 
@@ -181,33 +443,476 @@ contract Salad is ISalad, Ownable {
         return _dealId;
     }
 
+    function _generateVoucherDealIdMessage(uint _amount, bytes32[] memory _vouchers, uint _nonce)
+    public
+    returns (bytes memory) {
+        bytes memory _message;
+        _message = SaladCommon.appendMessage(_message, _amount.toBytes());
+        _message = SaladCommon.appendMessageArrayLength(_vouchers.length, _message);
+        for (uint i = 0; i < _vouchers.length; i++) {
+            _message = SaladCommon.appendMessage(_message, _vouchers[i].toBytes());
+        }
+        _message = SaladCommon.appendMessageArrayLength(_vouchers.length, _message);
+        for (uint i = 0; i < _vouchers.length; i++) {
+            _message = SaladCommon.appendMessage(_message, vouchers[_vouchers[i]].commitment.toBytes());
+        }
+        address _sender = msg.sender;
+        _message = SaladCommon.appendMessage(_message, _sender.toBytes());
+        _message = SaladCommon.appendMessage(_message, _nonce.toBytes());
+        return _message;
+    }
+
+    /**
+    * Generate the DealId of voucher deposits, binding each voucher to the commitment of its payload
+    * H(Amount, Voucher Ids, Commitments, Relayer Ethereum Address, Relayer Ethereum Nonce)
+    *
+    * @param _amount The required deposit amount (in Wei)
+    * @param _vouchers The voucher ids of the Deal deposits
+    * @param _nonce The nonce (operator's transaction count)
+    */
+    function generateVoucherDealId(uint _amount, bytes32[] memory _vouchers, uint _nonce)
+    public
+    returns (bytes32) {
+        bytes memory _message = _generateVoucherDealIdMessage(_amount, _vouchers, _nonce);
+        bytes32 _dealId = keccak256(_message);
+        return _dealId;
+    }
+
+    function _generateAssetDealIdMessage(uint _value, address[] memory _assets, uint[] memory _amounts, address[] memory _participants, uint _nonce)
+    public
+    returns (bytes memory) {
+        bytes memory _message;
+        _message = SaladCommon.appendMessage(_message, _value.toBytes());
+        _message = SaladCommon.appendMessageArrayLength(_assets.length, _message);
+        for (uint i = 0; i < _assets.length; i++) {
+            _message = SaladCommon.appendMessage(_message, _assets[i].toBytes());
+        }
+        _message = SaladCommon.appendMessageArrayLength(_participants.length, _message);
+        for (uint i = 0; i < _participants.length; i++) {
+            _message = SaladCommon.appendMessage(_message, _participants[i].toBytes());
+        }
+        _message = SaladCommon.appendMessageArrayLength(_amounts.length, _message);
+        for (uint i = 0; i < _amounts.length; i++) {
+            _message = SaladCommon.appendMessage(_message, _amounts[i].toBytes());
+        }
+        address _sender = msg.sender;
+        _message = SaladCommon.appendMessage(_message, _sender.toBytes());
+        _message = SaladCommon.appendMessage(_message, _nonce.toBytes());
+        return _message;
+    }
+
+    /**
+    * Generate the DealId of a multi-asset deal, binding the asset and amount of each deposit
+    * H(USD Value, Assets, Sender Addresses, Amounts, Relayer Ethereum Address, Relayer Ethereum Nonce)
+    *
+    * @param _value The common USD value of the deposits
+    * @param _assets The asset of each deposit, zero for ETH
+    * @param _amounts The amount of each deposit, in the units of its asset
+    * @param _participants The sender addresses of Deal participants
+    * @param _nonce The nonce (operator's transaction count)
+    */
+    function generateAssetDealId(uint _value, address[] memory _assets, uint[] memory _amounts, address[] memory _participants, uint _nonce)
+    public
+    returns (bytes32) {
+        bytes memory _message = _generateAssetDealIdMessage(_value, _assets, _amounts, _participants, _nonce);
+        bytes32 _dealId = keccak256(_message);
+        return _dealId;
+    }
+
+    /**
+    * Commit the Merkle roots of the Deal participants and shuffled outputs, opening the challenge window.
+    * Callable only by the Salad secret contract
+    *
+    * @param _dealId The DealId, a unique identifier and fingerprint for the Deal parameters
+    * @param _participantsRoot The Merkle root of the participant addresses
+    * @param _outputsRoot The Merkle root of the shuffled outputs
+    */
+    function commitRoots(uint256 _dealId, uint256 _participantsRoot, uint256 _outputsRoot)
+    public
+    onlyEnigma {
+        // TODO: This conversion is only necessary because of an Enigma callback bug with bytes32
+        bytes32 dealId = bytes32(_dealId);
+        require(deals[dealId].status == DealStatus.Executable, "Deal is not executable.");
+        deals[dealId].participantsRoot = bytes32(_participantsRoot);
+        deals[dealId].outputsRoot = bytes32(_outputsRoot);
+        deals[dealId].commitBlockNumber = block.number;
+        deals[dealId].status = DealStatus.Committed;
+        emit CommitRoots(dealId, bytes32(_participantsRoot), bytes32(_outputsRoot));
+    }
+
+    /**
+    * Commit the Merkle root of the note withdrawals paid out after the Deal, before its roots are committed.
+    * Callable only by the Salad secret contract
+    *
+    * @param _dealId The DealId
+    * @param _payoutsRoot The Merkle root of the note withdrawals, each leaf being nullifier || payout || amount
+    * @param _nbPayouts The number of note withdrawals
+    */
+    function commitNotePayouts(uint256 _dealId, uint256 _payoutsRoot, uint _nbPayouts)
+    public
+    onlyEnigma {
+        bytes32 dealId = bytes32(_dealId);
+        require(deals[dealId].status == DealStatus.Executable, "Deal is not executable.");
+        require(deals[dealId].notePayoutsRoot == bytes32(0), "Note payouts already committed.");
+        require(_payoutsRoot != 0 && _nbPayouts > 0, "Empty note payouts.");
+        deals[dealId].notePayoutsRoot = bytes32(_payoutsRoot);
+        deals[dealId].nbNotePayouts = _nbPayouts;
+        emit CommitNotePayouts(dealId, bytes32(_payoutsRoot), _nbPayouts);
+    }
+
+    modifier onlyDistributable(uint256 _dealId) {
+        bytes32 dealId = bytes32(_dealId);
+        require(deals[dealId].status == DealStatus.Committed, "Deal roots are not committed.");
+        require(deals[dealId].commitBlockNumber.add(challengeWindowInBlocks) <= block.number, "Challenge window not elapsed.");
+        _;
+    }
+
     /**
     * Distribute funds by executing Deal.
     * Callable only by the Salad secret contract
     *
     * @param _dealId The DealId, a unique identifier and fingerprint for the Deal parameters
     * @param _recipients The shuffled recipient addresses
+    * @param _amounts The amount (in Wei) transferred to each recipient
+    * @param _receiptHash The enclave receipt hash, emitted for off-chain indexers
     */
-    function distribute(uint256 _dealId, address payable[] memory _recipients)
-    public {
-        //    onlyEnigma() {
-
-        // Distribute the deposits to destination addresses
+    function distribute(uint256 _dealId, address payable[] memory _recipients, uint[] memory _amounts, uint _redeposited, uint _redeemed, uint256 _receiptHash)
+    public
+    onlyEnigma
+    onlyDistributable(_dealId) {
         // TODO: This conversion is only necessary because of an Enigma callback bug with bytes32
         bytes32 dealId = bytes32(_dealId);
-        require(deals[dealId].status == DealStatus.Executable, "Deal is not executable.");
+        require(_recipients.length == _amounts.length, "Mismatching recipients and amounts.");
+        _settle(dealId, _amounts, _redeposited, _redeemed);
+        // Distribute the deposits to destination addresses
         deals[dealId].recipients = _recipients;
         address[] memory _npRecipients = new address[](_recipients.length);
         for (uint i = 0; i < _recipients.length; i++) {
-            require(balances[deals[dealId].participants[i]].amount >= deals[dealId].deposit, "Not enough deposit to transfer.");
-            // Transferring the deal's deposit amount to each recipient
-            require(_recipients[i].send(deals[dealId].deposit), "Unable to distribute deposit");
-            balances[deals[dealId].participants[i]].amount = balances[deals[dealId].participants[i]].amount.sub(deals[dealId].deposit);
+            require(_recipients[i].send(_amounts[i]), "Unable to distribute deposit");
             _npRecipients[i] = _recipients[i];
         }
         deals[dealId].status = DealStatus.Executed;
         lastExecutionBlockNumber = block.number;
-        emit Distribute(dealId, deals[dealId].deposit, _npRecipients);
+        emit Distribute(dealId, deals[dealId].deposit, _npRecipients, bytes32(_receiptHash));
+    }
+
+    /**
+    * Distribute the deposits like `distribute`, the outputs with a guardian being held in escrow
+    * until released by both their recipient and their guardian
+    *
+    * @param _dealId The deal id
+    * @param _recipients The output recipients
+    * @param _guardians The guardian of each output, zero for outputs paid directly
+    * @param _amounts The output amounts
+    * @param _redeposited The total staged back into the pool
+    * @param _redeemed The total paid out of the pool
+    * @param _receiptHash The receipt hash
+    */
+    function distributeWithEscrow(uint256 _dealId, address payable[] memory _recipients, address[] memory _guardians, uint[] memory _amounts, uint _redeposited, uint _redeemed, uint256 _receiptHash)
+    public
+    onlyEnigma
+    onlyDistributable(_dealId) {
+        bytes32 dealId = bytes32(_dealId);
+        require(_recipients.length == _amounts.length && _guardians.length == _amounts.length, "Mismatching recipients, guardians and amounts.");
+        _settle(dealId, _amounts, _redeposited, _redeemed);
+        deals[dealId].recipients = _recipients;
+        address[] memory _npRecipients = new address[](_recipients.length);
+        for (uint i = 0; i < _recipients.length; i++) {
+            if (_guardians[i] == address(0)) {
+                require(_recipients[i].send(_amounts[i]), "Unable to distribute deposit");
+            } else {
+                bytes32 _escrowId = keccak256(abi.encodePacked(address(this), escrowCount));
+                escrowCount = escrowCount.add(1);
+                escrows[_escrowId] = Escrow(_amounts[i], _recipients[i], _guardians[i], false, false);
+                emit NewEscrow(_escrowId, _recipients[i], _guardians[i], _amounts[i]);
+            }
+            _npRecipients[i] = _recipients[i];
+        }
+        deals[dealId].status = DealStatus.Executed;
+        lastExecutionBlockNumber = block.number;
+        emit Distribute(dealId, deals[dealId].deposit, _npRecipients, bytes32(_receiptHash));
+    }
+
+    /**
+    * Commit the bridge contract the outputs of `distributeToBridge` are relayed through, as configured
+    * in the secret contract, zero disabling the bridge payouts.
+    * Callable only by the Salad secret contract
+    *
+    * @param _bridge The bridge contract
+    */
+    function setPayoutBridge(address _bridge)
+    public
+    onlyEnigma {
+        payoutBridge = _bridge;
+    }
+
+    /**
+    * Distribute the deposits like `distribute`, each output being relayed through the committed bridge contract
+    * to its recipient on the other side of the bridge. The deal is executed before the bridge is called,
+    * so that the bridge may not reenter the distribution of the deal.
+    * Callable only by the Salad secret contract
+    *
+    * @param _dealId The deal id
+    * @param _recipients The output recipients on the other side of the bridge
+    * @param _amounts The output amounts
+    * @param _redeposited The total staged back into the pool
+    * @param _redeemed The total paid out of the pool
+    * @param _receiptHash The receipt hash
+    */
+    function distributeToBridge(uint256 _dealId, address[] memory _recipients, uint[] memory _amounts, uint _redeposited, uint _redeemed, uint256 _receiptHash)
+    public
+    onlyEnigma
+    onlyDistributable(_dealId) {
+        bytes32 dealId = bytes32(_dealId);
+        require(payoutBridge != address(0), "No payout bridge committed.");
+        require(_recipients.length == _amounts.length, "Mismatching recipients and amounts.");
+        _settle(dealId, _amounts, _redeposited, _redeemed);
+        deals[dealId].recipients = _recipients;
+        deals[dealId].status = DealStatus.Executed;
+        lastExecutionBlockNumber = block.number;
+        for (uint i = 0; i < _recipients.length; i++) {
+            IPayoutBridge(payoutBridge).relay.value(_amounts[i])(_recipients[i]);
+        }
+        emit Distribute(dealId, deals[dealId].deposit, _recipients, bytes32(_receiptHash));
+    }
+
+    /**
+    * Distribute the outputs of a multi-asset deal out of the assets held by the contract, each output
+    * being paid in ETH for the zero asset and through the ERC20 transfer of its payee otherwise.
+    * The deposits of the deal are debited from the asset balances of its participants, and the deal is executed
+    * before any transfer, so that no asset contract may reenter the distribution of the deal. The outputs in
+    * each asset never exceed the deposits of the deal in the asset, so that no deal pays out the assets of others.
+    * Callable only by the Salad secret contract, once the roots of the deal are committed.
+    *
+    * @param _dealId The deal id
+    * @param _assets The asset of each output
+    * @param _payees The contract paying each output: its asset, or the adapter configured for the asset
+    * @param _recipients The output recipients
+    * @param _amounts The output amounts, in the units of their asset
+    * @param _receiptHash The receipt hash
+    */
+    function distributeAssets(uint256 _dealId, address[] memory _assets, address[] memory _payees, address payable[] memory _recipients, uint[] memory _amounts, uint256 _receiptHash)
+    public
+    onlyEnigma
+    onlyDistributable(_dealId) {
+        bytes32 dealId = bytes32(_dealId);
+        require(deals[dealId].assets.length > 0, "Deal is not a multi-asset deal.");
+        require(_assets.length == _amounts.length && _payees.length == _amounts.length && _recipients.length == _amounts.length, "Mismatching assets, payees, recipients and amounts.");
+        for (uint i = 0; i < deals[dealId].participants.length; i++) {
+            Balance storage _balance = assetBalances[deals[dealId].participants[i]][deals[dealId].assets[i]];
+            uint _amount = deals[dealId].assetAmounts[i];
+            _balance.amount = _balance.amount.sub(_amount);
+            _balance.locked = _balance.locked.sub(_amount);
+        }
+        for (uint i = 0; i < _assets.length; i++) {
+            uint _deposited = assetDealDeposits[dealId][_assets[i]];
+            require(_amounts[i] <= _deposited, "Outputs exceed the deposits in their asset.");
+            assetDealDeposits[dealId][_assets[i]] = _deposited.sub(_amounts[i]);
+        }
+        deals[dealId].recipients = _recipients;
+        deals[dealId].status = DealStatus.Executed;
+        lastExecutionBlockNumber = block.number;
+        address[] memory _npRecipients = new address[](_recipients.length);
+        for (uint i = 0; i < _recipients.length; i++) {
+            require(_payAsset(_payees[i], _recipients[i], _amounts[i]), "Unable to distribute asset");
+            _npRecipients[i] = _recipients[i];
+        }
+        emit Distribute(dealId, 0, _npRecipients, bytes32(_receiptHash));
+    }
+
+    /**
+    * Refund a Deal never committed, cancelled by its senders or timed out, each deposit being paid back
+    * from the balance of its sender (in its asset for the multi-asset deals), or from its voucher to its depositor. Only the deals past the execution
+    * window since their creation are refunded, so that no pending Deal is refunded before it could execute.
+    * Callable only by the Salad secret contract
+    *
+    * @param _dealId The deal id
+    */
+    function refundDeal(uint256 _dealId)
+    public
+    onlyEnigma {
+        bytes32 dealId = bytes32(_dealId);
+        require(deals[dealId].status == DealStatus.Executable, "Deal is not executable.");
+        require(deals[dealId].startBlockNumber.add(executionWindowInBlocks) < block.number, "Execution window not elapsed.");
+        deals[dealId].status = DealStatus.Refunded;
+        if (deals[dealId].assets.length > 0) {
+            _refundAssets(dealId);
+            return;
+        }
+        uint _deposit = deals[dealId].deposit;
+        for (uint i = 0; i < deals[dealId].participants.length; i++) {
+            address payable _participant = address(uint160(deals[dealId].participants[i]));
+            require(balances[_participant].amount >= _deposit, "Not enough deposit to refund.");
+            balances[_participant].amount = balances[_participant].amount.sub(_deposit);
+            balances[_participant].locked = balances[_participant].locked.sub(_deposit);
+            require(_participant.send(_deposit), "Unable to refund deposit");
+        }
+        for (uint i = 0; i < deals[dealId].vouchers.length; i++) {
+            Voucher storage _voucher = vouchers[deals[dealId].vouchers[i]];
+            require(_voucher.amount >= _deposit, "Not enough voucher deposit to refund.");
+            _voucher.amount = _voucher.amount.sub(_deposit);
+            _voucher.locked = _voucher.locked.sub(_deposit);
+            require(_voucher.depositor.send(_deposit), "Unable to refund deposit");
+        }
+        emit RefundDeal(dealId, deals[dealId].participants.length.add(deals[dealId].vouchers.length));
+    }
+
+    /**
+    * Release an escrowed output, paid to its recipient once released by both the recipient and the guardian
+    *
+    * @param _escrowId The escrow id
+    */
+    function releaseEscrow(bytes32 _escrowId)
+    public {
+        Escrow storage _escrow = escrows[_escrowId];
+        require(_escrow.amount > 0, "Unknown or released escrow");
+        if (msg.sender == _escrow.recipient) {
+            _escrow.releasedByRecipient = true;
+        } else if (msg.sender == _escrow.guardian) {
+            _escrow.releasedByGuardian = true;
+        } else {
+            revert("Not the escrow recipient or guardian");
+        }
+        emit ReleaseEscrow(_escrowId, msg.sender);
+        if (_escrow.releasedByRecipient && _escrow.releasedByGuardian) {
+            uint amount = _escrow.amount;
+            _escrow.amount = 0;
+            _escrow.recipient.transfer(amount);
+        }
+    }
+
+    /**
+    * Record the enclave receipt hash of a distributed deal, timestamped by the block recording it.
+    * Callable only by the Salad secret contract, once per deal
+    *
+    * @param _dealId The deal id
+    * @param _receiptHash The enclave receipt hash
+    */
+    function recordReceipt(uint256 _dealId, uint256 _receiptHash)
+    public
+    onlyEnigma {
+        bytes32 dealId = bytes32(_dealId);
+        require(deals[dealId].status == DealStatus.Executed, "Deal is not distributed.");
+        require(_receiptHash != 0, "Empty receipt hash.");
+        require(receipts[dealId].receiptHash == bytes32(0), "Receipt already recorded.");
+        receipts[dealId] = Receipt(bytes32(_receiptHash), block.timestamp, block.number);
+        emit RecordReceipt(dealId, bytes32(_receiptHash), block.timestamp);
+    }
+
+    /**
+    * Pay a withdrawal of the notes held by the enclave out of the pool reserve, once per nullifier. The withdrawal
+    * must be a leaf of the note payouts committed with a deal whose challenge window has elapsed.
+    * Callable only by the Salad secret contract
+    *
+    * @param _dealId The deal committing the withdrawal
+    * @param _nullifier The nullifier of the withdrawal
+    * @param _payout The withdrawal address
+    * @param _amount The amount withdrawn
+    * @param _index The index of the withdrawal in the committed note payouts
+    * @param _proof The sibling hashes from the leaf of the withdrawal up to the committed root
+    */
+    function withdrawNote(uint256 _dealId, uint256 _nullifier, address payable _payout, uint _amount, uint _index, uint256[] memory _proof)
+    public
+    onlyEnigma {
+        bytes32 dealId = bytes32(_dealId);
+        bytes32 nullifier = bytes32(_nullifier);
+        DealStatus _status = deals[dealId].status;
+        require(_status == DealStatus.Committed || _status == DealStatus.Executed, "Deal roots are not committed.");
+        require(deals[dealId].commitBlockNumber.add(challengeWindowInBlocks) <= block.number, "Challenge window not elapsed.");
+        require(deals[dealId].notePayoutsRoot != bytes32(0), "No note payouts committed.");
+        require(!notePayoutNullifiers[nullifier], "Note withdrawal already paid.");
+        bytes32 _leaf = keccak256(abi.encodePacked(nullifier, _payout, _amount));
+        require(_verifyProof(_leaf, _index, deals[dealId].nbNotePayouts, _proof, deals[dealId].notePayoutsRoot), "Note withdrawal not committed.");
+        require(redepositPool >= _amount, "Not enough funds in the pool reserve.");
+        notePayoutNullifiers[nullifier] = true;
+        redepositPool = redepositPool.sub(_amount);
+        _payout.transfer(_amount);
+        emit WithdrawNote(nullifier, _payout, _amount);
+    }
+
+    /**
+    * Whether the sibling hashes link the leaf hash at `_index` of `_nbLeaves` leaves to the root, the last node
+    * of an odd level being carried up unchanged as in the Merkle trees of the Salad secret contract
+    */
+    function _verifyProof(bytes32 _leaf, uint _index, uint _nbLeaves, uint256[] memory _proof, bytes32 _root)
+    internal
+    pure
+    returns (bool) {
+        if (_index >= _nbLeaves) {
+            return false;
+        }
+        bytes32 _node = _leaf;
+        uint _next = 0;
+        uint _size = _nbLeaves;
+        while (_size > 1) {
+            if ((_index ^ 1) < _size) {
+                if (_next == _proof.length) {
+                    return false;
+                }
+                bytes32 _sibling = bytes32(_proof[_next]);
+                _next = _next.add(1);
+                _node = _index % 2 == 0 ? keccak256(abi.encodePacked(_node, _sibling)) : keccak256(abi.encodePacked(_sibling, _node));
+            }
+            _size = _size.add(1) / 2;
+            _index = _index / 2;
+        }
+        return _next == _proof.length && _node == _root;
+    }
+
+    /**
+    * Refund the deposits of a multi-asset Deal never committed, each paid back in its asset to its sender
+    */
+    function _refundAssets(bytes32 dealId)
+    internal {
+        for (uint i = 0; i < deals[dealId].participants.length; i++) {
+            address payable _participant = address(uint160(deals[dealId].participants[i]));
+            Balance storage _balance = assetBalances[_participant][deals[dealId].assets[i]];
+            uint _amount = deals[dealId].assetAmounts[i];
+            _balance.amount = _balance.amount.sub(_amount);
+            _balance.locked = _balance.locked.sub(_amount);
+            require(_payAsset(deals[dealId].assets[i], _participant, _amount), "Unable to refund deposit");
+        }
+        emit RefundDeal(dealId, deals[dealId].participants.length);
+    }
+
+    /**
+    * Pay an amount of the asset, in ETH for the zero asset and through the ERC20 transfer of the asset otherwise
+    */
+    function _payAsset(address _asset, address payable _to, uint _amount)
+    internal
+    returns (bool) {
+        if (_asset == address(0)) {
+            return _to.send(_amount);
+        }
+        return IERC20(_asset).transfer(_to, _amount);
+    }
+
+    /**
+    * Debit the deposits of the deal, checking that the outputs and the pool movements match them,
+    * the amount paid out of the pool being the one recorded when the deal was created
+    */
+    function _settle(bytes32 dealId, uint[] memory _amounts, uint _redeposited, uint _redeemed)
+    internal {
+        require(_redeemed == deals[dealId].redeemed, "Redeemed amount does not match the deal.");
+        uint _total = 0;
+        for (uint i = 0; i < _amounts.length; i++) {
+            _total = _total.add(_amounts[i]);
+        }
+        uint _nbDeposits = deals[dealId].participants.length.add(deals[dealId].vouchers.length);
+        require(_total.add(_redeposited) == deals[dealId].deposit.mul(_nbDeposits).add(deals[dealId].redeemed), "Amounts do not match the deposits.");
+        redepositPool = redepositPool.add(_redeposited).sub(deals[dealId].redeemed);
+        for (uint i = 0; i < deals[dealId].participants.length; i++) {
+            address _participant = deals[dealId].participants[i];
+            require(balances[_participant].amount >= deals[dealId].deposit, "Not enough deposit to transfer.");
+            balances[_participant].amount = balances[_participant].amount.sub(deals[dealId].deposit);
+            balances[_participant].locked = balances[_participant].locked.sub(deals[dealId].deposit);
+        }
+        for (uint i = 0; i < deals[dealId].vouchers.length; i++) {
+            Voucher storage _voucher = vouchers[deals[dealId].vouchers[i]];
+            require(_voucher.amount >= deals[dealId].deposit, "Not enough voucher deposit to transfer.");
+            _voucher.amount = _voucher.amount.sub(deals[dealId].deposit);
+            _voucher.locked = _voucher.locked.sub(deals[dealId].deposit);
+        }
     }
 
     /**
@@ -239,7 +944,7 @@ contract Salad is ISalad, Ownable {
                 dealId[j] = _dealId;
                 organizer[j] = deals[_dealId].organizer;
                 deposit[j] = deals[_dealId].deposit;
-                numParticipants[j] = deals[_dealId].participants.length;
+                numParticipants[j] = deals[_dealId].participants.length.add(deals[_dealId].vouchers.length);
                 j = j.add(1);
             }
         }
@@ -257,7 +962,7 @@ contract Salad is ISalad, Ownable {
     returns (uint, uint, uint) {
         // TODO: Include status code
         // Key attributes of a deal
-        uint numParticipants = deals[_dealId].participants.length;
+        uint numParticipants = deals[_dealId].participants.length.add(deals[_dealId].vouchers.length);
         uint deposit = deals[_dealId].deposit;
         uint numDestAddresses = deals[_dealId].recipients.length;
 
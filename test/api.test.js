@@ -122,7 +122,8 @@ describe('Salad', () => {
         // expect(web3.utils.toChecksumAddress(`0x${plaintextRecipient}`)).to.equal(recipient);
 
         debug(`Sign deposit ${depositIndex} payload`);
-        signature = await salad.signDepositMetadataAsync(sender, amount, encRecipient, pubKey);
+        const expiresAt = (await web3.eth.getBlockNumber()) + 1000;
+        signature = await salad.signDepositMetadataAsync(sender, amount, encRecipient, pubKey, expiresAt);
         debug('The signature', signature);
         const sigBytes = web3Utils.hexToBytes(signature);
         debug('The signature length', sigBytes.length, sigBytes);
@@ -130,7 +131,7 @@ describe('Salad', () => {
 
         debug(`Submit signed deposit ${depositIndex} payload`);
         debug('Testing deposit submit with signature', signature);
-        const result = await salad.submitDepositMetadataAsync(sender, amount, encRecipient, pubKey, signature);
+        const result = await salad.submitDepositMetadataAsync(sender, amount, encRecipient, pubKey, expiresAt, signature);
         expect(result).to.equal(true);
     }
 
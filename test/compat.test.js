@@ -0,0 +1,44 @@
+const {CoinjoinClient} = require('@salad/client');
+const {expect} = require('chai');
+const {TypedDataUtils, signTypedData_v4} = require('eth-sig-util');
+const Web3 = require('web3');
+
+// The fixtures the enclave digests and parsing are checked against, by the `compat` test of salad-core
+const {deposits, envelopes} = require('./fixtures/compat.json');
+
+describe('Salad client compatibility', () => {
+    const web3 = new Web3();
+
+    deposits.forEach((deposit, i) => {
+        it(`should hash deposit ${i} to the digest verified by the enclave`, () => {
+            const {sender, amount, encRecipient, pubKey, expiresAt, chainId, digest} = deposit;
+            const typedData = CoinjoinClient.buildDepositTypedData({sender, amount, encRecipient, pubKey, expiresAt}, chainId);
+            const hash = `0x${TypedDataUtils.sign(typedData, true).toString('hex')}`;
+            expect(hash).to.equal(digest);
+        });
+
+        it(`should build the signing request of deposit ${i} for hardware wallets`, () => {
+            const {amount, encRecipient, pubKey, expiresAt, chainId} = deposit;
+            // A signer holding its own key, as a hardware wallet would
+            const privateKey = web3.utils.keccak256(`hardware wallet ${i}`);
+            const signer = web3.eth.accounts.privateKeyToAccount(privateKey).address;
+            const request = CoinjoinClient.buildDepositSigningRequest({sender: signer, amount, encRecipient, pubKey, expiresAt}, chainId);
+            const prehashed = web3.utils.keccak256(`0x1901${request.domainSeparator.slice(2)}${request.messageHash.slice(2)}`);
+            expect(prehashed).to.equal(request.digest);
+            const signature = signTypedData_v4(Buffer.from(privateKey.slice(2), 'hex'), {data: request.typedData});
+            expect(CoinjoinClient.verifyDepositSignature(request, signature, signer)).to.equal(true);
+            const [r, s, v] = [signature.slice(2, 66), signature.slice(66, 130), parseInt(signature.slice(130), 16)];
+            expect(CoinjoinClient.verifyDepositSignature(request, {v: v - 27, r, s}, signer)).to.equal(true);
+            expect(CoinjoinClient.verifyDepositSignature(request, signature, deposit.sender)).to.equal(false);
+        });
+    });
+
+    envelopes.forEach((envelope, i) => {
+        it(`should build envelope ${i} parsed by the enclave`, () => {
+            const {recipient, notificationKey, payload} = envelope;
+            const extensions = notificationKey ? [{type: 0x01, value: notificationKey}] : [];
+            const bytes = CoinjoinClient.prefixExtensions(web3, web3.utils.hexToBytes(recipient), extensions);
+            expect(web3.utils.bytesToHex(bytes)).to.equal(`0x${payload}`);
+        });
+    });
+});